@@ -18,13 +18,20 @@ pub enum Expression {
     Operator(Operator, Box<Expression>, Box<Expression>),
     SubExpression(Box<Expression>), // ( ... )
     Path(Box<Expression>, Box<Expression>), // struct.value
+    OptionalPath(Box<Expression>, Box<Expression>), // optional?.value
     IsNot(Box<Expression>), // !expr (where expr is a bool)
+    BitwiseNot(Box<Expression>), // ~expr (where expr is a number)
     Ternary(Box<Expression>, Box<Expression>, Box<Expression>), // bool expr, if true expr, else expr
     Cast(Box<Expression>, Type), // expr, type
     Range(Box<Expression>, Box<Expression>), // start, end
+    Try(Box<Expression>), // expr? (where expr is a result)
+    TupleConstructor(Vec<Expression>), // (a, b)
+    StructUpdate(Box<Expression>, Vec<(IdentifierType, Expression)>), // expr with { field: value }
+    Block(Vec<Statement>, Box<Expression>, Type), // { statements; value }, type of the value
+    ValueCall(Box<Expression>, Vec<Expression>), // expr(parameters) where expr is a function value
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub enum Statement {
     If(Expression, Vec<Statement>, Option<Vec<Statement>>),
     While(Expression, Vec<Statement>),
@@ -33,16 +40,20 @@ pub enum Statement {
     Expression(Expression),
     Return(Option<Expression>),
     Scope(Vec<Statement>),
-    Break,
-    Continue,
+    // Count of enclosing loops left before the targeted one, 0 is the innermost loop
+    Break(u16),
+    Continue(u16),
     Variable(DeclarationStatement),
+    TupleDeconstruction(Vec<(IdentifierType, Type)>, Expression), // let (a, b) = expr
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct DeclarationStatement {
     pub id: IdentifierType,
     pub value_type: Type,
     pub value: Expression,
+    // Declared with 'var', a 'let' variable can't be assigned after its declaration
+    pub mutable: bool,
 }
 
 impl std::hash::Hash for DeclarationStatement {
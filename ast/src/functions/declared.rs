@@ -32,10 +32,18 @@ impl DeclaredFunction {
         &self.statements
     }
 
+    pub fn get_statements_mut(&mut self) -> &mut Vec<Statement> {
+        &mut self.statements
+    }
+
     pub fn get_parameters(&self) -> &Vec<Parameter> {
         &self.parameters
     }
 
+    pub fn get_parameters_mut(&mut self) -> &mut Vec<Parameter> {
+        &mut self.parameters
+    }
+
     pub fn get_instance_name(&self) -> Option<&IdentifierType> {
         self.instance_name.as_ref()
     }
@@ -71,11 +79,21 @@ impl EntryFunction {
         &self.parameters
     }
 
+    // Get the mutable parameters of the function
+    pub fn get_parameters_mut(&mut self) -> &mut Vec<Parameter> {
+        &mut self.parameters
+    }
+
     // Get the statements of the function
     pub fn get_statements(&self) -> &Vec<Statement> {
         &self.statements
     }
 
+    // Get the mutable statements of the function
+    pub fn get_statements_mut(&mut self) -> &mut Vec<Statement> {
+        &mut self.statements
+    }
+
     // Get the variables count of the function
     pub fn get_variables_count(&self) -> u16 {
         self.variables_count
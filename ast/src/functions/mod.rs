@@ -1,7 +1,7 @@
 mod declared;
 
 use xelis_types::{Type, IdentifierType};
-use super::Statement;
+use super::{Expression, Statement};
 
 pub use declared::{DeclaredFunction, EntryFunction};
 
@@ -14,7 +14,9 @@ pub const ENTRY_FN_RETURN_TYPE: Type = Type::U64;
 #[derive(Debug, PartialEq, Eq)]
 pub struct Parameter {
     name: IdentifierType,
-    value_type: Type
+    value_type: Type,
+    // Expression evaluated when the argument is omitted by the caller
+    default_value: Option<Expression>
 }
 
 impl Parameter {
@@ -22,7 +24,17 @@ impl Parameter {
     pub fn new(name: IdentifierType, value_type: Type) -> Self {
         Parameter {
             name,
-            value_type
+            value_type,
+            default_value: None
+        }
+    }
+
+    #[inline(always)]
+    pub fn with_default_value(name: IdentifierType, value_type: Type, default_value: Option<Expression>) -> Self {
+        Parameter {
+            name,
+            value_type,
+            default_value
         }
     }
 
@@ -36,6 +48,16 @@ impl Parameter {
         &self.value_type
     }
 
+    #[inline(always)]
+    pub fn get_default_value(&self) -> Option<&Expression> {
+        self.default_value.as_ref()
+    }
+
+    #[inline(always)]
+    pub fn get_default_value_mut(&mut self) -> Option<&mut Expression> {
+        self.default_value.as_mut()
+    }
+
     #[inline(always)]
     pub fn consume(self) -> (IdentifierType, Type) {
         (self.name, self.value_type)
@@ -120,6 +142,15 @@ impl FunctionType {
         }
     }
 
+    // Get the mutable parameters of the function
+    #[inline(always)]
+    pub fn get_parameters_mut(&mut self) -> &mut Vec<Parameter> {
+        match self {
+            FunctionType::Declared(f) => f.get_parameters_mut(),
+            FunctionType::Entry(f) => f.get_parameters_mut()
+        }
+    }
+
     // Get the statements of the function
     #[inline(always)]
     pub fn get_statements(&self) -> &Vec<Statement> {
@@ -129,6 +160,15 @@ impl FunctionType {
         }
     }
 
+    // Get the mutable statements of the function
+    #[inline(always)]
+    pub fn get_statements_mut(&mut self) -> &mut Vec<Statement> {
+        match self {
+            FunctionType::Declared(f) => f.get_statements_mut(),
+            FunctionType::Entry(f) => f.get_statements_mut()
+        }
+    }
+
     // Get the count of variables declared in the function
     pub fn get_variables_count(&self) -> u16 {
         match self {
@@ -3,9 +3,13 @@ mod operator;
 mod token;
 mod functions;
 mod program;
+mod visitor;
+mod variables;
 
 pub use expressions::{Expression, Statement, DeclarationStatement};
-pub use operator::Operator;
+pub use operator::{Associativity, Operator};
 pub use token::*;
 pub use functions::*;
-pub use program::Program;
\ No newline at end of file
+pub use program::Program;
+pub use visitor::*;
+pub use variables::*;
\ No newline at end of file
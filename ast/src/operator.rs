@@ -36,7 +36,45 @@ pub enum Operator {
     // AssignBitwiseRight, // >>=
 }
 
+// Side on which the operators of the same precedence are grouped
+#[derive(Debug, PartialEq, Clone, Copy, Eq)]
+pub enum Associativity {
+    // a - b - c is (a - b) - c
+    Left,
+    // a = b = c is a = (b = c)
+    Right
+}
+
 impl Operator {
+    // Binding power of the operator used by the parser, the highest binds first
+    pub fn precedence(&self) -> u8 {
+        match self {
+            Operator::Multiply | Operator::Divide | Operator::Rem => 10,
+            Operator::Plus | Operator::Minus => 9,
+            Operator::BitwiseLeft | Operator::BitwiseRight => 8,
+            Operator::BitwiseAnd => 7,
+            Operator::BitwiseXor => 6,
+            Operator::BitwiseOr => 5,
+            Operator::Equals
+            | Operator::NotEquals
+            | Operator::GreaterThan
+            | Operator::LessThan
+            | Operator::GreaterOrEqual
+            | Operator::LessOrEqual => 4,
+            Operator::And => 3,
+            Operator::Or => 2,
+            Operator::Assign(_) => 1
+        }
+    }
+
+    // Grouping of the operators of the same precedence used by the parser
+    pub fn associativity(&self) -> Associativity {
+        match self {
+            Operator::Assign(_) => Associativity::Right,
+            _ => Associativity::Left
+        }
+    }
+
     pub fn value_of(token: &Token) -> Option<Operator> {
         use Operator::*;
         let value = match token {
@@ -77,6 +115,24 @@ impl Operator {
         Some(value)
     }
 
+    // Precedence of the operator of this token
+    // Unlike value_of, it doesn't allocate the inner operator of an assignment
+    pub fn precedence_of(token: &Token) -> Option<u8> {
+        match token {
+            Token::OperatorPlusAssign
+            | Token::OperatorMinusAssign
+            | Token::OperatorDivideAssign
+            | Token::OperatorMultiplyAssign
+            | Token::OperatorModuloAssign
+            | Token::OperatorBitwiseXorAssign
+            | Token::OperatorBitwiseAndAssign
+            | Token::OperatorBitwiseOrAssign
+            | Token::OperatorBitwiseLeftAssign
+            | Token::OperatorBitwiseRightAssign => Some(Operator::Assign(None).precedence()),
+            _ => Self::value_of(token).map(|op| op.precedence())
+        }
+    }
+
     pub fn is_assignation(&self) -> bool {
         match &self {
             Operator::Assign(_) => true,
@@ -117,10 +173,50 @@ impl Operator {
         }
     }
 
+    // Operators comparing two values, they can't be chained
+    pub fn is_comparison(&self) -> bool {
+        matches!(
+            self,
+            Operator::Equals
+            | Operator::NotEquals
+            | Operator::GreaterThan
+            | Operator::LessThan
+            | Operator::GreaterOrEqual
+            | Operator::LessOrEqual
+        )
+    }
+
     pub fn is_and_or_or(&self) -> bool {
         match &self {
             Operator::And | Operator::Or => true,
             _ => false
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_precedence() {
+        assert!(Operator::Multiply.precedence() > Operator::Plus.precedence());
+        assert!(Operator::Plus.precedence() > Operator::Equals.precedence());
+        assert!(Operator::Equals.precedence() > Operator::And.precedence());
+        assert!(Operator::And.precedence() > Operator::Or.precedence());
+        assert!(Operator::Or.precedence() > Operator::Assign(None).precedence());
+
+        // The token precedence matches the operator built from it
+        for token in [Token::OperatorMultiply, Token::OperatorOr, Token::OperatorAssign, Token::OperatorPlusAssign, Token::OperatorBitwiseRightAssign] {
+            assert_eq!(Operator::precedence_of(&token), Operator::value_of(&token).map(|op| op.precedence()));
+        }
+        assert_eq!(Operator::precedence_of(&Token::Dot), None);
+    }
+
+    #[test]
+    fn test_associativity() {
+        assert_eq!(Operator::Assign(None).associativity(), Associativity::Right);
+        assert_eq!(Operator::Assign(Some(Box::new(Operator::Plus))).associativity(), Associativity::Right);
+        assert_eq!(Operator::Minus.associativity(), Associativity::Left);
+    }
+}
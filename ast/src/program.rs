@@ -2,7 +2,7 @@ use std::collections::HashSet;
 use xelis_types::StructType;
 use super::{DeclarationStatement, FunctionType};
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 pub struct Program {
     // All constants declared
     constants: HashSet<DeclarationStatement>,
@@ -66,4 +66,10 @@ impl Program {
     pub fn functions(&self) -> &[FunctionType] {
         &self.functions
     }
+
+    // Get the mutable functions declared in the program
+    #[inline]
+    pub fn functions_mut(&mut self) -> &mut [FunctionType] {
+        &mut self.functions
+    }
 }
\ No newline at end of file
@@ -1,4 +1,4 @@
-use std::borrow::Cow;
+use std::{borrow::Cow, fmt};
 
 use xelis_types::U256;
 
@@ -7,6 +7,7 @@ use xelis_types::U256;
 pub enum TokenGeneric {
     Optional,
     Range,
+    Result,
 }
 
 impl TokenGeneric {
@@ -15,16 +16,28 @@ impl TokenGeneric {
         Some(match s {
             "optional" => Self::Optional,
             "range" => Self::Range,
+            "result" => Self::Result,
             _ => return None,
         })
     }
 
-    // Convert the token to a token with the inner token
-    pub fn to_token(self, token: Token) -> Token {
+    // Number of inner tokens expected by the generic
+    pub fn inner_count(&self) -> usize {
+        match self {
+            Self::Result => 2,
+            _ => 1,
+        }
+    }
+
+    // Convert the token to a token with the inner tokens
+    // Panic if the inner tokens count doesn't match
+    pub fn to_token<'a>(self, mut tokens: Vec<Token<'a>>) -> Token<'a> {
         use Token::*;
+        let first = Box::new(tokens.remove(0));
         match self {
-            Self::Optional => Optional(Box::new(token)),
-            Self::Range => Range(Box::new(token)),
+            Self::Optional => Optional(first),
+            Self::Range => Range(first),
+            Self::Result => Result(first, Box::new(tokens.remove(0))),
         }
     }
 }
@@ -53,6 +66,19 @@ impl NumberType {
     }
 }
 
+impl fmt::Display for NumberType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::U8 => "u8",
+            Self::U16 => "u16",
+            Self::U32 => "u32",
+            Self::U64 => "u64",
+            Self::U128 => "u128",
+            Self::U256 => "u256",
+        })
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum Literal<'a> {
     U8(u8),
@@ -63,15 +89,38 @@ pub enum Literal<'a> {
     U256(U256),
     // Default number type when no type is specified
     Number(u64),
+    // Number with a type suffix that doesn't fit in it (300u8)
+    OutOfRange(NumberType, u128),
     String(Cow<'a, str>),
     Bool(bool),
     Null,
 }
 
+// Render the literal as written in the source code
+impl fmt::Display for Literal<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::U8(n) => write!(f, "{}u8", n),
+            Self::U16(n) => write!(f, "{}u16", n),
+            Self::U32(n) => write!(f, "{}u32", n),
+            Self::U64(n) => write!(f, "{}u64", n),
+            Self::U128(n) => write!(f, "{}u128", n),
+            Self::U256(n) => write!(f, "{}u256", n),
+            Self::Number(n) => write!(f, "{}", n),
+            Self::OutOfRange(t, n) => write!(f, "{}{}", n, t),
+            Self::String(s) => write!(f, "{:?}", s),
+            Self::Bool(b) => write!(f, "{}", b),
+            Self::Null => f.write_str("null"),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum Token<'a> {
     // Variable / function names
     Identifier(&'a str),
+    // Loop label such as 'outer
+    Label(&'a str),
     Value(Literal<'a>),
 
     // Types supported
@@ -80,6 +129,7 @@ pub enum Token<'a> {
     String,
     Optional(Box<Token<'a>>),
     Range(Box<Token<'a>>),
+    Result(Box<Token<'a>>, Box<Token<'a>>),
 
     BraceOpen,
     BraceClose,
@@ -88,6 +138,7 @@ pub enum Token<'a> {
 
     Const,
     Let,
+    Var,
     Entry,
     Function,
     Dot,
@@ -103,6 +154,7 @@ pub enum Token<'a> {
     Continue,
     In,
     IsNot, // !
+    BitwiseNot, // ~
 
     ParenthesisOpen,
     ParenthesisClose,
@@ -146,6 +198,7 @@ pub enum Token<'a> {
     Import,
     From,
     As,
+    With,
     ReturnType
 }
 
@@ -206,6 +259,7 @@ impl Token<'_> {
             "string" => String,
 
             "let" => Let,
+            "var" => Var,
 
             "const" => Const,
             "entry" => Entry,
@@ -221,6 +275,7 @@ impl Token<'_> {
             "continue" => Continue,
             "in" => In,
             "!" => IsNot,
+            "~" => BitwiseNot,
 
             "null" => Value(Literal::Null),
             "true" => Value(Literal::Bool(true)),
@@ -229,6 +284,7 @@ impl Token<'_> {
             "import" => Import,
             "from" => From,
             "as" => As,
+            "with" => With,
             "->" => ReturnType,
 
             e => Number(NumberType::value_of(e)?),
@@ -277,7 +333,8 @@ impl Token<'_> {
             | BracketOpen
             | Dot
             | OperatorTernary
-            | As => true,
+            | As
+            | With => true,
             _ => false,
         }
     }
@@ -289,7 +346,8 @@ impl Token<'_> {
             | Bool
             | String
             | Identifier(_)
-            | Optional(_) => true,
+            | Optional(_)
+            | Result(_, _) => true,
             _ => false,
         }
     }
@@ -301,4 +359,122 @@ impl Token<'_> {
             _ => false,
         }
     }
-}
\ No newline at end of file
+}
+
+// Render the token as the user would type it
+impl fmt::Display for Token<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use Token::*;
+
+        let s = match self {
+            Identifier(name) => return f.write_str(name),
+            Label(name) => return write!(f, "'{}", name),
+            Value(literal) => return write!(f, "{}", literal),
+            Number(t) => return write!(f, "{}", t),
+            Optional(inner) => return write!(f, "optional<{}>", inner),
+            Range(inner) => return write!(f, "range<{}>", inner),
+            Result(ok, err) => return write!(f, "result<{}, {}>", ok, err),
+
+            Bool => "bool",
+            String => "string",
+
+            BraceOpen => "{",
+            BraceClose => "}",
+            BracketOpen => "[",
+            BracketClose => "]",
+
+            Const => "const",
+            Let => "let",
+            Var => "var",
+            Entry => "entry",
+            Function => "fn",
+            Dot => ".",
+            Comma => ",",
+            Colon => ":",
+            Return => "return",
+            If => "if",
+            Else => "else",
+            For => "for",
+            ForEach => "foreach",
+            While => "while",
+            Break => "break",
+            Continue => "continue",
+            In => "in",
+            IsNot => "!",
+            BitwiseNot => "~",
+
+            ParenthesisOpen => "(",
+            ParenthesisClose => ")",
+            Struct => "struct",
+
+            OperatorAssign => "=",
+            OperatorEquals => "==",
+            OperatorNotEquals => "!=",
+            OperatorAnd => "&&",
+            OperatorOr => "||",
+            OperatorGreaterThan => ">",
+            OperatorLessThan => "<",
+            OperatorGreaterOrEqual => ">=",
+            OperatorLessOrEqual => "<=",
+
+            OperatorPlus => "+",
+            OperatorMinus => "-",
+            OperatorMultiply => "*",
+            OperatorDivide => "/",
+            OperatorModulo => "%",
+            OperatorBitwiseXor => "^",
+            OperatorBitwiseOr => "|",
+            OperatorBitwiseAnd => "&",
+            OperatorBitwiseLeft => "<<",
+            OperatorBitwiseRight => ">>",
+
+            OperatorPlusAssign => "+=",
+            OperatorMinusAssign => "-=",
+            OperatorMultiplyAssign => "*=",
+            OperatorDivideAssign => "/=",
+            OperatorModuloAssign => "%=",
+
+            OperatorBitwiseXorAssign => "^=",
+            OperatorBitwiseOrAssign => "|=",
+            OperatorBitwiseAndAssign => "&=",
+            OperatorBitwiseLeftAssign => "<<=",
+            OperatorBitwiseRightAssign => ">>=",
+
+            OperatorTernary => "?",
+
+            Import => "import",
+            From => "from",
+            As => "as",
+            With => "with",
+            ReturnType => "->",
+        };
+
+        f.write_str(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_display() {
+        assert_eq!(Token::BraceClose.to_string(), "}");
+        assert_eq!(Token::OperatorPlusAssign.to_string(), "+=");
+        assert_eq!(Token::Identifier("foo").to_string(), "foo");
+        assert_eq!(Token::Label("outer").to_string(), "'outer");
+        assert_eq!(Token::Value(Literal::Number(10)).to_string(), "10");
+        assert_eq!(Token::Value(Literal::U8(10)).to_string(), "10u8");
+        assert_eq!(Token::Value(Literal::String(Cow::Borrowed("a \"b\""))).to_string(), "\"a \\\"b\\\"\"");
+        assert_eq!(Token::Result(Box::new(Token::Number(NumberType::U64)), Box::new(Token::String)).to_string(), "result<u64, string>");
+    }
+
+    #[test]
+    fn test_token_display_round_trip() {
+        // Every fixed token is rendered as the text it is read from
+        for s in ["{", "}", "+=", ">>=", "->", "fn", "foreach", "?", "!", "null", "true", "u128", "with"] {
+            let token = Token::value_of(s).unwrap();
+            assert_eq!(token.to_string(), s);
+        }
+    }
+}
@@ -0,0 +1,313 @@
+use std::{collections::BTreeSet, fmt};
+use xelis_types::IdentifierType;
+use crate::{
+    walk_expression,
+    walk_statement,
+    DeclarationStatement,
+    Expression,
+    FunctionType,
+    Operator,
+    Program,
+    Statement,
+    Visitor
+};
+
+// Variable declared by the analyzed statements
+struct Declaration {
+    id: IdentifierType,
+    read: bool,
+    // Parameters are not reported as unused
+    reported: bool
+}
+
+// Free variable and unused variable analysis
+// Variable ids are the slots of the stack and are reused by sibling scopes,
+// so each read is resolved against the declarations still in scope
+#[derive(Default)]
+pub struct VariableUsage {
+    declarations: Vec<Declaration>,
+    // Index in declarations of the variables of each scope
+    scopes: Vec<Vec<usize>>,
+    free: BTreeSet<IdentifierType>
+}
+
+impl VariableUsage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Analyze a function, its parameters and instance are declared
+    pub fn of_function(function: &FunctionType) -> Self {
+        let mut usage = Self::new();
+        usage.visit_function(function);
+        usage
+    }
+
+    // Analyze statements outside of any function
+    // The variables they read without declaring them are free
+    pub fn of_statements(statements: &[Statement]) -> Self {
+        let mut usage = Self::new();
+        usage.walk_scope(statements, None);
+        usage
+    }
+
+    // Variables read or assigned without being declared by the analyzed code
+    pub fn free_variables(&self) -> Vec<IdentifierType> {
+        self.free.iter().copied().collect()
+    }
+
+    // Variables declared but never read, in declaration order
+    // An id is listed once per unused declaration
+    pub fn unused_variables(&self) -> Vec<IdentifierType> {
+        self.declarations.iter()
+            .filter(|d| d.reported && !d.read)
+            .map(|d| d.id)
+            .collect()
+    }
+
+    fn begin_scope(&mut self) {
+        self.scopes.push(Vec::new());
+    }
+
+    fn end_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    fn declare(&mut self, id: IdentifierType, reported: bool) {
+        let index = self.declarations.len();
+        self.declarations.push(Declaration { id, read: false, reported });
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.push(index);
+        }
+    }
+
+    // Find the declaration in scope for this id, the latest one shadows the others
+    fn resolve(&mut self, id: IdentifierType) -> Option<&mut Declaration> {
+        let index = self.scopes.iter()
+            .rev()
+            .flat_map(|scope| scope.iter().rev())
+            .copied()
+            .find(|index| self.declarations[*index].id == id)?;
+
+        self.declarations.get_mut(index)
+    }
+
+    fn use_variable(&mut self, id: IdentifierType, read: bool) {
+        match self.resolve(id) {
+            Some(declaration) => declaration.read |= read,
+            None => {
+                self.free.insert(id);
+            }
+        }
+    }
+
+    // Visit statements in their own scope
+    // The declaration, if any, is part of the scope such as the variable of a for loop
+    fn walk_scope(&mut self, statements: &[Statement], declaration: Option<IdentifierType>) {
+        self.begin_scope();
+        if let Some(id) = declaration {
+            self.declare(id, true);
+        }
+
+        for statement in statements {
+            self.visit_statement(statement);
+        }
+        self.end_scope();
+    }
+}
+
+impl Visitor for VariableUsage {
+    fn visit_function(&mut self, function: &FunctionType) {
+        self.begin_scope();
+        if let Some(instance) = function.get_instance_name() {
+            self.declare(*instance, false);
+        }
+
+        for parameter in function.get_parameters() {
+            if let Some(default_value) = parameter.get_default_value() {
+                self.visit_expression(default_value);
+            }
+            self.declare(*parameter.get_name(), false);
+        }
+
+        for statement in function.get_statements() {
+            self.visit_statement(statement);
+        }
+        self.end_scope();
+    }
+
+    fn visit_statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::If(condition, statements, else_statements) => {
+                self.visit_expression(condition);
+                self.walk_scope(statements, None);
+                if let Some(else_statements) = else_statements {
+                    self.walk_scope(else_statements, None);
+                }
+            },
+            Statement::While(condition, statements) => {
+                self.visit_expression(condition);
+                self.walk_scope(statements, None);
+            },
+            Statement::ForEach(id, expr, statements) => {
+                self.visit_expression(expr);
+                self.walk_scope(statements, Some(*id));
+            },
+            Statement::For(declaration, condition, increment, statements) => {
+                self.begin_scope();
+                self.visit_declaration(declaration);
+                self.visit_expression(condition);
+                self.visit_expression(increment);
+                self.walk_scope(statements, None);
+                self.end_scope();
+            },
+            Statement::Scope(statements) => self.walk_scope(statements, None),
+            Statement::TupleDeconstruction(variables, expr) => {
+                self.visit_expression(expr);
+                for (id, _) in variables {
+                    self.declare(*id, true);
+                }
+            },
+            _ => walk_statement(self, statement)
+        }
+    }
+
+    fn visit_declaration(&mut self, declaration: &DeclarationStatement) {
+        // The value can't read the variable being declared
+        self.visit_expression(&declaration.value);
+        self.declare(declaration.id, true);
+    }
+
+    fn visit_expression(&mut self, expression: &Expression) {
+        match expression {
+            Expression::Variable(id) => self.use_variable(*id, true),
+            // The right side is a field of the left value, not a variable
+            Expression::Path(left, _) | Expression::OptionalPath(left, _) => self.visit_expression(left),
+            // Assigning a variable doesn't read it
+            Expression::Operator(Operator::Assign(None), left, right) if matches!(left.as_ref(), Expression::Variable(_)) => {
+                self.visit_expression(right);
+                if let Expression::Variable(id) = left.as_ref() {
+                    self.use_variable(*id, false);
+                }
+            },
+            Expression::Block(statements, value, _) => {
+                self.begin_scope();
+                for statement in statements {
+                    self.visit_statement(statement);
+                }
+                self.visit_expression(value);
+                self.end_scope();
+            },
+            _ => walk_expression(self, expression)
+        }
+    }
+}
+
+// Warning reported on a variable of a program
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VariableWarning {
+    // The variable is declared in the function at this index but never read
+    Unused(usize, IdentifierType)
+}
+
+impl fmt::Display for VariableWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VariableWarning::Unused(function, id) => write!(f, "variable {} of function {} is never read", id, function)
+        }
+    }
+}
+
+// Collect the variables declared but never read by each function of the program
+// These warnings are optional: the program is valid and can be executed
+pub fn unused_variable_warnings(program: &Program) -> Vec<VariableWarning> {
+    program.functions()
+        .iter()
+        .enumerate()
+        .flat_map(|(index, function)| VariableUsage::of_function(function)
+            .unused_variables()
+            .into_iter()
+            .map(move |id| VariableWarning::Unused(index, id))
+        )
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use xelis_types::{Type, Value};
+    use crate::{DeclaredFunction, Parameter};
+    use super::*;
+
+    fn declare(id: IdentifierType, value: Expression) -> Statement {
+        Statement::Variable(DeclarationStatement {
+            id,
+            value_type: Type::U64,
+            value,
+            mutable: true
+        })
+    }
+
+    fn value(v: u64) -> Expression {
+        Expression::Value(Value::U64(v))
+    }
+
+    #[test]
+    fn test_free_variables() {
+        // let b: u64 = a + 1; return b
+        let statements = vec![
+            declare(1, Expression::Operator(Operator::Plus, Box::new(Expression::Variable(0)), Box::new(value(1)))),
+            Statement::Return(Some(Expression::Variable(1))),
+        ];
+
+        let usage = VariableUsage::of_statements(&statements);
+        assert_eq!(usage.free_variables(), vec![0]);
+        assert!(usage.unused_variables().is_empty());
+    }
+
+    #[test]
+    fn test_sibling_scopes() {
+        // { let a: u64 = 1; } { return a }
+        // The slot 0 is reused, the second scope reads a variable it doesn't declare
+        let statements = vec![
+            Statement::Scope(vec![declare(0, value(1))]),
+            Statement::Scope(vec![Statement::Return(Some(Expression::Variable(0)))]),
+        ];
+
+        let usage = VariableUsage::of_statements(&statements);
+        assert_eq!(usage.free_variables(), vec![0]);
+        assert_eq!(usage.unused_variables(), vec![0]);
+    }
+
+    #[test]
+    fn test_unused_variables() {
+        // fn f(a: u64) -> u64 { let b: u64 = 1; let c: u64 = 2; c = a; return c }
+        let statements = vec![
+            declare(1, value(1)),
+            declare(2, value(2)),
+            Statement::Expression(Expression::Operator(Operator::Assign(None), Box::new(Expression::Variable(2)), Box::new(Expression::Variable(0)))),
+            Statement::Return(Some(Expression::Variable(2))),
+        ];
+
+        let mut program = Program::new();
+        program.add_function(FunctionType::Declared(DeclaredFunction::new(
+            None,
+            None,
+            vec![Parameter::new(0, Type::U64)],
+            statements,
+            Some(Type::U64),
+            3
+        )));
+
+        let usage = VariableUsage::of_function(&program.functions()[0]);
+        assert!(usage.free_variables().is_empty());
+        assert_eq!(usage.unused_variables(), vec![1]);
+        assert_eq!(unused_variable_warnings(&program), vec![VariableWarning::Unused(0, 1)]);
+
+        // An assignment alone is not a read
+        let statements = vec![
+            declare(0, value(1)),
+            Statement::Expression(Expression::Operator(Operator::Assign(None), Box::new(Expression::Variable(0)), Box::new(value(2)))),
+        ];
+        assert_eq!(VariableUsage::of_statements(&statements).unused_variables(), vec![0]);
+    }
+}
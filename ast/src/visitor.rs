@@ -0,0 +1,393 @@
+use crate::{DeclarationStatement, Expression, FunctionType, Program, Statement};
+
+// Read-only visitor over the AST
+// Every method recurse by default, a pass only override the nodes it cares about
+// and call the matching walk_* function to continue the traversal
+pub trait Visitor {
+    fn visit_program(&mut self, program: &Program) {
+        walk_program(self, program);
+    }
+
+    fn visit_function(&mut self, function: &FunctionType) {
+        walk_function(self, function);
+    }
+
+    fn visit_statement(&mut self, statement: &Statement) {
+        walk_statement(self, statement);
+    }
+
+    fn visit_declaration(&mut self, declaration: &DeclarationStatement) {
+        walk_declaration(self, declaration);
+    }
+
+    fn visit_expression(&mut self, expression: &Expression) {
+        walk_expression(self, expression);
+    }
+}
+
+// Mutable visitor over the AST
+// Same as Visitor, but nodes can be rewritten in place
+// Program constants are stored in a set and are not visited
+pub trait VisitorMut {
+    fn visit_program_mut(&mut self, program: &mut Program) {
+        walk_program_mut(self, program);
+    }
+
+    fn visit_function_mut(&mut self, function: &mut FunctionType) {
+        walk_function_mut(self, function);
+    }
+
+    fn visit_statement_mut(&mut self, statement: &mut Statement) {
+        walk_statement_mut(self, statement);
+    }
+
+    fn visit_declaration_mut(&mut self, declaration: &mut DeclarationStatement) {
+        walk_declaration_mut(self, declaration);
+    }
+
+    fn visit_expression_mut(&mut self, expression: &mut Expression) {
+        walk_expression_mut(self, expression);
+    }
+}
+
+pub fn walk_program<V: Visitor + ?Sized>(visitor: &mut V, program: &Program) {
+    for constant in program.constants() {
+        visitor.visit_declaration(constant);
+    }
+
+    for function in program.functions() {
+        visitor.visit_function(function);
+    }
+}
+
+pub fn walk_function<V: Visitor + ?Sized>(visitor: &mut V, function: &FunctionType) {
+    for default_value in function.get_parameters().iter().filter_map(|p| p.get_default_value()) {
+        visitor.visit_expression(default_value);
+    }
+
+    for statement in function.get_statements() {
+        visitor.visit_statement(statement);
+    }
+}
+
+pub fn walk_statements<V: Visitor + ?Sized>(visitor: &mut V, statements: &[Statement]) {
+    for statement in statements {
+        visitor.visit_statement(statement);
+    }
+}
+
+pub fn walk_statement<V: Visitor + ?Sized>(visitor: &mut V, statement: &Statement) {
+    match statement {
+        Statement::If(condition, statements, else_statements) => {
+            visitor.visit_expression(condition);
+            walk_statements(visitor, statements);
+            if let Some(else_statements) = else_statements {
+                walk_statements(visitor, else_statements);
+            }
+        },
+        Statement::While(condition, statements) => {
+            visitor.visit_expression(condition);
+            walk_statements(visitor, statements);
+        },
+        Statement::ForEach(_, expr, statements) => {
+            visitor.visit_expression(expr);
+            walk_statements(visitor, statements);
+        },
+        Statement::For(declaration, condition, increment, statements) => {
+            visitor.visit_declaration(declaration);
+            visitor.visit_expression(condition);
+            visitor.visit_expression(increment);
+            walk_statements(visitor, statements);
+        },
+        Statement::Expression(expr) => visitor.visit_expression(expr),
+        Statement::Return(expr) => {
+            if let Some(expr) = expr {
+                visitor.visit_expression(expr);
+            }
+        },
+        Statement::Scope(statements) => walk_statements(visitor, statements),
+        Statement::Variable(declaration) => visitor.visit_declaration(declaration),
+        Statement::TupleDeconstruction(_, expr) => visitor.visit_expression(expr),
+        Statement::Break(_) | Statement::Continue(_) => {}
+    }
+}
+
+pub fn walk_declaration<V: Visitor + ?Sized>(visitor: &mut V, declaration: &DeclarationStatement) {
+    visitor.visit_expression(&declaration.value);
+}
+
+pub fn walk_expression<V: Visitor + ?Sized>(visitor: &mut V, expression: &Expression) {
+    match expression {
+        Expression::FunctionCall(path, _, parameters) => {
+            if let Some(path) = path {
+                visitor.visit_expression(path);
+            }
+            for param in parameters {
+                visitor.visit_expression(param);
+            }
+        },
+        Expression::ArrayConstructor(values)
+        | Expression::StructConstructor(values, _)
+        | Expression::TupleConstructor(values) => {
+            for value in values {
+                visitor.visit_expression(value);
+            }
+        },
+        Expression::ArrayCall(left, right)
+        | Expression::Operator(_, left, right)
+        | Expression::Path(left, right)
+        | Expression::OptionalPath(left, right)
+        | Expression::Range(left, right) => {
+            visitor.visit_expression(left);
+            visitor.visit_expression(right);
+        },
+        Expression::Ternary(condition, left, right) => {
+            visitor.visit_expression(condition);
+            visitor.visit_expression(left);
+            visitor.visit_expression(right);
+        },
+        Expression::SubExpression(expr)
+        | Expression::IsNot(expr)
+        | Expression::BitwiseNot(expr)
+        | Expression::Cast(expr, _)
+        | Expression::Try(expr) => visitor.visit_expression(expr),
+        Expression::StructUpdate(expr, fields) => {
+            visitor.visit_expression(expr);
+            for (_, value) in fields {
+                visitor.visit_expression(value);
+            }
+        },
+        Expression::Block(statements, value, _) => {
+            walk_statements(visitor, statements);
+            visitor.visit_expression(value);
+        },
+        Expression::ValueCall(function, parameters) => {
+            visitor.visit_expression(function);
+            for param in parameters {
+                visitor.visit_expression(param);
+            }
+        },
+        Expression::Variable(_) | Expression::Value(_) => {}
+    }
+}
+
+pub fn walk_program_mut<V: VisitorMut + ?Sized>(visitor: &mut V, program: &mut Program) {
+    for function in program.functions_mut() {
+        visitor.visit_function_mut(function);
+    }
+}
+
+pub fn walk_function_mut<V: VisitorMut + ?Sized>(visitor: &mut V, function: &mut FunctionType) {
+    for default_value in function.get_parameters_mut().iter_mut().filter_map(|p| p.get_default_value_mut()) {
+        visitor.visit_expression_mut(default_value);
+    }
+
+    for statement in function.get_statements_mut() {
+        visitor.visit_statement_mut(statement);
+    }
+}
+
+pub fn walk_statements_mut<V: VisitorMut + ?Sized>(visitor: &mut V, statements: &mut [Statement]) {
+    for statement in statements {
+        visitor.visit_statement_mut(statement);
+    }
+}
+
+pub fn walk_statement_mut<V: VisitorMut + ?Sized>(visitor: &mut V, statement: &mut Statement) {
+    match statement {
+        Statement::If(condition, statements, else_statements) => {
+            visitor.visit_expression_mut(condition);
+            walk_statements_mut(visitor, statements);
+            if let Some(else_statements) = else_statements {
+                walk_statements_mut(visitor, else_statements);
+            }
+        },
+        Statement::While(condition, statements) => {
+            visitor.visit_expression_mut(condition);
+            walk_statements_mut(visitor, statements);
+        },
+        Statement::ForEach(_, expr, statements) => {
+            visitor.visit_expression_mut(expr);
+            walk_statements_mut(visitor, statements);
+        },
+        Statement::For(declaration, condition, increment, statements) => {
+            visitor.visit_declaration_mut(declaration);
+            visitor.visit_expression_mut(condition);
+            visitor.visit_expression_mut(increment);
+            walk_statements_mut(visitor, statements);
+        },
+        Statement::Expression(expr) => visitor.visit_expression_mut(expr),
+        Statement::Return(expr) => {
+            if let Some(expr) = expr {
+                visitor.visit_expression_mut(expr);
+            }
+        },
+        Statement::Scope(statements) => walk_statements_mut(visitor, statements),
+        Statement::Variable(declaration) => visitor.visit_declaration_mut(declaration),
+        Statement::TupleDeconstruction(_, expr) => visitor.visit_expression_mut(expr),
+        Statement::Break(_) | Statement::Continue(_) => {}
+    }
+}
+
+pub fn walk_declaration_mut<V: VisitorMut + ?Sized>(visitor: &mut V, declaration: &mut DeclarationStatement) {
+    visitor.visit_expression_mut(&mut declaration.value);
+}
+
+pub fn walk_expression_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expression: &mut Expression) {
+    match expression {
+        Expression::FunctionCall(path, _, parameters) => {
+            if let Some(path) = path {
+                visitor.visit_expression_mut(path);
+            }
+            for param in parameters {
+                visitor.visit_expression_mut(param);
+            }
+        },
+        Expression::ArrayConstructor(values)
+        | Expression::StructConstructor(values, _)
+        | Expression::TupleConstructor(values) => {
+            for value in values {
+                visitor.visit_expression_mut(value);
+            }
+        },
+        Expression::ArrayCall(left, right)
+        | Expression::Operator(_, left, right)
+        | Expression::Path(left, right)
+        | Expression::OptionalPath(left, right)
+        | Expression::Range(left, right) => {
+            visitor.visit_expression_mut(left);
+            visitor.visit_expression_mut(right);
+        },
+        Expression::Ternary(condition, left, right) => {
+            visitor.visit_expression_mut(condition);
+            visitor.visit_expression_mut(left);
+            visitor.visit_expression_mut(right);
+        },
+        Expression::SubExpression(expr)
+        | Expression::IsNot(expr)
+        | Expression::BitwiseNot(expr)
+        | Expression::Cast(expr, _)
+        | Expression::Try(expr) => visitor.visit_expression_mut(expr),
+        Expression::StructUpdate(expr, fields) => {
+            visitor.visit_expression_mut(expr);
+            for (_, value) in fields {
+                visitor.visit_expression_mut(value);
+            }
+        },
+        Expression::Block(statements, value, _) => {
+            walk_statements_mut(visitor, statements);
+            visitor.visit_expression_mut(value);
+        },
+        Expression::ValueCall(function, parameters) => {
+            visitor.visit_expression_mut(function);
+            for param in parameters {
+                visitor.visit_expression_mut(param);
+            }
+        },
+        Expression::Variable(_) | Expression::Value(_) => {}
+    }
+}
+
+// Example visitor counting every function call in the visited nodes
+#[derive(Debug, Default)]
+pub struct FunctionCallCounter {
+    count: usize,
+}
+
+impl FunctionCallCounter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Count the function calls of a whole program
+    pub fn count(program: &Program) -> usize {
+        let mut counter = Self::new();
+        counter.visit_program(program);
+        counter.count
+    }
+
+    // Get the count of function calls visited
+    pub fn get_count(&self) -> usize {
+        self.count
+    }
+}
+
+impl Visitor for FunctionCallCounter {
+    fn visit_expression(&mut self, expression: &Expression) {
+        if let Expression::FunctionCall(..) | Expression::ValueCall(..) = expression {
+            self.count += 1;
+        }
+        walk_expression(self, expression);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use xelis_types::{Type, Value};
+    use crate::{EntryFunction, Operator};
+    use super::*;
+
+    fn call(path: Option<Expression>, id: u16, parameters: Vec<Expression>) -> Expression {
+        Expression::FunctionCall(path.map(Box::new), id, parameters)
+    }
+
+    // entry main() { let a: u64 = f(g(1)); if a.h() > 0 { return f(a) } return 0 }
+    fn build_program() -> Program {
+        let statements = vec![
+            Statement::Variable(DeclarationStatement {
+                id: 0,
+                value_type: Type::U64,
+                value: call(None, 0, vec![call(None, 1, vec![Expression::Value(Value::U64(1))])]),
+                mutable: false,
+            }),
+            Statement::If(
+                Expression::Operator(
+                    Operator::GreaterThan,
+                    Box::new(call(Some(Expression::Variable(0)), 2, Vec::new())),
+                    Box::new(Expression::Value(Value::U64(0)))
+                ),
+                vec![Statement::Return(Some(call(None, 0, vec![Expression::Variable(0)])))],
+                None
+            ),
+            Statement::Return(Some(Expression::Value(Value::U64(0)))),
+        ];
+
+        let mut program = Program::new();
+        program.add_function(FunctionType::Entry(EntryFunction::new(Vec::new(), statements, 1)));
+        program
+    }
+
+    #[test]
+    fn test_function_call_counter() {
+        let program = build_program();
+        assert_eq!(FunctionCallCounter::count(&program), 4);
+    }
+
+    // Replace every u64 constant by its double
+    struct DoubleConstants;
+
+    impl VisitorMut for DoubleConstants {
+        fn visit_expression_mut(&mut self, expression: &mut Expression) {
+            if let Expression::Value(Value::U64(v)) = expression {
+                *v *= 2;
+            }
+            walk_expression_mut(self, expression);
+        }
+    }
+
+    #[test]
+    fn test_visitor_mut() {
+        let mut program = build_program();
+        DoubleConstants.visit_program_mut(&mut program);
+
+        let statements = program.functions()[0].get_statements();
+        assert_eq!(statements[2], Statement::Return(Some(Expression::Value(Value::U64(0)))));
+        match &statements[0] {
+            Statement::Variable(declaration) => assert_eq!(
+                declaration.value,
+                call(None, 0, vec![call(None, 1, vec![Expression::Value(Value::U64(2))])])
+            ),
+            _ => panic!("expected a variable declaration")
+        }
+    }
+}
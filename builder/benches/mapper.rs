@@ -0,0 +1,25 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use xelis_ast::{Expression, Signature};
+use xelis_builder::FunctionMapper;
+use xelis_types::{Type, Value};
+
+// Resolve a call that doesn't match exactly a signature
+// among 200 registered functions
+fn bench_get_compatible(c: &mut Criterion) {
+    let mut mapper = FunctionMapper::new();
+    for i in 0..100 {
+        let name = format!("f{}", i);
+        mapper.register(Signature::new(name.clone(), None, vec![Type::U64])).unwrap();
+        mapper.register(Signature::new(name, Some(Type::U64), vec![Type::U64, Type::String])).unwrap();
+    }
+
+    c.bench_function("get_compatible_200", |b| {
+        b.iter(|| {
+            let mut expressions = [Expression::Value(Value::U8(1))];
+            mapper.get_compatible(Signature::new("f99".to_owned(), None, vec![Type::U8]), &mut expressions).unwrap()
+        });
+    });
+}
+
+criterion_group!(benches, bench_get_compatible);
+criterion_main!(benches);
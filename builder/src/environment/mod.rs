@@ -1,10 +1,37 @@
 pub mod xstd;
 
-use std::{borrow::Cow, collections::HashMap};
+use std::{borrow::Cow, cell::RefCell, collections::HashMap, rc::Rc};
 use xelis_ast::Signature;
-use xelis_types::{Type, Value};
-use xelis_environment::{Environment, NativeFunction, OnCallFn};
-use crate::{StructManager, FunctionMapper};
+use xelis_types::{StructType, Type, Value, ValueError, ValueOwnable};
+use xelis_environment::{CostFn, Environment, NativeFunction, OnCallFn, OutputSink, RandomSource};
+use crate::{StructManager, FunctionMapper, BuilderError};
+
+// Check if a value can be stored in a field of the expected type
+// Null, empty arrays and empty optionals have no type and are accepted by their container type
+fn is_value_of_type(value: &Value, expected: &Type) -> bool {
+    match Type::from_value(value) {
+        Some(_type) => _type.is_compatible_with(expected),
+        None => match value {
+            Value::Null | Value::Optional(None) => expected.allow_null(),
+            Value::Array(_) => expected.is_array(),
+            _ => false
+        }
+    }
+}
+
+// Description of a native function to register
+// Used when the cost of a call also depends on its instance and parameters
+pub struct NativeFunctionDescriptor<'a> {
+    pub name: &'a str,
+    pub for_type: Option<Type>,
+    pub parameters: Vec<Type>,
+    pub on_call: OnCallFn,
+    // cost for each call
+    pub cost: u64,
+    // additional cost computed for each call
+    pub cost_fn: Option<CostFn>,
+    pub return_type: Option<Type>
+}
 
 // EnvironmentBuilder is used to create an environment
 // it is used to register all the native functions and structures
@@ -13,6 +40,8 @@ pub struct EnvironmentBuilder<'a> {
     functions_mapper: FunctionMapper<'a>,
     struct_manager: StructManager<'a>,
     constants: HashMap<Type, HashMap<&'a str, Value>>,
+    // Constants accessible by their name only
+    global_constants: HashMap<&'a str, Value>,
     env: Environment
 }
 
@@ -23,24 +52,85 @@ impl<'a> EnvironmentBuilder<'a> {
             functions_mapper: FunctionMapper::new(),
             struct_manager: StructManager::new(),
             constants: HashMap::new(),
+            global_constants: HashMap::new(),
             env: Environment::new(),
         }
     }
 
+    // Verify that a name isn't already used by a global constant
+    fn check_global_constant_name(&self, name: &str) -> Result<(), BuilderError> {
+        if self.global_constants.contains_key(name) {
+            return Err(BuilderError::NameAlreadyUsed(name.to_owned()))
+        }
+
+        Ok(())
+    }
+
     // Register a native function
-    // Panic if the function signature is already registered
+    // Panic if the function signature is already registered or its name is used by a global constant
     pub fn register_native_function(&mut self, name: &str, for_type: Option<Type>, parameters: Vec<Type>, on_call: OnCallFn, cost: u64, return_type: Option<Type>) {
+        self.check_global_constant_name(name).unwrap();
         let _ = self.functions_mapper.register(Signature::new(name.to_owned(), for_type.clone(), parameters.clone())).unwrap();
         self.env.add_function(NativeFunction::new(for_type, parameters, on_call, cost, return_type));
     }
 
+    // Register a native function from its description
+    // Panic if the function signature is already registered or its name is used by a global constant
+    pub fn register_native_function_with_descriptor(&mut self, descriptor: NativeFunctionDescriptor) {
+        self.check_global_constant_name(descriptor.name).unwrap();
+        let _ = self.functions_mapper.register(Signature::new(descriptor.name.to_owned(), descriptor.for_type.clone(), descriptor.parameters.clone())).unwrap();
+        let mut function = NativeFunction::new(descriptor.for_type, descriptor.parameters, descriptor.on_call, descriptor.cost, descriptor.return_type);
+        function.set_cost_fn(descriptor.cost_fn);
+        self.env.add_function(function);
+    }
+
+    // Set the sink receiving the text printed by the program
+    pub fn set_output_sink(&mut self, output: Rc<dyn OutputSink>) {
+        self.env.set_output_sink(output);
+    }
+
+    // Set the source used by the random functions
+    // Without it, calling them fails
+    pub fn set_random_source(&mut self, random: Option<Rc<RefCell<dyn RandomSource>>>) {
+        self.env.set_random_source(random);
+    }
+
     // Register a structure in the environment
-    // Panic if the structure name is already used
+    // Panic if the structure name is already used, also by a global constant
     pub fn register_structure(&mut self, name: &'a str, fields: Vec<(&'a str, Type)>) {
+        self.check_global_constant_name(name).unwrap();
         let _type = self.struct_manager.build_struct(Cow::Borrowed(name), fields).unwrap();
         self.env.add_structure(_type);
     }
 
+    // Define a structure from the host
+    // Returns its type and a constructor to create instances of it
+    // The constructor verifies that each value is compatible with its field type
+    // Panic if the structure name is already used, also by a global constant
+    pub fn define_struct(&mut self, name: &'a str, fields: &[(&'a str, Type)]) -> (StructType, impl Fn(Vec<Value>) -> Result<Value, BuilderError>) {
+        self.check_global_constant_name(name).unwrap();
+        let _type = self.struct_manager.build_struct(Cow::Borrowed(name), fields.to_vec()).unwrap();
+        self.env.add_structure(_type.clone());
+
+        let struct_type = _type.clone();
+        let constructor = move |values: Vec<Value>| {
+            let fields = struct_type.fields();
+            if values.len() != fields.len() {
+                return Err(BuilderError::InvalidStructFieldsCount(fields.len(), values.len()));
+            }
+
+            for (value, field_type) in values.iter().zip(fields) {
+                if !is_value_of_type(value, field_type) {
+                    return Err(ValueError::InvalidValue(value.clone(), field_type.clone()).into());
+                }
+            }
+
+            Ok(Value::Struct(values.into_iter().map(|v| ValueOwnable::Owned(Box::new(v))).collect(), struct_type.clone()))
+        };
+
+        (_type, constructor)
+    }
+
     // Register a constant in the environment
     // Panic if the constant name is already used
     pub fn register_constant(&mut self, _type: Type, name: &'a str, value: Value) {
@@ -53,6 +143,25 @@ impl<'a> EnvironmentBuilder<'a> {
         self.constants.get(_type).and_then(|v| v.get(name))
     }
 
+    // Register a constant the scripts can read by its name, like a variable
+    // The name can't be shared with a function, a struct or another constant
+    pub fn register_global_constant(&mut self, name: &'a str, value: Value) -> Result<(), BuilderError> {
+        if self.global_constants.contains_key(name)
+            || self.functions_mapper.has_name(name)
+            || self.struct_manager.get_by_name(name).is_ok()
+        {
+            return Err(BuilderError::NameAlreadyUsed(name.to_owned()))
+        }
+
+        self.global_constants.insert(name, value);
+        Ok(())
+    }
+
+    // Get a constant registered by register_global_constant
+    pub fn get_global_constant(&self, name: &str) -> Option<&Value> {
+        self.global_constants.get(name)
+    }
+
     // functions mapper, used to find the function id
     pub fn get_functions_mapper(&self) -> &FunctionMapper {
         &self.functions_mapper
@@ -1,6 +1,58 @@
-use xelis_types::{Type, Value, ValueOwnable};
-use xelis_environment::{EnvironmentError, FnInstance, FnParams, FnReturnType};
-use super::EnvironmentBuilder;
+use xelis_types::{Path, Type, Value, ValueOwnable, U256 as u256};
+use xelis_environment::{Context, EnvironmentError, FnInstance, FnParams, FnReturnType};
+use paste::paste;
+use crate::{EnvironmentBuilder, NativeFunctionDescriptor};
+
+// Reductions over an array of numbers
+// All of them return none if the array is empty, sum also returns none on overflow
+macro_rules! register_reductions {
+    ($env: expr, $t: ident, $f: ident) => {
+        paste! {
+            fn [<sum_ $f>](zelf: FnInstance, _: FnParams, _: &mut Context) -> FnReturnType {
+                let vec = zelf?.as_vec()?;
+                let mut sum: Option<$f> = None;
+                for value in vec {
+                    let value = value.handle().[<as_ $f>]()?;
+                    sum = match sum {
+                        Some(sum) => match sum.checked_add(value) {
+                            Some(sum) => Some(sum),
+                            None => return Ok(Some(Value::Optional(None)))
+                        },
+                        None => Some(value)
+                    };
+                }
+
+                Ok(Some(Value::Optional(sum.map(|v| ValueOwnable::Owned(Box::new(Value::$t(v)))))))
+            }
+
+            fn [<min_ $f>](zelf: FnInstance, _: FnParams, _: &mut Context) -> FnReturnType {
+                let vec = zelf?.as_vec()?;
+                let mut min: Option<$f> = None;
+                for value in vec {
+                    let value = value.handle().[<as_ $f>]()?;
+                    min = Some(min.map_or(value, |min| min.min(value)));
+                }
+
+                Ok(Some(Value::Optional(min.map(|v| ValueOwnable::Owned(Box::new(Value::$t(v)))))))
+            }
+
+            fn [<max_ $f>](zelf: FnInstance, _: FnParams, _: &mut Context) -> FnReturnType {
+                let vec = zelf?.as_vec()?;
+                let mut max: Option<$f> = None;
+                for value in vec {
+                    let value = value.handle().[<as_ $f>]()?;
+                    max = Some(max.map_or(value, |max| max.max(value)));
+                }
+
+                Ok(Some(Value::Optional(max.map(|v| ValueOwnable::Owned(Box::new(Value::$t(v)))))))
+            }
+
+            $env.register_native_function("sum", Some(Type::Array(Box::new(Type::$t))), vec![], [<sum_ $f>], 5, Some(Type::Optional(Box::new(Type::$t))));
+            $env.register_native_function("min", Some(Type::Array(Box::new(Type::$t))), vec![], [<min_ $f>], 5, Some(Type::Optional(Box::new(Type::$t))));
+            $env.register_native_function("max", Some(Type::Array(Box::new(Type::$t))), vec![], [<max_ $f>], 5, Some(Type::Optional(Box::new(Type::$t))));
+        }
+    };
+}
 
 pub fn register(env: &mut EnvironmentBuilder) {
     env.register_native_function("len", Some(Type::Array(Box::new(Type::T))), vec![], len, 1, Some(Type::U32));
@@ -8,25 +60,61 @@ pub fn register(env: &mut EnvironmentBuilder) {
     env.register_native_function("remove", Some(Type::Array(Box::new(Type::T))), vec![Type::U32], remove, 1, Some(Type::T));
     env.register_native_function("pop", Some(Type::Array(Box::new(Type::T))), vec![], pop, 1, Some(Type::Optional(Box::new(Type::T))));
     env.register_native_function("slice", Some(Type::Array(Box::new(Type::T))), vec![Type::U32, Type::U32], slice, 3, Some(Type::Array(Box::new(Type::T))));
-    env.register_native_function("contains", Some(Type::Array(Box::new(Type::T))), vec![Type::T], contains, 1, Some(Type::Bool));
+    // Searching and deduplicating compare the elements one by one
+    env.register_native_function_with_descriptor(NativeFunctionDescriptor {
+        name: "contains",
+        for_type: Some(Type::Array(Box::new(Type::T))),
+        parameters: vec![Type::T],
+        on_call: contains,
+        cost: 1,
+        cost_fn: Some(per_element_cost),
+        return_type: Some(Type::Bool)
+    });
+    env.register_native_function_with_descriptor(NativeFunctionDescriptor {
+        name: "index_of",
+        for_type: Some(Type::Array(Box::new(Type::T))),
+        parameters: vec![Type::T],
+        on_call: index_of,
+        cost: 1,
+        cost_fn: Some(per_element_cost),
+        return_type: Some(Type::Optional(Box::new(Type::U64)))
+    });
+    env.register_native_function_with_descriptor(NativeFunctionDescriptor {
+        name: "dedup",
+        for_type: Some(Type::Array(Box::new(Type::T))),
+        parameters: vec![],
+        on_call: dedup,
+        cost: 3,
+        cost_fn: Some(per_element_cost),
+        return_type: None
+    });
     env.register_native_function("get", Some(Type::Array(Box::new(Type::T))), vec![Type::U32], get, 1, Some(Type::Optional(Box::new(Type::T))));
+    env.register_native_function("get_or", Some(Type::Array(Box::new(Type::T))), vec![Type::U32, Type::T], get_or, 1, Some(Type::T));
     env.register_native_function("first", Some(Type::Array(Box::new(Type::T))), vec![], first, 1, Some(Type::Optional(Box::new(Type::T))));
     env.register_native_function("last", Some(Type::Array(Box::new(Type::T))), vec![], last, 1, Some(Type::Optional(Box::new(Type::T))));
+
+    register_reductions!(env, U8, u8);
+    register_reductions!(env, U16, u16);
+    register_reductions!(env, U32, u32);
+    register_reductions!(env, U64, u64);
+    register_reductions!(env, U128, u128);
+    register_reductions!(env, U256, u256);
 }
 
 // native functions
-fn len(zelf: FnInstance, _: FnParams) -> FnReturnType {
+fn len(zelf: FnInstance, _: FnParams, _: &mut Context) -> FnReturnType {
     let len = zelf?.as_vec()?.len();
     Ok(Some(Value::U32(len as u32)))
 }
 
-fn push(zelf: FnInstance, mut parameters: FnParams) -> FnReturnType {
+fn push(zelf: FnInstance, mut parameters: FnParams, context: &mut Context) -> FnReturnType {
+    context.allocate(1)?;
     let param = parameters.remove(0);
     zelf?.as_mut_vec()?.push(ValueOwnable::Owned(Box::new(param.into_owned())));
     Ok(None)
 }
 
-fn remove(zelf: FnInstance, mut parameters: FnParams) -> FnReturnType {
+fn remove(zelf: FnInstance, mut parameters: FnParams, _: &mut Context) -> FnReturnType {
     let index = parameters.remove(0).as_u32()? as usize;
 
     let array = zelf?.as_mut_vec()?;
@@ -37,7 +125,7 @@ fn remove(zelf: FnInstance, mut parameters: FnParams) -> FnReturnType {
     Ok(Some(array.remove(index).into_inner()))
 }
 
-fn pop(zelf: FnInstance, _: FnParams) -> FnReturnType {
+fn pop(zelf: FnInstance, _: FnParams, _: &mut Context) -> FnReturnType {
     let array = zelf?.as_mut_vec()?;
     if let Some(value) = array.pop() {
         Ok(Some(value.into_inner()))
@@ -46,7 +134,7 @@ fn pop(zelf: FnInstance, _: FnParams) -> FnReturnType {
     }
 }
 
-fn slice(zelf: FnInstance, mut parameters: FnParams) -> FnReturnType {
+fn slice(zelf: FnInstance, mut parameters: FnParams, _: &mut Context) -> FnReturnType {
     let start = parameters.remove(0).as_u32()?;
     let end = parameters.remove(0).as_u32()?;
 
@@ -69,15 +157,54 @@ fn slice(zelf: FnInstance, mut parameters: FnParams) -> FnReturnType {
     Ok(Some(Value::Array(slice)))
 }
 
-fn contains(zelf: FnInstance, mut parameters: FnParams) -> FnReturnType {
+// Index of the first value equal to the expected one
+// Values are compared like the == operator, so structs and nested arrays are compared by content
+fn position(vec: &[ValueOwnable], expected: &Value) -> Result<Option<usize>, EnvironmentError> {
+    for (i, value) in vec.iter().enumerate() {
+        if value.handle().is_equal(expected)? {
+            return Ok(Some(i))
+        }
+    }
+
+    Ok(None)
+}
+
+// Cost of 1 for each element of the array instance
+fn per_element_cost(instance: Option<&Path>, _: &[Path]) -> u64 {
+    instance.and_then(|v| v.as_ref().as_vec().ok().map(|values| values.len() as u64)).unwrap_or(0)
+}
+
+fn contains(zelf: FnInstance, mut parameters: FnParams, _: &mut Context) -> FnReturnType {
     let value = parameters.remove(0);
     let handle = value.as_ref();
-    let expected = handle.as_value();
     let vec = zelf?.as_vec()?;
-    Ok(Some(Value::Boolean(vec.iter().find(|v| *v.handle() == *expected).is_some())))
+    Ok(Some(Value::Boolean(position(vec, handle.as_value())?.is_some())))
 }
 
-fn get(zelf: FnInstance, mut parameters: FnParams) -> FnReturnType {
+fn index_of(zelf: FnInstance, mut parameters: FnParams, _: &mut Context) -> FnReturnType {
+    let value = parameters.remove(0);
+    let handle = value.as_ref();
+    let vec = zelf?.as_vec()?;
+    let index = position(vec, handle.as_value())?;
+    Ok(Some(Value::Optional(index.map(|i| ValueOwnable::Owned(Box::new(Value::U64(i as u64)))))))
+}
+
+// Remove the consecutive duplicated values, in place
+// Like Vec::dedup, only a run of equal values is reduced to one value:
+// the array must be sorted first to remove all the duplicates
+fn dedup(zelf: FnInstance, _: FnParams, _: &mut Context) -> FnReturnType {
+    let vec = zelf?.as_mut_vec()?;
+    let mut keep = Vec::with_capacity(vec.len());
+    for (i, value) in vec.iter().enumerate() {
+        keep.push(i == 0 || !value.handle().is_equal(&vec[i - 1].handle())?);
+    }
+
+    let mut keep = keep.into_iter();
+    vec.retain(|_| keep.next().unwrap_or(true));
+    Ok(None)
+}
+
+fn get(zelf: FnInstance, mut parameters: FnParams, _: &mut Context) -> FnReturnType {
     let index = parameters.remove(0).as_u32()? as usize;
     let vec = zelf?.as_vec()?;
     if let Some(value) = vec.get(index) {
@@ -87,7 +214,18 @@ fn get(zelf: FnInstance, mut parameters: FnParams) -> FnReturnType {
     }
 }
 
-fn first(zelf: FnInstance, _: FnParams) -> FnReturnType {
+// Same as get, but the default value is returned if the index is out of bounds
+fn get_or(zelf: FnInstance, mut parameters: FnParams, _: &mut Context) -> FnReturnType {
+    let default = parameters.remove(1);
+    let index = parameters.remove(0).as_u32()? as usize;
+    let vec = zelf?.as_vec()?;
+    match vec.get(index) {
+        Some(value) => Ok(Some(value.handle().clone())),
+        None => Ok(Some(default.into_owned()))
+    }
+}
+
+fn first(zelf: FnInstance, _: FnParams, _: &mut Context) -> FnReturnType {
     let vec = zelf?.as_vec()?;
     if let Some(value) = vec.first() {
         Ok(Some(Value::Optional(Some(value.clone()))))
@@ -96,7 +234,7 @@ fn first(zelf: FnInstance, _: FnParams) -> FnReturnType {
     }
 }
 
-fn last(zelf: FnInstance, _: FnParams) -> FnReturnType {
+fn last(zelf: FnInstance, _: FnParams, _: &mut Context) -> FnReturnType {
     let vec = zelf?.as_vec()?;
     if let Some(value) = vec.last() {
         Ok(Some(Value::Optional(Some(value.clone()))))
@@ -0,0 +1,37 @@
+use sha2::{Digest, Sha256};
+use xelis_environment::{Context, FnInstance, FnParams, FnReturnType};
+use xelis_types::{Path, Type, Value, ValueOwnable};
+
+use crate::{EnvironmentBuilder, NativeFunctionDescriptor};
+
+pub fn register(env: &mut EnvironmentBuilder) {
+    env.register_native_function_with_descriptor(NativeFunctionDescriptor {
+        name: "sha256",
+        for_type: None,
+        parameters: vec![Type::Array(Box::new(Type::U8))],
+        on_call: sha256,
+        cost: 75,
+        cost_fn: Some(per_byte_cost),
+        return_type: Some(Type::Array(Box::new(Type::U8)))
+    });
+}
+
+// Cost of 1 for each byte to hash
+fn per_byte_cost(_: Option<&Path>, parameters: &[Path]) -> u64 {
+    parameters[0].as_ref().as_vec().map(|bytes| bytes.len() as u64).unwrap_or(0)
+}
+
+// Hash the bytes using SHA-256, the result is always 32 bytes
+fn sha256(_: FnInstance, parameters: FnParams, _: &mut Context) -> FnReturnType {
+    let mut hasher = Sha256::new();
+    for value in parameters[0].as_ref().as_vec()? {
+        hasher.update([value.handle().as_u8()?]);
+    }
+
+    let hash = hasher.finalize()
+        .iter()
+        .map(|b| ValueOwnable::Owned(Box::new(Value::U8(*b))))
+        .collect();
+
+    Ok(Some(Value::Array(hash)))
+}
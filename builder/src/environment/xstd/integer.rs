@@ -1,4 +1,6 @@
 use xelis_environment::{
+    Context,
+    EnvironmentError,
     FnInstance,
     FnParams,
     FnReturnType
@@ -11,7 +13,7 @@ use crate::EnvironmentBuilder;
 macro_rules! overflow_fn {
     ($env: expr, $op: ident, $t: ident, $f: ident) => {
         paste! {
-            fn [<overflowing_ $op _ $f>](zelf: FnInstance, mut parameters: FnParams) -> FnReturnType {
+            fn [<overflowing_ $op _ $f>](zelf: FnInstance, mut parameters: FnParams, _: &mut Context) -> FnReturnType {
                 // Extract and convert parameters
                 let other = parameters.remove(0).into_owned().[<as_ $f>]()?;
                 let value = zelf?.[<as_ $f>]()?;
@@ -55,10 +57,110 @@ macro_rules! register_overflows {
     };
 }
 
+// Subtraction helpers that can't fail on underflow
+macro_rules! register_sub {
+    ($env: expr, $t: ident, $f: ident) => {
+        paste! {
+            // Returns none if the other value is greater
+            fn [<checked_sub_ $f>](zelf: FnInstance, mut parameters: FnParams, _: &mut Context) -> FnReturnType {
+                let other = parameters.remove(0).into_owned().[<as_ $f>]()?;
+                let value = zelf?.[<as_ $f>]()?;
+
+                Ok(Some(Value::Optional(value.checked_sub(other)
+                    .map(|v| ValueOwnable::Owned(Box::new(Value::$t(v))))
+                )))
+            }
+
+            fn [<abs_diff_ $f>](zelf: FnInstance, mut parameters: FnParams, _: &mut Context) -> FnReturnType {
+                let other = parameters.remove(0).into_owned().[<as_ $f>]()?;
+                let value = zelf?.[<as_ $f>]()?;
+
+                Ok(Some(Value::$t(value.abs_diff(other))))
+            }
+
+            $env.register_native_function(
+                "checked_sub",
+                Some(Type::$t),
+                vec![Type::$t],
+                [<checked_sub_ $f>],
+                2,
+                Some(Type::Optional(Box::new(Type::$t)))
+            );
+
+            $env.register_native_function(
+                "abs_diff",
+                Some(Type::$t),
+                vec![Type::$t],
+                [<abs_diff_ $f>],
+                2,
+                Some(Type::$t)
+            );
+        }
+    };
+}
+
+// Division helpers rounding the quotient up or to the nearest integer
+// The result is computed from the remainder, so a + b - 1 is never computed and can't overflow
+macro_rules! register_div {
+    ($env: expr, $t: ident, $f: ident) => {
+        paste! {
+            // Returns the quotient and the remainder, the divisor can't be zero
+            fn [<div_rem_ $f>](mut parameters: FnParams) -> Result<($f, $f, $f), EnvironmentError> {
+                let divisor = parameters.remove(1).into_owned().[<as_ $f>]()?;
+                let value = parameters.remove(0).into_owned().[<as_ $f>]()?;
+                let quotient = value.checked_div(divisor).ok_or(EnvironmentError::DivByZero)?;
+
+                Ok((quotient, value % divisor, divisor))
+            }
+
+            fn [<div_ceil_ $f>](_: FnInstance, parameters: FnParams, _: &mut Context) -> FnReturnType {
+                let (quotient, remainder, _) = [<div_rem_ $f>](parameters)?;
+                let ceil = if remainder != $f::from(0u8) {
+                    quotient + $f::from(1u8)
+                } else {
+                    quotient
+                };
+
+                Ok(Some(Value::$t(ceil)))
+            }
+
+            // Half is rounded up
+            fn [<div_round_ $f>](_: FnInstance, parameters: FnParams, _: &mut Context) -> FnReturnType {
+                let (quotient, remainder, divisor) = [<div_rem_ $f>](parameters)?;
+                let rounded = if remainder != $f::from(0u8) && remainder >= divisor - remainder {
+                    quotient + $f::from(1u8)
+                } else {
+                    quotient
+                };
+
+                Ok(Some(Value::$t(rounded)))
+            }
+
+            $env.register_native_function(
+                "div_ceil",
+                None,
+                vec![Type::$t, Type::$t],
+                [<div_ceil_ $f>],
+                2,
+                Some(Type::$t)
+            );
+
+            $env.register_native_function(
+                "div_round",
+                None,
+                vec![Type::$t, Type::$t],
+                [<div_round_ $f>],
+                2,
+                Some(Type::$t)
+            );
+        }
+    };
+}
+
 macro_rules! to_endian_bytes {
     ($env: expr, $t: ident, $f: ident, $endian: ident) => {
         paste! {
-            fn [<to_ $endian _bytes_ $f>](zelf: FnInstance, _: FnParams) -> FnReturnType {
+            fn [<to_ $endian _bytes_ $f>](zelf: FnInstance, _: FnParams, _: &mut Context) -> FnReturnType {
                 let value = zelf?.[<as_ $f>]()?;
                 let bytes = value.[<to_ $endian _bytes>]();
                 let vec = bytes.iter().map(|b| ValueOwnable::Owned(Box::new(Value::U8(*b)))).collect();
@@ -84,6 +186,37 @@ macro_rules! register_to_endian_bytes {
     };
 }
 
+macro_rules! bits_fn {
+    ($env: expr, $op: ident, $t: ident, $f: ident) => {
+        paste! {
+            fn [<$op _ $f>](zelf: FnInstance, _: FnParams, _: &mut Context) -> FnReturnType {
+                let value = zelf?.[<as_ $f>]()?;
+                Ok(Some(Value::U32(value.$op())))
+            }
+
+            $env.register_native_function(
+                stringify!($op),
+                Some(Type::$t),
+                vec![],
+                [<$op _ $f>],
+                1,
+                Some(Type::U32)
+            );
+        }
+    };
+}
+
+// macro to register the bit counting functions for a specific type
+macro_rules! register_bits {
+    ($env: expr, $t: ident, $f: ident) => {
+        {
+            bits_fn!($env, count_ones, $t, $f);
+            bits_fn!($env, leading_zeros, $t, $f);
+            bits_fn!($env, trailing_zeros, $t, $f);
+        }
+    };
+}
+
 macro_rules! register_constants_min_max {
     ($env: expr, $t: ident, $f: ident) => {
         let min = $f::MIN;
@@ -106,6 +239,22 @@ pub fn register(env: &mut EnvironmentBuilder) {
     register_overflows!(env, U128, u128);
     register_overflows!(env, U256, u256);
 
+    // Register checked_sub and abs_diff for all types
+    register_sub!(env, U8, u8);
+    register_sub!(env, U16, u16);
+    register_sub!(env, U32, u32);
+    register_sub!(env, U64, u64);
+    register_sub!(env, U128, u128);
+    register_sub!(env, U256, u256);
+
+    // Register div_ceil and div_round for all types
+    register_div!(env, U8, u8);
+    register_div!(env, U16, u16);
+    register_div!(env, U32, u32);
+    register_div!(env, U64, u64);
+    register_div!(env, U128, u128);
+    register_div!(env, U256, u256);
+
     // Register min/max functions for all types
     register_constants_min_max!(env, U8, u8);
     register_constants_min_max!(env, U16, u16);
@@ -120,4 +269,12 @@ pub fn register(env: &mut EnvironmentBuilder) {
     register_to_endian_bytes!(env, U64, u64);
     register_to_endian_bytes!(env, U128, u128);
     register_to_endian_bytes!(env, U256, u256);
+
+    // Register the bit counting functions for all types
+    register_bits!(env, U8, u8);
+    register_bits!(env, U16, u16);
+    register_bits!(env, U32, u32);
+    register_bits!(env, U64, u64);
+    register_bits!(env, U128, u128);
+    register_bits!(env, U256, u256);
 }
\ No newline at end of file
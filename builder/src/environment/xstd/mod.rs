@@ -1,34 +1,192 @@
 mod array;
 mod optional;
+mod result;
 mod string;
 mod integer;
 mod range;
+#[cfg(feature = "hash")]
+mod hash;
 
-use xelis_types::Type;
-use xelis_environment::{EnvironmentError, FnInstance, FnParams, FnReturnType};
+use std::cmp::Ordering;
+use xelis_types::{Type, Value, ValueError, ValueOwnable};
+use xelis_environment::{Context, EnvironmentError, FnInstance, FnParams, FnReturnType};
 use super::EnvironmentBuilder;
 
+// Results of compare, ordered like the values: the result minus one is -1, 0 or 1
+pub const ORDERING_LESS: u8 = 0;
+pub const ORDERING_EQUAL: u8 = 1;
+pub const ORDERING_GREATER: u8 = 2;
+
 pub fn register(env: &mut EnvironmentBuilder) {
     array::register(env);
     optional::register(env);
+    result::register(env);
     string::register(env);
     integer::register(env);
     range::register(env);
+    #[cfg(feature = "hash")]
+    hash::register(env);
 
+    env.register_native_function("print", None, vec![Type::Any], print, 1, None);
     env.register_native_function("println", None, vec![Type::Any], println, 1, None);
-    env.register_native_function("panic", None, vec![Type::Any], panic, 1, Some(Type::Any));
+    env.register_native_function("panic", None, vec![Type::Any], panic, 1, Some(Type::Never));
+    env.register_native_function("assert", None, vec![Type::Bool], assert, 1, None);
+    env.register_native_function("assert", None, vec![Type::Bool, Type::String], assert_with_message, 1, None);
+    env.register_native_function("emit", None, vec![Type::String, Type::Any], emit, 5, None);
+    env.register_native_function("compare", None, vec![Type::T, Type::T], compare, 1, Some(Type::U8));
+    env.register_global_constant("ORDERING_LESS", Value::U8(ORDERING_LESS)).unwrap();
+    env.register_global_constant("ORDERING_EQUAL", Value::U8(ORDERING_EQUAL)).unwrap();
+    env.register_global_constant("ORDERING_GREATER", Value::U8(ORDERING_GREATER)).unwrap();
+    env.register_native_function("field_count", None, vec![Type::AnyStruct], field_count, 1, Some(Type::U32));
+    env.register_native_function("field_names", None, vec![Type::AnyStruct], field_names, 5, Some(Type::Array(Box::new(Type::String))));
+    env.register_native_function("yield", None, vec![Type::T], yield_value, 1, Some(Type::T));
+    env.register_native_function("random", None, vec![], random, 5, Some(Type::U64));
+    env.register_native_function("random_range", None, vec![Type::U64, Type::U64], random_range, 5, Some(Type::U64));
+
+    for _type in [Type::U8, Type::U16, Type::U32, Type::U64, Type::U128, Type::U256] {
+        env.register_native_function("clamp", None, vec![_type.clone(); 3], clamp, 1, Some(_type));
+    }
 }
 
-fn println(_: FnInstance, parameters: FnParams) -> FnReturnType {
+// Write the value to the output sink of the environment
+fn print(_: FnInstance, parameters: FnParams, context: &mut Context) -> FnReturnType {
     let param = &parameters[0];
-    println!("{}", param.as_ref().as_value());
+    context.write_output(&param.as_ref().as_value().to_string());
 
     Ok(None)
 }
 
-fn panic(_: FnInstance, mut parameters: FnParams) -> FnReturnType {
+fn println(_: FnInstance, parameters: FnParams, context: &mut Context) -> FnReturnType {
+    let param = &parameters[0];
+    context.write_output(&format!("{}\n", param.as_ref().as_value()));
+
+    Ok(None)
+}
+
+fn panic(_: FnInstance, mut parameters: FnParams, _: &mut Context) -> FnReturnType {
     let param = parameters.remove(0);
     let value = param.into_owned();
 
-    Err(EnvironmentError::Panic(value))
-}
\ No newline at end of file
+    Err(EnvironmentError::Aborted(format!("{}", value)))
+}
+
+fn assert(_: FnInstance, parameters: FnParams, _: &mut Context) -> FnReturnType {
+    if !parameters[0].as_bool()? {
+        return Err(EnvironmentError::Aborted("assertion failed".to_owned()))
+    }
+
+    Ok(None)
+}
+
+fn assert_with_message(_: FnInstance, mut parameters: FnParams, _: &mut Context) -> FnReturnType {
+    if !parameters[0].as_bool()? {
+        let message = parameters.remove(1).into_owned().to_string()?;
+        return Err(EnvironmentError::Aborted(message))
+    }
+
+    Ok(None)
+}
+
+// Emit an event to the host, it is stored in the execution context
+fn emit(_: FnInstance, mut parameters: FnParams, context: &mut Context) -> FnReturnType {
+    let data = parameters.remove(1).into_owned();
+    let name = parameters.remove(0).into_owned().to_string()?;
+    context.emit_event(name, data);
+
+    Ok(None)
+}
+
+// Compare two primitive values of the same type
+// Returns ORDERING_LESS if the first one is lower, ORDERING_EQUAL if both are equal
+// and ORDERING_GREATER if it is greater
+// There is no signed type, so -1, 0 and 1 are shifted by one: the results keep the same order,
+// compare(a, b) < ORDERING_EQUAL means a < b
+fn compare(_: FnInstance, parameters: FnParams, _: &mut Context) -> FnReturnType {
+    let ordering = parameters[0].as_ref()
+        .as_value()
+        .compare(parameters[1].as_ref().as_value())?;
+
+    Ok(Some(Value::U8(match ordering {
+        Ordering::Less => ORDERING_LESS,
+        Ordering::Equal => ORDERING_EQUAL,
+        Ordering::Greater => ORDERING_GREATER
+    })))
+}
+
+// Restrict a number to the min..=max bounds
+// All the values have the same number type, enforced by the function signature
+fn clamp(_: FnInstance, mut parameters: FnParams, _: &mut Context) -> FnReturnType {
+    let max = parameters.remove(2).into_owned();
+    let min = parameters.remove(1).into_owned();
+    if min.compare(&max)? == Ordering::Greater {
+        return Err(EnvironmentError::InvalidClampBounds(min, max))
+    }
+
+    let value = parameters.remove(0).into_owned();
+    Ok(Some(if value.compare(&min)? == Ordering::Less {
+        min
+    } else if value.compare(&max)? == Ordering::Greater {
+        max
+    } else {
+        value
+    }))
+}
+
+// Count the fields of a struct value
+fn field_count(_: FnInstance, parameters: FnParams, _: &mut Context) -> FnReturnType {
+    let fields = parameters[0].as_ref().as_map()?.len();
+    Ok(Some(Value::U32(fields as u32)))
+}
+
+// Names of the fields of a struct value, in declaration order
+// They are carried by its type, a type without names is an error
+fn field_names(_: FnInstance, parameters: FnParams, context: &mut Context) -> FnReturnType {
+    let handle = parameters[0].as_ref();
+    let Value::Struct(fields, _type) = handle.as_value() else {
+        return Err(ValueError::InvalidStructValue(handle.as_value().clone()).into())
+    };
+
+    let names = _type.fields_names();
+    if names.len() != fields.len() {
+        return Err(EnvironmentError::UnknownFieldsNames)
+    }
+
+    context.allocate(names.len() as u64)?;
+    Ok(Some(Value::Array(names.iter().map(|name| ValueOwnable::Owned(Box::new(Value::String(name.clone())))).collect())))
+}
+
+// Suspending is handled by the interpreter running a resumable execution,
+// reaching this function means the yield can't suspend the program
+fn yield_value(_: FnInstance, _: FnParams, _: &mut Context) -> FnReturnType {
+    Err(EnvironmentError::InvalidYield)
+}
+
+// Maximum count of values drawn from the source to get a uniform one
+// A fair source needs more than one draw with a probability of at most 1/2
+const MAX_RANDOM_DRAWS: usize = 64;
+
+fn random(_: FnInstance, _: FnParams, context: &mut Context) -> FnReturnType {
+    Ok(Some(Value::U64(context.next_random()?)))
+}
+
+// Random number in start..end, the end is excluded
+// Values from the source in the incomplete last block are rejected to keep the distribution uniform
+fn random_range(_: FnInstance, parameters: FnParams, context: &mut Context) -> FnReturnType {
+    let start = parameters[0].as_u64()?;
+    let end = parameters[1].as_u64()?;
+    if start >= end {
+        return Err(EnvironmentError::EmptyRandomRange(start, end))
+    }
+
+    let span = end - start;
+    // 2^64 % span, the count of values to reject
+    let threshold = span.wrapping_neg() % span;
+    for _ in 0..MAX_RANDOM_DRAWS {
+        let value = context.next_random()?;
+        if value >= threshold {
+            return Ok(Some(Value::U64(start + value % span)))
+        }
+    }
+
+    Err(EnvironmentError::RandomSourceExhausted)
+}
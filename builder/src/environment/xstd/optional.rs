@@ -1,5 +1,6 @@
-use xelis_types::{Type, Value};
-use xelis_environment::{FnInstance, FnParams, FnReturnType};
+use std::mem;
+use xelis_types::{Type, Value, ValueError, ValueOwnable};
+use xelis_environment::{Context, FnInstance, FnParams, FnReturnType};
 use super::EnvironmentBuilder;
 
 pub fn register(env: &mut EnvironmentBuilder) {
@@ -9,24 +10,33 @@ pub fn register(env: &mut EnvironmentBuilder) {
     env.register_native_function("unwrap_or", Some(Type::Optional(Box::new(Type::T))), vec![Type::T], unwrap_or, 1, Some(Type::T));
 }
 
-fn is_none(zelf: FnInstance, _: FnParams) -> FnReturnType {
-    Ok(Some(Value::Boolean(zelf?.as_optional(&Type::T)?.is_none())))
+// A value of type T is accepted where an optional<T> is expected without being wrapped,
+// so any value other than null or an empty optional is a present value
+fn take_value(zelf: &mut Value) -> Option<Value> {
+    match mem::replace(zelf, Value::Null) {
+        Value::Null => None,
+        Value::Optional(opt) => opt.map(ValueOwnable::into_inner),
+        value => Some(value)
+    }
+}
+
+fn is_none(zelf: FnInstance, _: FnParams, _: &mut Context) -> FnReturnType {
+    Ok(Some(Value::Boolean(matches!(zelf?, Value::Null | Value::Optional(None)))))
 }
 
-fn is_some(zelf: FnInstance, _: FnParams) -> FnReturnType {
-    Ok(Some(Value::Boolean(zelf?.as_optional(&Type::T)?.is_some())))
+fn is_some(zelf: FnInstance, _: FnParams, _: &mut Context) -> FnReturnType {
+    Ok(Some(Value::Boolean(!matches!(zelf?, Value::Null | Value::Optional(None)))))
 }
 
-fn unwrap(zelf: FnInstance, _: FnParams) -> FnReturnType {
-    let opt = zelf?.take_from_optional(&Type::T)?;
-    Ok(Some(opt.into_inner()))
+fn unwrap(zelf: FnInstance, _: FnParams, _: &mut Context) -> FnReturnType {
+    let value = take_value(zelf?).ok_or(ValueError::OptionalIsNull)?;
+    Ok(Some(value))
 }
 
-fn unwrap_or(zelf: FnInstance, mut parameters: FnParams) -> FnReturnType {
+fn unwrap_or(zelf: FnInstance, mut parameters: FnParams, _: &mut Context) -> FnReturnType {
     let default = parameters.remove(0);
-    let optional = zelf?.take_optional()?;
-    match optional {
-        Some(value) => Ok(Some(value.into_inner())),
+    match take_value(zelf?) {
+        Some(value) => Ok(Some(value)),
         None => Ok(Some(default.into_owned()))
     }
-}
\ No newline at end of file
+}
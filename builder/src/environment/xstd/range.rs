@@ -1,28 +1,20 @@
-use xelis_environment::{EnvironmentError, FnInstance, FnParams, FnReturnType};
-use xelis_types::{Type, Value, ValueOwnable};
+use xelis_environment::{Context, EnvironmentError, FnInstance, FnParams, FnReturnType};
+use xelis_types::{Type, Value, ValueError, ValueOwnable, U256 as u256};
 use paste::paste;
 
 use crate::EnvironmentBuilder;
 
-macro_rules! contains {
-    ($t: ident, $start: expr, $end: expr, $value: expr) => {
-        paste! {
-            {
-                let start = $start.[<as_ $t>]()?;
-                let end = $end.[<as_ $t>]()?;
-                let value = $value.[<as_ $t>]()?;
-                Value::Boolean((start..end).contains(&value))
-            }
-        }
-    };
-}
-
+// The elements are charged against the allocation budget before being created
 macro_rules! collect {
-    ($t: ident, $start: expr, $end: expr, $type: ident) => {
+    ($t: ident, $start: expr, $end: expr, $type: ident, $context: expr) => {
         paste! {
             {
                 let start = $start.[<as_ $type>]()?;
                 let end = $end.[<as_ $type>]()?;
+                let len = u64::try_from(end.saturating_sub(start))
+                    .map_err(|_| EnvironmentError::RangeTooLarge)?;
+                $context.allocate(len)?;
+
                 let vec = (start..end).map(|i| ValueOwnable::Owned(Box::new(Value::$t(i)))).collect();
                 Value::Array(vec)
             }
@@ -49,47 +41,50 @@ pub fn register(env: &mut EnvironmentBuilder) {
     env.register_native_function("max", Some(Type::Range(Box::new(Type::T))), vec![], max, 1, Some(Type::T));
     env.register_native_function("min", Some(Type::Range(Box::new(Type::T))), vec![], min, 1, Some(Type::T));
     env.register_native_function("count", Some(Type::Range(Box::new(Type::T))), vec![], count, 5, Some(Type::T));
+    env.register_native_function("range_to_array", None, vec![Type::Range(Box::new(Type::T))], range_to_array, 500, Some(Type::Array(Box::new(Type::T))));
 }
 
-fn contains(zelf: FnInstance, mut parameters: FnParams) -> FnReturnType {
+fn contains(zelf: FnInstance, mut parameters: FnParams, _: &mut Context) -> FnReturnType {
     let value = parameters.remove(0);
     let zelf = zelf?;
     let (start, end, _type) = zelf.as_range()?;
 
     let value = value.as_ref();
-    Ok(Some(match _type {
-        Type::U8 => contains!(u8, start, end, value),
-        Type::U16 => contains!(u16, start, end, value),
-        Type::U32 => contains!(u32, start, end, value),
-        Type::U64 => contains!(u64, start, end, value),
-        Type::U128 => contains!(u128, start, end, value),
-        Type::U256 => contains!(u256, start, end, value),
+    let value = value.as_value();
+    Ok(Some(Value::Boolean(match _type {
+        Type::U256 => (start.as_u256()?..end.as_u256()?).contains(&value.as_u256()?),
+        // Any other number fits in a u128, the bounds have the type of the range
+        _type if _type.is_number() => match value.as_number()? {
+            (value, value_type) if value_type == *_type => (start.as_u128_lossless()?..end.as_u128_lossless()?).contains(&value),
+            _ => return Err(ValueError::InvalidValue(value.clone(), _type.clone()).into())
+        },
         _ => return Err(EnvironmentError::InvalidType(zelf.clone()))
-    }))
+    })))
 }
 
-fn collect(zelf: FnInstance, _: FnParams) -> FnReturnType {
+fn collect(zelf: FnInstance, _: FnParams, context: &mut Context) -> FnReturnType {
     let zelf = zelf?;
     let (start, end, _type) = zelf.as_range()?;
     Ok(Some(match _type {
-        Type::U8 => collect!(U8, start, end, u8),
-        Type::U16 => collect!(U16, start, end, u16),
-        Type::U32 => collect!(U32, start, end, u32),
-        Type::U64 => collect!(U64, start, end, u64),
-        Type::U128 => collect!(U128, start, end, u128),
+        Type::U8 => collect!(U8, start, end, u8, context),
+        Type::U16 => collect!(U16, start, end, u16, context),
+        Type::U32 => collect!(U32, start, end, u32, context),
+        Type::U64 => collect!(U64, start, end, u64, context),
+        Type::U128 => collect!(U128, start, end, u128, context),
         Type::U256 => {
             let start = start.as_u256()?;
             let end = end.as_u256()?;
             let mut vec = Vec::new();
             let (diff, overflow) = end.overflowing_sub(start);
             if !overflow {
-                if diff.low_u64() > u32::MAX as u64 {
+                if diff > u256::from(u32::MAX) {
                     return Err(EnvironmentError::RangeTooLarge);
                 }
 
-                let max: u32 = diff.into();
-                for i in 0..max {
-                    vec.push(ValueOwnable::Owned(Box::new(Value::U256(i.into()))));
+                let len: u32 = diff.into();
+                context.allocate(len as u64)?;
+                for i in 0..len {
+                    vec.push(ValueOwnable::Owned(Box::new(Value::U256(start + u256::from(i)))));
                 }
             }
 
@@ -99,19 +94,19 @@ fn collect(zelf: FnInstance, _: FnParams) -> FnReturnType {
     }))
 }
 
-fn max(zelf: FnInstance, _: FnParams) -> FnReturnType {
+fn max(zelf: FnInstance, _: FnParams, _: &mut Context) -> FnReturnType {
     let zelf = zelf?;
     let (_, end, _) = zelf.as_range()?;
     Ok(Some(end.clone()))
 }
 
-fn min(zelf: FnInstance, _: FnParams) -> FnReturnType {
+fn min(zelf: FnInstance, _: FnParams, _: &mut Context) -> FnReturnType {
     let zelf = zelf?;
     let (start, _, _) = zelf.as_range()?;
     Ok(Some(start.clone()))
 }
 
-fn count(zelf: FnInstance, _: FnParams) -> FnReturnType {
+fn count(zelf: FnInstance, _: FnParams, _: &mut Context) -> FnReturnType {
     let zelf = zelf?;
     let (start, end, _type) = zelf.as_range()?;
 
@@ -124,4 +119,10 @@ fn count(zelf: FnInstance, _: FnParams) -> FnReturnType {
         Type::U256 => count!(U256, start, end, u256),
         _ => return Err(EnvironmentError::InvalidType(zelf.clone()))
     }))
-}
\ No newline at end of file
+}
+
+// Same as collect, but the range is given as parameter
+fn range_to_array(_: FnInstance, mut parameters: FnParams, context: &mut Context) -> FnReturnType {
+    let mut range = parameters.remove(0).into_owned();
+    collect(Ok(&mut range), Vec::new(), context)
+}
@@ -0,0 +1,49 @@
+use xelis_types::{Type, Value, ValueOwnable};
+use xelis_environment::{Context, EnvironmentError, FnInstance, FnParams, FnReturnType};
+use super::EnvironmentBuilder;
+
+pub fn register(env: &mut EnvironmentBuilder) {
+    let ok_type = Type::Result(Box::new(Type::T), Box::new(Type::Any));
+    let err_type = Type::Result(Box::new(Type::Any), Box::new(Type::T));
+
+    env.register_native_function("ok", None, vec![Type::T], ok, 1, Some(ok_type.clone()));
+    env.register_native_function("err", None, vec![Type::T], err, 1, Some(err_type.clone()));
+    env.register_native_function("is_ok", Some(ok_type.clone()), vec![], is_ok, 1, Some(Type::Bool));
+    env.register_native_function("is_err", Some(ok_type.clone()), vec![], is_err, 1, Some(Type::Bool));
+    env.register_native_function("unwrap", Some(ok_type), vec![], unwrap, 1, Some(Type::T));
+    env.register_native_function("unwrap_err", Some(err_type), vec![], unwrap_err, 1, Some(Type::T));
+}
+
+fn ok(_: FnInstance, mut parameters: FnParams, _: &mut Context) -> FnReturnType {
+    let value = parameters.remove(0).into_owned();
+    Ok(Some(Value::Ok(ValueOwnable::Owned(Box::new(value)))))
+}
+
+fn err(_: FnInstance, mut parameters: FnParams, _: &mut Context) -> FnReturnType {
+    let value = parameters.remove(0).into_owned();
+    Ok(Some(Value::Err(ValueOwnable::Owned(Box::new(value)))))
+}
+
+fn is_ok(zelf: FnInstance, _: FnParams, _: &mut Context) -> FnReturnType {
+    Ok(Some(Value::Boolean(zelf?.as_result()?.is_ok())))
+}
+
+fn is_err(zelf: FnInstance, _: FnParams, _: &mut Context) -> FnReturnType {
+    Ok(Some(Value::Boolean(zelf?.as_result()?.is_err())))
+}
+
+fn unwrap(zelf: FnInstance, _: FnParams, _: &mut Context) -> FnReturnType {
+    let zelf = zelf?;
+    match zelf.as_result()? {
+        Ok(value) => Ok(Some(value.handle().clone())),
+        Err(_) => Err(EnvironmentError::Panic(zelf.clone()))
+    }
+}
+
+fn unwrap_err(zelf: FnInstance, _: FnParams, _: &mut Context) -> FnReturnType {
+    let zelf = zelf?;
+    match zelf.as_result()? {
+        Err(value) => Ok(Some(value.handle().clone())),
+        Ok(_) => Err(EnvironmentError::Panic(zelf.clone()))
+    }
+}
@@ -1,11 +1,46 @@
-use xelis_types::{Type, Value, ValueOwnable};
+use std::str::FromStr;
+use xelis_types::{Type, Value, ValueOwnable, U256 as u256};
+use paste::paste;
 use super::{
+    Context,
     FnInstance,
     FnParams,
     FnReturnType,
     EnvironmentBuilder
 };
 
+// Parse a decimal string into a number
+// Only digits are accepted: no sign, no whitespace and no empty string
+// Returns none on an invalid input or an overflow
+macro_rules! register_parse {
+    ($env: expr, $t: ident, $f: ident) => {
+        paste! {
+            fn [<parse_ $f>](_: FnInstance, mut parameters: FnParams, _: &mut Context) -> FnReturnType {
+                let param = parameters.remove(0);
+                let handle = param.as_ref();
+                let s = handle.as_string()?;
+
+                let value = if !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit()) {
+                    $f::from_str(s).ok()
+                } else {
+                    None
+                };
+
+                Ok(Some(Value::Optional(value.map(|v| ValueOwnable::Owned(Box::new(Value::$t(v)))))))
+            }
+
+            $env.register_native_function(
+                stringify!([<parse_ $f>]),
+                None,
+                vec![Type::String],
+                [<parse_ $f>],
+                3,
+                Some(Type::Optional(Box::new(Type::$t)))
+            );
+        }
+    };
+}
+
 pub fn register(env: &mut EnvironmentBuilder) {
     // String
     env.register_native_function("len", Some(Type::String), vec![], len, 1, Some(Type::U32));
@@ -27,19 +62,40 @@ pub fn register(env: &mut EnvironmentBuilder) {
     env.register_native_function("matches", Some(Type::String), vec![Type::String], string_matches, 50, Some(Type::Array(Box::new(Type::String))));
     env.register_native_function("substring", Some(Type::String), vec![Type::U32], string_substring, 3, Some(Type::Optional(Box::new(Type::String))));
     env.register_native_function("substring", Some(Type::String), vec![Type::U32, Type::U32], string_substring_range, 3, Some(Type::Optional(Box::new(Type::String))));
+
+    register_parse!(env, U8, u8);
+    register_parse!(env, U16, u16);
+    register_parse!(env, U32, u32);
+    register_parse!(env, U64, u64);
+    register_parse!(env, U128, u128);
+    register_parse!(env, U256, u256);
+    env.register_native_function("parse_bool", None, vec![Type::String], parse_bool, 1, Some(Type::Optional(Box::new(Type::Bool))));
+}
+
+// Only "true" and "false" are accepted
+fn parse_bool(_: FnInstance, mut parameters: FnParams, _: &mut Context) -> FnReturnType {
+    let param = parameters.remove(0);
+    let handle = param.as_ref();
+    let value = match handle.as_string()?.as_str() {
+        "true" => Some(true),
+        "false" => Some(false),
+        _ => None
+    };
+
+    Ok(Some(Value::Optional(value.map(|v| ValueOwnable::Owned(Box::new(Value::Boolean(v)))))))
 }
 
-fn len(zelf: FnInstance, _: FnParams) -> FnReturnType {
+fn len(zelf: FnInstance, _: FnParams, _: &mut Context) -> FnReturnType {
     let s: &String = zelf?.as_string()?;
     Ok(Some(Value::U32(s.len() as u32)))
 }
 
-fn trim(zelf: FnInstance, _: FnParams) -> FnReturnType {
+fn trim(zelf: FnInstance, _: FnParams, _: &mut Context) -> FnReturnType {
     let s = zelf?.as_string()?.trim().to_string();
     Ok(Some(Value::String(s)))
 }
 
-fn contains(zelf: FnInstance, mut parameters: FnParams) -> FnReturnType {
+fn contains(zelf: FnInstance, mut parameters: FnParams, _: &mut Context) -> FnReturnType {
     let param = parameters.remove(0);
     let handle = param.as_ref();
     let value = handle.as_string()?;
@@ -47,7 +103,7 @@ fn contains(zelf: FnInstance, mut parameters: FnParams) -> FnReturnType {
     Ok(Some(Value::Boolean(s.contains(value))))
 }
 
-fn contains_ignore_case(zelf: FnInstance, mut parameters: FnParams) -> FnReturnType {
+fn contains_ignore_case(zelf: FnInstance, mut parameters: FnParams, _: &mut Context) -> FnReturnType {
     let param = parameters.remove(0);
     let handle = param.as_ref();
     let value = handle.as_string()?.to_lowercase();
@@ -55,17 +111,17 @@ fn contains_ignore_case(zelf: FnInstance, mut parameters: FnParams) -> FnReturnT
     Ok(Some(Value::Boolean(s.contains(&value))))
 }
 
-fn to_uppercase(zelf: FnInstance, _: FnParams) -> FnReturnType {
+fn to_uppercase(zelf: FnInstance, _: FnParams, _: &mut Context) -> FnReturnType {
     let s: String = zelf?.as_string()?.to_uppercase();
     Ok(Some(Value::String(s)))
 }
 
-fn to_lowercase(zelf: FnInstance, _: FnParams) -> FnReturnType {
+fn to_lowercase(zelf: FnInstance, _: FnParams, _: &mut Context) -> FnReturnType {
     let s: String = zelf?.as_string()?.to_lowercase();
     Ok(Some(Value::String(s)))
 }
 
-fn to_bytes(zelf: FnInstance, _: FnParams) -> FnReturnType {
+fn to_bytes(zelf: FnInstance, _: FnParams, _: &mut Context) -> FnReturnType {
     let s: &String = zelf?.as_string()?;
 
     let mut bytes = Vec::new();
@@ -76,7 +132,7 @@ fn to_bytes(zelf: FnInstance, _: FnParams) -> FnReturnType {
     Ok(Some(Value::Array(bytes)))
 }
 
-fn index_of(zelf: FnInstance, mut parameters: FnParams) -> FnReturnType {
+fn index_of(zelf: FnInstance, mut parameters: FnParams, _: &mut Context) -> FnReturnType {
     let s: &String = zelf?.as_string()?;
     let param = parameters.remove(0);
     let handle = param.as_ref();
@@ -89,7 +145,7 @@ fn index_of(zelf: FnInstance, mut parameters: FnParams) -> FnReturnType {
     }
 }
 
-fn last_index_of(zelf: FnInstance, mut parameters: FnParams) -> FnReturnType {
+fn last_index_of(zelf: FnInstance, mut parameters: FnParams, _: &mut Context) -> FnReturnType {
     let s: &String = zelf?.as_string()?;
     let param = parameters.remove(0);
     let handle = param.as_ref();
@@ -102,7 +158,7 @@ fn last_index_of(zelf: FnInstance, mut parameters: FnParams) -> FnReturnType {
     }
 }
 
-fn replace(zelf: FnInstance, mut parameters: FnParams) -> FnReturnType {
+fn replace(zelf: FnInstance, mut parameters: FnParams, _: &mut Context) -> FnReturnType {
     let s: &String = zelf?.as_string()?;
     let param1 = parameters.remove(0);
     let param2 = parameters.remove(0);
@@ -114,7 +170,7 @@ fn replace(zelf: FnInstance, mut parameters: FnParams) -> FnReturnType {
     Ok(Some(Value::String(s)))
 }
 
-fn starts_with(zelf: FnInstance, mut parameters: FnParams) -> FnReturnType {
+fn starts_with(zelf: FnInstance, mut parameters: FnParams, _: &mut Context) -> FnReturnType {
     let s: &String = zelf?.as_string()?;
     let param = parameters.remove(0);
     let handle = param.as_ref();
@@ -122,7 +178,7 @@ fn starts_with(zelf: FnInstance, mut parameters: FnParams) -> FnReturnType {
     Ok(Some(Value::Boolean(s.starts_with(value))))
 }
 
-fn ends_with(zelf: FnInstance, mut parameters: FnParams) -> FnReturnType {
+fn ends_with(zelf: FnInstance, mut parameters: FnParams, _: &mut Context) -> FnReturnType {
     let s: &String = zelf?.as_string()?;
     let param = parameters.remove(0);
     let handle = param.as_ref();
@@ -130,7 +186,7 @@ fn ends_with(zelf: FnInstance, mut parameters: FnParams) -> FnReturnType {
     Ok(Some(Value::Boolean(s.ends_with(value))))
 }
 
-fn split(zelf: FnInstance, mut parameters: FnParams) -> FnReturnType {
+fn split(zelf: FnInstance, mut parameters: FnParams, _: &mut Context) -> FnReturnType {
     let s: &String = zelf?.as_string()?;
     let param = parameters.remove(0);
     let handle = param.as_ref();
@@ -142,7 +198,7 @@ fn split(zelf: FnInstance, mut parameters: FnParams) -> FnReturnType {
     Ok(Some(Value::Array(values)))
 }
 
-fn char_at(zelf: FnInstance, mut parameters: FnParams) -> FnReturnType {
+fn char_at(zelf: FnInstance, mut parameters: FnParams, _: &mut Context) -> FnReturnType {
     let param =  parameters.remove(0);
     let index = param.as_u32()? as usize;
     let s: &String = zelf?.as_string()?;
@@ -154,12 +210,12 @@ fn char_at(zelf: FnInstance, mut parameters: FnParams) -> FnReturnType {
     }
 }
 
-fn is_empty(zelf: FnInstance, _: FnParams) -> FnReturnType {
+fn is_empty(zelf: FnInstance, _: FnParams, _: &mut Context) -> FnReturnType {
     let s: &String = zelf?.as_string()?;
     Ok(Some(Value::Boolean(s.is_empty())))
 }
 
-fn string_matches(zelf: FnInstance, mut parameters: FnParams) -> FnReturnType {
+fn string_matches(zelf: FnInstance, mut parameters: FnParams, _: &mut Context) -> FnReturnType {
     let s: &String = zelf?.as_string()?;
     let param = parameters.remove(0);
     let handle = param.as_ref();
@@ -168,7 +224,7 @@ fn string_matches(zelf: FnInstance, mut parameters: FnParams) -> FnReturnType {
     Ok(Some(Value::Array(m.map(|s| ValueOwnable::Owned(Box::new(Value::String(s.to_string())))).collect())))
 }
 
-fn string_substring(zelf: FnInstance, mut parameters: FnParams) -> FnReturnType {
+fn string_substring(zelf: FnInstance, mut parameters: FnParams, _: &mut Context) -> FnReturnType {
     let s: &String = zelf?.as_string()?;
     let param = parameters.remove(0);
     let start = param.as_u32()? as usize;
@@ -180,7 +236,7 @@ fn string_substring(zelf: FnInstance, mut parameters: FnParams) -> FnReturnType
     }
 }
 
-fn string_substring_range(zelf: FnInstance, mut parameters: FnParams) -> FnReturnType {
+fn string_substring_range(zelf: FnInstance, mut parameters: FnParams, _: &mut Context) -> FnReturnType {
     let s: &String = zelf?.as_string()?;
     let param1 = parameters.remove(0);
     let param2 = parameters.remove(0);
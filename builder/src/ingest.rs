@@ -0,0 +1,288 @@
+use std::io::BufRead;
+use xelis_types::{StructType, Type, Value, ValueOwnable, U256};
+use crate::{BuilderError, StructManager};
+
+fn owned(value: Value) -> ValueOwnable {
+    ValueOwnable::Owned(Box::new(value))
+}
+
+// Parse a number written in base 10 into the expected number type
+fn parse_number(s: &str, _type: &Type) -> Result<Value, String> {
+    let s = s.trim();
+    let invalid = || format!("'{}' is not a valid {}", s, _type);
+    Ok(match _type {
+        Type::U8 => Value::U8(s.parse().map_err(|_| invalid())?),
+        Type::U16 => Value::U16(s.parse().map_err(|_| invalid())?),
+        Type::U32 => Value::U32(s.parse().map_err(|_| invalid())?),
+        Type::U64 => Value::U64(s.parse().map_err(|_| invalid())?),
+        Type::U128 => Value::U128(s.parse().map_err(|_| invalid())?),
+        Type::U256 => Value::U256(s.parse::<U256>().map_err(|_| invalid())?),
+        _ => return Err(invalid())
+    })
+}
+
+// Convert a CSV cell into a value of the field type
+// An empty cell is an empty optional, only primitive and optional fields are supported
+fn value_from_cell(cell: &str, _type: &Type) -> Result<Value, String> {
+    Ok(match _type {
+        Type::String => Value::String(cell.to_owned()),
+        Type::Bool => match cell.trim() {
+            "true" => Value::Boolean(true),
+            "false" => Value::Boolean(false),
+            s => return Err(format!("'{}' is not a valid bool", s))
+        },
+        Type::Optional(_) if cell.is_empty() => Value::Optional(None),
+        Type::Optional(inner) => Value::Optional(Some(owned(value_from_cell(cell, inner)?))),
+        _type if _type.is_number() => parse_number(cell, _type)?,
+        _type => return Err(format!("type {} can't be read from CSV", _type))
+    })
+}
+
+// Split a CSV line into its cells
+// A cell can be quoted to contain commas, a quote is escaped by doubling it
+fn split_csv_line(line: &str) -> Result<Vec<String>, String> {
+    let mut cells = Vec::new();
+    let mut chars = line.chars().peekable();
+    loop {
+        let mut cell = String::new();
+        if chars.peek() == Some(&'"') {
+            chars.next();
+            loop {
+                match chars.next() {
+                    Some('"') if chars.peek() == Some(&'"') => {
+                        chars.next();
+                        cell.push('"');
+                    },
+                    Some('"') => break,
+                    Some(c) => cell.push(c),
+                    None => return Err("unterminated quoted cell".to_owned())
+                }
+            }
+
+            if !matches!(chars.peek(), Some(',') | None) {
+                return Err("unexpected character after a quoted cell".to_owned())
+            }
+        } else {
+            while let Some(c) = chars.peek().filter(|c| **c != ',') {
+                cell.push(*c);
+                chars.next();
+            }
+        }
+
+        cells.push(cell);
+        if chars.next().is_none() {
+            return Ok(cells)
+        }
+    }
+}
+
+impl<'a> StructManager<'a> {
+    // Read CSV rows into an array of structs of the given type
+    // The first line is a header naming the fields, in any order
+    // Lines are read and converted one by one, but all the rows are kept in the returned array
+    // Errors report the index of the row, starting at 0 after the header, and the field
+    pub fn values_from_csv<R: BufRead>(&self, _type: &StructType, reader: R) -> Result<Value, BuilderError> {
+        let names = self.get_field_names(_type)?;
+        let mut lines = reader.lines();
+
+        let header = match lines.next() {
+            Some(line) => split_csv_line(line?.trim_end_matches('\r')).map_err(BuilderError::InvalidInput)?,
+            None => return Err(BuilderError::InvalidInput("missing CSV header".to_owned()))
+        };
+        if header.len() != names.len() {
+            return Err(BuilderError::InvalidStructFieldsCount(names.len(), header.len()))
+        }
+
+        // Index of the field stored in each column
+        let mut columns = Vec::with_capacity(header.len());
+        for column in &header {
+            let index = names.iter()
+                .position(|name| *name == column.trim())
+                .ok_or_else(|| BuilderError::InvalidInput(format!("unknown column '{}'", column)))?;
+
+            if columns.contains(&index) {
+                return Err(BuilderError::InvalidInput(format!("duplicated column '{}'", column)))
+            }
+            columns.push(index);
+        }
+
+        let mut rows = Vec::new();
+        for (row, line) in lines.enumerate() {
+            let line = line?;
+            let line = line.trim_end_matches('\r');
+            if line.is_empty() {
+                continue;
+            }
+
+            let cells = split_csv_line(line)
+                .map_err(|reason| BuilderError::InvalidRow(row, reason))?;
+            if cells.len() != columns.len() {
+                return Err(BuilderError::InvalidRow(row, format!("expected {} cells, got {}", columns.len(), cells.len())))
+            }
+
+            let mut fields = vec![Value::Null; columns.len()];
+            for (cell, index) in cells.iter().zip(&columns) {
+                fields[*index] = value_from_cell(cell, &_type.fields()[*index])
+                    .map_err(|reason| BuilderError::InvalidRowField(row, names[*index].to_owned(), reason))?;
+            }
+
+            rows.push(owned(Value::Struct(fields.into_iter().map(owned).collect(), _type.clone())));
+        }
+
+        Ok(Value::Array(rows))
+    }
+
+    // Read a JSON array of objects into an array of structs of the given type
+    // Nested structs are objects, arrays and tuples are arrays
+    // A missing field or null is accepted for an optional field
+    // Numbers too large for JSON can be written as strings
+    // The whole document is parsed before its rows are converted
+    #[cfg(feature = "json")]
+    pub fn values_from_json<R: std::io::Read>(&self, _type: &StructType, reader: R) -> Result<Value, BuilderError> {
+        let json: serde_json::Value = serde_json::from_reader(reader)
+            .map_err(|e| BuilderError::InvalidInput(e.to_string()))?;
+
+        let rows = json.as_array()
+            .ok_or_else(|| BuilderError::InvalidInput("expected an array of objects".to_owned()))?;
+
+        let names = self.get_field_names(_type)?;
+        let mut values = Vec::with_capacity(rows.len());
+        for (row, object) in rows.iter().enumerate() {
+            let object = object.as_object()
+                .ok_or_else(|| BuilderError::InvalidRow(row, "expected an object".to_owned()))?;
+
+            if let Some(key) = object.keys().find(|key| !names.contains(&key.as_str())) {
+                return Err(BuilderError::InvalidRowField(row, key.clone(), "unknown field".to_owned()))
+            }
+
+            let mut fields = Vec::with_capacity(names.len());
+            for (name, field_type) in names.iter().zip(_type.fields()) {
+                let value = self.value_from_json(object.get(*name), field_type)
+                    .map_err(|reason| BuilderError::InvalidRowField(row, (*name).to_owned(), reason))?;
+                fields.push(owned(value));
+            }
+
+            values.push(owned(Value::Struct(fields, _type.clone())));
+        }
+
+        Ok(Value::Array(values))
+    }
+
+    // Convert a JSON value into a value of the expected type
+    // None is a missing field of an object
+    #[cfg(feature = "json")]
+    fn value_from_json(&self, json: Option<&serde_json::Value>, _type: &Type) -> Result<Value, String> {
+        use serde_json::Value as Json;
+
+        Ok(match (json, _type) {
+            (None | Some(Json::Null), Type::Optional(_)) => Value::Optional(None),
+            (None, _) => return Err("missing field".to_owned()),
+            (json, Type::Optional(inner)) => Value::Optional(Some(owned(self.value_from_json(json, inner)?))),
+            (Some(Json::Bool(b)), Type::Bool) => Value::Boolean(*b),
+            (Some(Json::String(s)), Type::String) => Value::String(s.clone()),
+            (Some(Json::Number(n)), _type) if _type.is_number() => parse_number(&n.to_string(), _type)?,
+            (Some(Json::String(s)), _type) if _type.is_number() => parse_number(s, _type)?,
+            (Some(Json::Array(values)), Type::Array(inner)) => Value::Array(
+                values.iter()
+                    .map(|value| self.value_from_json(Some(value), inner).map(owned))
+                    .collect::<Result<_, _>>()?
+            ),
+            (Some(Json::Array(values)), Type::Tuple(types)) if values.len() == types.len() => Value::Tuple(
+                values.iter()
+                    .zip(types)
+                    .map(|(value, _type)| self.value_from_json(Some(value), _type).map(owned))
+                    .collect::<Result<_, _>>()?
+            ),
+            (Some(Json::Object(object)), Type::Struct(struct_type)) => {
+                let names = self.get_field_names(struct_type).map_err(|e| e.to_string())?;
+                let mut fields = Vec::with_capacity(names.len());
+                for (name, field_type) in names.iter().zip(struct_type.fields()) {
+                    let value = self.value_from_json(object.get(*name), field_type)
+                        .map_err(|reason| format!("{}: {}", name, reason))?;
+                    fields.push(owned(value));
+                }
+
+                Value::Struct(fields, struct_type.clone())
+            },
+            (Some(json), _type) => return Err(format!("{} is not a valid {}", json, _type))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+    use super::*;
+
+    fn manager() -> (StructManager<'static>, StructType) {
+        let mut manager = StructManager::new();
+        let _type = manager.build_struct(Cow::Borrowed("Row"), vec![
+            ("id", Type::U64),
+            ("name", Type::String),
+            ("active", Type::Bool),
+            ("score", Type::Optional(Box::new(Type::U8)))
+        ]).unwrap();
+
+        (manager, _type)
+    }
+
+    fn row(_type: &StructType, id: u64, name: &str, active: bool, score: Option<u8>) -> ValueOwnable {
+        owned(Value::Struct(vec![
+            owned(Value::U64(id)),
+            owned(Value::String(name.to_owned())),
+            owned(Value::Boolean(active)),
+            owned(Value::Optional(score.map(|s| owned(Value::U8(s)))))
+        ], _type.clone()))
+    }
+
+    #[test]
+    fn test_values_from_csv() {
+        let (manager, _type) = manager();
+        let csv = "name,id,active,score\n\"Doe, John\",1,true,10\nJane,2,false,\r\n\"say \"\"hi\"\"\",3,true,255\n";
+
+        let value = manager.values_from_csv(&_type, csv.as_bytes()).unwrap();
+        assert_eq!(value, Value::Array(vec![
+            row(&_type, 1, "Doe, John", true, Some(10)),
+            row(&_type, 2, "Jane", false, None),
+            row(&_type, 3, "say \"hi\"", true, Some(255)),
+        ]));
+    }
+
+    #[test]
+    fn test_values_from_csv_invalid() {
+        let (manager, _type) = manager();
+
+        // The score of the second row doesn't fit in a u8
+        let csv = "id,name,active,score\n1,a,true,1\n2,b,false,256\n3,c,true,";
+        let err = manager.values_from_csv(&_type, csv.as_bytes()).unwrap_err();
+        assert!(matches!(err, BuilderError::InvalidRowField(1, ref field, _) if field == "score"), "{:?}", err);
+
+        let csv = "id,name,active,score\n1,a,true\n";
+        assert!(matches!(manager.values_from_csv(&_type, csv.as_bytes()), Err(BuilderError::InvalidRow(0, _))));
+
+        let csv = "id,name,enabled,score\n";
+        assert!(matches!(manager.values_from_csv(&_type, csv.as_bytes()), Err(BuilderError::InvalidInput(_))));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_values_from_json() {
+        let (manager, _type) = manager();
+        let json = r#"[
+            { "id": 1, "name": "a", "active": true, "score": 10 },
+            { "id": 2, "name": "b", "active": false, "score": null },
+            { "id": 3, "name": "c", "active": true }
+        ]"#;
+
+        let value = manager.values_from_json(&_type, json.as_bytes()).unwrap();
+        assert_eq!(value, Value::Array(vec![
+            row(&_type, 1, "a", true, Some(10)),
+            row(&_type, 2, "b", false, None),
+            row(&_type, 3, "c", true, None),
+        ]));
+
+        let json = r#"[{ "id": 1, "name": "a", "active": true }, { "id": "x", "name": "b", "active": false }]"#;
+        let err = manager.values_from_json(&_type, json.as_bytes()).unwrap_err();
+        assert!(matches!(err, BuilderError::InvalidRowField(1, ref field, _) if field == "id"), "{:?}", err);
+    }
+}
@@ -1,6 +1,7 @@
 mod environment;
 mod struct_manager;
 mod mapper;
+mod ingest;
 
 pub use environment::*;
 pub use struct_manager::*;
@@ -14,10 +15,24 @@ pub enum BuilderError {
     StructNameAlreadyUsed,
     #[error("Struct not found")]
     StructNotFound,
+    #[error("Struct field not found")]
+    FieldNotFound,
+    #[error("Invalid struct fields count: expected {0}, got {1}")]
+    InvalidStructFieldsCount(usize, usize),
     #[error("mapping not found")]
     MappingNotFound,
     #[error("mapping already exists")]
     MappingExists,
+    #[error("Name already used: {0}")]
+    NameAlreadyUsed(String),
     #[error(transparent)]
     ValueError(#[from] ValueError),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+    #[error("Invalid row {0}: {1}")]
+    InvalidRow(usize, String),
+    #[error("Invalid field {1} at row {0}: {2}")]
+    InvalidRowField(usize, String, String),
 }
\ No newline at end of file
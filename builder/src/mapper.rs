@@ -12,28 +12,49 @@ use crate::BuilderError;
 pub type IdMapper<'a> = Mapper<'a, Cow<'a, str>>;
 pub type FunctionMapper<'a> = Mapper<'a, Signature>;
 
+// Key registered in a Mapper
+// Keys sharing the same name are indexed together to narrow the lookups
+pub trait MapperKey: Clone + Eq + Hash + Debug {
+    fn name(&self) -> &str;
+}
+
+impl MapperKey for Cow<'_, str> {
+    fn name(&self) -> &str {
+        self
+    }
+}
+
+impl MapperKey for Signature {
+    fn name(&self) -> &str {
+        self.get_name()
+    }
+}
+
 // VariableMapper is used to store the mapping between variable names and their identifiers
 // So we can reduce the memory footprint of the interpreter by using an incremented id
 #[derive(Debug, Clone)]
-pub struct Mapper<'a, T: Clone + Eq + Hash> {
+pub struct Mapper<'a, T: MapperKey> {
     parent: Option<&'a Mapper<'a, T>>,
     next_id: IdentifierType,
-    mappings: HashMap<T, IdentifierType>
+    mappings: HashMap<T, IdentifierType>,
+    // Keys registered under each name with their id, in registration order
+    names: HashMap<String, Vec<(T, IdentifierType)>>
 }
 
-impl<'a, T: Clone + Eq + Hash + Debug> Default for Mapper<'a, T> {
+impl<'a, T: MapperKey> Default for Mapper<'a, T> {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl<'a, T: Clone + Eq + Hash + Debug> Mapper<'a, T> {
+impl<'a, T: MapperKey> Mapper<'a, T> {
     // Create a new VariableMapper
     pub fn new() -> Self {
         Self {
             parent: None,
             next_id: 0,
-            mappings: HashMap::new()
+            mappings: HashMap::new(),
+            names: HashMap::new()
         }
     }
 
@@ -42,7 +63,8 @@ impl<'a, T: Clone + Eq + Hash + Debug> Mapper<'a, T> {
         Self {
             next_id: parent.get_next_id(),
             parent: Some(parent),
-            mappings: HashMap::new()
+            mappings: HashMap::new(),
+            names: HashMap::new()
         }
     }
 
@@ -86,15 +108,31 @@ impl<'a, T: Clone + Eq + Hash + Debug> Mapper<'a, T> {
         }
 
         let id = self.next_id;
+        self.names.entry(name.name().to_owned())
+            .or_default()
+            .push((name.clone(), id));
         self.mappings.insert(name, id);
 
         self.next_id += 1;
         Ok(id)
     }
 
+    // Check if a key is registered with this name, in this mapper or its parent
+    pub fn has_name(&self, name: &str) -> bool {
+        self.names.contains_key(name) || self.parent.is_some_and(|p| p.has_name(name))
+    }
+
     pub fn count(&self) -> usize {
         self.parent.map_or(0, |p| p.count()) + self.mappings.len()
     }
+
+    // Keys registered with this name, without the parent ones
+    fn get_by_name(&self, name: &str) -> impl Iterator<Item = (&T, IdentifierType)> {
+        self.names.get(name)
+            .into_iter()
+            .flatten()
+            .map(|(key, id)| (key, *id))
+    }
 }
 
 impl<'a> FunctionMapper<'a> {
@@ -105,7 +143,8 @@ impl<'a> FunctionMapper<'a> {
         }
 
         // Lets find a compatible signature
-        'main: for (signature, id) in self.mappings.iter().filter(|(s, _)| s.get_name() == key.get_name() && s.get_parameters().len() == key.get_parameters().len()) {            
+        // Only the signatures with the same name are candidates
+        'main: for (signature, id) in self.get_by_name(key.get_name()).filter(|(s, _)| s.get_parameters().len() == key.get_parameters().len()) {
             let on_type = match (signature.get_on_type(), key.get_on_type()) {
                 (Some(s), Some(k)) => s.is_compatible_with(k),
                 (None, None) => true,
@@ -118,10 +157,11 @@ impl<'a> FunctionMapper<'a> {
 
             let mut updated_expressions = Vec::new();
             for (i, (a, b)) in signature.get_parameters().iter().zip(key.get_parameters()).enumerate() {
+                // A generic parameter takes the inner type of the instance, like the value pushed in an array
                 let mut cast_to_type = key.get_on_type()
                     .as_ref()
                     .map(Type::get_inner_type)
-                    .filter(|t| b.is_castable_to(t));
+                    .filter(|t| *a == Type::T && b.is_castable_to(t));
 
                 if cast_to_type.is_none() && !a.is_compatible_with(b) {
                     // If our parameter is castable to the signature parameter, cast it
@@ -138,7 +178,7 @@ impl<'a> FunctionMapper<'a> {
                     if let Expression::Value(value) = &expressions[i] {
                         let cloned = value.clone();
                         let v = cloned.checked_cast_to_primitive_type(a)?;
-                        updated_expressions.push(Expression::Value(v));
+                        updated_expressions.push((i, Expression::Value(v)));
                         continue;
                     } else {
                         continue 'main;
@@ -146,11 +186,11 @@ impl<'a> FunctionMapper<'a> {
                 }
             }
 
-            for (i, expr) in updated_expressions.into_iter().enumerate() {
+            for (i, expr) in updated_expressions {
                 expressions[i] = expr;
             }
 
-            return Ok(id.clone());
+            return Ok(id);
         }
 
         if let Some(parent) = self.parent {
@@ -159,4 +199,33 @@ impl<'a> FunctionMapper<'a> {
 
         Err(BuilderError::MappingNotFound)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use xelis_types::Value;
+    use super::*;
+
+    #[test]
+    fn test_get_compatible_by_name() {
+        let mut parent = FunctionMapper::new();
+        let a = parent.register(Signature::new("a".to_owned(), None, vec![Type::U64])).unwrap();
+        let b = parent.register(Signature::new("b".to_owned(), None, vec![Type::U64])).unwrap();
+
+        let mut mapper = FunctionMapper::with_parent(&parent);
+        let c = mapper.register(Signature::new("a".to_owned(), Some(Type::String), vec![Type::U64])).unwrap();
+
+        // The literal is casted to the parameter type
+        let mut expressions = [Expression::Value(Value::U8(1))];
+        assert_eq!(mapper.get_compatible(Signature::new("b".to_owned(), None, vec![Type::U8]), &mut expressions).unwrap(), b);
+        assert_eq!(expressions[0], Expression::Value(Value::U64(1)));
+
+        let mut expressions = [Expression::Value(Value::U8(1))];
+        assert_eq!(mapper.get_compatible(Signature::new("a".to_owned(), None, vec![Type::U8]), &mut expressions).unwrap(), a);
+        let mut expressions = [Expression::Value(Value::U8(1))];
+        assert_eq!(mapper.get_compatible(Signature::new("a".to_owned(), Some(Type::String), vec![Type::U8]), &mut expressions).unwrap(), c);
+
+        let mut expressions = [Expression::Value(Value::U8(1))];
+        assert!(mapper.get_compatible(Signature::new("c".to_owned(), None, vec![Type::U8]), &mut expressions).is_err());
+    }
+}
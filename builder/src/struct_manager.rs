@@ -3,6 +3,11 @@ use xelis_types::{
     IdentifierType,
     StructType,
     Type,
+    TypeParseError,
+    Value,
+    ValueError,
+    ValueHandle,
+    ValueHandleMut
 };
 use crate::{
     BuilderError,
@@ -11,16 +16,36 @@ use crate::{
 
 #[derive(Debug, Clone)]
 pub struct StructBuilder<'a> {
+    name: Cow<'a, str>,
     inner: StructType,
     fields_names: Vec<&'a str>
 }
 
+// Description of a struct built by the manager
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeSummary {
+    pub name: String,
+    pub id: IdentifierType,
+    // Name and type of each field, in declaration order
+    pub fields: Vec<(String, Type)>
+}
+
 impl<'a> StructBuilder<'a> {
+    // Get the name of the struct
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
     // Get the index of a field by name
     pub fn get_id_for_field(&self, name: &str) -> Option<IdentifierType> {
         self.fields_names.iter().position(|k| *k == name).map(|v| v as IdentifierType)
     }
 
+    // Get the names of the fields, in declaration order
+    pub fn fields_names(&self) -> &[&'a str] {
+        &self.fields_names
+    }
+
     pub fn inner(&self) -> &StructType {
         &self.inner
     }
@@ -60,10 +85,11 @@ impl<'a> StructManager<'a> {
 
         let (fields_names, fields_types) = split_vec(fields);
 
-        let id = self.mapper.register(name)?;
-        let inner = StructType::new(id, fields_types);
+        let id = self.mapper.register(name.clone())?;
+        let inner = StructType::with_names(id, name.to_string(), fields_types, fields_names.iter().map(|name| name.to_string()).collect());
 
         Ok(StructBuilder {
+            name,
             inner,
             fields_names
         })
@@ -112,12 +138,66 @@ impl<'a> StructManager<'a> {
         self.structures.iter().find(|v| &v.inner == _type).ok_or(BuilderError::StructNotFound)   
     }
 
+    // Resolve the names of the fields of a struct type
+    // The StructType only knows the types of its fields
+    pub fn get_field_names(&self, _type: &StructType) -> Result<&[&'a str], BuilderError> {
+        self.get_by_ref(_type).map(StructBuilder::fields_names)
+    }
+
+    // Parse a type from its Display format, struct names are resolved using this manager
+    pub fn parse_type(&self, input: &str) -> Result<Type, TypeParseError> {
+        Type::parse_with(input, |name| self.get_by_name(name).ok().map(|b| b.inner().clone()))
+    }
+
+    // Describe all the structs known by the manager, including the ones of its parent
+    // They are sorted by id, so in registration order
+    pub fn summary(&self) -> Vec<TypeSummary> {
+        let mut summary = self.parent.map(StructManager::summary).unwrap_or_default();
+        summary.extend(self.structures.iter().map(|builder| TypeSummary {
+            name: builder.name().to_owned(),
+            id: builder.inner.id(),
+            fields: builder.fields_names.iter()
+                .zip(builder.inner.fields())
+                .map(|(name, _type)| ((*name).to_owned(), _type.clone()))
+                .collect()
+        }));
+        summary.sort_by_key(|s| s.id);
+        summary
+    }
+
     // Convert the struct manager into a list of structs
     pub fn finalize(self) -> Vec<StructType> {
         self.structures.into_iter().map(|builder| builder.inner).collect()
     }
 }
 
+// Get the field of a struct value by its name
+pub fn get_field<'a>(value: &'a Value, struct_builder: &StructBuilder, name: &str) -> Result<ValueHandle<'a>, BuilderError> {
+    match value {
+        Value::Struct(fields, _type) if _type == struct_builder.inner() => {
+            let id = struct_builder.get_id_for_field(name).ok_or(BuilderError::FieldNotFound)?;
+            fields.get(id as usize)
+                .map(|field| field.handle())
+                .ok_or(BuilderError::FieldNotFound)
+        },
+        v => Err(ValueError::InvalidStructValue(v.clone()).into())
+    }
+}
+
+// Same as get_field but returns a mutable handle
+pub fn get_field_mut<'a>(value: &'a mut Value, struct_builder: &StructBuilder, name: &str) -> Result<ValueHandleMut<'a>, BuilderError> {
+    if !matches!(value, Value::Struct(_, _type) if _type == struct_builder.inner()) {
+        return Err(ValueError::InvalidStructValue(value.clone()).into())
+    }
+
+    let fields = value.as_mut_sub_vec()?;
+
+    let id = struct_builder.get_id_for_field(name).ok_or(BuilderError::FieldNotFound)?;
+    fields.get_mut(id as usize)
+        .map(|field| field.handle_mut())
+        .ok_or(BuilderError::FieldNotFound)
+}
+
 fn split_vec<A, B>(input: Vec<(A, B)>) -> (Vec<A>, Vec<B>) {
     let mut vec_a = Vec::with_capacity(input.len());
     let mut vec_b = Vec::with_capacity(input.len());
@@ -128,4 +208,64 @@ fn split_vec<A, B>(input: Vec<(A, B)>) -> (Vec<A>, Vec<B>) {
     }
 
     (vec_a, vec_b)
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use xelis_types::ValueOwnable;
+    use super::*;
+
+    #[test]
+    fn test_get_field_by_name() {
+        let mut manager = StructManager::new();
+        manager.add(Cow::Borrowed("Point"), vec![("x", Type::U64), ("y", Type::U64)]).unwrap();
+        let builder = manager.get_by_name("Point").unwrap();
+
+        let mut value = Value::Struct(vec![
+            ValueOwnable::Owned(Box::new(Value::U64(10))),
+            ValueOwnable::Owned(Box::new(Value::U64(20)))
+        ], builder.inner().clone());
+
+        assert_eq!(*get_field(&value, builder, "y").unwrap().as_value(), Value::U64(20));
+        assert!(matches!(get_field(&value, builder, "z"), Err(BuilderError::FieldNotFound)));
+        assert!(matches!(get_field(&Value::U64(0), builder, "x"), Err(BuilderError::ValueError(_))));
+
+        *get_field_mut(&mut value, builder, "x").unwrap().as_value_mut() = Value::U64(5);
+        assert_eq!(*get_field(&value, builder, "x").unwrap().as_value(), Value::U64(5));
+    }
+
+    #[test]
+    fn test_get_field_names() {
+        let mut manager = StructManager::new();
+        let point = manager.build_struct(Cow::Borrowed("Point"), vec![("x", Type::U64), ("y", Type::U64)]).unwrap();
+
+        assert_eq!(manager.get_field_names(&point).unwrap(), &["x", "y"]);
+        assert!(matches!(manager.get_field_names(&StructType::new(99, Vec::new())), Err(BuilderError::StructNotFound)));
+    }
+
+    #[test]
+    fn test_summary() {
+        let mut parent = StructManager::new();
+        let point = parent.build_struct(Cow::Borrowed("Point"), vec![("x", Type::U64), ("y", Type::U64)]).unwrap();
+
+        let mut manager = StructManager::with_parent(&parent);
+        manager.add(Cow::Borrowed("Line"), vec![("from", Type::Struct(point.clone())), ("to", Type::Struct(point.clone()))]).unwrap();
+        manager.add(Cow::Borrowed("Empty"), Vec::new()).unwrap();
+
+        assert_eq!(manager.summary(), vec![
+            TypeSummary { name: "Point".to_owned(), id: 0, fields: vec![("x".to_owned(), Type::U64), ("y".to_owned(), Type::U64)] },
+            TypeSummary { name: "Line".to_owned(), id: 1, fields: vec![("from".to_owned(), Type::Struct(point.clone())), ("to".to_owned(), Type::Struct(point))] },
+            TypeSummary { name: "Empty".to_owned(), id: 2, fields: Vec::new() },
+        ]);
+        assert_eq!(parent.summary().len(), 1);
+    }
+
+    #[test]
+    fn test_parse_type() {
+        let mut manager = StructManager::new();
+        let point = manager.build_struct(Cow::Borrowed("Point"), vec![("x", Type::U64), ("y", Type::U64)]).unwrap();
+
+        assert_eq!(manager.parse_type("optional<Point[]>"), Ok(Type::Optional(Box::new(Type::Array(Box::new(Type::Struct(point)))))));
+        assert_eq!(manager.parse_type("Line"), Err(TypeParseError::UnknownType("Line".into())));
+    }
+}
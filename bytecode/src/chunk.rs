@@ -17,6 +17,14 @@ impl Chunk {
         }
     }
 
+    // Create a chunk from already encoded instructions
+    #[inline]
+    pub fn from_instructions(instructions: Vec<u8>) -> Self {
+        Chunk {
+            instructions
+        }
+    }
+
     // Get the opcodes length
     #[inline]
     pub fn index(&self) -> usize {
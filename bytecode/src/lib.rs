@@ -4,4 +4,4 @@ mod module;
 
 pub use chunk::Chunk;
 pub use opcode::OpCode;
-pub use module::Module;
\ No newline at end of file
+pub use module::{Module, ModuleError, MODULE_FORMAT_VERSION};
\ No newline at end of file
@@ -1,14 +1,72 @@
 
-use std::collections::HashSet;
-use xelis_types::{StructType, Value};
+use std::{collections::HashSet, io::{self, Read, Write}};
+use thiserror::Error;
+use xelis_types::{StructType, Type, Value, ValueError, MAX_VALUE_DEPTH};
 
 use super::Chunk;
 
+// Version of the serialized module format
+// Increased on each change of the format, older modules are rejected
+pub const MODULE_FORMAT_VERSION: u16 = 1;
+
+// Magic bytes at the start of a serialized module
+const MODULE_MAGIC: [u8; 4] = *b"XVMM";
+
+// Magic, version and checksum of the body
+const HEADER_SIZE: usize = 4 + 2 + 8;
+
+#[derive(Debug, Error)]
+pub enum ModuleError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    ValueError(#[from] ValueError),
+    #[error("Invalid magic bytes")]
+    InvalidMagic,
+    #[error("Unsupported module format version {0}, expected {MODULE_FORMAT_VERSION}")]
+    InvalidVersion(u16),
+    #[error("Invalid checksum")]
+    InvalidChecksum,
+    #[error("Unexpected end of input")]
+    UnexpectedEndOfInput,
+    #[error("Unexpected bytes after the module")]
+    TrailingBytes,
+    #[error("Invalid entry chunk id {0}")]
+    InvalidEntryChunk(usize)
+}
+
+// 64 bits FNV-1a hash, used to detect a corrupted module
+fn checksum(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| (hash ^ *byte as u64).wrapping_mul(0x100000001b3))
+}
+
+fn write_len(buf: &mut Vec<u8>, len: usize) {
+    buf.extend_from_slice(&(len as u32).to_be_bytes());
+}
+
+fn read_bytes<'a>(reader: &mut &'a [u8], len: usize) -> Result<&'a [u8], ModuleError> {
+    if reader.len() < len {
+        return Err(ModuleError::UnexpectedEndOfInput)
+    }
+
+    let (bytes, rest) = reader.split_at(len);
+    *reader = rest;
+    Ok(bytes)
+}
+
+fn read_len(reader: &mut &[u8]) -> Result<usize, ModuleError> {
+    let bytes = read_bytes(reader, 4)?;
+    Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+}
+
 // A module is a collection of declared chunks, constants and structs
 // It represents a program compiled in bytecode
 pub struct Module {
     // TODO use a IndexSet
     constants: Vec<Value>,
+    // Type of each constant, computed once when added
+    // None if the type can't be known (empty array, null...)
+    constants_types: Vec<Option<Type>>,
     // Available chunks
     chunks: Vec<Chunk>,
     // Chunks callable from external programs
@@ -22,6 +80,7 @@ impl Module {
     pub fn new() -> Self {
         Module {
             constants: Vec::new(),
+            constants_types: Vec::new(),
             chunks: Vec::new(),
             entry_chunk_ids: HashSet::new(),
             structs: Vec::new()
@@ -40,11 +99,18 @@ impl Module {
         if let Some(index) = self.constants.iter().position(|v| v == &value) {
             index
         } else {
+            self.constants_types.push(value.get_type().ok());
             self.constants.push(value);
             self.constants.len() - 1
         }
     }
 
+    // Get the type of a constant at a specific index
+    #[inline]
+    pub fn get_constant_type(&self, index: usize) -> Option<&Type> {
+        self.constants_types.get(index).and_then(Option::as_ref)
+    }
+
     // Get a constant at a specific index
     #[inline]
     pub fn get_constant_at(&self, index: usize) -> Option<&Value> {
@@ -106,4 +172,231 @@ impl Module {
     pub fn get_struct_at(&self, index: usize) -> Option<&StructType> {
         self.structs.get(index)
     }
+
+    // Encode the constants, structs and chunks of the module
+    fn serialize_body(&self, buf: &mut Vec<u8>) {
+        write_len(buf, self.constants.len());
+        for constant in &self.constants {
+            constant.serialize(buf);
+        }
+
+        write_len(buf, self.structs.len());
+        for structure in &self.structs {
+            Type::Struct(structure.clone()).serialize(buf);
+        }
+
+        write_len(buf, self.chunks.len());
+        for chunk in &self.chunks {
+            let instructions = chunk.get_instructions();
+            write_len(buf, instructions.len());
+            buf.extend_from_slice(instructions);
+        }
+
+        // Sorted so the same module always produces the same bytes
+        let mut entry_ids = self.entry_chunk_ids.iter().copied().collect::<Vec<_>>();
+        entry_ids.sort_unstable();
+        write_len(buf, entry_ids.len());
+        for id in entry_ids {
+            write_len(buf, id);
+        }
+    }
+
+    fn deserialize_body(mut reader: &[u8]) -> Result<Self, ModuleError> {
+        let mut module = Module::new();
+
+        // Constants are already unique, keep their indexes
+        // The module may be untrusted, the nesting of the values and types is limited
+        let len = read_len(&mut reader)?;
+        for _ in 0..len {
+            let value = Value::deserialize_with_max_depth(&mut reader, MAX_VALUE_DEPTH)?;
+            module.constants_types.push(value.get_type().ok());
+            module.constants.push(value);
+        }
+
+        let len = read_len(&mut reader)?;
+        for _ in 0..len {
+            match Type::deserialize_with_max_depth(&mut reader, MAX_VALUE_DEPTH)? {
+                Type::Struct(structure) => module.add_struct(structure),
+                _ => return Err(ValueError::InvalidPrimitiveType.into())
+            }
+        }
+
+        let len = read_len(&mut reader)?;
+        for _ in 0..len {
+            let size = read_len(&mut reader)?;
+            let instructions = read_bytes(&mut reader, size)?;
+            module.add_chunk(Chunk::from_instructions(instructions.to_vec()));
+        }
+
+        let len = read_len(&mut reader)?;
+        for _ in 0..len {
+            let id = read_len(&mut reader)?;
+            if id >= module.chunks.len() {
+                return Err(ModuleError::InvalidEntryChunk(id))
+            }
+            module.entry_chunk_ids.insert(id);
+        }
+
+        if !reader.is_empty() {
+            return Err(ModuleError::TrailingBytes)
+        }
+
+        Ok(module)
+    }
+
+    // Write the module as a single artifact
+    // The header contains the format version and the checksum of the body
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<(), ModuleError> {
+        let mut body = Vec::new();
+        self.serialize_body(&mut body);
+
+        writer.write_all(&MODULE_MAGIC)?;
+        writer.write_all(&MODULE_FORMAT_VERSION.to_be_bytes())?;
+        writer.write_all(&checksum(&body).to_be_bytes())?;
+        writer.write_all(&body)?;
+        Ok(())
+    }
+
+    // Read a module written by Module::write
+    // The version and the checksum are verified before decoding the body
+    pub fn read<R: Read>(reader: &mut R) -> Result<Self, ModuleError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        if bytes.len() < HEADER_SIZE {
+            return Err(ModuleError::UnexpectedEndOfInput)
+        }
+
+        let (header, body) = bytes.split_at(HEADER_SIZE);
+        if header[0..4] != MODULE_MAGIC {
+            return Err(ModuleError::InvalidMagic)
+        }
+
+        let version = u16::from_be_bytes([header[4], header[5]]);
+        if version != MODULE_FORMAT_VERSION {
+            return Err(ModuleError::InvalidVersion(version))
+        }
+
+        let mut expected = [0; 8];
+        expected.copy_from_slice(&header[6..HEADER_SIZE]);
+        if checksum(body) != u64::from_be_bytes(expected) {
+            return Err(ModuleError::InvalidChecksum)
+        }
+
+        Self::deserialize_body(body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use xelis_types::ValueOwnable;
+    use super::*;
+
+    #[test]
+    fn test_constant_type() {
+        let mut module = Module::new();
+        let values = [
+            Value::U64(10),
+            Value::String("hello".to_owned()),
+            Value::Array(vec![ValueOwnable::Owned(Box::new(Value::U8(1)))]),
+            Value::Array(Vec::new()),
+            Value::Null
+        ];
+
+        for value in values {
+            let index = module.add_constant(value);
+            let value = module.get_constant_at(index).unwrap();
+            assert_eq!(module.get_constant_type(index), value.get_type().ok().as_ref());
+        }
+
+        assert_eq!(module.get_constant_type(0), Some(&Type::U64));
+        assert_eq!(module.get_constant_type(3), None);
+        assert_eq!(module.get_constant_type(5), None);
+    }
+
+    fn build_module() -> Module {
+        let mut module = Module::new();
+        let structure = StructType::new(0, vec![Type::U64, Type::String]);
+        module.add_struct(structure.clone());
+        module.add_constant(Value::U64(10));
+        module.add_constant(Value::Struct(vec![
+            ValueOwnable::Owned(Box::new(Value::U64(1))),
+            ValueOwnable::Owned(Box::new(Value::String("hello".to_owned())))
+        ], structure));
+
+        let mut chunk = Chunk::new();
+        chunk.emit_opcode(crate::OpCode::Constant);
+        chunk.write_u16(0);
+        chunk.emit_opcode(crate::OpCode::Return);
+        module.add_chunk(chunk);
+
+        let mut chunk = Chunk::new();
+        chunk.emit_opcode(crate::OpCode::Return);
+        module.add_entry_chunk(chunk);
+        module
+    }
+
+    #[test]
+    fn test_module_round_trip() {
+        let module = build_module();
+        let mut buf = Vec::new();
+        module.write(&mut buf).unwrap();
+
+        let decoded = Module::read(&mut buf.as_slice()).unwrap();
+        assert_eq!(decoded.constants(), module.constants());
+        assert_eq!(decoded.get_constant_type(1), module.get_constant_type(1));
+        assert_eq!(decoded.get_struct_at(0), module.get_struct_at(0));
+        assert_eq!(decoded.chunks().len(), 2);
+        for (a, b) in decoded.chunks().iter().zip(module.chunks()) {
+            assert_eq!(a.get_instructions(), b.get_instructions());
+        }
+        assert_eq!(decoded.chunks_entry_ids(), module.chunks_entry_ids());
+
+        // The same module is always written the same way
+        let mut again = Vec::new();
+        decoded.write(&mut again).unwrap();
+        assert_eq!(again, buf);
+    }
+
+    #[test]
+    fn test_module_invalid_header() {
+        let mut buf = Vec::new();
+        build_module().write(&mut buf).unwrap();
+
+        let mut bad_version = buf.clone();
+        bad_version[4..6].copy_from_slice(&(MODULE_FORMAT_VERSION + 1).to_be_bytes());
+        assert!(matches!(Module::read(&mut bad_version.as_slice()), Err(ModuleError::InvalidVersion(v)) if v == MODULE_FORMAT_VERSION + 1));
+
+        let mut corrupted = buf.clone();
+        *corrupted.last_mut().unwrap() ^= 1;
+        assert!(matches!(Module::read(&mut corrupted.as_slice()), Err(ModuleError::InvalidChecksum)));
+
+        assert!(matches!(Module::read(&mut &buf[..HEADER_SIZE - 1]), Err(ModuleError::UnexpectedEndOfInput)));
+        assert!(matches!(Module::read(&mut &b"nope"[..]), Err(ModuleError::UnexpectedEndOfInput)));
+    }
+
+    #[test]
+    fn test_module_deeply_nested_constant() {
+        let nested = |depth: usize| {
+            let mut value = Value::Null;
+            for _ in 1..depth {
+                value = Value::Optional(Some(ValueOwnable::Owned(Box::new(value))));
+            }
+            value
+        };
+
+        let mut module = Module::new();
+        module.add_constant(nested(MAX_VALUE_DEPTH));
+        let mut buf = Vec::new();
+        module.write(&mut buf).unwrap();
+        assert_eq!(Module::read(&mut buf.as_slice()).unwrap().constants(), module.constants());
+
+        let mut module = Module::new();
+        module.add_constant(nested(MAX_VALUE_DEPTH + 1));
+        let mut buf = Vec::new();
+        module.write(&mut buf).unwrap();
+        assert!(matches!(
+            Module::read(&mut buf.as_slice()),
+            Err(ModuleError::ValueError(ValueError::MaxDepthReached(MAX_VALUE_DEPTH)))
+        ));
+    }
 }
@@ -77,7 +77,7 @@ pub enum OpCode {
     Shr,
     // ==
     Eq,
-    // !
+    // ! (logical not of a bool)
     Neg,
     // >
     Gt,
@@ -122,6 +122,20 @@ pub enum OpCode {
 
     // N..Y
     NewRange,
+
+    // ~ (bitwise not of a number)
+    Not,
+
+    // pop length, pop N values => create tuple
+    NewTuple,
+
+    // pop, set an independent copy in registers[index]
+    // used by variable declarations to not alias the value
+    MemorySetOwned,
+
+    // pop, push its truthiness as a boolean
+    // used by the conditions of a program compiled with truthy conditions
+    Truthy,
 }
 
 impl OpCode {
@@ -192,6 +206,10 @@ impl OpCode {
             OpCode::Inc => 51,
             OpCode::Dec => 52,
             OpCode::NewRange => 53,
+            OpCode::Not => 54,
+            OpCode::NewTuple => 55,
+            OpCode::MemorySetOwned => 56,
+            OpCode::Truthy => 57,
         }
     }
 
@@ -256,6 +274,10 @@ impl OpCode {
             51 => OpCode::Inc,
             52 => OpCode::Dec,
             53 => OpCode::NewRange,
+            54 => OpCode::Not,
+            55 => OpCode::NewTuple,
+            56 => OpCode::MemorySetOwned,
+            57 => OpCode::Truthy,
             _ => return None,
         })
     }
@@ -16,4 +16,20 @@ pub enum CompilerError {
     ExpectedVariable,
     #[error("expected a primitive type")]
     ExpectedPrimitiveType,
+    #[error("expected a function")]
+    ExpectedFunction,
+    #[error("expected a default value for the omitted argument")]
+    ExpectedDefaultValue,
+    #[error("try operator is not supported")]
+    UnsupportedTryOperator,
+    #[error("optional chaining is not supported")]
+    UnsupportedOptionalChaining,
+    #[error("struct update is not supported")]
+    UnsupportedStructUpdate,
+    #[error("block expression is not supported")]
+    UnsupportedBlockExpression,
+    #[error("function value call is not supported")]
+    UnsupportedValueCall,
+    #[error("labeled jump out of a foreach loop is not supported")]
+    UnsupportedLabeledJump,
 }
\ No newline at end of file
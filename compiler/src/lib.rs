@@ -25,10 +25,14 @@ pub struct Compiler<'a> {
     loop_break_patch: Vec<Vec<usize>>,
     // Index of continue jump to patch
     loop_continue_patch: Vec<Vec<usize>>,
+    // Is each loop an iterator loop (foreach)
+    loop_iterators: Vec<bool>,
     // Used for OpCode::MemorySet
     // For each scope, we store the next id to use
     // So, outside of a scope we reset to the same level
     memstore_ids: Vec<u16>,
+    // Convert each condition to a boolean using its truthiness
+    truthy_conditions: bool,
 }
 
 impl<'a> Compiler<'a> {
@@ -40,10 +44,18 @@ impl<'a> Compiler<'a> {
             module: Module::new(),
             loop_break_patch: Vec::new(),
             loop_continue_patch: Vec::new(),
+            loop_iterators: Vec::new(),
             memstore_ids: Vec::new(),
+            truthy_conditions: false,
         }
     }
 
+    // Must be set for a program parsed with truthy conditions
+    // Otherwise, a condition that isn't a boolean fails in the VM
+    pub fn set_truthy_conditions(&mut self, truthy_conditions: bool) {
+        self.truthy_conditions = truthy_conditions;
+    }
+
     // Map the operator to the opcode
     fn map_operator_to_opcode(op: &Operator) -> Result<OpCode, CompilerError> {
         Ok(match op {
@@ -72,9 +84,29 @@ impl<'a> Compiler<'a> {
         })
     }
 
+    // Compile the condition of an if, while, for or ternary
+    fn compile_condition(&mut self, chunk: &mut Chunk, condition: &Expression) -> Result<(), CompilerError> {
+        self.compile_expr(chunk, condition)?;
+        if self.truthy_conditions {
+            chunk.emit_opcode(OpCode::Truthy);
+        }
+
+        Ok(())
+    }
+
     // Emit a memory store
     fn memstore(&mut self, chunk: &mut Chunk) -> Result<(), CompilerError> {
-        chunk.emit_opcode(OpCode::MemorySet);
+        self.memstore_with(chunk, OpCode::MemorySet)
+    }
+
+    // Emit a memory store of an independent copy of the value
+    // Used by the variables declarations
+    fn memstore_owned(&mut self, chunk: &mut Chunk) -> Result<(), CompilerError> {
+        self.memstore_with(chunk, OpCode::MemorySetOwned)
+    }
+
+    fn memstore_with(&mut self, chunk: &mut Chunk, opcode: OpCode) -> Result<(), CompilerError> {
+        chunk.emit_opcode(opcode);
         let id = self.memstore_ids.last_mut().ok_or(CompilerError::ExpectedMemstoreId)?;
         chunk.write_u16(*id);
         *id += 1;
@@ -98,6 +130,13 @@ impl<'a> Compiler<'a> {
                 chunk.emit_opcode(OpCode::NewArray);
                 chunk.write_u32(exprs.len() as u32);
             },
+            Expression::TupleConstructor(exprs) => {
+                for expr in exprs {
+                    self.compile_expr(chunk, expr)?;
+                }
+                chunk.emit_opcode(OpCode::NewTuple);
+                chunk.write_u32(exprs.len() as u32);
+            },
             Expression::StructConstructor(exprs, _type) => {
                 for expr in exprs {
                     self.compile_expr(chunk, expr)?;
@@ -125,6 +164,10 @@ impl<'a> Compiler<'a> {
                 self.compile_expr(chunk, expr)?;
                 chunk.emit_opcode(OpCode::Neg);
             },
+            Expression::BitwiseNot(expr) => {
+                self.compile_expr(chunk, expr)?;
+                chunk.emit_opcode(OpCode::Not);
+            },
             Expression::ArrayCall(expr, expr_index) => {
                 self.compile_expr(chunk, expr)?;
                 self.compile_expr(chunk, expr_index)?;
@@ -134,7 +177,7 @@ impl<'a> Compiler<'a> {
                 self.compile_expr(chunk, expr)?;
             },
             Expression::Ternary(condition, valid, invalid) => {
-                self.compile_expr(chunk, condition)?;
+                self.compile_condition(chunk, condition)?;
 
                 // Emit the jump if false
                 // We will overwrite the addr later
@@ -177,6 +220,22 @@ impl<'a> Compiler<'a> {
 
                 // Functions from the environment are system calls
                 let len = self.environment.get_functions().len();
+
+                // Omitted arguments are compiled from their default value
+                // They can't reference any variable, so it's the same as evaluating it in the callee
+                let mut args = params.len();
+                if (*id as usize) >= len {
+                    let function = self.program.functions()
+                        .get(*id as usize - len)
+                        .ok_or(CompilerError::ExpectedFunction)?;
+
+                    for param in function.get_parameters().iter().skip(args) {
+                        let default_value = param.get_default_value().ok_or(CompilerError::ExpectedDefaultValue)?;
+                        self.compile_expr(chunk, default_value)?;
+                        args += 1;
+                    }
+                }
+
                 if (*id as usize) < len {
                     chunk.emit_opcode(OpCode::SysCall);
                     chunk.write_u16(*id);
@@ -186,7 +245,7 @@ impl<'a> Compiler<'a> {
                 }
 
                 chunk.write_bool(expr_on.is_some());
-                chunk.write_u8(params.len() as u8);
+                chunk.write_u8(args as u8);
             },
             Expression::Operator(op, left, right) => {
                 match op {
@@ -252,7 +311,15 @@ impl<'a> Compiler<'a> {
                 self.compile_expr(chunk, min)?;
                 self.compile_expr(chunk, max)?;
                 chunk.emit_opcode(OpCode::NewRange);
-            }
+            },
+            // The VM can't unwind a chunk from an expression yet
+            Expression::Try(_) => return Err(CompilerError::UnsupportedTryOperator),
+            Expression::OptionalPath(_, _) => return Err(CompilerError::UnsupportedOptionalChaining),
+            Expression::StructUpdate(_, _) => return Err(CompilerError::UnsupportedStructUpdate),
+            // The VM can't return from a function in the middle of an expression yet
+            Expression::Block(_, _, _) => return Err(CompilerError::UnsupportedBlockExpression),
+            // The VM has no opcode to call a function from its id on the stack yet
+            Expression::ValueCall(_, _) => return Err(CompilerError::UnsupportedValueCall)
         }
 
         Ok(())
@@ -269,9 +336,25 @@ impl<'a> Compiler<'a> {
     }
 
     // Start a loop by pushing the break/continue vec to track them
-    fn start_loop(&mut self) {
+    // An iterator loop must run its IteratorEnd when left
+    fn start_loop(&mut self, iterator: bool) {
         self.loop_break_patch.push(Vec::new());
         self.loop_continue_patch.push(Vec::new());
+        self.loop_iterators.push(iterator);
+    }
+
+    // Index of the loop targeted by a break or continue
+    // A jump can't leave an iterator loop without ending its iterator
+    fn get_loop_index(&self, depth: u16, missing: CompilerError) -> Result<usize, CompilerError> {
+        let index = self.loop_break_patch.len()
+            .checked_sub(depth as usize + 1)
+            .ok_or(missing)?;
+
+        if self.loop_iterators[index + 1..].iter().any(|iterator| *iterator) {
+            return Err(CompilerError::UnsupportedLabeledJump)
+        }
+
+        Ok(index)
     }
 
     // End the loop by patching all continue/break
@@ -285,6 +368,7 @@ impl<'a> Compiler<'a> {
         for jump in self.loop_continue_patch.pop().ok_or(CompilerError::ExpectedContinue)? {
             chunk.patch_jump(jump, start_index as u32);
         }
+        self.loop_iterators.pop();
 
         Ok(())
     }
@@ -303,7 +387,19 @@ impl<'a> Compiler<'a> {
                 },
                 Statement::Variable(declaration) => {
                     self.compile_expr(chunk, &declaration.value)?;
-                    self.memstore(chunk)?;
+                    self.memstore_owned(chunk)?;
+                },
+                Statement::TupleDeconstruction(variables, expr) => {
+                    self.compile_expr(chunk, expr)?;
+                    for i in 0..variables.len() {
+                        // Keep the tuple on the stack until its last value
+                        if i + 1 < variables.len() {
+                            chunk.emit_opcode(OpCode::Copy);
+                        }
+                        chunk.emit_opcode(OpCode::SubLoad);
+                        chunk.write_u16(i as u16);
+                        self.memstore_owned(chunk)?;
+                    }
                 },
                 Statement::Scope(statements) => {
                     self.push_mem_scope();
@@ -311,7 +407,7 @@ impl<'a> Compiler<'a> {
                     self.pop_mem_scope();
                 },
                 Statement::If(condition, statements, else_statements) => {
-                    self.compile_expr(chunk, condition)?;
+                    self.compile_condition(chunk, condition)?;
 
                     self.push_mem_scope();
 
@@ -355,7 +451,7 @@ impl<'a> Compiler<'a> {
                 },
                 Statement::While(expr, statements) => {
                     let start_index = chunk.index();
-                    self.compile_expr(chunk, expr)?;
+                    self.compile_condition(chunk, expr)?;
 
                     // Emit the jump if false
                     // We will overwrite the addr later
@@ -363,7 +459,7 @@ impl<'a> Compiler<'a> {
                     chunk.write_u32(INVALID_ADDR);
                     let jump_addr = chunk.last_index();
 
-                    self.start_loop();
+                    self.start_loop(false);
                     // Compile the valid condition
                     self.compile_statements(chunk, statements)?;
 
@@ -392,7 +488,7 @@ impl<'a> Compiler<'a> {
                     // Store the value
                     self.memstore(chunk)?;
 
-                    self.start_loop();
+                    self.start_loop(true);
                     // Compile the valid condition
                     self.compile_statements(chunk, statements)?;
 
@@ -419,7 +515,7 @@ impl<'a> Compiler<'a> {
 
                     // Compile the condition
                     let start_index = chunk.index();
-                    self.compile_expr(chunk, expr_condition)?;
+                    self.compile_condition(chunk, expr_condition)?;
 
                     // Emit the jump if false
                     // We will overwrite the addr later
@@ -427,7 +523,7 @@ impl<'a> Compiler<'a> {
                     chunk.write_u32(INVALID_ADDR);
                     let jump_addr = chunk.last_index();
 
-                    self.start_loop();
+                    self.start_loop(false);
                     // Compile the valid condition
                     self.compile_statements(chunk, statements)?;
 
@@ -446,21 +542,19 @@ impl<'a> Compiler<'a> {
 
                     self.end_loop(chunk, continue_index, jump_false_addr)?;
                 },
-                Statement::Break => {
+                Statement::Break(depth) => {
+                    let index = self.get_loop_index(*depth, CompilerError::ExpectedBreak)?;
+
                     chunk.emit_opcode(OpCode::Jump);
                     chunk.write_u32(INVALID_ADDR);
-
-                    let last = self.loop_break_patch.last_mut()
-                        .ok_or(CompilerError::ExpectedBreak)?;
-                    last.push(chunk.last_index());
+                    self.loop_break_patch[index].push(chunk.last_index());
                 },
-                Statement::Continue => {
+                Statement::Continue(depth) => {
+                    let index = self.get_loop_index(*depth, CompilerError::ExpectedContinue)?;
+
                     chunk.emit_opcode(OpCode::Jump);
                     chunk.write_u32(INVALID_ADDR);
-
-                    let last = self.loop_continue_patch.last_mut()
-                        .ok_or(CompilerError::ExpectedContinue)?;
-                    last.push(chunk.last_index());
+                    self.loop_continue_patch[index].push(chunk.last_index());
                 }
             };
         }
@@ -653,7 +747,7 @@ mod tests {
 
     #[test]
     fn test_while() {
-        let (program, environment) = prepare_program("entry main() { let i: u64 = 0; while i < 10 { i += 1; } return i }");
+        let (program, environment) = prepare_program("entry main() { var i: u64 = 0; while i < 10 { i += 1; } return i }");
         let compiler = Compiler::new(&program, &environment);
         let module = compiler.compile().unwrap();
 
@@ -662,7 +756,7 @@ mod tests {
             chunk.get_instructions(),
             &[
                 OpCode::Constant.as_byte(), 0, 0,
-                OpCode::MemorySet.as_byte(), 0, 0,
+                OpCode::MemorySetOwned.as_byte(), 0, 0,
                 OpCode::MemoryLoad.as_byte(), 0, 0,
                 OpCode::Constant.as_byte(), 1, 0,
                 OpCode::Lt.as_byte(),
@@ -749,7 +843,7 @@ mod tests {
                 OpCode::Constant.as_byte(), 0, 0,
                 OpCode::Constant.as_byte(), 1, 0,
                 OpCode::NewStruct.as_byte(), 0, 0,
-                OpCode::MemorySet.as_byte(), 0, 0,
+                OpCode::MemorySetOwned.as_byte(), 0, 0,
                 OpCode::MemoryLoad.as_byte(), 0, 0,
                 OpCode::SubLoad.as_byte(), 0, 0,
                 OpCode::Return.as_byte()
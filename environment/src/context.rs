@@ -0,0 +1,117 @@
+use std::{cell::RefCell, fmt, rc::Rc};
+use xelis_types::Value;
+use crate::{EnvironmentError, OutputSink, RandomSource, StdoutSink};
+
+// Context given to the native functions during a program execution
+// It is owned by the interpreter / VM and kept between the calls
+pub struct Context {
+    // Events emitted by the program, in order
+    events: Vec<(String, Value)>,
+    // Sink receiving the text printed by the program
+    output: Rc<dyn OutputSink>,
+    // Source of the random numbers
+    random: Option<Rc<RefCell<dyn RandomSource>>>,
+    // Count of array and struct elements created
+    allocations: u64,
+    // Maximum count of elements that can be created, 0 means unlimited
+    max_allocations: u64
+}
+
+impl Default for Context {
+    fn default() -> Self {
+        Self::with_output(Rc::new(StdoutSink))
+    }
+}
+
+impl fmt::Debug for Context {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Context")
+            .field("events", &self.events)
+            .field("allocations", &self.allocations)
+            .field("max_allocations", &self.max_allocations)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Context {
+    // Create a new empty context
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Create a new empty context printing to the given sink
+    pub fn with_output(output: Rc<dyn OutputSink>) -> Self {
+        Self {
+            events: Vec::new(),
+            output,
+            random: None,
+            allocations: 0,
+            max_allocations: 0
+        }
+    }
+
+    // Set the maximum count of array and struct elements that can be created
+    // 0 disable the limit
+    pub fn set_max_allocations(&mut self, max_allocations: u64) {
+        self.max_allocations = max_allocations;
+    }
+
+    // Get the count of array and struct elements created until now
+    #[inline(always)]
+    pub fn get_allocations(&self) -> u64 {
+        self.allocations
+    }
+
+    // Reset the count of elements created, for a new execution
+    #[inline(always)]
+    pub fn reset_allocations(&mut self) {
+        self.allocations = 0;
+    }
+
+    // Account the creation of count elements
+    pub fn allocate(&mut self, count: u64) -> Result<(), EnvironmentError> {
+        self.allocations = self.allocations.saturating_add(count);
+        if self.max_allocations != 0 && self.allocations > self.max_allocations {
+            return Err(EnvironmentError::AllocationLimitReached(self.max_allocations))
+        }
+
+        Ok(())
+    }
+
+    // Send text printed by the program to the output sink
+    #[inline(always)]
+    pub fn write_output(&self, s: &str) {
+        self.output.write(s);
+    }
+
+    // Set the source of the random numbers, none disable the random functions
+    pub fn set_random_source(&mut self, random: Option<Rc<RefCell<dyn RandomSource>>>) {
+        self.random = random;
+    }
+
+    // Get the next random number from the source
+    pub fn next_random(&self) -> Result<u64, EnvironmentError> {
+        match &self.random {
+            Some(random) => Ok(random.borrow_mut().next_u64()),
+            None => Err(EnvironmentError::NoRandomSource)
+        }
+    }
+
+    // Push an event to be read by the host
+    #[inline(always)]
+    pub fn emit_event(&mut self, name: String, data: Value) {
+        self.events.push((name, data));
+    }
+
+    // Get the events emitted until now
+    #[inline(always)]
+    pub fn get_events(&self) -> &Vec<(String, Value)> {
+        &self.events
+    }
+
+    // Take all the events emitted, leaving the context empty
+    #[inline(always)]
+    pub fn take_events(&mut self) -> Vec<(String, Value)> {
+        std::mem::take(&mut self.events)
+    }
+}
@@ -9,6 +9,9 @@ pub enum EnvironmentError {
     FnExpectedInstance,
     #[error("Panic: {0}")]
     Panic(Value),
+    // Execution deliberately stopped by the program
+    #[error("Aborted: {0}")]
+    Aborted(String),
     #[error("Out of bounds: {0} > {1}")]
     OutOfBounds(usize, usize),
     #[error("Invalid range: {0} > {1}")]
@@ -21,4 +24,20 @@ pub enum EnvironmentError {
     ValueError(#[from] ValueError),
     #[error("Invalid range: too large")]
     RangeTooLarge,
+    #[error("Allocation limit reached: {0}")]
+    AllocationLimitReached(u64),
+    #[error("yield is only allowed as a top level statement of a resumable execution")]
+    InvalidYield,
+    #[error("No random source configured")]
+    NoRandomSource,
+    #[error("Invalid random range: {0}..{1} is empty")]
+    EmptyRandomRange(u64, u64),
+    #[error("Random source failed to provide a uniform value")]
+    RandomSourceExhausted,
+    #[error("Invalid clamp bounds: {0} > {1}")]
+    InvalidClampBounds(Value, Value),
+    #[error("Division by zero")]
+    DivByZero,
+    #[error("Names of the struct fields are unknown")]
+    UnknownFieldsNames,
 }
\ No newline at end of file
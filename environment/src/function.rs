@@ -1,12 +1,16 @@
 use xelis_types::{Path, Type, Value};
-use super::EnvironmentError;
+use super::{Context, EnvironmentError};
 
 // first parameter is the current value / instance
 // second is the list of all parameters for this function call
+// third is the context of the current execution
 pub type FnReturnType = Result<Option<Value>, EnvironmentError>;
 pub type FnInstance<'a> = Result<&'a mut Value, EnvironmentError>;
 pub type FnParams<'a> = Vec<Path<'a>>;
-pub type OnCallFn = fn(FnInstance, FnParams) -> FnReturnType;
+pub type OnCallFn = fn(FnInstance, FnParams, &mut Context) -> FnReturnType;
+// Additional cost of a call computed from its instance, if any, and its parameters
+// Allow to price a call proportionally to the size of its input
+pub type CostFn = fn(Option<&Path>, &[Path]) -> u64;
 
 // Native function that is implemented in Rust
 // This is used to register functions in the environment
@@ -18,6 +22,8 @@ pub struct NativeFunction {
     on_call: OnCallFn,
     // cost for each call
     cost: u64,
+    // additional cost computed for each call
+    cost_fn: Option<CostFn>,
     // expected type of the returned value
     return_type: Option<Type>
 }
@@ -30,12 +36,13 @@ impl NativeFunction {
             parameters,
             on_call,
             cost,
+            cost_fn: None,
             return_type
         }
     }
 
     // Execute the function
-    pub fn call_function(&self, instance_value: Option<&mut Value>, parameters: FnParams) -> Result<Option<Value>, EnvironmentError> {
+    pub fn call_function(&self, instance_value: Option<&mut Value>, parameters: FnParams, context: &mut Context) -> Result<Option<Value>, EnvironmentError> {
         if parameters.len() != self.parameters.len() || (instance_value.is_some() != self.for_type.is_some()) {
             return Err(EnvironmentError::InvalidFnCall)
         }
@@ -44,7 +51,12 @@ impl NativeFunction {
             Some(v) => Ok(v),
             None => Err(EnvironmentError::FnExpectedInstance)
         };
-        (self.on_call)(instance, parameters)
+        (self.on_call)(instance, parameters, context)
+    }
+
+    // Get the type on which the function is called
+    pub fn get_for_type(&self) -> &Option<Type> {
+        &self.for_type
     }
 
     // Get parameters of the function
@@ -66,4 +78,17 @@ impl NativeFunction {
     pub fn set_cost(&mut self, cost: u64) {
         self.cost = cost;
     }
+
+    // Set the function computing the additional cost of a call
+    pub fn set_cost_fn(&mut self, cost_fn: Option<CostFn>) {
+        self.cost_fn = cost_fn;
+    }
+
+    // Get the total cost of a call on this instance with these parameters
+    pub fn get_call_cost(&self, instance: Option<&Path>, parameters: &[Path]) -> u64 {
+        match self.cost_fn {
+            Some(cost_fn) => self.cost.saturating_add(cost_fn(instance, parameters)),
+            None => self.cost
+        }
+    }
 }
\ No newline at end of file
@@ -1,18 +1,31 @@
 mod error;
 mod function;
+mod context;
+mod output;
+mod random;
 
 pub use error::EnvironmentError;
 pub use function::*;
+pub use context::Context;
+pub use output::*;
+pub use random::*;
 
+use std::{cell::RefCell, rc::Rc};
 use xelis_types::StructType;
 
 /// Environment is used to store all the registered functions and structures
 /// It is used to give a context/std library to the parser / interpreter / VM
 pub struct Environment {
     // All functions provided by the Environment
+    // A call is resolved by its id, the index in this list: the names are
+    // only known by the FunctionMapper used while parsing
     functions: Vec<NativeFunction>,
     // All structures provided by the Environment
     structures: Vec<StructType>,
+    // Sink receiving the text printed by the program
+    output: Rc<dyn OutputSink>,
+    // Source of the random numbers, none disable the random functions
+    random: Option<Rc<RefCell<dyn RandomSource>>>,
 }
 
 impl Default for Environment {
@@ -20,6 +33,8 @@ impl Default for Environment {
         Self {
             functions: Vec::new(),
             structures: Vec::new(),
+            output: Rc::new(StdoutSink),
+            random: None,
         }
     }
 }
@@ -42,6 +57,28 @@ impl Environment {
         &self.structures
     }
 
+    // Get the sink receiving the text printed by the program
+    #[inline(always)]
+    pub fn get_output_sink(&self) -> &Rc<dyn OutputSink> {
+        &self.output
+    }
+
+    // Set the sink receiving the text printed by the program
+    pub fn set_output_sink(&mut self, output: Rc<dyn OutputSink>) {
+        self.output = output;
+    }
+
+    // Get the source of the random numbers
+    #[inline(always)]
+    pub fn get_random_source(&self) -> Option<&Rc<RefCell<dyn RandomSource>>> {
+        self.random.as_ref()
+    }
+
+    // Set the source of the random numbers, none disable the random functions
+    pub fn set_random_source(&mut self, random: Option<Rc<RefCell<dyn RandomSource>>>) {
+        self.random = random;
+    }
+
     // Add a new function to the environment
     #[inline(always)]
     pub fn add_function(&mut self, function: NativeFunction) {
@@ -60,4 +97,11 @@ impl Environment {
             function.set_cost(cost);
         }
     }
+
+    // Allow to change the additional cost computed for each call of a function
+    pub fn set_cost_fn_for_function_at_index(&mut self, index: usize, cost_fn: Option<CostFn>) {
+        if let Some(function) = self.functions.get_mut(index) {
+            function.set_cost_fn(cost_fn);
+        }
+    }
 }
\ No newline at end of file
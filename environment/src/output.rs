@@ -0,0 +1,23 @@
+// Destination of the text printed by the program
+// The host can redirect it to a log, a buffer or discard it
+pub trait OutputSink {
+    fn write(&self, s: &str);
+}
+
+// Write the output to the standard output
+#[derive(Debug, Default, Clone, Copy)]
+pub struct StdoutSink;
+
+impl OutputSink for StdoutSink {
+    fn write(&self, s: &str) {
+        print!("{}", s);
+    }
+}
+
+// Discard the output
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NoopSink;
+
+impl OutputSink for NoopSink {
+    fn write(&self, _: &str) {}
+}
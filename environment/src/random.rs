@@ -0,0 +1,46 @@
+// Source of the random numbers used by the program
+// The host decides how they are produced: a seeded generator keeps
+// the executions deterministic, a fixed sequence is enough for tests
+pub trait RandomSource {
+    fn next_u64(&mut self) -> u64;
+}
+
+// Deterministic generator from a seed, using SplitMix64
+#[derive(Debug, Clone)]
+pub struct SeededRandom {
+    state: u64
+}
+
+impl SeededRandom {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            state: seed
+        }
+    }
+}
+
+impl RandomSource for SeededRandom {
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seeded_random() {
+        let mut a = SeededRandom::new(42);
+        let mut b = SeededRandom::new(42);
+        let values: Vec<u64> = (0..8).map(|_| a.next_u64()).collect();
+        assert_eq!(values, (0..8).map(|_| b.next_u64()).collect::<Vec<_>>());
+
+        let mut c = SeededRandom::new(43);
+        assert_ne!(values[0], c.next_u64());
+    }
+}
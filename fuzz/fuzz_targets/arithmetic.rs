@@ -0,0 +1,90 @@
+#![no_main]
+
+// Differential fuzzing of the interpreter arithmetic against native Rust
+// Each input is a binary operation on two operands of the same type,
+// it is executed by the interpreter and compared to the checked Rust operation
+// Both must agree on the result, or both must report an error (overflow, division by zero)
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use interpreter::{Interpreter, State};
+use xelis_ast::Signature;
+use xelis_builder::EnvironmentBuilder;
+use xelis_lexer::Lexer;
+use xelis_parser::Parser;
+
+#[derive(Debug, Arbitrary)]
+enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+impl Op {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Op::Add => "+",
+            Op::Sub => "-",
+            Op::Mul => "*",
+            Op::Div => "/",
+            Op::Rem => "%",
+        }
+    }
+}
+
+#[derive(Debug, Arbitrary)]
+enum Operands {
+    U8(u8, u8),
+    U64(u64, u64),
+}
+
+#[derive(Debug, Arbitrary)]
+struct Input {
+    op: Op,
+    operands: Operands,
+}
+
+macro_rules! checked_op {
+    ($op: expr, $a: expr, $b: expr) => {
+        match $op {
+            Op::Add => $a.checked_add($b),
+            Op::Sub => $a.checked_sub($b),
+            Op::Mul => $a.checked_mul($b),
+            Op::Div => $a.checked_div($b),
+            Op::Rem => $a.checked_rem($b),
+        }.map(|v| v as u64)
+    };
+}
+
+// Run the code through the lexer, parser and interpreter
+// Returns None if the interpreter reported an error during execution
+fn interpret(code: &str) -> Option<u64> {
+    let builder = EnvironmentBuilder::default();
+    let tokens = Lexer::new(code).get().expect(code);
+    let (program, mapper) = Parser::new(tokens, &builder).parse().expect(code);
+
+    let interpreter = Interpreter::new(&program, builder.environment()).expect("interpreter");
+    let main = mapper.get(&Signature::new("main".to_string(), None, Vec::new())).expect("main");
+
+    let mut state = State::new(None, None, None);
+    interpreter.call_entry_function(&main, Vec::new(), &mut state).ok()
+}
+
+fuzz_target!(|input: Input| {
+    let (type_name, a, b, expected) = match input.operands {
+        Operands::U8(a, b) => ("u8", a as u64, b as u64, checked_op!(input.op, a, b)),
+        Operands::U64(a, b) => ("u64", a, b, checked_op!(input.op, a, b)),
+    };
+
+    // Entry functions must return a u64, casting to the same type is rejected by the parser
+    let cast = if type_name == "u64" { "" } else { " as u64" };
+    let code = format!(
+        "entry main() {{ let a: {t} = {a}; let b: {t} = {b}; return (a {op} b){cast}; }}",
+        t = type_name,
+        op = input.op.as_str()
+    );
+
+    assert_eq!(interpret(&code), expected, "{}", code);
+});
@@ -0,0 +1,22 @@
+#![no_main]
+
+// Round trip of the value serialization
+// Each input is a value with consistent types, it is serialized then deserialized
+// The decoded value must be equal to the original one and be serialized to the same bytes
+
+use libfuzzer_sys::fuzz_target;
+use xelis_types::Value;
+
+fuzz_target!(|value: Value| {
+    let mut buf = Vec::new();
+    value.serialize(&mut buf);
+
+    let mut reader = buf.as_slice();
+    let decoded = Value::deserialize(&mut reader).expect("deserialize");
+    assert!(reader.is_empty(), "{} bytes left", reader.len());
+    assert!(decoded.is_equal(&value).expect("is equal"), "{:?} != {:?}", decoded, value);
+
+    let mut decoded_buf = Vec::new();
+    decoded.serialize(&mut decoded_buf);
+    assert_eq!(buf, decoded_buf);
+});
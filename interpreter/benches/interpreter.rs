@@ -0,0 +1,87 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use interpreter::{Interpreter, State};
+use xelis_ast::Signature;
+use xelis_builder::EnvironmentBuilder;
+use xelis_lexer::Lexer;
+use xelis_parser::Parser;
+
+macro_rules! bench {
+    ($group: expr, $name: expr, $code: expr) => {
+        $group.bench_function($name, |b| {
+            let tokens = Lexer::new($code).get().unwrap();
+            let env = EnvironmentBuilder::default();
+            let (program, mapper) = Parser::new(tokens, &env).parse().unwrap();
+            let interpreter = Interpreter::new(&program, env.environment()).unwrap();
+            let name = mapper.get(&Signature::new("main".to_string(), None, Vec::new())).unwrap();
+
+            b.iter(|| {
+                let mut state = State::new(None, None, None);
+                interpreter.call_entry_function(&name, Vec::new(), &mut state).unwrap();
+            });
+        });
+    };
+}
+
+fn bench_loop(c: &mut Criterion) {
+    let mut group = c.benchmark_group("loop");
+
+    // Condition and increment are handled by the integer loops fast-path
+    bench!(
+        group,
+        "for_int",
+        r#"
+        entry main() {
+            var a: u64 = 0;
+            for i: u64 = 0; i < 1_000_000; i += 1 {
+                a = i;
+            }
+
+            return 0;
+        }
+        "#
+    );
+
+    // Same loop going through the general expression path
+    bench!(
+        group,
+        "for_general",
+        r#"
+        entry main() {
+            var a: u64 = 0;
+            for i: u64 = 0; 1_000_000 > i; i = i + 1 {
+                a = i;
+            }
+
+            return 0;
+        }
+        "#
+    );
+}
+
+fn bench_function_call(c: &mut Criterion) {
+    let mut group = c.benchmark_group("function_call");
+
+    // Calls are resolved to a function index by the parser,
+    // this measures the call overhead of the interpreter
+    bench!(
+        group,
+        "loop",
+        r#"
+        fn square(n: u64) -> u64 {
+            return n * n;
+        }
+
+        entry main() {
+            var a: u64 = 0;
+            for i: u64 = 0; i < 10_000; i += 1 {
+                a = square(i);
+            }
+
+            return 0;
+        }
+        "#
+    );
+}
+
+criterion_group!(benches, bench_loop, bench_function_call);
+criterion_main!(benches);
@@ -0,0 +1,45 @@
+use xelis_types::{Type, Value, MAX_VALUE_DEPTH};
+
+// Signature expected by the host for the entry functions it calls
+// An entry function not matching it is rejected before its execution
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntryContract {
+    parameters: Vec<Type>,
+    return_type: Type
+}
+
+impl EntryContract {
+    pub fn new(parameters: Vec<Type>, return_type: Type) -> Self {
+        Self {
+            parameters,
+            return_type
+        }
+    }
+
+    #[inline(always)]
+    pub fn get_parameters(&self) -> &Vec<Type> {
+        &self.parameters
+    }
+
+    #[inline(always)]
+    pub fn get_return_type(&self) -> &Type {
+        &self.return_type
+    }
+}
+
+// Check if a value provided by the host can be used as the expected type
+pub(crate) fn is_value_of_type(value: &Value, expected: &Type) -> bool {
+    match Type::from_value_with_max_depth(value, MAX_VALUE_DEPTH) {
+        Ok(Some(value_type)) => value_type.is_compatible_with(expected),
+        // a value nested too deeply is never accepted
+        Err(_) => false,
+        // null, empty optionals, empty arrays and functions don't carry their type
+        Ok(None) => matches!(
+            (value, expected),
+            (_, Type::Any | Type::T)
+            | (Value::Null | Value::Optional(None), Type::Optional(_))
+            | (Value::Array(_), Type::Array(_))
+            | (Value::Function(_), Type::Function(..))
+        )
+    }
+}
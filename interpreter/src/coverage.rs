@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+use xelis_ast::{walk_statement, Program, Statement, Visitor};
+
+// Stable id of a statement: its index in a pre-order traversal of the program functions
+// The same program always gives the same ids
+pub type StatementId = usize;
+
+// Assign an id to each statement of the program
+#[derive(Default)]
+struct StatementIds {
+    // Statement address to its id
+    ids: HashMap<usize, StatementId>,
+    // Function index of each statement
+    functions: Vec<usize>,
+    function: usize
+}
+
+impl Visitor for StatementIds {
+    fn visit_program(&mut self, program: &Program) {
+        // Constants are stored in a set without a stable order, only functions are numbered
+        for (index, function) in program.functions().iter().enumerate() {
+            self.function = index;
+            self.visit_function(function);
+        }
+    }
+
+    fn visit_statement(&mut self, statement: &Statement) {
+        self.ids.insert(statement as *const Statement as usize, self.functions.len());
+        self.functions.push(self.function);
+        walk_statement(self, statement);
+    }
+}
+
+// Collect the statements executed by the interpreter
+pub(crate) struct Coverage {
+    ids: HashMap<usize, StatementId>,
+    functions: Vec<usize>,
+    executed: Vec<bool>
+}
+
+impl Coverage {
+    pub fn new(program: &Program) -> Self {
+        let mut ids = StatementIds::default();
+        ids.visit_program(program);
+
+        Self {
+            executed: vec![false; ids.functions.len()],
+            ids: ids.ids,
+            functions: ids.functions
+        }
+    }
+
+    // Mark the statement as executed
+    // Statements that are not part of the program, like the ones of an execution context, are ignored
+    #[inline]
+    pub fn hit(&mut self, statement: &Statement) {
+        if let Some(id) = self.ids.get(&(statement as *const Statement as usize)) {
+            self.executed[*id] = true;
+        }
+    }
+
+    pub fn report(&self) -> CoverageReport {
+        CoverageReport {
+            functions: self.functions.clone(),
+            executed: self.executed.clone()
+        }
+    }
+}
+
+// Statements of the program executed at least once
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoverageReport {
+    // Function index of each statement
+    functions: Vec<usize>,
+    executed: Vec<bool>
+}
+
+impl CoverageReport {
+    // Count of statements in the program
+    pub fn total(&self) -> usize {
+        self.executed.len()
+    }
+
+    // Count of statements executed
+    pub fn covered_count(&self) -> usize {
+        self.executed.iter().filter(|executed| **executed).count()
+    }
+
+    pub fn is_covered(&self, id: StatementId) -> bool {
+        self.executed.get(id).copied().unwrap_or(false)
+    }
+
+    // Index of the function containing the statement
+    pub fn function_of(&self, id: StatementId) -> Option<usize> {
+        self.functions.get(id).copied()
+    }
+
+    // Statements executed, in id order
+    pub fn covered(&self) -> Vec<StatementId> {
+        self.ids_where(true)
+    }
+
+    // Statements never executed, in id order
+    pub fn uncovered(&self) -> Vec<StatementId> {
+        self.ids_where(false)
+    }
+
+    fn ids_where(&self, executed: bool) -> Vec<StatementId> {
+        self.executed.iter()
+            .enumerate()
+            .filter(|(_, e)| **e == executed)
+            .map(|(id, _)| id)
+            .collect()
+    }
+}
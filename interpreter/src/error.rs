@@ -1,7 +1,7 @@
-use xelis_ast::Expression;
+use xelis_ast::{Expression, Operator};
 use xelis_environment::EnvironmentError;
 use thiserror::Error;
-use xelis_types::{IdentifierType, Type, ValueError};
+use xelis_types::{IdentifierType, Type, Value, ValueError};
 
 
 #[derive(Debug, Error)]
@@ -18,8 +18,16 @@ pub enum InterpreterError {
     NoMatchingFunction,
     #[error("Function entry error: expected {0}, got {1}")]
     FunctionEntry(bool, bool), // expected, got
+    #[error("Entry function doesn't match the expected signature")]
+    EntryContractMismatch,
+    #[error("Invalid entry arguments count: expected {0}, got {1}")]
+    InvalidEntryArgumentsCount(usize, usize),
+    #[error("Invalid entry argument at index {0}: expected type {1}")]
+    InvalidEntryArgumentType(usize, Type),
     #[error("Limit reached")]
     LimitReached,
+    #[error("Allocation limit reached: {0}")]
+    AllocationLimitReached(u64),
     #[error("Not implemented")]
     NotImplemented,
     #[error("no exit code found")]
@@ -36,16 +44,156 @@ pub enum InterpreterError {
     VariableNotFound(IdentifierType),
     #[error("Recursive limit reached")]
     RecursiveLimitReached,
+    #[error("Expression depth limit reached")]
+    ExpressionDepthLimitReached,
     #[error("Gas limit reached")]
     GasLimitReached,
+    #[error("Execution deadline reached")]
+    Timeout,
+    #[error("Invalid range: bounds must be numbers of the same type, got {0} and {1}")]
+    InvalidRange(Type, Type),
     #[error("Invalid cast type: {0}")]
     InvalidCastType(Type),
     #[error("Division by zero")]
     DivByZero,
+    #[error("Overflow occured")]
+    OverflowOccured,
     #[error("Operation not number type")]
     OperationNotNumberType,
+    #[error("Arithmetic operation on an optional value of type {0}, unwrap it first")]
+    ArithmeticOnOptional(Type),
     #[error("Operation not boolean type")]
     OperationNotBooleanType,
     #[error("Unexpected operator")]
     UnexpectedOperator,
-}
\ No newline at end of file
+    #[error("Missing argument without default value")]
+    MissingArgument,
+    #[error("Execution is suspended, a value must be resumed first")]
+    ExpectedResume,
+    #[error("Execution is not suspended")]
+    NotSuspended,
+    #[error("Execution is already finished")]
+    ExecutionFinished,
+    #[error("Expected a function value")]
+    ExpectedFunctionValue,
+    // Only checked when enabled with Interpreter::set_check_struct_fields
+    // The struct id and the field index are used when the names are unknown
+    #[error("Invalid value for field {field} of struct {struct_name}: expected {expected}, got {}", got.as_ref().map_or_else(|| "null".to_string(), Type::to_string))]
+    StructFieldTypeMismatch {
+        struct_name: String,
+        field: String,
+        expected: Type,
+        got: Option<Type>
+    },
+    #[error("Operator {0:?} can't be used to assign a string, only += is supported")]
+    InvalidStringOperation(Operator),
+    #[error("A function that never returns has returned")]
+    NeverReturned,
+}
+
+impl InterpreterError {
+    // Stable numeric code of the error, a host can rely on it across versions
+    // A code is never reused: a new variant takes the next free code
+    pub fn code(&self) -> u32 {
+        match self {
+            InterpreterError::EnvironmentError(_) => 1,
+            InterpreterError::ValueError(_) => 2,
+            InterpreterError::MissingValueOnStack => 3,
+            InterpreterError::StackError => 4,
+            InterpreterError::NoMatchingFunction => 5,
+            InterpreterError::FunctionEntry(_, _) => 6,
+            InterpreterError::EntryContractMismatch => 7,
+            InterpreterError::InvalidEntryArgumentsCount(_, _) => 8,
+            InterpreterError::InvalidEntryArgumentType(_, _) => 9,
+            InterpreterError::LimitReached => 10,
+            InterpreterError::AllocationLimitReached(_) => 11,
+            InterpreterError::NotImplemented => 12,
+            InterpreterError::NoExitCode => 13,
+            InterpreterError::ExpectedValue => 14,
+            InterpreterError::ExpectedPath(_) => 15,
+            InterpreterError::NativeFunctionExpectedInstance => 16,
+            InterpreterError::InvalidType(_) => 17,
+            InterpreterError::VariableNotFound(_) => 18,
+            InterpreterError::RecursiveLimitReached => 19,
+            InterpreterError::ExpressionDepthLimitReached => 20,
+            InterpreterError::GasLimitReached => 21,
+            InterpreterError::Timeout => 22,
+            InterpreterError::InvalidRange(_, _) => 23,
+            InterpreterError::InvalidCastType(_) => 24,
+            InterpreterError::DivByZero => 25,
+            InterpreterError::OverflowOccured => 26,
+            InterpreterError::OperationNotNumberType => 27,
+            InterpreterError::ArithmeticOnOptional(_) => 28,
+            InterpreterError::OperationNotBooleanType => 29,
+            InterpreterError::UnexpectedOperator => 30,
+            InterpreterError::MissingArgument => 31,
+            // 32 and 33 are reserved
+            InterpreterError::ExpectedResume => 34,
+            InterpreterError::NotSuspended => 35,
+            InterpreterError::ExecutionFinished => 36,
+            InterpreterError::ExpectedFunctionValue => 37,
+            InterpreterError::StructFieldTypeMismatch { .. } => 38,
+            InterpreterError::InvalidStringOperation(_) => 39,
+            InterpreterError::NeverReturned => 40,
+        }
+    }
+}
+
+// Reason the execution of an expression or a statement stopped early
+// The try operator and a return in a block expression unwind until the function boundary
+pub(crate) enum Unwind {
+    Error(InterpreterError),
+    // Error propagated by the try operator
+    Err(Value),
+    // Value returned from a block expression
+    Return(Option<Value>),
+}
+
+impl Unwind {
+    // Value returned by the function once the unwinding reached its boundary
+    pub(crate) fn into_returned(self) -> Result<Option<Value>, InterpreterError> {
+        match self {
+            Unwind::Error(e) => Err(e),
+            Unwind::Err(err) => Ok(Some(err)),
+            Unwind::Return(value) => Ok(value)
+        }
+    }
+
+    // Error of an expression evaluated outside of a function body
+    // The parser prevents it from returning early
+    pub(crate) fn into_error(self) -> InterpreterError {
+        match self {
+            Unwind::Error(e) => e,
+            _ => InterpreterError::ExpectedValue
+        }
+    }
+}
+
+impl<T: Into<InterpreterError>> From<T> for Unwind {
+    fn from(e: T) -> Self {
+        Unwind::Error(e.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_codes() {
+        assert_eq!(InterpreterError::DivByZero.code(), 25);
+        assert_eq!(InterpreterError::GasLimitReached.code(), 21);
+        assert_eq!(InterpreterError::from(ValueError::CastError).code(), 2);
+
+        let errors = [
+            InterpreterError::DivByZero,
+            InterpreterError::Timeout,
+            InterpreterError::InvalidType(Type::U64),
+            InterpreterError::from(ValueError::CastError),
+        ];
+        for error in errors {
+            assert!(!error.to_string().is_empty());
+            let _: &dyn std::error::Error = &error;
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use thiserror::Error;
+use xelis_ast::{Program, Statement};
+use xelis_builder::EnvironmentBuilder;
+use xelis_lexer::{Lexer, LexerError};
+use xelis_parser::{Parser, ParserError};
+use xelis_types::Value;
+use crate::{ExecutionContext, Interpreter, InterpreterError, State};
+
+#[derive(Debug, Error)]
+pub enum EvalError<'a> {
+    #[error("{0:?}")]
+    LexerError(LexerError),
+    // Not a source as the error borrows the tokens
+    #[error("{0}")]
+    ParserError(ParserError<'a>),
+    #[error(transparent)]
+    InterpreterError(#[from] InterpreterError)
+}
+
+// Evaluate a single expression, such as `1 + 2 * 3`, and return its value
+// The environment is needed to resolve the functions and types used by the expression
+// It is executed in a fresh context without any limit
+pub fn eval<'a>(source: &'a str, env: &'a EnvironmentBuilder<'a>) -> Result<Value, EvalError<'a>> {
+    let tokens = Lexer::new(source).get()
+        .map_err(EvalError::LexerError)?;
+    let (expression, _) = Parser::new(tokens, env).parse_expression()?;
+
+    let program = Program::new();
+    let interpreter = Interpreter::new(&program, env.environment())?;
+    let statements = [Statement::Return(Some(expression))];
+    let mut context = ExecutionContext::new();
+    let mut state = State::new(None, None, None);
+
+    interpreter.execute_in_context(&statements, 0, &mut context, &mut state)?
+        .ok_or(EvalError::InterpreterError(InterpreterError::ExpectedValue))
+}
+
+impl<'a> From<ParserError<'a>> for EvalError<'a> {
+    fn from(e: ParserError<'a>) -> Self {
+        EvalError::ParserError(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use xelis_ast::Token;
+    use super::*;
+
+    #[test]
+    fn test_eval() {
+        let env = EnvironmentBuilder::default();
+        assert_eq!(eval("1 + 2 * 3", &env).unwrap(), Value::U64(7));
+        assert_eq!(eval("\"hello\" + \" \" + \"world\"", &env).unwrap(), Value::String("hello world".to_owned()));
+        assert_eq!(eval("\"abc\".len() * 2 + 0", &env).unwrap(), Value::U32(6));
+    }
+
+    #[test]
+    fn test_eval_rejects_statements() {
+        let env = EnvironmentBuilder::default();
+        assert!(matches!(eval("let a: u64 = 1", &env), Err(EvalError::ParserError(ParserError::ExpectedExpression(Token::Let)))));
+        assert!(matches!(eval("fn f() {}", &env), Err(EvalError::ParserError(ParserError::ExpectedExpression(Token::Function)))));
+        assert!(matches!(eval("1 + 2 return", &env), Err(EvalError::ParserError(ParserError::UnexpectedToken(Token::Return)))));
+        assert!(matches!(eval("1 / 0", &env), Err(EvalError::InterpreterError(InterpreterError::DivByZero))));
+    }
+}
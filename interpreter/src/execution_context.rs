@@ -0,0 +1,28 @@
+use crate::stack::Stack;
+
+// Variables kept between evaluations
+// The host owns it and decides how long the variables live,
+// which allows interactive sessions like a REPL
+#[derive(Debug)]
+pub struct ExecutionContext<'a> {
+    stack: Stack<'a>
+}
+
+impl<'a> ExecutionContext<'a> {
+    pub fn new() -> Self {
+        Self {
+            stack: Stack::new(0)
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn stack_mut(&mut self) -> &mut Stack<'a> {
+        &mut self.stack
+    }
+}
+
+impl Default for ExecutionContext<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,31 +1,57 @@
 mod stack;
 mod state;
 mod error;
-
-use xelis_environment::{Environment, NativeFunction};
+mod contract;
+mod execution_context;
+mod resumable;
+mod coverage;
+mod eval;
+
+use xelis_environment::{Context, Environment, EnvironmentError, NativeFunction};
+use std::{cell::RefCell, fmt::Write};
 use xelis_types::{
     IdentifierType,
     NoHashMap,
     Path,
     Type,
     Value,
+    ValueError,
     ValueOwnable
 };
 use xelis_ast::{
     Expression, FunctionType, Operator, Parameter, Program, Statement
 };
 use stack::Stack;
+use coverage::Coverage;
 
-pub use state::State;
+pub use state::{State, DEADLINE_CHECK_INTERVAL, MAX_ARRAY_LENGTH, MAX_EXPRESSION_DEPTH};
 pub use error::InterpreterError;
+use error::Unwind;
+pub use contract::EntryContract;
+pub use execution_context::ExecutionContext;
+pub use resumable::{ExecutionResult, Resumable};
+pub use coverage::{CoverageReport, StatementId};
+pub use eval::{eval, EvalError};
 
 enum StatementResult<'a> {
     Return(Option<Path<'a>>),
-    Break,
-    Continue,
+    // Count of loops to leave before the targeted one
+    Break(u16),
+    Continue(u16),
     None
 }
 
+impl StatementResult<'_> {
+    // Result propagated to the enclosing loop once the current one is left
+    fn leave_loop(self) -> Self {
+        match self {
+            StatementResult::Break(depth) => StatementResult::Break(depth - 1),
+            StatementResult::Continue(depth) => StatementResult::Continue(depth - 1),
+            result => result
+        }
+    }
+}
+
 macro_rules! op {
     ($a: expr, $b: expr, $op: tt) => {{
         match ($a, $b) {
@@ -34,7 +60,7 @@ macro_rules! op {
             (Value::U32(a), Value::U32(b)) => Value::U32(a $op b),
             (Value::U64(a), Value::U64(b)) => Value::U64(a $op b),
             (Value::U128(a), Value::U128(b)) => Value::U128(a $op b),
-            _ => return Err(InterpreterError::OperationNotNumberType)
+            (a, b) => return Err(not_number_error(a, b))
         }
     }};
 }
@@ -56,11 +82,30 @@ macro_rules! op_div {
             (Value::U32(a), Value::U32(b)) => op_div!(U32, a, b),
             (Value::U64(a), Value::U64(b)) => op_div!(U64, a, b),
             (Value::U128(a), Value::U128(b)) => op_div!(U128, a, b),
-            _ => return Err(InterpreterError::OperationNotNumberType)
+            (a, b) => return Err(not_number_error(a, b))
         }
     };
 }
 
+// Shift a number by a value of the same type
+// A shift greater or equal to the bits count of the type is an overflow,
+// instead of masking the shift as the native operators do
+// Bits shifted out of the value are discarded
+macro_rules! op_shift {
+    ($a: expr, $b: expr, $op: ident) => {{
+        let shifted = match ($a, $b) {
+            (Value::U8(a), Value::U8(b)) => a.$op(*b as u32).map(Value::U8),
+            (Value::U16(a), Value::U16(b)) => a.$op(*b as u32).map(Value::U16),
+            (Value::U32(a), Value::U32(b)) => a.$op(*b).map(Value::U32),
+            (Value::U64(a), Value::U64(b)) => u32::try_from(*b).ok().and_then(|b| a.$op(b)).map(Value::U64),
+            (Value::U128(a), Value::U128(b)) => u32::try_from(*b).ok().and_then(|b| a.$op(b)).map(Value::U128),
+            (a, b) => return Err(not_number_error(a, b))
+        };
+
+        shifted.ok_or(InterpreterError::OverflowOccured)?
+    }};
+}
+
 macro_rules! op_bool {
     ($a: expr, $b: expr, $op: tt) => {{
         match ($a, $b) {
@@ -94,14 +139,27 @@ macro_rules! execute_foreach {
         $stack.register_variable($var, $val)?;
         match $self.execute_statements($statements, $stack, $state)? {
             StatementResult::Return(v) => return Ok(StatementResult::Return(v)),
-            StatementResult::Break => {
+            StatementResult::Break(0) => {
                 break;
             },
-            _ => {}
+            StatementResult::Continue(0) | StatementResult::None => {},
+            // A labeled jump to an outer loop
+            result => return Ok(result.leave_loop())
         };
     };
 }
 
+// Temporaries used while executing an expression
+// With the small-values feature, they are kept inline
+// to not allocate on each path access
+#[cfg(feature = "small-values")]
+type Buffer<T> = smallvec::SmallVec<[T; 4]>;
+#[cfg(not(feature = "small-values"))]
+type Buffer<T> = Vec<T>;
+
+// Result of an entry call with the events it emitted
+pub type EntryCallResult = (Result<u64, InterpreterError>, Vec<(String, Value)>);
+
 #[derive(Debug)]
 enum ExprHelper<'a> {
     Expr(&'a Expression),
@@ -129,12 +187,102 @@ enum Function<'a> {
     Program(&'a FunctionType)
 }
 
-impl<'a> Function<'a> {
-    fn is_entry(&self) -> bool {
-        match self {
-            Function::Program(f) => f.is_entry(),
-            _ => false
-        }
+// Map an error returned by the environment
+// The allocation limit is shared with the natives but reported as an interpreter limit
+fn environment_error(e: EnvironmentError) -> InterpreterError {
+    match e {
+        EnvironmentError::AllocationLimitReached(max) => InterpreterError::AllocationLimitReached(max),
+        e => InterpreterError::EnvironmentError(e)
+    }
+}
+
+// Error of an arithmetic operation on values that aren't numbers
+// An optional is reported on its own as it must be unwrapped first
+fn not_number_error(left: &Value, right: &Value) -> InterpreterError {
+    match [left, right].into_iter().find(|v| matches!(v, Value::Optional(_))) {
+        Some(value) => InterpreterError::ArithmeticOnOptional(Type::from_value(value).unwrap_or(Type::Optional(Box::new(Type::Any)))),
+        None => InterpreterError::OperationNotNumberType
+    }
+}
+
+// Account the creation of count array or struct elements
+fn allocate(context: &RefCell<Context>, count: usize) -> Result<(), InterpreterError> {
+    context.borrow_mut()
+        .allocate(count as u64)
+        .map_err(environment_error)
+}
+
+// Concatenate two arrays, values are copied
+fn concat_arrays(left: &[ValueOwnable], right: &[ValueOwnable], state: &State, context: &RefCell<Context>) -> Result<Value, InterpreterError> {
+    state.check_array_length(left.len() + right.len())?;
+    allocate(context, left.len() + right.len())?;
+    Ok(Value::Array(left.iter().chain(right).map(ValueOwnable::deep_clone).collect()))
+}
+
+// Repeat the values of an array count times
+fn repeat_array(values: &[ValueOwnable], count: &Value, state: &State, context: &RefCell<Context>) -> Result<Value, InterpreterError> {
+    let count = usize::try_from(count.clone().checked_cast_to_u64()?)
+        .map_err(|_| ValueError::CastError)?;
+    let len = values.len().checked_mul(count).ok_or(ValueError::CastError)?;
+    state.check_array_length(len)?;
+    allocate(context, len)?;
+
+    // The length can be unlimited by the host, the allocation must not abort the process
+    let mut array = Vec::new();
+    array.try_reserve_exact(len)
+        .map_err(|_| InterpreterError::AllocationLimitReached(len as u64))?;
+    for _ in 0..count {
+        array.extend(values.iter().map(ValueOwnable::deep_clone));
+    }
+
+    Ok(Value::Array(array))
+}
+
+// Match `variable <op> constant` where the constant is a number
+// Used by the integer loops fast-path
+fn int_operation(expr: &Expression, allowed: fn(&Operator) -> bool) -> Option<(IdentifierType, &Operator, &Value)> {
+    match expr {
+        Expression::Operator(op, left, right) if allowed(op) => match (left.as_ref(), right.as_ref()) {
+            (Expression::Variable(id), Expression::Value(v)) if v.is_number() => Some((*id, op, v)),
+            _ => None
+        },
+        _ => None
+    }
+}
+
+// Append a value to a string, reusing its buffer
+fn append_to_string(s: &mut String, value: &Value, state: &State) -> Result<(), InterpreterError> {
+    let start = s.len();
+    // Writing into a String can't fail
+    let _ = write!(s, "{}", value);
+    if let Err(e) = state.check_string_length(s.len()) {
+        s.truncate(start);
+        return Err(e)
+    }
+
+    Ok(())
+}
+
+// Loop condition comparing a variable against a number constant
+// Example: i < 10
+fn int_condition(condition: &Expression) -> Option<(IdentifierType, &Operator, &Value)> {
+    int_operation(condition, |op| matches!(
+        op,
+        Operator::LessThan
+        | Operator::LessOrEqual
+        | Operator::GreaterThan
+        | Operator::GreaterOrEqual
+        | Operator::Equals
+        | Operator::NotEquals
+    ))
+}
+
+// Loop increment adding or subtracting a number constant to a variable
+// Example: i += 1
+fn int_increment(increment: &Expression) -> Option<(IdentifierType, &Operator, &Value)> {
+    match int_operation(increment, |op| matches!(op, Operator::Assign(Some(_))))? {
+        (id, Operator::Assign(Some(op)), v) if matches!(op.as_ref(), Operator::Plus | Operator::Minus) => Some((id, op.as_ref(), v)),
+        _ => None
     }
 }
 
@@ -145,18 +293,79 @@ pub struct Interpreter<'a> {
     // Environment linked to execute the program
     env: &'a Environment,
     // All the constants defined in the program
-    constants: Option<NoHashMap<Value>>
+    constants: Option<NoHashMap<Value>>,
+    // Signature expected from the entry functions
+    entry_contract: Option<EntryContract>,
+    // Context shared with the native functions
+    context: RefCell<Context>,
+    // Use the truthiness of a condition instead of expecting a boolean
+    truthy_conditions: bool,
+    // Check the type of the struct fields values at each construction
+    // Disabled by default
+    check_struct_fields: bool,
+    // Statements executed, only collected when enabled
+    coverage: Option<RefCell<Coverage>>
 }
 
 impl<'a> Interpreter<'a> {
     pub fn new(program: &'a Program, env: &'a Environment) -> Result<Self, InterpreterError> {
+        Self::with_max_allocations(program, env, 0)
+    }
+
+    // Create an interpreter limiting the count of array and struct elements
+    // created during an execution, 0 disable the limit
+    pub fn with_max_allocations(program: &'a Program, env: &'a Environment, max_allocations: u64) -> Result<Self, InterpreterError> {
+        let mut context = Context::with_output(env.get_output_sink().clone());
+        context.set_random_source(env.get_random_source().cloned());
+        context.set_max_allocations(max_allocations);
+
         Ok(Self {
             program,
             env,
-            constants: None
+            constants: None,
+            entry_contract: None,
+            context: RefCell::new(context),
+            truthy_conditions: false,
+            check_struct_fields: false,
+            coverage: None
         })
     }
 
+    // Take the events emitted by the program, in order
+    pub fn take_events(&self) -> Vec<(String, Value)> {
+        self.context.borrow_mut().take_events()
+    }
+
+    // Set the signature the entry functions must match to be called
+    // None accept any entry function
+    pub fn set_entry_contract(&mut self, contract: Option<EntryContract>) {
+        self.entry_contract = contract;
+    }
+
+    // Evaluate the conditions of if, while, for and ternary using Value::truthy
+    // By default, a condition must be a boolean
+    pub fn set_truthy_conditions(&mut self, truthy_conditions: bool) {
+        self.truthy_conditions = truthy_conditions;
+    }
+
+    // Reject a struct field value not matching its declared type
+    // Disabled by default: a program built by the host must enable it
+    // to be checked, the parser already checks the programs it builds
+    pub fn set_check_struct_fields(&mut self, check_struct_fields: bool) {
+        self.check_struct_fields = check_struct_fields;
+    }
+
+    // Collect the statements executed by the next executions
+    // Enabling it again reset the statements already collected
+    pub fn set_coverage(&mut self, enabled: bool) {
+        self.coverage = enabled.then(|| RefCell::new(Coverage::new(self.program)));
+    }
+
+    // Get the statements executed since the coverage was enabled
+    pub fn coverage(&self) -> Option<CoverageReport> {
+        self.coverage.as_ref().map(|coverage| coverage.borrow().report())
+    }
+
     // Get the function from the environment or the program based on the index
     fn get_function(&self, name: &IdentifierType) -> Result<Function, InterpreterError> {
         let index = *name as usize;
@@ -170,18 +379,35 @@ impl<'a> Interpreter<'a> {
         }
     }
 
+    // Call a function with its evaluated arguments
+    fn call_function(&'a self, id: &IdentifierType, on_value: Option<Path<'a>>, values: Vec<Path<'a>>, state: &mut State) -> Result<Option<Path<'a>>, InterpreterError> {
+        state.increase_recursive_depth()?;
+
+        // Nesting depth of expressions is counted per function
+        let depth = state.take_expression_depth();
+        let res = self.get_function(id)
+            .and_then(|f| self.execute_function(f, on_value, values, state));
+
+        // Restore the depths even on error as they're decreased while unwinding
+        state.set_expression_depth(depth);
+        state.decrease_recursive_depth();
+
+        res
+    }
+
     // Get a mutable reference to a value so we can update its content
-    fn get_from_path(&'a self, path: &'a Expression, stack: &mut Stack<'a>, state: &mut State) -> Result<Path<'a>, InterpreterError> {
+    fn get_from_path(&'a self, path: &'a Expression, stack: &mut Stack<'a>, state: &mut State) -> Result<Path<'a>, Unwind> {
         // Fast path on no-depth expressions       
         match path {
-            Expression::Variable(name) => return stack.get_variable_path(name),
+            Expression::Variable(name) => return Ok(stack.get_variable_path(name)?),
             Expression::Value(v) => return Ok(Path::Borrowed(v)),
-            Expression::FunctionCall(_, _, _) => return self.execute_expression_and_expect_value(path, stack, state),
+            Expression::FunctionCall(_, _, _) | Expression::ValueCall(_, _) | Expression::OptionalPath(_, _) => return self.execute_expression_and_expect_value(path, stack, state),
             _ => ()
         };
 
-        let mut local_stack: Vec<ExprHelper<'a>> = vec![ExprHelper::Expr(path)];
-        let mut local_result: Vec<Path<'a>> = Vec::with_capacity(4);
+        let mut local_stack: Buffer<ExprHelper<'a>> = Buffer::new();
+        local_stack.push(ExprHelper::Expr(path));
+        let mut local_result: Buffer<Path<'a>> = Buffer::with_capacity(4);
 
         while let Some(h) = local_stack.pop() {
             match h {
@@ -194,6 +420,8 @@ impl<'a> Interpreter<'a> {
                         local_stack.push(ExprHelper::ArrayCall(expr_index));
                         local_stack.push(ExprHelper::Expr(expr));
                     },
+                    // On the right side of a path, the parser already resolved the field name
+                    // to its index in the struct type, matching the positional fields of the value
                     Expression::Variable(name) => {
                         let inner_value = match local_result.pop() {
                             Some(v) => v.get_sub_variable(*name as usize)?,
@@ -205,11 +433,11 @@ impl<'a> Interpreter<'a> {
                     Expression::Value(v) => {
                         local_result.push(Path::Borrowed(v));
                     },
-                    Expression::FunctionCall(_, _, _) => {
+                    Expression::FunctionCall(_, _, _) | Expression::ValueCall(_, _) | Expression::OptionalPath(_, _) => {
                         let value = self.execute_expression_and_expect_value(expr, stack, state)?;
                         local_result.push(value);
                     },
-                    e => return Err(InterpreterError::ExpectedPath(e.clone()))
+                    e => return Err(InterpreterError::ExpectedPath(e.clone()).into())
                 },
                 ExprHelper::ArrayCall(index) => {
                     let index = self.execute_expression_and_expect_value(index, stack, state)?
@@ -221,48 +449,90 @@ impl<'a> Interpreter<'a> {
             }
         }
 
-        local_result.pop().ok_or(InterpreterError::MissingValueOnStack)
+        Ok(local_result.pop().ok_or(InterpreterError::MissingValueOnStack)?)
     }
 
     // Execute the selected operator
-    fn execute_operator(&self, op: &Operator, left: &Value, right: &Value, _: &mut State) -> Result<Value, InterpreterError> {
+    fn execute_operator(&self, op: &Operator, left: &Value, right: &Value, state: &mut State) -> Result<Value, InterpreterError> {
         match op {
-            Operator::Equals => Ok(Value::Boolean(left ==right)),
-            Operator::NotEquals => Ok(Value::Boolean(left != right)),
+            Operator::Equals => Ok(Value::Boolean(left.is_equal(right)?)),
+            Operator::NotEquals => Ok(Value::Boolean(!left.is_equal(right)?)),
             Operator::Plus => {
-                if left.is_string() || right.is_string() {
-                    Ok(Value::String(format!("{}{}", left, right)))
+                if let (Value::Array(left), Value::Array(right)) = (left, right) {
+                    concat_arrays(left, right, state, &self.context)
+                } else if left.is_string() || right.is_string() {
+                    let value = format!("{}{}", left, right);
+                    state.check_string_length(value.len())?;
+                    Ok(Value::String(value))
                 } else {
                     Ok(op!(left, right, +))
                 }
             },
             Operator::Minus => Ok(op!(left, right, -)),
             Operator::Divide => Ok(op_div!(left, right)),
-            Operator::Multiply => Ok(op!(left, right, *)),
+            Operator::Multiply => match left {
+                Value::Array(values) => repeat_array(values, right, state, &self.context),
+                _ => Ok(op!(left, right, *))
+            },
             Operator::Rem => Ok(op!(left, right, %)),
             Operator::BitwiseXor => Ok(op!(left, right, ^)),
             Operator::BitwiseAnd => Ok(op_num_with_bool!(left, right, &)),
             Operator::BitwiseOr => Ok(op_num_with_bool!(left, right, |)),
-            Operator::BitwiseLeft => Ok(op!(left, right, <<)),
-            Operator::BitwiseRight => Ok(op!(left, right, >>)),
+            Operator::BitwiseLeft => Ok(op_shift!(left, right, checked_shl)),
+            Operator::BitwiseRight => Ok(op_shift!(left, right, checked_shr)),
             Operator::GreaterOrEqual => Ok(op_bool!(left, right, >=)),
             Operator::GreaterThan => Ok(op_bool!(left, right, >)),
             Operator::LessOrEqual => Ok(op_bool!(left, right, <=)),
             Operator::LessThan => Ok(op_bool!(left, right, <)),
             // Those are handled in the execute_expression function
-            Operator::And | Operator::Or | Operator::Assign(_) => return Err(InterpreterError::UnexpectedOperator)
+            Operator::And | Operator::Or | Operator::Assign(_) => return Err(InterpreterError::UnexpectedOperator.into())
         }
     }
 
+    // Evaluate a loop condition matched by int_condition
+    // without going through the general expression machinery
+    // The expressions count is the same as the general path
+    fn execute_int_condition(&self, id: &IdentifierType, op: &Operator, bound: &Value, stack: &mut Stack<'a>, state: &mut State) -> Result<bool, InterpreterError> {
+        state.increase_expressions_executed_by(3)?;
+        let value = stack.get_variable_path(id)?;
+        let result = self.execute_operator(op, &value.as_ref(), bound, state)?;
+        Ok(result.as_bool()?)
+    }
+
+    // Apply a loop increment matched by int_increment
+    fn execute_int_increment(&self, id: &IdentifierType, op: &Operator, step: &Value, stack: &mut Stack<'a>, state: &mut State) -> Result<(), InterpreterError> {
+        state.increase_expressions_executed_by(2)?;
+        let mut value = stack.get_variable_path(id)?;
+        let result = self.execute_operator(op, &value.as_ref(), step, state)?;
+        *value.as_mut() = result;
+        Ok(())
+    }
+
     #[inline(always)]
-    fn execute_expression_and_expect_value(&'a self, expr: &'a Expression, stack: &mut Stack<'a>, state: &mut State) -> Result<Path<'a>, InterpreterError> {
+    fn execute_expression_and_expect_value(&'a self, expr: &'a Expression, stack: &mut Stack<'a>, state: &mut State) -> Result<Path<'a>, Unwind> {
         match self.execute_expression(expr, stack, state)? {
             Some(val) => Ok(val),
-            None => Err(InterpreterError::ExpectedValue)
+            None => Err(InterpreterError::ExpectedValue.into())
+        }
+    }
+
+    fn execute_condition(&'a self, expr: &'a Expression, stack: &mut Stack<'a>, state: &mut State) -> Result<bool, Unwind> {
+        let value = self.execute_expression_and_expect_value(expr, stack, state)?;
+        if self.truthy_conditions {
+            Ok(value.as_ref().truthy())
+        } else {
+            Ok(value.as_bool()?)
         }
     }
 
-    fn execute_expression(&'a self, expr: &'a Expression, stack: &mut Stack<'a>, state: &mut State) -> Result<Option<Path<'a>>, InterpreterError> {
+    fn execute_expression(&'a self, expr: &'a Expression, stack: &mut Stack<'a>, state: &mut State) -> Result<Option<Path<'a>>, Unwind> {
+        state.increase_expression_depth()?;
+        let res = self.execute_expression_internal(expr, stack, state);
+        state.decrease_expression_depth();
+        res
+    }
+
+    fn execute_expression_internal(&'a self, expr: &'a Expression, stack: &mut Stack<'a>, state: &mut State) -> Result<Option<Path<'a>>, Unwind> {
         state.increase_expressions_executed()?;
         match expr {
             Expression::FunctionCall(path, name, parameters) => {
@@ -276,16 +546,23 @@ impl<'a> Interpreter<'a> {
                     values.push(self.execute_expression_and_expect_value(param, stack, state)?);
                 }
 
-                state.increase_recursive_depth()?;
-
-                let f = self.get_function(name)?;
-                let res = self.execute_function(f, on_value, values, state)?;
+                Ok(self.call_function(name, on_value, values, state)?)
+            },
+            Expression::ValueCall(function, parameters) => {
+                let id = match *self.execute_expression_and_expect_value(function, stack, state)?.as_ref() {
+                    Value::Function(id) => id,
+                    _ => return Err(InterpreterError::ExpectedFunctionValue.into())
+                };
 
-                state.decrease_recursive_depth();
+                let mut values = Vec::with_capacity(parameters.len());
+                for param in parameters {
+                    values.push(self.execute_expression_and_expect_value(param, stack, state)?);
+                }
 
-                Ok(res)
+                Ok(self.call_function(&id, None, values, state)?)
             },
             Expression::ArrayConstructor(expressions) => {
+                allocate(&self.context, expressions.len())?;
                 let mut values = Vec::with_capacity(expressions.len());
                 for expr in expressions {
                     let value = self.execute_expression_and_expect_value(&expr, stack, state)?;
@@ -294,11 +571,34 @@ impl<'a> Interpreter<'a> {
 
                 Ok(Some(Path::Owned(Value::Array(values))))
             },
+            Expression::TupleConstructor(expressions) => {
+                let mut values = Vec::with_capacity(expressions.len());
+                for expr in expressions {
+                    let value = self.execute_expression_and_expect_value(expr, stack, state)?;
+                    values.push(ValueOwnable::Owned(Box::new(value.into_owned())));
+                }
+
+                Ok(Some(Path::Owned(Value::Tuple(values))))
+            },
             Expression::StructConstructor(expr_fields, _type) => {
+                allocate(&self.context, expr_fields.len())?;
                 let mut fields = Vec::with_capacity(expr_fields.len());
-                for expr in expr_fields {
-                    let value = self.execute_expression_and_expect_value(&expr, stack, state)?;
-                    fields.push(ValueOwnable::Owned(Box::new(value.into_owned())));
+                for (field, (expr, expected)) in expr_fields.iter().zip(_type.fields()).enumerate() {
+                    let value = self.execute_expression_and_expect_value(expr, stack, state)?.into_owned();
+                    if self.check_struct_fields && !contract::is_value_of_type(&value, expected) {
+                        let struct_name = match _type.name() {
+                            "" => _type.id().to_string(),
+                            name => name.to_string()
+                        };
+                        return Err(InterpreterError::StructFieldTypeMismatch {
+                            struct_name,
+                            field: _type.fields_names().get(field).cloned().unwrap_or_else(|| field.to_string()),
+                            expected: expected.clone(),
+                            got: Type::from_value(&value)
+                        }.into())
+                    }
+
+                    fields.push(ValueOwnable::Owned(Box::new(value)));
                 }
 
                 Ok(Some(Path::Owned(Value::Struct(fields, _type.clone()))))
@@ -306,10 +606,14 @@ impl<'a> Interpreter<'a> {
             Expression::IsNot(expr) => {
                 let val = self.execute_expression_and_expect_value(&expr, stack, state)?.as_bool()?;
                 Ok(Some(Path::Owned(Value::Boolean(!val))))
-            }
+            },
+            Expression::BitwiseNot(expr) => {
+                let val = self.execute_expression_and_expect_value(expr, stack, state)?.as_ref().bitwise_not()?;
+                Ok(Some(Path::Owned(val)))
+            },
             Expression::SubExpression(expr) => self.execute_expression(expr, stack, state),
             Expression::Ternary(condition, left, right) => {
-                if self.execute_expression_and_expect_value(&condition, stack, state)?.as_bool()? {
+                if self.execute_condition(condition, stack, state)? {
                     Ok(Some(self.execute_expression_and_expect_value(&left, stack, state)?))
                 } else {
                     Ok(Some(self.execute_expression_and_expect_value(&right, stack, state)?))
@@ -323,8 +627,14 @@ impl<'a> Interpreter<'a> {
                         let mut path = self.get_from_path(expr_left, stack, state)?;
 
                         if let Some(op) = op {
-                            let result = self.execute_operator(&op, &path.as_ref(), &value.as_ref(), state)?;
-                            *path.as_mut() = result;
+                            let mut handle = path.as_mut();
+                            match (op.as_ref(), handle.as_value_mut()) {
+                                // Grow the string in place instead of building a new one
+                                (Operator::Plus, Value::String(s)) => append_to_string(s, &value.as_ref(), state)?,
+                                // Only += is overloaded for strings
+                                (op, Value::String(_)) => return Err(InterpreterError::InvalidStringOperation(op.clone()).into()),
+                                (op, left) => *left = self.execute_operator(op, left, &value.as_ref(), state)?
+                            };
                         } else {
                             *path.as_mut() = value.into_owned();
                         }
@@ -353,7 +663,7 @@ impl<'a> Interpreter<'a> {
                         let left_handle = left.as_ref();
                         let right = self.execute_expression_and_expect_value(&expr_right, stack, state)?;
                         let right_handle = right.as_ref();
-                        self.execute_operator(op, &left_handle, &right_handle, state).map(Path::Owned).map(Some)
+                        Ok(Some(Path::Owned(self.execute_operator(op, &left_handle, &right_handle, state)?)))
                     }
                 }
             },
@@ -367,31 +677,112 @@ impl<'a> Interpreter<'a> {
                     Type::U128 => Value::U128(value.cast_to_u128()?),
                     Type::U256 => Value::U256(value.cast_to_u256()?),
                     Type::String => Value::String(value.cast_to_string()?),
-                    _ => return Err(InterpreterError::InvalidType(cast_type.clone()))
+                    _ => return Err(InterpreterError::InvalidType(cast_type.clone()).into())
                 })))
             },
+            Expression::Range(start, end) => {
+                let start = self.execute_expression_and_expect_value(start, stack, state)?.into_owned();
+                let end = self.execute_expression_and_expect_value(end, stack, state)?.into_owned();
+
+                // Both bounds must be numbers of the same type
+                // which is also the element type of the range
+                let start_type = start.get_type()?;
+                let end_type = end.get_type()?;
+                if !start.is_number() || start_type != end_type {
+                    return Err(InterpreterError::InvalidRange(start_type, end_type).into())
+                }
+
+                Ok(Some(Path::Owned(Value::Range(Box::new(start), Box::new(end), start_type))))
+            },
+            Expression::Try(expr) => {
+                let value = self.execute_expression_and_expect_value(expr, stack, state)?.into_owned();
+                match value.to_result()? {
+                    Ok(value) => Ok(Some(Path::Owned(value.into_inner()))),
+                    // Unwind until the function boundary which returns it
+                    Err(err) => Err(Unwind::Err(Value::Err(err)))
+                }
+            },
+            Expression::Block(statements, value, _) => match self.execute_statements(statements, stack, state)? {
+                // Unwind until the function boundary which returns it
+                StatementResult::Return(value) => Err(Unwind::Return(value.map(Path::into_owned))),
+                // break and continue are rejected by the parser in a block expression
+                _ => self.execute_expression(value, stack, state)
+            },
+            Expression::StructUpdate(expr, fields) => {
+                // The base struct is copied, it is never modified
+                let mut value = self.execute_expression_and_expect_value(expr, stack, state)?.as_ref().as_value().deep_clone();
+                let values = match &mut value {
+                    Value::Struct(values, _) => values,
+                    v => return Err(ValueError::InvalidValue(v.clone(), Type::Any).into())
+                };
+
+                for (id, field) in fields {
+                    let field_value = self.execute_expression_and_expect_value(field, stack, state)?.into_owned();
+                    let len = values.len();
+                    let slot = values.get_mut(*id as usize).ok_or(ValueError::OutOfBounds(*id as usize, len))?;
+                    *slot = ValueOwnable::Owned(Box::new(field_value));
+                }
+
+                Ok(Some(Path::Owned(value)))
+            },
+            Expression::OptionalPath(expr, field) => {
+                let Expression::Variable(id) = field.as_ref() else {
+                    return Err(InterpreterError::ExpectedPath(field.as_ref().clone()).into())
+                };
+
+                let value = match self.execute_expression_and_expect_value(expr, stack, state)?.into_owned() {
+                    Value::Null | Value::Optional(None) => return Ok(Some(Path::Owned(Value::Optional(None)))),
+                    Value::Optional(Some(inner)) => inner.into_inner(),
+                    value => value
+                };
+
+                let mut fields = value.to_sub_vec()?;
+                let index = *id as usize;
+                if index >= fields.len() {
+                    return Err(ValueError::OutOfBounds(index, fields.len()).into())
+                }
+
+                // An optional field is not wrapped a second time
+                let mut value = Value::Optional(Some(ValueOwnable::Owned(Box::new(fields.swap_remove(index).into_inner()))));
+                value.normalize();
+                Ok(Some(Path::Owned(value)))
+            },
             expr => Ok(Some(self.get_from_path(expr, stack, state)?)),
         }
     }
 
-    fn execute_statements<'b>(&'b self, statements: &'b [Statement], stack: &mut Stack<'b>, state: &mut State) -> Result<StatementResult<'b>, InterpreterError> {
+    fn execute_statements<'b>(&'b self, statements: &'b [Statement], stack: &mut Stack<'b>, state: &mut State) -> Result<StatementResult<'b>, Unwind> {
         for statement in statements {
             // Increase the number of executed expressions
             state.increase_expressions_executed()?;
 
+            if let Some(coverage) = &self.coverage {
+                coverage.borrow_mut().hit(statement);
+            }
+
             match statement {
-                Statement::Break => {
-                    return Ok(StatementResult::Break);
+                Statement::Break(depth) => {
+                    return Ok(StatementResult::Break(*depth));
                 },
-                Statement::Continue => {
-                    return Ok(StatementResult::Continue);
+                Statement::Continue(depth) => {
+                    return Ok(StatementResult::Continue(*depth));
                 },
                 Statement::Variable(var) => {
+                    // Declaring a variable copies the value, it never aliases another variable
                     let value = self.execute_expression_and_expect_value(&var.value, stack, state)?;
-                    stack.register_variable(var.id.clone(), value)?;
+                    stack.register_variable(var.id, Path::Owned(value.into_owned()))?;
+                },
+                Statement::TupleDeconstruction(variables, expr) => {
+                    let values = self.execute_expression_and_expect_value(expr, stack, state)?
+                        .into_owned()
+                        .to_sub_vec()?;
+
+                    for ((id, _), value) in variables.iter().zip(values) {
+                        stack.register_variable(*id, Path::Owned(value.into_inner()))?;
+                    }
                 },
                 Statement::If(condition, statements, else_statements) => {
-                    let statements = if self.execute_expression_and_expect_value(&condition, stack, state)?.as_bool()? {
+                    let statements = if self.execute_condition(condition, stack, state)? {
                         Some(statements)
                     } else if let Some(statements) = else_statements {
                         Some(statements)
@@ -402,9 +793,8 @@ impl<'a> Interpreter<'a> {
                     if let Some(statements) = statements {
                         match self.execute_statements(&statements, stack, state)? {
                             StatementResult::Return(v) => return Ok(StatementResult::Return(v)),
-                            StatementResult::Break => return Ok(StatementResult::Break),
-                            StatementResult::Continue => return Ok(StatementResult::Continue),
-                            _ => {}
+                            result @ (StatementResult::Break(_) | StatementResult::Continue(_)) => return Ok(result),
+                            StatementResult::None => {}
                         };
                     }
                 },
@@ -413,22 +803,42 @@ impl<'a> Interpreter<'a> {
                     let value = self.execute_expression_and_expect_value(&var.value, stack, state)?;
                     stack.register_variable(var.id.clone(), value)?;
 
+                    // Integer loops on the declared variable skip the general expression machinery
+                    let fast_condition = int_condition(condition).filter(|(id, _, _)| *id == var.id);
+                    let fast_increment = int_increment(increment).filter(|(id, _, _)| *id == var.id);
+
+                    // Set when a labeled jump targets an outer loop
+                    let mut outer_jump = None;
                     loop {
                         // check the condition
-                        if !self.execute_expression_and_expect_value(condition, stack, state)?.as_bool()? {
+                        let condition = match fast_condition {
+                            Some((id, op, bound)) => self.execute_int_condition(&id, op, bound, stack, state)?,
+                            None => self.execute_condition(condition, stack, state)?
+                        };
+
+                        if !condition {
                             break;
                         }
 
                         // execute the statements
+                        // on continue, we still have to run the increment
                         match self.execute_statements(&statements, stack, state)? {
                             StatementResult::Return(v) => return Ok(StatementResult::Return(v)),
-                            StatementResult::Break => break,
-                            StatementResult::Continue => continue,
-                            _ => {}
+                            StatementResult::Break(0) => break,
+                            StatementResult::Continue(0) | StatementResult::None => {},
+                            result => {
+                                outer_jump = Some(result.leave_loop());
+                                break;
+                            }
                         }
 
                         // increment once the iteration is done
-                        self.execute_expression(increment, stack, state)?;
+                        match fast_increment {
+                            Some((id, op, step)) => self.execute_int_increment(&id, op, step, stack, state)?,
+                            None => {
+                                self.execute_expression(increment, stack, state)?;
+                            }
+                        };
 
                         // We clear the last scope to avoid keeping the variables
                         // as we may register them again in next iteration
@@ -438,6 +848,9 @@ impl<'a> Interpreter<'a> {
                     // Because we registered outside of the newly created scope
                     // as we clear this scope each time
                     stack.remove_variable(&var.id)?;
+                    if let Some(result) = outer_jump {
+                        return Ok(result)
+                    }
                 },
                 Statement::ForEach(var, expr, statements) => {
                     let v = self.execute_expression_and_expect_value(expr, stack, state)?;
@@ -461,12 +874,22 @@ impl<'a> Interpreter<'a> {
                     }
                 },
                 Statement::While(condition, statements) => {
-                    while self.execute_expression_and_expect_value(&condition, stack, state)?.as_bool()? {
+                    let fast_condition = int_condition(condition);
+                    loop {
+                        let condition = match fast_condition {
+                            Some((id, op, bound)) => self.execute_int_condition(&id, op, bound, stack, state)?,
+                            None => self.execute_condition(condition, stack, state)?
+                        };
+
+                        if !condition {
+                            break;
+                        }
+
                         match self.execute_statements(&statements, stack, state)? {
                             StatementResult::Return(v) => return Ok(StatementResult::Return(v)),
-                            StatementResult::Break => break,
-                            StatementResult::Continue => continue,
-                            _ => {}
+                            StatementResult::Break(0) => break,
+                            StatementResult::Continue(0) | StatementResult::None => {},
+                            result => return Ok(result.leave_loop())
                         };
                     }
                 },
@@ -479,9 +902,8 @@ impl<'a> Interpreter<'a> {
                 Statement::Scope(statements) => {
                     match self.execute_statements(&statements, stack, state)? {
                         StatementResult::Return(v) => return Ok(StatementResult::Return(v)),
-                        StatementResult::Break => return Ok(StatementResult::Break),
-                        StatementResult::Continue => return Ok(StatementResult::Continue),
-                        _ => {}
+                        result @ (StatementResult::Break(_) | StatementResult::Continue(_)) => return Ok(result),
+                        StatementResult::None => {}
                     };
                 },
                 Statement::Expression(expr) => {
@@ -492,19 +914,36 @@ impl<'a> Interpreter<'a> {
         Ok(StatementResult::None)
     }
 
-    fn execute_function_internal(&'a self, type_instance: Option<(Path<'a>, IdentifierType)>, parameters: &'a Vec<Parameter>, values: Vec<Path<'a>>, statements: &'a Vec<Statement>, variables_count: u16, state: &mut State) -> Result<Option<Path<'a>>, InterpreterError> {
+    // Create the stack of a function call with its instance and parameters registered
+    fn prepare_stack(&'a self, type_instance: Option<(Path<'a>, IdentifierType)>, parameters: &'a [Parameter], values: Vec<Path<'a>>, variables_count: u16, state: &mut State) -> Result<Stack<'a>, InterpreterError> {
         let mut stack = Stack::new(variables_count);
         if let Some((instance, instance_name)) = type_instance {
             stack.register_variable(instance_name, instance)?;
         }
 
+        let provided = values.len();
         for (param, value) in parameters.iter().zip(values.into_iter()) {
             stack.register_variable(param.get_name().clone(), value)?;
         }
 
-        match self.execute_statements(statements, &mut stack, state)? {
-            StatementResult::Return(v) => Ok(v),
-            _ => Ok(None)
+        // Omitted arguments are evaluated from their default value
+        for param in parameters.iter().skip(provided) {
+            let default_value = param.get_default_value().ok_or(InterpreterError::MissingArgument)?;
+            let value = self.execute_expression_and_expect_value(default_value, &mut stack, state)
+                .map_err(Unwind::into_error)?;
+            stack.register_variable(*param.get_name(), value)?;
+        }
+
+        Ok(stack)
+    }
+
+    fn execute_function_internal(&'a self, type_instance: Option<(Path<'a>, IdentifierType)>, parameters: &'a Vec<Parameter>, values: Vec<Path<'a>>, statements: &'a Vec<Statement>, variables_count: u16, state: &mut State) -> Result<Option<Path<'a>>, InterpreterError> {
+        let mut stack = self.prepare_stack(type_instance, parameters, values, variables_count, state)?;
+        match self.execute_statements(statements, &mut stack, state) {
+            Ok(StatementResult::Return(v)) => Ok(v),
+            Ok(_) => Ok(None),
+            // The try operator or a return in a block expression returns from the function
+            Err(unwind) => unwind.into_returned().map(|value| value.map(Path::Owned))
         }
     }
 
@@ -512,20 +951,23 @@ impl<'a> Interpreter<'a> {
     fn execute_function(&'a self, f: Function<'a>, type_instance: Option<Path<'a>>, values: Vec<Path<'a>>, state: &mut State) -> Result<Option<Path<'a>>, InterpreterError> {
         match f {
             Function::Native(f) => {
-                state.increase_gas_usage(f.get_cost())?;
-                match type_instance {
+                state.increase_gas_usage(f.get_call_cost(type_instance.as_ref(), &values))?;
+                let mut context = self.context.borrow_mut();
+                let value = match type_instance {
                     Some(mut v) => {
                         let mut instance = v.as_mut();
-                        f.call_function(Some(instance.as_mut()), values)
-                            .map(|v| v.map(Path::Owned))
-                            .map_err(InterpreterError::EnvironmentError)
+                        f.call_function(Some(instance.as_mut()), values, &mut context)
                     },
-                    None => {
-                        f.call_function(None, values)
-                            .map(|v| v.map(Path::Owned))
-                            .map_err(InterpreterError::EnvironmentError)
-                    }
+                    None => f.call_function(None, values, &mut context)
+                }.map_err(environment_error)?;
+
+                // Reaching the end of a diverging function aborts the execution
+                if matches!(f.return_type(), Some(Type::Never)) {
+                    return Err(InterpreterError::NeverReturned)
                 }
+
+                // Not normalized: an optional may contain another one, like the first element of an array of optionals
+                Ok(value.map(Path::Owned))
             },
             Function::Program(f) => {
                 let instance = match (type_instance, f.get_instance_name()) {
@@ -538,6 +980,20 @@ impl<'a> Interpreter<'a> {
         }
     }
 
+    // Execute statements against a context owned by the caller
+    // Variables declared are kept in the context for the next evaluations
+    // variables_count is the count of variables used by the statements and the previous ones
+    pub fn execute_in_context(&'a self, statements: &'a [Statement], variables_count: u16, context: &mut ExecutionContext<'a>, state: &mut State) -> Result<Option<Value>, InterpreterError> {
+        let stack = context.stack_mut();
+        stack.reserve(variables_count);
+
+        match self.execute_statements(statements, stack, state) {
+            Ok(StatementResult::Return(v)) => Ok(v.map(Path::into_owned)),
+            Ok(_) => Ok(None),
+            Err(unwind) => unwind.into_returned()
+        }
+    }
+
     // Compute the constants defined in the program
     pub fn compute_constants(&mut self, state: &mut State) -> Result<(), InterpreterError> {
         if self.constants.is_none() {
@@ -545,7 +1001,8 @@ impl<'a> Interpreter<'a> {
             let mut stack = Stack::new(self.program.constants().len() as u16);
 
             for constant in self.program.constants().iter() {
-                let value = self.execute_expression_and_expect_value(&constant.value, &mut stack, state)?
+                let value = self.execute_expression_and_expect_value(&constant.value, &mut stack, state)
+                    .map_err(Unwind::into_error)?
                     .into_owned();
                 constants.insert(constant.id.clone(), value);
             }
@@ -556,29 +1013,101 @@ impl<'a> Interpreter<'a> {
         Ok(())
     }
 
+    // Verify the entry function against the contract set by the host
+    // and the provided parameters against the entry function
+    // so a mismatch is reported before running anything
+    fn verify_entry_call(&self, f: &FunctionType, parameters: &[Path<'a>]) -> Result<(), InterpreterError> {
+        let expected = f.get_parameters();
+        if let Some(contract) = self.entry_contract.as_ref() {
+            let matches = contract.get_parameters().len() == expected.len()
+                && contract.get_parameters().iter().zip(expected).all(|(a, b)| a == b.get_type())
+                && f.return_type().as_ref() == Some(contract.get_return_type());
+
+            if !matches {
+                return Err(InterpreterError::EntryContractMismatch)
+            }
+        }
+
+        // Omitted parameters must have a default value
+        let required = expected.iter().filter(|p| p.get_default_value().is_none()).count();
+        if parameters.len() < required || parameters.len() > expected.len() {
+            return Err(InterpreterError::InvalidEntryArgumentsCount(expected.len(), parameters.len()))
+        }
+
+        for (i, (param, value)) in expected.iter().zip(parameters).enumerate() {
+            let value = value.as_ref();
+            if !contract::is_value_of_type(&value, param.get_type()) || !self.is_function_of_type(&value, param.get_type()) {
+                return Err(InterpreterError::InvalidEntryArgumentType(i, param.get_type().clone()))
+            }
+        }
+
+        Ok(())
+    }
+
+    // A function value provided by the host must be a function of the program
+    // that can be used as a value, with the same parameters and return types
+    fn is_function_of_type(&self, value: &Value, expected: &Type) -> bool {
+        let (Value::Function(id), Type::Function(parameters, return_type)) = (value, expected) else {
+            return true
+        };
+
+        match self.get_function(id) {
+            Ok(Function::Program(FunctionType::Declared(f))) => f.get_on_type().is_none()
+                && f.get_parameters().len() == parameters.len()
+                && f.get_parameters().iter().zip(parameters).all(|(p, t)| p.get_type() == t)
+                && f.get_return_type().as_ref() == Some(return_type.as_ref()),
+            _ => false
+        }
+    }
+
     // Execute the program by calling an available entry function
     pub fn call_entry_function(&'a self, function_name: &IdentifierType, parameters: Vec<Path<'a>>, state: &mut State) -> Result<u64, InterpreterError> {
         let f = self.get_function(function_name)?;
 
         // only function marked as entry can be called from external
-        if !f.is_entry() {
-            return Err(InterpreterError::FunctionEntry(true, false))
-        }
+        let f = match f {
+            Function::Program(f) if f.is_entry() => f,
+            _ => return Err(InterpreterError::FunctionEntry(true, false))
+        };
 
-        match self.execute_function(f, None, parameters, state)? {
+        self.verify_entry_call(f, &parameters)?;
+        self.context.borrow_mut().reset_allocations();
+
+        match self.execute_function(Function::Program(f), None, parameters, state)? {
             Some(val) => Ok(val.as_u64()?),
             None => return Err(InterpreterError::NoExitCode)
         }
     }
+
+    // Call the same entry function once per input, in order
+    // The interpreter is linked only once for all the calls
+    // The state counters are reset before each call,
+    // so a failing call doesn't affect the following ones
+    // Each result is returned with the events emitted by its call,
+    // the events not taken before the batch are dropped
+    pub fn call_entry_many(&'a self, function_name: &IdentifierType, inputs: Vec<Vec<Value>>, state: &mut State) -> Vec<EntryCallResult> {
+        inputs.into_iter()
+            .map(|parameters| {
+                state.reset();
+                self.take_events();
+                let parameters = parameters.into_iter().map(Path::Owned).collect();
+                let result = self.call_entry_function(function_name, parameters, state);
+                (result, self.take_events())
+            })
+            .collect()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use xelis_ast::Signature;
+    use std::rc::Rc;
+    use xelis_environment::{EnvironmentError, OutputSink};
+    use xelis_types::StructType;
+    use xelis_ast::{DeclarationStatement, EntryFunction, Signature, Token};
     use xelis_lexer::Lexer;
-    use xelis_parser::Parser;
-    use xelis_builder::EnvironmentBuilder;
+    use xelis_parser::{Parser, ParserError};
+    use xelis_builder::{BuilderError, EnvironmentBuilder, NativeFunctionDescriptor};
 
     #[track_caller]
     fn test_code_expect_value(key: &Signature, code: &str) -> Value {
@@ -601,6 +1130,22 @@ mod tests {
         result.unwrap().into_owned()
     }
 
+    // Execute the main entry in the given environment and state
+    #[track_caller]
+    fn try_execute_with_state(code: &str, env: &EnvironmentBuilder, state: &mut State) -> Result<u64, InterpreterError> {
+        let tokens = Lexer::new(code).get().unwrap();
+        let (program, mapper) = Parser::new(tokens, env).parse().unwrap();
+        let interpreter = Interpreter::new(&program, env.environment()).unwrap();
+        let main = mapper.get(&Signature::new("main".to_string(), None, Vec::new())).unwrap();
+        interpreter.call_entry_function(&main, Vec::new(), state)
+    }
+
+    // Execute the main entry and return the error it failed with
+    #[track_caller]
+    fn test_code_expect_error(code: &str) -> InterpreterError {
+        try_execute_with_state(code, &EnvironmentBuilder::default(), &mut State::new(None, None, None)).unwrap_err()
+    }
+
     #[track_caller]
     fn test_code_expect_return(code: &str, expected: u64) {
         assert_eq!(test_code_expect_value(&Signature::new("main".to_string(), None, Vec::new()), code).to_u64().unwrap(), expected);
@@ -611,51 +1156,111 @@ mod tests {
         assert_eq!(test_code_expect_value_with_env(env, &Signature::new("main".to_string(), None, Vec::new()), code).to_u64().unwrap(), expected);
     }
 
+    #[track_caller]
+    fn test_truthy_code_expect_return(code: &str, expected: u64) {
+        let env = EnvironmentBuilder::default();
+        let mut parser = Parser::new(Lexer::new(code).get().unwrap(), &env);
+        parser.set_truthy_conditions(true);
+        let (program, mapper) = parser.parse().unwrap();
+
+        let mut state = State::new(None, None, None);
+        let mut interpreter = Interpreter::new(&program, env.environment()).unwrap();
+        interpreter.set_truthy_conditions(true);
+
+        let mapped_name = mapper.get(&Signature::new("main".to_string(), None, Vec::new())).unwrap();
+        let result = interpreter.call_entry_function(&mapped_name, Vec::new(), &mut state).unwrap();
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_truthy_conditions() {
+        // Numbers
+        test_truthy_code_expect_return("entry main() { let a: u64 = 0; if a { return 1 } return 0 }", 0);
+        test_truthy_code_expect_return("entry main() { let a: u8 = 3; if a { return 1 } return 0 }", 1);
+        test_truthy_code_expect_return("entry main() { var a: u64 = 3; while a { a -= 1; } return a }", 0);
+
+        // Strings
+        test_truthy_code_expect_return("entry main() { let s: string = \"\"; return s ? 1 : 0 }", 0);
+        test_truthy_code_expect_return("entry main() { let s: string = \"a\"; return s ? 1 : 0 }", 1);
+
+        // Arrays
+        test_truthy_code_expect_return("entry main() { let a: u64[] = []; if a { return 1 } return 0 }", 0);
+        test_truthy_code_expect_return("entry main() { let a: u64[] = [0]; if a { return 1 } return 0 }", 1);
+        test_truthy_code_expect_return("entry main() { let a: u64[] = [1, 2, 3]; var n: u64 = 0; for i: u64 = 0; a; i += 1 { a.pop(); n += 1; } return n }", 3);
+
+        // Optionals
+        test_truthy_code_expect_return("entry main() { let a: optional<u64> = null; if a { return 1 } return 0 }", 0);
+        test_truthy_code_expect_return("entry main() { let a: u64[] = []; if a.first() { return 1 } return 0 }", 0);
+        test_truthy_code_expect_return("entry main() { let a: u64[] = [0]; if a.first() { return 1 } return 0 }", 1);
+
+        // Booleans keep their value
+        test_truthy_code_expect_return("entry main() { if false { return 1 } return 0 }", 0);
+    }
+
+    #[test]
+    fn test_strict_conditions() {
+        let env = EnvironmentBuilder::default();
+        let tokens = Lexer::new("entry main() { let a: u64 = 1; if a { return 1 } return 0 }").get().unwrap();
+        assert!(matches!(Parser::new(tokens, &env).parse(), Err(ParserError::InvalidCondition(Type::U64, _))));
+    }
+
     #[test]
     fn test_no_stackoverflow() {
-        let code = "entry main() { let a: u64 = 0; for i: u64 = 0; i < 100000; i += 1 { a += i; } return a; }";
+        let code = "entry main() { var a: u64 = 0; for i: u64 = 0; i < 100000; i += 1 { a += i; } return a; }";
         test_code_expect_return(code, 4999950000);
 
-        // TODO
-        let mut code = "entry main() { let a: u64 = 1; return ".to_string();
-        code.push_str("a + a + ".repeat(10000).as_str());
-        code.push_str("a; }");
-        test_code_expect_return(code.as_str(), 4999950000);
+        // Long chains are bounded by the parser, anything it accepts is executed
+        // The limits are sized for release builds, debug builds use larger frames
+        std::thread::Builder::new()
+            .stack_size(64 * 1024 * 1024)
+            .spawn(|| {
+                let code = |count: usize| format!("entry main() {{ let a: u64 = 1; return {}; }}", vec!["a"; count].join(" + "));
+                test_code_expect_return(&code(200), 200);
+
+                let env = EnvironmentBuilder::default();
+                let source = code(10000);
+                let tokens = Lexer::new(&source).get().unwrap();
+                let err = Parser::new(tokens, &env).parse().unwrap_err();
+                assert!(matches!(err, ParserError::MaxDepthReached(_)));
+            })
+            .unwrap()
+            .join()
+            .unwrap();
     }
 
     #[test]
     fn test_self_assign() {
         // For mutability check, we must be sure to be able to use the same variable
-        test_code_expect_return("entry main() { let a: u64 = 10; a = a; return a; }", 10);
-        test_code_expect_return("entry main() { let a: u64 = 10; a = a + a; return a; }", 20);
+        test_code_expect_return("entry main() { var a: u64 = 10; a = a; return a; }", 10);
+        test_code_expect_return("entry main() { var a: u64 = 10; a = a + a; return a; }", 20);
     }
 
     #[test]
     fn test_op_assignation() {
-        test_code_expect_return("entry main() { let a: u64 = 10; a += 10; return a; }", 20);
-        test_code_expect_return("entry main() { let a: u64 = 10; a -= 10; return a; }", 0);
-        test_code_expect_return("entry main() { let a: u64 = 10; a *= 10; return a; }", 100);
-        test_code_expect_return("entry main() { let a: u64 = 10; a /= 10; return a; }", 1);
-        test_code_expect_return("entry main() { let a: u64 = 10; a %= 10; return a; }", 0);
-        test_code_expect_return("entry main() { let a: u64 = 10; a &= 10; return a; }", 10);
-        test_code_expect_return("entry main() { let a: u64 = 10; a |= 10; return a; }", 10);
-        test_code_expect_return("entry main() { let a: u64 = 10; a ^= 10; return a; }", 0);
-        test_code_expect_return("entry main() { let a: u64 = 10; a <<= 10; return a; }", 10240);
-        test_code_expect_return("entry main() { let a: u64 = 10; a >>= 10; return a; }", 0);
+        test_code_expect_return("entry main() { var a: u64 = 10; a += 10; return a; }", 20);
+        test_code_expect_return("entry main() { var a: u64 = 10; a -= 10; return a; }", 0);
+        test_code_expect_return("entry main() { var a: u64 = 10; a *= 10; return a; }", 100);
+        test_code_expect_return("entry main() { var a: u64 = 10; a /= 10; return a; }", 1);
+        test_code_expect_return("entry main() { var a: u64 = 10; a %= 10; return a; }", 0);
+        test_code_expect_return("entry main() { var a: u64 = 10; a &= 10; return a; }", 10);
+        test_code_expect_return("entry main() { var a: u64 = 10; a |= 10; return a; }", 10);
+        test_code_expect_return("entry main() { var a: u64 = 10; a ^= 10; return a; }", 0);
+        test_code_expect_return("entry main() { var a: u64 = 10; a <<= 10; return a; }", 10240);
+        test_code_expect_return("entry main() { var a: u64 = 10; a >>= 10; return a; }", 0);
     }
 
     #[test]
     fn test_op_bool_assignation() {
-        test_code_expect_return("entry main() { let a: bool = true; a = a && true; return a as u64; }", 1);
-        test_code_expect_return("entry main() { let a: bool = true; a = a && false; return a as u64; }", 0);
-        test_code_expect_return("entry main() { let a: bool = true; a = a || false; return a as u64; }", 1);
-        test_code_expect_return("entry main() { let a: bool = true; a = a || true; return a as u64; }", 1);
+        test_code_expect_return("entry main() { var a: bool = true; a = a && true; return a as u64; }", 1);
+        test_code_expect_return("entry main() { var a: bool = true; a = a && false; return a as u64; }", 0);
+        test_code_expect_return("entry main() { var a: bool = true; a = a || false; return a as u64; }", 1);
+        test_code_expect_return("entry main() { var a: bool = true; a = a || true; return a as u64; }", 1);
         // |=
-        test_code_expect_return("entry main() { let a: bool = false; a |= true; return a as u64; }", 1);
-        test_code_expect_return("entry main() { let a: bool = false; a |= false; return a as u64; }", 0);
+        test_code_expect_return("entry main() { var a: bool = false; a |= true; return a as u64; }", 1);
+        test_code_expect_return("entry main() { var a: bool = false; a |= false; return a as u64; }", 0);
         // &=
-        test_code_expect_return("entry main() { let a: bool = true; a &= true; return a as u64; }", 1);
-        test_code_expect_return("entry main() { let a: bool = true; a &= false; return a as u64; }", 0);
+        test_code_expect_return("entry main() { var a: bool = true; a &= true; return a as u64; }", 1);
+        test_code_expect_return("entry main() { var a: bool = true; a &= false; return a as u64; }", 0);
     }
 
     #[test]
@@ -680,103 +1285,492 @@ mod tests {
     #[test]
     fn test_optional() {
         test_code_expect_return("entry main() { let a: u64[] = []; return a.first().unwrap_or(777); }", 777);
+
+        // The element of an array of optionals is wrapped in another optional
+        test_code_expect_return("entry main() { let a: optional<u64>[] = [5]; let f: optional<u64> = a.first().unwrap(); return f.unwrap(); }", 5);
+        test_code_expect_return("entry main() { let n: optional<u64> = null; let a: optional<u64>[] = [n]; let f: optional<optional<u64>> = a.first(); return (f.is_some() as u64) * 10 + (f.unwrap().is_some() as u64); }", 10);
     }
 
     #[test]
-    fn test_number_operations() {
-        test_code_expect_return("entry main() { return 10; }", 10);
-        test_code_expect_return("entry main() { return 10 + 10; }", 20);
-        test_code_expect_return("entry main() { return 10 - 10; }", 0);
-        test_code_expect_return("entry main() { return 10 * 10; }", 100);
-        test_code_expect_return("entry main() { return 10 / 10; }", 1);
-        test_code_expect_return("entry main() { return 10 % 10; }", 0);
-        test_code_expect_return("entry main() { return (10 == 10) as u64; }", 1);
-        test_code_expect_return("entry main() { return (10 != 10) as u64; }", 0);
-        test_code_expect_return("entry main() { return (10 > 10) as u64; }", 0);
-        test_code_expect_return("entry main() { return (10 >= 10) as u64; }", 1);
-        test_code_expect_return("entry main() { return (10 < 10) as u64; }", 0);
-        test_code_expect_return("entry main() { return (10 <= 10) as u64; }", 1);
-        test_code_expect_return("entry main() { return 10 & 10; }", 10);
-        test_code_expect_return("entry main() { return 10 | 10; }", 10);
-        test_code_expect_return("entry main() { return 10 ^ 10; }", 0);
-        test_code_expect_return("entry main() { return 10 << 10; }", 10240);
-        test_code_expect_return("entry main() { return 10 >> 10; }", 0);
+    fn test_optional_chaining() {
+        let structs = "struct Inner { c: u64 } struct Outer { b: optional<Inner> }";
+        let present = "let inners: Inner[] = [Inner { c: 10 }]; let outers: Outer[] = [Outer { b: inners.first() }]; let o: optional<Outer> = outers.first();";
+        // Present at every level
+        test_code_expect_return(&format!("{structs} entry main() {{ {present} return o?.b?.c.unwrap_or(777); }}"), 10);
+        // Field of a non optional value is wrapped in some
+        test_code_expect_return(&format!("{structs} entry main() {{ {present} let c: optional<u64> = o?.b?.c; return c.is_some() as u64; }}"), 1);
+        // Null at the first level
+        test_code_expect_return(&format!("{structs} entry main() {{ let o: optional<Outer> = null; return o?.b?.c.unwrap_or(777); }}"), 777);
+        // Null at the middle level
+        test_code_expect_return(&format!("{structs} entry main() {{ let inners: Inner[] = []; let outers: Outer[] = [Outer {{ b: inners.first() }}]; let o: optional<Outer> = outers.first(); return o?.b?.c.unwrap_or(777); }}"), 777);
+    }
 
-        test_code_expect_return("entry main() { return 10 + 10 * 10; }", 110);
-        test_code_expect_return("entry main() { return (10 + 10) * 10; }", 200);
+    #[test]
+    fn test_struct_update() {
+        let code = "struct Point { x: u64, y: u64 } entry main() { let p: Point = Point { x: 1, y: 2 }; let q: Point = p with { x: 5 }; return (((q.x * 100) + (q.y * 10)) + p.x) }";
+        test_code_expect_return(code, 521);
+
+        // Nested structs are copied too
+        let code = "struct Inner { v: u64 } struct Outer { inner: Inner, n: u64 } entry main() { let a: Outer = Outer { inner: Inner { v: 1 }, n: 2 }; var b: Outer = a with { n: 3 }; b.inner.v = 7; return (a.inner.v * 10) + b.n }";
+        test_code_expect_return(code, 13);
     }
 
     #[test]
-    fn test_u128() {
-        test_code_expect_return("entry main() { let j: u128 = 10; j = 2 + j; return j as u64; }", 12);
-        test_code_expect_return("entry main() { let j: u128 = 10; j = ((2 + j) * (3 + j) * (4 + j)); return j as u64; }", 2184);
+    fn test_struct_default_fields() {
+        let structs = "struct Point { x: u64 = 1, y: u64 = 2, z: u64 }";
+        test_code_expect_return(&format!("{structs} entry main() {{ let p: Point = Point {{ x: 5, y: 6, z: 7 }}; return ((p.x * 100) + (p.y * 10)) + p.z }}"), 567);
+        test_code_expect_return(&format!("{structs} entry main() {{ let p: Point = Point {{ z: 7, x: 5 }}; return ((p.x * 100) + (p.y * 10)) + p.z }}"), 527);
+
+        // Each construction gets its own copy of the default value
+        let code = "struct Bag { items: u64[] = [] } entry main() { let a: Bag = Bag {}; let b: Bag = Bag {}; a.items.push(1); return b.items.len() as u64 }";
+        test_code_expect_return(code, 0);
+
+        let tokens = Lexer::new("struct Point { x: u64 = 1, y: u64 } entry main() { let p: Point = Point { x: 5 }; return p.x }").get().unwrap();
+        let env = EnvironmentBuilder::default();
+        assert!(matches!(Parser::new(tokens, &env).parse(), Err(ParserError::MissingStructField(name)) if name == "y"));
+
+        let tokens = Lexer::new("struct Point { x: u64 = 1 } entry main() { let p: Point = Point { x: 5, x: 6 }; return p.x }").get().unwrap();
+        assert!(matches!(Parser::new(tokens, &env).parse(), Err(ParserError::DuplicateStructField(name)) if name == "x"));
     }
 
     #[test]
-    fn test_array() {
-        test_code_expect_return("entry main() { let a: u64[] = [1]; let b: u32 = 0; return a[b]; }", 1);
-        test_code_expect_return("fn test() -> u64[] { return [0, 1, 2]; } entry main() { let b: u32 = 0; return test()[b]; }", 0);
+    fn test_nested_field_assignment() {
+        let structs = "struct C { x: u64, y: u64 } struct B { n: u64, c: C } struct A { b: B, items: B[] }";
+        let init = "var a: A = A { b: B { n: 1, c: C { x: 2, y: 3 } }, items: [B { n: 4, c: C { x: 5, y: 6 } }] };";
 
-        test_code_expect_return("entry main() { let a: u64[] = [1, 2, 3]; return a[0]; }", 1);
-        test_code_expect_return("entry main() { let a: u64[] = [1, 2, 3]; return a[1]; }", 2);
-        test_code_expect_return("entry main() { let a: u64[] = [1, 2, 3]; return a[2]; }", 3);
+        let code = format!("{} entry main() {{ {} a.b.c.y = 30; return (a.b.c.x * 100) + a.b.c.y }}", structs, init);
+        test_code_expect_return(&code, 230);
 
-        test_code_expect_return("entry main() { let a: u64[] = [1, 2, 3]; return a[0] + a[1] + a[2]; }", 6);
-        test_code_expect_return("entry main() { let a: u64[] = [1, 2, 3]; a[0] = 10; return a[0]; }", 10);
-        test_code_expect_return("entry main() { let a: u64[] = [1, 2, 3]; a[0] = 10; return a[1]; }", 2);
-        test_code_expect_return("entry main() { let a: u64[] = [1, 2, 3]; a[0] = 10; return a[2]; }", 3);
-        test_code_expect_return("entry main() { let a: u64[] = [1, 2, 3]; a[0] = 10; return a[0] + a[1] + a[2]; }", 15);
+        // Compound assignment through the path
+        let code = format!("{} entry main() {{ {} a.b.c.x += 40; a.b.n *= 3; return (a.b.c.x * 100) + a.b.n }}", structs, init);
+        test_code_expect_return(&code, 4203);
 
-        // Push
-        test_code_expect_return("entry main() { let a: u64[] = [1, 2, 3]; a.push(10); return a[3]; }", 10);
-        test_code_expect_return("entry main() { let a: u64[] = [1, 2, 3]; let v: u64 = 10; a.push(v); return a[0] + a[1] + a[2] + a[3]; }", 16);
-        test_code_expect_return("entry main() { let a: u64[] = [1, 2, 3]; let b: u64[] = []; let v: u64 = 10; b.push(10); a.push(b[0]); return a[0] + a[1] + a[2] + a[3]; }", 16);
+        // Field, then index computed at runtime, then field again
+        let code = format!("{} entry main() {{ {} let i: u32 = 0; a.items[i + 0].c.y = 60; return (a.items[0].c.y * 100) + a.b.c.y }}", structs, init);
+        test_code_expect_return(&code, 6003);
 
-        // Pop
-        test_code_expect_return("entry main() { let a: u64[] = [1, 2, 3]; a.pop(); return a.len() as u64; }", 2);
+        // Replace a whole nested struct
+        let code = format!("{} entry main() {{ {} a.b.c = C {{ x: 7, y: 8 }}; return (a.b.c.x * 10) + a.b.c.y }}", structs, init);
+        test_code_expect_return(&code, 78);
+
+        // A copy isn't changed by an assignment on the original
+        let code = format!("{} entry main() {{ {} let b: B = a.b; a.b.c.x = 9; return (b.c.x * 10) + a.b.c.x }}", structs, init);
+        test_code_expect_return(&code, 29);
     }
 
     #[test]
-    fn test_number_operations_priority() {
-        test_code_expect_return("entry main() { return 10 + 10 * 10; }", 110);
-        test_code_expect_return("entry main() { return (10 + 10) * 10; }", 200);
+    fn test_struct_field_type_mismatch() {
+        // The parser rejects it, build the program by hand
+        let struct_type = StructType::new(0, vec![Type::U64, Type::String]);
+        let constructor = Expression::StructConstructor(vec![
+            Expression::Value(Value::U64(1)),
+            Expression::Value(Value::Boolean(true))
+        ], struct_type.clone());
+
+        let mut program = Program::new();
+        program.add_structure(struct_type);
+        program.add_function(FunctionType::Entry(EntryFunction::new(Vec::new(), vec![Statement::Expression(constructor)], 0)));
+
+        let env = EnvironmentBuilder::default();
+        let mut interpreter = Interpreter::new(&program, env.environment()).unwrap();
+        let mut state = State::new(None, None, None);
+        let id = env.environment().get_functions().len() as IdentifierType;
+        // Not checked by default
+        assert!(!matches!(interpreter.call_entry_function(&id, Vec::new(), &mut state), Err(InterpreterError::StructFieldTypeMismatch { .. })));
+
+        interpreter.set_check_struct_fields(true);
+        let err = interpreter.call_entry_function(&id, Vec::new(), &mut state).unwrap_err();
+        assert!(matches!(
+            &err,
+            InterpreterError::StructFieldTypeMismatch { expected: Type::String, got: Some(Type::Bool), .. }
+        ));
+        assert_eq!(err.to_string(), "Invalid value for field 1 of struct 0: expected string, got bool");
+
+        // The names are reported when they are known
+        let struct_type = StructType::with_names(0, "Point".into(), vec![Type::U64, Type::String], vec!["x".into(), "label".into()]);
+        let constructor = Expression::StructConstructor(vec![
+            Expression::Value(Value::U64(1)),
+            Expression::Value(Value::Boolean(true))
+        ], struct_type.clone());
+
+        let mut program = Program::new();
+        program.add_structure(struct_type);
+        program.add_function(FunctionType::Entry(EntryFunction::new(Vec::new(), vec![Statement::Expression(constructor)], 0)));
+
+        let mut interpreter = Interpreter::new(&program, env.environment()).unwrap();
+        interpreter.set_check_struct_fields(true);
+        let err = interpreter.call_entry_function(&id, Vec::new(), &mut state).unwrap_err();
+        assert_eq!(err.to_string(), "Invalid value for field label of struct Point: expected string, got bool");
+    }
 
-        test_code_expect_return("entry main() { return 10 + 10 / 5 + 3; }", 15);
-        test_code_expect_return("entry main() { return 10 + 10 / 5 * 3; }", 16);
-        test_code_expect_return("entry main() { return 10 + 10 / 5 + 3 * 10; }", 42);
+    #[test]
+    fn test_struct_function_field_checked() {
+        let code = "struct Op { f: fn(u64) -> u64 } fn double(a: u64) -> u64 { return a * 2 } entry main() { let op: Op = Op { f: double }; let f: fn(u64) -> u64 = op.f; return f(21) }";
+        let env = EnvironmentBuilder::default();
+        let (program, mapper) = Parser::new(Lexer::new(code).get().unwrap(), &env).parse().unwrap();
+        let mut interpreter = Interpreter::new(&program, env.environment()).unwrap();
+        interpreter.set_check_struct_fields(true);
+
+        let mut state = State::new(None, None, None);
+        let id = mapper.get(&Signature::new("main".to_string(), None, Vec::new())).unwrap();
+        assert_eq!(interpreter.call_entry_function(&id, Vec::new(), &mut state).unwrap(), 42);
     }
 
     #[test]
-    fn test_basic_function_call() {
-        test_code_expect_return("fn add(a: u64, b: u64) -> u64 { return a + b; } entry main() { return add(10, 10); }", 20);
-        test_code_expect_return("fn add(a: u64, b: u64) -> u64 { return a + b; } entry main() { return add(10, add(10, 10)); }", 30);
+    fn test_struct_function_field_call() {
+        let code = "struct Op { f: fn(u64) -> u64 } fn double(a: u64) -> u64 { return a * 2 } entry main() { let op: Op = Op { f: double }; return op.f(21) }";
+        test_code_expect_return(code, 42);
 
-        // With variable
-        test_code_expect_return("fn add(a: u64, b: u64) -> u64 { return a + b; } entry main() { let a: u64 = 10; return add(a, 10); }", 20);
-        test_code_expect_return("fn add(a: u64, b: u64) -> u64 { return a + b; } entry main() { let a: u64 = 10; return add(a, add(10, 10)); }", 30);
+        let code = "struct Op { f: fn(u64) -> u64 } struct Ops { op: Op } fn double(a: u64) -> u64 { return a * 2 }
+        entry main() { let ops: Ops[] = [Ops { op: Op { f: double } }]; return ops[0].op.f(5) + 1 }";
+        test_code_expect_return(code, 11);
     }
 
     #[test]
-    fn test_function_call_on_value() {
-        test_code_expect_return("struct Test { a: u64 } fn (v Test) add(b: u64) -> u64 { return v.a + b; } entry main() { let t: Test = Test {a: 10}; return t.add(10); }", 20);
-        test_code_expect_return("struct Test { a: u64 } fn (v Test) add(b: u64) -> u64 { return v.a + b; } entry main() { let t: Test = Test {a: 10}; return t.add(t.add(10)); }", 30);
-        test_code_expect_return("struct Test { a: u64 } fn (v Test) add(b: u64) { v.a += b; } entry main() { let t: Test = Test {a: 10}; t.add(10); return t.a }", 20);
+    fn test_struct_update_unknown_field() {
+        let code = "struct Point { x: u64, y: u64 } entry main() { let p: Point = Point { x: 1, y: 2 }; let q: Point = p with { z: 5 }; return q.x }";
+        let tokens = Lexer::new(code).get().unwrap();
+        let env = EnvironmentBuilder::default();
+        assert!(matches!(Parser::new(tokens, &env).parse(), Err(ParserError::StructureFieldNotFound(name)) if name == "z"));
     }
 
     #[test]
-    fn test_casting() {
-        let key = Signature::new("main".to_string(), None, Vec::new());
+    fn test_block_expression() {
+        let code = "entry main() { let a: u64 = 5; let b: u64 = { let c: u64 = a * 2; c + 1 }; return b }";
+        test_code_expect_return(code, 11);
 
-        // Auto casting
-        assert_eq!(Value::U8(10), test_code_expect_value(&key, "fn main() -> u8 { return 10; }"));
-        assert_eq!(Value::U16(10), test_code_expect_value(&key, "fn main() -> u16 { return 10; }"));
-        assert_eq!(Value::U32(10), test_code_expect_value(&key, "fn main() -> u32 { return 10; }"));
-        assert_eq!(Value::U64(10), test_code_expect_value(&key, "fn main() -> u64 { return 10; }"));
-        assert_eq!(Value::U128(10), test_code_expect_value(&key, "fn main() -> u128 { return 10u128; }"));
+        // The literal is inferred from the declared type
+        test_code_expect_return("entry main() { let a: u8 = { 255 }; return a as u64 }", 255);
 
-        // Explicit casting
-        assert_eq!(Value::U8(10), test_code_expect_value(&key, "fn main() -> u8 { let a: u64 = 10; return a as u8; }"));
-        assert_eq!(Value::U16(10), test_code_expect_value(&key, "fn main() -> u16 { let a: u64 = 10; return a as u16; }"));
+        // Statements are executed before the value
+        let code = "entry main() { var a: u64 = 1; let b: u64 = { a += 10; if a > 5 { a *= 2 } a }; return (a * 100) + b }";
+        test_code_expect_return(code, 2222);
+    }
+
+    #[test]
+    fn test_block_expression_return() {
+        // A return leaves the function, not the block
+        let code = r#"
+            fn check(a: u64) -> u64 {
+                let b: u64 = {
+                    if a == 0 {
+                        return 100
+                    }
+                    a * 2
+                };
+                return b + 1
+            }
+
+            entry main() {
+                return (check(0) * 1000) + check(3)
+            }
+        "#;
+        test_code_expect_return(code, 100007);
+    }
+
+    #[test]
+    fn test_block_expression_without_value() {
+        let env = EnvironmentBuilder::default();
+        for code in [
+            "entry main() { let a: u64 = {}; return a }",
+            "entry main() { let a: u64 = { let b: u64 = 1 }; return a }",
+        ] {
+            let tokens = Lexer::new(code).get().unwrap();
+            assert!(matches!(Parser::new(tokens, &env).parse(), Err(ParserError::ExpectedBlockValue)));
+        }
+
+        // break can't leave a block expression
+        let code = "entry main() { while true { let a: u64 = { break; 1 } } return 0 }";
+        let tokens = Lexer::new(code).get().unwrap();
+        assert!(matches!(Parser::new(tokens, &env).parse(), Err(ParserError::UnexpectedToken(Token::Break))));
+    }
+
+    #[test]
+    fn test_field_count() {
+        let code = r#"
+            struct Point { x: u64, y: u64, label: string }
+
+            entry main() {
+                let p: Point = Point { x: 1, y: 2, label: "a" };
+                return field_count(p) as u64
+            }
+        "#;
+        test_code_expect_return(code, 3);
+    }
+
+    #[test]
+    fn test_field_names() {
+        let code = r#"
+            struct Point { x: u64, y: u64, label: string }
+
+            entry main() {
+                let p: Point = Point { x: 1, y: 2, label: "a" };
+                let names: string[] = field_names(p);
+                assert(names == ["x", "y", "label"]);
+                return names.len() as u64
+            }
+        "#;
+        test_code_expect_return(code, 3);
+
+        // Only the structs have fields
+        let env = EnvironmentBuilder::default();
+        for call in ["field_count(5)", "field_names([1, 2])"] {
+            let code = format!("entry main() {{ {}; return 0 }}", call);
+            let tokens = Lexer::new(&code).get().unwrap();
+            assert!(matches!(Parser::new(tokens, &env).parse(), Err(ParserError::BuilderError(BuilderError::MappingNotFound))), "{}", call);
+        }
+    }
+
+    #[test]
+    fn test_resumable() {
+        let code = r#"
+            entry main() {
+                let a: u64 = yield(10);
+                let b: u64 = yield(a + 1);
+                return a + b
+            }
+        "#;
+
+        let env = EnvironmentBuilder::default();
+        let tokens = Lexer::new(code).get().unwrap();
+        let (program, mapper) = Parser::new(tokens, &env).parse().unwrap();
+        let interpreter = Interpreter::new(&program, env.environment()).unwrap();
+
+        let main = mapper.get(&Signature::new("main".to_string(), None, Vec::new())).unwrap();
+        let yield_id = env.get_functions_mapper().get(&Signature::new("yield".to_string(), None, vec![Type::T])).unwrap();
+
+        let mut state = State::new(None, None, None);
+        let mut execution = Resumable::new(&interpreter, &main, Vec::new(), yield_id, &mut state).unwrap();
+
+        assert!(matches!(execution.run_until_yield(&mut state), Ok(ExecutionResult::Yield(Value::U64(10)))));
+        assert!(matches!(execution.run_until_yield(&mut state), Err(InterpreterError::ExpectedResume)));
+        // The resumed value must match the variable type
+        assert!(matches!(execution.resume(Value::Boolean(true)), Err(InterpreterError::InvalidType(Type::U64))));
+        execution.resume(Value::U64(5)).unwrap();
+
+        assert!(matches!(execution.run_until_yield(&mut state), Ok(ExecutionResult::Yield(Value::U64(6)))));
+        execution.resume(Value::U64(100)).unwrap();
+        assert!(matches!(execution.resume(Value::U64(100)), Err(InterpreterError::NotSuspended)));
+
+        assert!(matches!(execution.run_until_yield(&mut state), Ok(ExecutionResult::Done(Some(Value::U64(105))))));
+        assert!(matches!(execution.run_until_yield(&mut state), Err(InterpreterError::ExecutionFinished)));
+    }
+
+    #[test]
+    fn test_yield_not_top_level() {
+        let code = "entry main() { let a: u64 = 1; if a > 0 { yield(a) } return a }";
+        assert!(matches!(
+            test_code_expect_error(code),
+            InterpreterError::EnvironmentError(EnvironmentError::InvalidYield)
+        ));
+    }
+
+    #[test]
+    fn test_global_constant() {
+        let mut env = EnvironmentBuilder::default();
+        env.register_global_constant("MAX_SUPPLY", Value::U64(21_000_000)).unwrap();
+        env.register_structure("Point", vec![("x", Type::U64)]);
+
+        // Names are unique across the constants, functions and structs
+        assert!(matches!(env.register_global_constant("MAX_SUPPLY", Value::U64(0)), Err(BuilderError::NameAlreadyUsed(_))));
+        assert!(matches!(env.register_global_constant("println", Value::U64(0)), Err(BuilderError::NameAlreadyUsed(_))));
+        assert!(matches!(env.register_global_constant("Point", Value::U64(0)), Err(BuilderError::NameAlreadyUsed(_))));
+
+        test_code_expect_return_with_env("entry main() { let burned: u64 = 1000; return MAX_SUPPLY - burned }", 20_999_000, env);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_global_constant_then_function() {
+        let mut env = EnvironmentBuilder::default();
+        env.register_global_constant("MAX_SUPPLY", Value::U64(21_000_000)).unwrap();
+        env.register_native_function("MAX_SUPPLY", None, vec![], |_, _, _| Ok(None), 1, None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_global_constant_then_struct() {
+        let mut env = EnvironmentBuilder::default();
+        env.register_global_constant("MAX_SUPPLY", Value::U64(21_000_000)).unwrap();
+        env.register_structure("MAX_SUPPLY", vec![("x", Type::U64)]);
+    }
+
+    #[test]
+    fn test_compare() {
+        // 0 is lower, 1 is equal and 2 is greater
+        test_code_expect_return("entry main() { return compare(1, 2) as u64 }", 0);
+        test_code_expect_return("entry main() { return compare(2u8, 2u8) as u64 }", 1);
+        test_code_expect_return("entry main() { let a: u256 = 10; let b: u256 = 3; return compare(a, b) as u64 }", 2);
+        test_code_expect_return("entry main() { return compare(\"abc\", \"abd\") as u64 }", 0);
+        test_code_expect_return("entry main() { return compare(\"b\", \"abc\") as u64 }", 2);
+        test_code_expect_return("entry main() { return compare(\"\", \"\") as u64 }", 1);
+        test_code_expect_return("entry main() { return compare(true, false) as u64 }", 2);
+
+        // The results are exposed as constants, and ordered like the values
+        test_code_expect_return("entry main() { return (compare(1, 2) == ORDERING_LESS) as u64 }", 1);
+        test_code_expect_return("entry main() { return (compare(\"b\", \"a\") == ORDERING_GREATER) as u64 }", 1);
+        test_code_expect_return("entry main() { return (compare(3, 3) == ORDERING_EQUAL) as u64 }", 1);
+        test_code_expect_return("entry main() { return (compare(1, 2) < ORDERING_EQUAL) as u64 }", 1);
+    }
+
+    #[test]
+    fn test_compare_incomparable() {
+        // Arrays have no order
+        let code = "entry main() { let a: u64[] = [1]; let b: u64[] = [2]; return compare(a, b) as u64 }";
+        assert!(matches!(try_execute_with_count(code).0, Err(InterpreterError::EnvironmentError(EnvironmentError::ValueError(ValueError::Incomparable(_, _))))));
+
+        // Both values must have the same type
+        let tokens = Lexer::new("entry main() { return compare(1, \"a\") as u64 }").get().unwrap();
+        let env = EnvironmentBuilder::default();
+        assert!(matches!(Parser::new(tokens, &env).parse(), Err(ParserError::InvalidValueType(Type::String, Type::U64))));
+    }
+
+    #[test]
+    fn test_shift_bits_width() {
+        // The shift must be lower than the bits count of the type
+        for (t, bits) in [("u8", 8), ("u16", 16), ("u32", 32), ("u64", 64), ("u128", 128)] {
+            let code = format!("entry main() {{ let a: {t} = 1; let b: {t} = a << {}; return ((b >> {}) == a) as u64 }}", bits - 1, bits - 1);
+            test_code_expect_return(&code, 1);
+
+            for shift in [bits, bits + 1] {
+                let code = format!("entry main() {{ let a: {t} = 1; let b: {t} = a << {shift}; return (b == a) as u64 }}");
+                assert!(matches!(try_execute_with_count(&code).0, Err(InterpreterError::OverflowOccured)), "{} << {}", t, shift);
+
+                let code = format!("entry main() {{ var a: {t} = 1; a >>= {shift}; return (a == 1) as u64 }}");
+                assert!(matches!(try_execute_with_count(&code).0, Err(InterpreterError::OverflowOccured)), "{} >>= {}", t, shift);
+            }
+        }
+
+        // Bits shifted out are discarded
+        test_code_expect_return("entry main() { let a: u8 = 255; return (a << 4) as u64 }", 240);
+    }
+
+    #[test]
+    fn test_number_operations() {
+        test_code_expect_return("entry main() { return 10; }", 10);
+        test_code_expect_return("entry main() { return 10 + 10; }", 20);
+        test_code_expect_return("entry main() { return 10 - 10; }", 0);
+        test_code_expect_return("entry main() { return 10 * 10; }", 100);
+        test_code_expect_return("entry main() { return 10 / 10; }", 1);
+        test_code_expect_return("entry main() { return 10 % 10; }", 0);
+        test_code_expect_return("entry main() { return (10 == 10) as u64; }", 1);
+        test_code_expect_return("entry main() { return (10 != 10) as u64; }", 0);
+        test_code_expect_return("entry main() { return (10 > 10) as u64; }", 0);
+        test_code_expect_return("entry main() { return (10 >= 10) as u64; }", 1);
+        test_code_expect_return("entry main() { return (10 < 10) as u64; }", 0);
+        test_code_expect_return("entry main() { return (10 <= 10) as u64; }", 1);
+        test_code_expect_return("entry main() { return 10 & 10; }", 10);
+        test_code_expect_return("entry main() { return 10 | 10; }", 10);
+        test_code_expect_return("entry main() { return 10 ^ 10; }", 0);
+        test_code_expect_return("entry main() { return 10 << 10; }", 10240);
+        test_code_expect_return("entry main() { return 10 >> 10; }", 0);
+
+        test_code_expect_return("entry main() { return 10 + 10 * 10; }", 110);
+        test_code_expect_return("entry main() { return (10 + 10) * 10; }", 200);
+    }
+
+    #[test]
+    fn test_u128() {
+        test_code_expect_return("entry main() { var j: u128 = 10; j = 2 + j; return j as u64; }", 12);
+        test_code_expect_return("entry main() { var j: u128 = 10; j = ((2 + j) * (3 + j) * (4 + j)); return j as u64; }", 2184);
+    }
+
+    #[test]
+    fn test_array() {
+        test_code_expect_return("entry main() { let a: u64[] = [1]; let b: u32 = 0; return a[b]; }", 1);
+        test_code_expect_return("fn test() -> u64[] { return [0, 1, 2]; } entry main() { let b: u32 = 0; return test()[b]; }", 0);
+
+        test_code_expect_return("entry main() { let a: u64[] = [1, 2, 3]; return a[0]; }", 1);
+        test_code_expect_return("entry main() { let a: u64[] = [1, 2, 3]; return a[1]; }", 2);
+        test_code_expect_return("entry main() { let a: u64[] = [1, 2, 3]; return a[2]; }", 3);
+
+        test_code_expect_return("entry main() { let a: u64[] = [1, 2, 3]; return a[0] + a[1] + a[2]; }", 6);
+        test_code_expect_return("entry main() { var a: u64[] = [1, 2, 3]; a[0] = 10; return a[0]; }", 10);
+        test_code_expect_return("entry main() { var a: u64[] = [1, 2, 3]; a[0] = 10; return a[1]; }", 2);
+        test_code_expect_return("entry main() { var a: u64[] = [1, 2, 3]; a[0] = 10; return a[2]; }", 3);
+        test_code_expect_return("entry main() { var a: u64[] = [1, 2, 3]; a[0] = 10; return a[0] + a[1] + a[2]; }", 15);
+
+        // Push
+        test_code_expect_return("entry main() { let a: u64[] = [1, 2, 3]; a.push(10); return a[3]; }", 10);
+        test_code_expect_return("entry main() { let a: u64[] = [1, 2, 3]; let v: u64 = 10; a.push(v); return a[0] + a[1] + a[2] + a[3]; }", 16);
+        test_code_expect_return("entry main() { let a: u64[] = [1, 2, 3]; let b: u64[] = []; let v: u64 = 10; b.push(10); a.push(b[0]); return a[0] + a[1] + a[2] + a[3]; }", 16);
+
+        // Pop
+        test_code_expect_return("entry main() { let a: u64[] = [1, 2, 3]; a.pop(); return a.len() as u64; }", 2);
+    }
+
+    #[test]
+    fn test_number_operations_priority() {
+        test_code_expect_return("entry main() { return 10 + 10 * 10; }", 110);
+        test_code_expect_return("entry main() { return (10 + 10) * 10; }", 200);
+
+        test_code_expect_return("entry main() { return 10 + 10 / 5 + 3; }", 15);
+        test_code_expect_return("entry main() { return 10 + 10 / 5 * 3; }", 16);
+        test_code_expect_return("entry main() { return 10 + 10 / 5 + 3 * 10; }", 42);
+    }
+
+    #[test]
+    fn test_basic_function_call() {
+        test_code_expect_return("fn add(a: u64, b: u64) -> u64 { return a + b; } entry main() { return add(10, 10); }", 20);
+        test_code_expect_return("fn add(a: u64, b: u64) -> u64 { return a + b; } entry main() { return add(10, add(10, 10)); }", 30);
+
+        // With variable
+        test_code_expect_return("fn add(a: u64, b: u64) -> u64 { return a + b; } entry main() { let a: u64 = 10; return add(a, 10); }", 20);
+        test_code_expect_return("fn add(a: u64, b: u64) -> u64 { return a + b; } entry main() { let a: u64 = 10; return add(a, add(10, 10)); }", 30);
+    }
+
+    #[test]
+    fn test_named_arguments_function_call() {
+        test_code_expect_return("fn sub(a: u64, b: u64) -> u64 { return a - b; } entry main() { return sub(a: 10, b: 2); }", 8);
+        test_code_expect_return("fn sub(a: u64, b: u64) -> u64 { return a - b; } entry main() { return sub(b: 2, a: 10); }", 8);
+
+        // Positional arguments first
+        test_code_expect_return("fn f(a: u64, b: u64, c: u64) -> u64 { return (a * 100) + (b * 10) + c; } entry main() { return f(1, c: 3, b: 2); }", 123);
+
+        // On value
+        test_code_expect_return("struct Test { a: u64 } fn (v Test) sub(b: u64, c: u64) -> u64 { return (v.a * b) - c; } entry main() { let t: Test = Test {a: 10}; return t.sub(c: 1, b: 2); }", 19);
+    }
+
+    #[test]
+    fn test_number_literal_inference() {
+        test_code_expect_return("entry main() { let a: u8 = 255; return a as u64; }", 255);
+        test_code_expect_return("entry main() { let a: u16 = 5u8 as u16; return a as u64; }", 5);
+
+        // Literals are inferred from the parameter type
+        test_code_expect_return("fn f(a: u64, b: u8) -> u64 { return a + (b as u64); } entry main() { let x: u64 = 1; return f(x, 5); }", 6);
+    }
+
+    #[test]
+    fn test_default_parameter_value() {
+        test_code_expect_return("fn f(x: u64, y: u64 = 10) -> u64 { return x + y; } entry main() { return f(5); }", 15);
+        test_code_expect_return("fn f(x: u64, y: u64 = 10) -> u64 { return x + y; } entry main() { return f(5, 20); }", 25);
+
+        // Combined with named arguments
+        test_code_expect_return("fn f(x: u64, y: u64 = 10, z: u64 = 100) -> u64 { return x + (y + z); } entry main() { return f(z: 1, x: 5); }", 16);
+        test_code_expect_return("fn f(x: u64 = 1, y: u64 = 10) -> u64 { return x + y; } entry main() { return f(); }", 11);
+    }
+
+    #[test]
+    fn test_function_call_on_value() {
+        test_code_expect_return("struct Test { a: u64 } fn (v Test) add(b: u64) -> u64 { return v.a + b; } entry main() { let t: Test = Test {a: 10}; return t.add(10); }", 20);
+        test_code_expect_return("struct Test { a: u64 } fn (v Test) add(b: u64) -> u64 { return v.a + b; } entry main() { let t: Test = Test {a: 10}; return t.add(t.add(10)); }", 30);
+        test_code_expect_return("struct Test { a: u64 } fn (v Test) add(b: u64) { v.a += b; } entry main() { let t: Test = Test {a: 10}; t.add(10); return t.a }", 20);
+    }
+
+    #[test]
+    fn test_casting() {
+        let key = Signature::new("main".to_string(), None, Vec::new());
+
+        // Auto casting
+        assert_eq!(Value::U8(10), test_code_expect_value(&key, "fn main() -> u8 { return 10; }"));
+        assert_eq!(Value::U16(10), test_code_expect_value(&key, "fn main() -> u16 { return 10; }"));
+        assert_eq!(Value::U32(10), test_code_expect_value(&key, "fn main() -> u32 { return 10; }"));
+        assert_eq!(Value::U64(10), test_code_expect_value(&key, "fn main() -> u64 { return 10; }"));
+        assert_eq!(Value::U128(10), test_code_expect_value(&key, "fn main() -> u128 { return 10u128; }"));
+
+        // Explicit casting
+        assert_eq!(Value::U8(10), test_code_expect_value(&key, "fn main() -> u8 { let a: u64 = 10; return a as u8; }"));
+        assert_eq!(Value::U16(10), test_code_expect_value(&key, "fn main() -> u16 { let a: u64 = 10; return a as u16; }"));
         assert_eq!(Value::U32(10), test_code_expect_value(&key, "fn main() -> u32 { let a: u64 = 10; return a as u32; }"));
         assert_eq!(Value::U64(10), test_code_expect_value(&key, "fn main() -> u64 { let a: u32 = 10; return a as u64; }"));
         assert_eq!(Value::U128(10), test_code_expect_value(&key, "fn main() -> u128 { let a: u64 = 10; return a as u128; }"));
@@ -817,37 +1811,165 @@ mod tests {
         assert_eq!(Value::String("10hello world10".to_string()), test_code_expect_value(&key, "fn main() -> string { let a: u64 = 10; return (a + \"hello world\" + a); }"));
     }
 
+    #[test]
+    fn test_string_assign_plus() {
+        let key = Signature::new("main".to_string(), None, Vec::new());
+        assert_eq!(Value::String("a5".to_string()), test_code_expect_value(&key, "fn main() -> string { var s: string = \"a\"; s += 5; return s }"));
+        assert_eq!(Value::String("atrue".to_string()), test_code_expect_value(&key, "fn main() -> string { var s: string = \"a\"; s += true; return s }"));
+        assert_eq!(Value::String("a7false".to_string()), test_code_expect_value(&key, "fn main() -> string { var s: string = \"a\"; let n: u8 = 7; s += n; s += !true; return s }"));
+
+        // Other compound assignments are rejected on strings
+        for op in ["-=", "*=", "%="] {
+            let code = format!("fn main() -> string {{ var s: string = \"a\"; s {} 1; return s }}", op);
+            let tokens = Lexer::new(&code).get().unwrap();
+            let env = EnvironmentBuilder::default();
+            assert!(matches!(Parser::new(tokens, &env).parse(), Err(ParserError::InvalidStringOperation(_))), "{}", op);
+        }
+
+        // The parser rejects it, so the statement is built by hand
+        let program = Program::new();
+        let env = EnvironmentBuilder::default();
+        let interpreter = Interpreter::new(&program, env.environment()).unwrap();
+        let statements = vec![
+            Statement::Variable(DeclarationStatement { id: 0, value_type: Type::String, value: Expression::Value(Value::String("a".to_owned())), mutable: true }),
+            Statement::Expression(Expression::Operator(
+                Operator::Assign(Some(Box::new(Operator::Minus))),
+                Box::new(Expression::Variable(0)),
+                Box::new(Expression::Value(Value::U64(1)))
+            ))
+        ];
+
+        let mut context = ExecutionContext::new();
+        let mut state = State::new(None, None, None);
+        assert!(matches!(
+            interpreter.execute_in_context(&statements, 1, &mut context, &mut state),
+            Err(InterpreterError::InvalidStringOperation(Operator::Minus))
+        ));
+    }
+
+    #[test]
+    fn test_never_type() {
+        let key = Signature::new("main".to_string(), None, Vec::new());
+        test_code_expect_return("entry main() { let a: bool = true; let x: u64 = a ? 5 : panic(\"no\"); return x }", 5);
+        test_code_expect_return("entry main() { let a: bool = false; let x: u64 = a ? panic(\"no\") : 6; return x }", 6);
+        assert_eq!(
+            Value::String("yes".to_owned()),
+            test_code_expect_value(&key, "fn main() -> string { let a: bool = true; return a ? \"yes\" : panic(\"no\") }")
+        );
+        assert_eq!(
+            Value::Boolean(false),
+            test_code_expect_value(&key, "fn main() -> bool { let a: u8 = 0; let b: bool = a == 0 ? false : panic(a); return b }")
+        );
+
+        // Taking the diverging branch aborts
+        let code = "entry main() { let a: bool = false; let x: u64 = a ? 5 : panic(\"no\"); return x }";
+        assert!(matches!(
+            test_code_expect_error(code),
+            InterpreterError::EnvironmentError(EnvironmentError::Aborted(message)) if message == "no"
+        ));
+
+        // Both branches must still agree when none of them diverges
+        let env = EnvironmentBuilder::default();
+        let tokens = Lexer::new("entry main() { let a: bool = true; let x: u64 = a ? 5 : \"no\"; return x }").get().unwrap();
+        assert!(Parser::new(tokens, &env).parse().is_err());
+
+        // A native declared as never returning that returns aborts anyway
+        fn stop(_: xelis_environment::FnInstance, _: xelis_environment::FnParams, _: &mut xelis_environment::Context) -> xelis_environment::FnReturnType {
+            Ok(None)
+        }
+
+        let mut env = EnvironmentBuilder::default();
+        env.register_native_function("stop", None, vec![], stop, 1, Some(Type::Never));
+        assert!(matches!(
+            try_execute_with_state("entry main() { let x: u64 = stop(); return x }", &env, &mut State::new(None, None, None)),
+            Err(InterpreterError::NeverReturned)
+        ));
+    }
+
     #[test]
     fn test_negative_bool() {
         let key = Signature::new("main".to_string(), None, Vec::new());
         assert_eq!(Value::Boolean(true), test_code_expect_value(&key, "fn main() -> bool { return !false; }"));
         assert_eq!(Value::Boolean(false), test_code_expect_value(&key, "fn main() -> bool { return !true; }"));
-        assert_eq!(Value::Boolean(false), test_code_expect_value(&key, "fn main() -> bool { let add: bool = true; add = !add; return add; }"));
+        assert_eq!(Value::Boolean(false), test_code_expect_value(&key, "fn main() -> bool { var add: bool = true; add = !add; return add; }"));
     }
 
     #[test]
     fn test_foreach() {
-        test_code_expect_return("entry main() { let a: u64[] = [1, 2, 3]; let sum: u64 = 0; foreach i in a { sum += i; } return sum; }", 6);
+        test_code_expect_return("entry main() { let a: u64[] = [1, 2, 3]; var sum: u64 = 0; foreach i in a { sum += i; } return sum; }", 6);
     }
 
     #[test]
     fn test_while() {
-        test_code_expect_return("entry main() { let a: u64 = 0; while a < 10 { a += 1; } return a; }", 10);
+        test_code_expect_return("entry main() { var a: u64 = 0; while a < 10 { a += 1; } return a; }", 10);
     }
 
     #[test]
     fn test_for() {
-        test_code_expect_return("entry main() { let a: u64 = 1; for i: u64 = 0; i < 10; i += 1 { a *= 2; } return a; }", 1024);
+        test_code_expect_return("entry main() { var a: u64 = 1; for i: u64 = 0; i < 10; i += 1 { a *= 2; } return a; }", 1024);
     }
 
     #[test]
     fn test_break() {
-        test_code_expect_return("entry main() { let a: u64 = 0; while a < 10 { a += 1; if a == 5 { break; } } return a; }", 5);
+        test_code_expect_return("entry main() { var a: u64 = 0; while a < 10 { a += 1; if a == 5 { break; } } return a; }", 5);
     }
 
     #[test]
     fn test_continue() {
-        test_code_expect_return("entry main() { let i: u64 = 0; let a: u64 = 1; while i < 10 { i += 1; if i == 5 { continue; } a *= 2; } return a; }", 512);
+        test_code_expect_return("entry main() { var i: u64 = 0; var a: u64 = 1; while i < 10 { i += 1; if i == 5 { continue; } a *= 2; } return a; }", 512);
+    }
+
+    #[test]
+    fn test_coverage() {
+        let code = "entry main() { var a: u64 = 1; if a == 1 { return 1 } else { a += 1; return a } }";
+        let tokens = Lexer::new(code).get().unwrap();
+        let env = EnvironmentBuilder::default();
+        let (program, mapper) = Parser::new(tokens, &env).parse().unwrap();
+        let main = mapper.get(&Signature::new("main".to_string(), None, Vec::new())).unwrap();
+
+        let mut interpreter = Interpreter::new(&program, env.environment()).unwrap();
+        assert!(interpreter.coverage().is_none());
+
+        interpreter.set_coverage(true);
+        let mut state = State::new(None, None, None);
+        assert_eq!(interpreter.call_entry_function(&main, Vec::new(), &mut state).unwrap(), 1);
+
+        // let, if, return 1 are executed, the else branch isn't
+        let report = interpreter.coverage().unwrap();
+        assert_eq!(report.total(), 5);
+        assert_eq!(report.covered(), vec![0, 1, 2]);
+        assert_eq!(report.uncovered(), vec![3, 4]);
+        assert!(!report.is_covered(3));
+        assert_eq!(report.function_of(4), Some(0));
+
+        // Enabling it again starts a new report
+        interpreter.set_coverage(true);
+        assert_eq!(interpreter.coverage().unwrap().covered_count(), 0);
+    }
+
+    #[test]
+    fn test_labeled_break() {
+        // Leave two loops at once
+        let code = "entry main() { var n: u64 = 0; 'outer: while true { for i: u64 = 0; i < 10; i += 1 { foreach v in [1, 2, 3] { n += v; if n > 20 { break 'outer; } } } } return n }";
+        test_code_expect_return(code, 21);
+
+        // An unlabeled break still leaves the innermost loop
+        let code = "entry main() { var n: u64 = 0; 'outer: for i: u64 = 0; i < 3; i += 1 { while true { n += 1; break; } } return n }";
+        test_code_expect_return(code, 3);
+
+        // Statements are not required to be separated before a label
+        let code = "entry main() { var n: u64 = 0\n'outer: while true { n += 1; break 'outer } let a: u64 = n 'inner: while true { break 'inner } return a }";
+        test_code_expect_return(code, 1);
+    }
+
+    #[test]
+    fn test_labeled_continue() {
+        // Skip the rest of the inner loop and the end of the outer iteration
+        let code = "entry main() { var n: u64 = 0; var after: u64 = 0; 'outer: for i: u64 = 0; i < 5; i += 1 { var j: u64 = 0; while j < 5 { j += 1; if j > i { continue 'outer; } n += 1; } after += 1; } return (n * 10) + after }";
+        test_code_expect_return(code, 100);
+
+        let code = "entry main() { var n: u64 = 0; 'outer: foreach a in [1, 2, 3] { foreach b in [1, 2, 3] { if b == 2 { continue 'outer; } n += a * b; } } return n }";
+        test_code_expect_return(code, 6);
     }
 
     #[test]
@@ -889,9 +2011,1055 @@ mod tests {
         test_code_expect_return_with_env("entry main() { let t: Test = Test { a: 10 }; return t.a; }", 10, env);
     }
 
+    #[test]
+    fn test_bitwise_not() {
+        test_code_expect_return("entry main() { let a: u8 = ~0u8; return a as u64; }", 255);
+        test_code_expect_return("entry main() { if ~0u8 == 255 { return 1; } return 0; }", 1);
+        test_code_expect_return("entry main() { let a: u64 = 5; return ~a; }", !5u64);
+    }
+
+    #[test]
+    fn test_logical_not() {
+        test_code_expect_return("entry main() { if !true == false { return 1; } return 0; }", 1);
+        test_code_expect_return("entry main() { let a: bool = !false; return a ? 1 : 0; }", 1);
+    }
+
+    #[test]
+    fn test_result() {
+        test_code_expect_return("entry main() { let r: result<u64, string> = ok(10); return r.unwrap(); }", 10);
+        test_code_expect_return("entry main() { let r: result<u64, string> = err(\"error\"); return r.unwrap_err().len() as u64; }", 5);
+        test_code_expect_return("entry main() { let r: result<u64, string> = err(\"error\"); if r.is_err() { return 1; } return 0; }", 1);
+        test_code_expect_return("entry main() { let r: result<u64, string> = ok(10); if r.is_ok() { return 1; } return 0; }", 1);
+    }
+
+    #[test]
+    fn test_try_operator() {
+        let code = "fn parse(v: u64) -> result<u64, string> { if v == 0 { return err(\"zero\"); } return ok(v); }
+            fn double(v: u64) -> result<u64, string> { let a: u64 = parse(v)?; return ok(a * 2); }";
+
+        // Ok value is passed through
+        test_code_expect_return(&format!("{} entry main() {{ return double(5).unwrap(); }}", code), 10);
+        // Err is returned early from the function
+        test_code_expect_return(&format!("{} entry main() {{ return double(0).unwrap_err().len() as u64; }}", code), 4);
+        // Inside an expression
+        test_code_expect_return("fn f() -> result<u64, string> { return ok(1 + ok(5)?); } entry main() { return f().unwrap(); }", 6);
+    }
+
+    #[test]
+    fn test_struct_defined_by_host() {
+        let mut env = EnvironmentBuilder::default();
+        let (_type, constructor) = env.define_struct("Point", &[("x", Type::U64), ("y", Type::U64)]);
+        assert_eq!(_type.fields(), &vec![Type::U64, Type::U64]);
+
+        // Fields are verified against the struct definition
+        assert!(constructor(vec![Value::U64(1)]).is_err());
+        assert!(constructor(vec![Value::U64(1), Value::Boolean(true)]).is_err());
+
+        let point = constructor(vec![Value::U64(10), Value::U64(20)]).unwrap();
+        let tokens = Lexer::new("entry main(p: Point) { return p.x + p.y; }").get().unwrap();
+        let (program, mapper) = Parser::new(tokens, &env).parse().unwrap();
+
+        let interpreter = Interpreter::new(&program, env.environment()).unwrap();
+        let main = mapper.get(&Signature::new("main".to_string(), None, vec![Type::Struct(_type)])).unwrap();
+
+        let mut state = State::new(None, None, None);
+        assert_eq!(interpreter.call_entry_function(&main, vec![Path::Owned(point)], &mut state).unwrap(), 30);
+    }
+
     #[test]
     fn test_struct() {
         test_code_expect_return("struct Test { a: u64 } entry main() { let t: Test = Test { a: 10 }; return t.a; }", 10);
-        test_code_expect_return("struct Test { a: u64 } entry main() { let t: Test = Test { a: 10 }; t.a = 20; return t.a; }", 20);
+        test_code_expect_return("struct Test { a: u64 } entry main() { var t: Test = Test { a: 10 }; t.a = 20; return t.a; }", 20);
+    }
+
+    #[test]
+    fn test_max_expression_depth() {
+        let code = "entry main() { let a: u64[][][][][][][][] = [[[[[[[[1]]]]]]]]; return a[0][0][0][0][0][0][0][0]; }";
+        test_code_expect_return(code, 1);
+
+        let mut state = State::new(None, None, None);
+        state.set_max_expression_depth(Some(4));
+        assert!(matches!(
+            try_execute_with_state(code, &EnvironmentBuilder::default(), &mut state),
+            Err(InterpreterError::ExpressionDepthLimitReached)
+        ));
+    }
+
+    #[test]
+    fn test_struct_method() {
+        let code = r#"
+            struct Point { x: u64, y: u64 }
+            struct Line { a: Point, b: Point }
+
+            fn (self Point) sum(z: u64) -> u64 {
+                return self.x + self.y + z
+            }
+
+            fn (self Point) set_x(v: u64) {
+                self.x = v
+            }
+
+            fn (self Line) length() -> u64 {
+                return self.a.sum(0) + self.b.sum(0)
+            }
+
+            entry main() {
+                let l: Line = Line { a: Point { x: 1, y: 2 }, b: Point { x: 3, y: 4 } };
+                let points: Point[] = [Point { x: 100, y: 0 }];
+                l.a.set_x(11);
+                return l.length() + l.a.sum(0) + points[0].sum(1)
+            }
+        "#;
+        test_code_expect_return(code, 134);
+    }
+
+    #[test]
+    fn test_null_equals_empty_optional() {
+        let code = r#"
+            entry main() {
+                let a: u64[] = [5];
+                let some: optional<u64> = a.first();
+                let none: optional<u64> = a.get(5);
+                var r: u64 = 0;
+                if none == null { r += 1 }
+                if null == none { r += 10 }
+                if some != null { r += 100 }
+                if !(some == null) { r += 1000 }
+                return r
+            }
+        "#;
+        test_code_expect_return(code, 1111);
+    }
+
+    #[test]
+    fn test_range() {
+        let code = r#"
+            entry main() {
+                let r: range<u64> = 5..10;
+                if r.contains(7) { return r.count() }
+                return 0
+            }
+        "#;
+        test_code_expect_return(code, 5);
     }
-}
\ No newline at end of file
+
+    // Execute a range expression built without the parser checks
+    fn execute_range(start: Value, end: Value) -> Result<Value, InterpreterError> {
+        let program = Program::new();
+        let builder = EnvironmentBuilder::default();
+        let interpreter = Interpreter::new(&program, builder.environment()).unwrap();
+        let expr = Expression::Range(Box::new(Expression::Value(start)), Box::new(Expression::Value(end)));
+
+        let mut stack = Stack::new(0);
+        let mut state = State::new(None, None, None);
+        let value = interpreter.execute_expression_and_expect_value(&expr, &mut stack, &mut state).map_err(Unwind::into_error)?;
+        Ok(value.into_owned())
+    }
+
+    #[test]
+    fn test_range_invalid_bounds() {
+        assert_eq!(
+            execute_range(Value::U8(0), Value::U8(10)).unwrap(),
+            Value::Range(Box::new(Value::U8(0)), Box::new(Value::U8(10)), Type::U8)
+        );
+
+        // Bounds of different widths
+        let err = execute_range(Value::U8(0), Value::U64(10)).unwrap_err();
+        assert!(matches!(err, InterpreterError::InvalidRange(Type::U8, Type::U64)));
+
+        // Range over strings
+        let err = execute_range(Value::String("a".to_owned()), Value::String("z".to_owned())).unwrap_err();
+        assert!(matches!(err, InterpreterError::InvalidRange(Type::String, Type::String)));
+    }
+
+    #[test]
+    fn test_bit_counts() {
+        let code = r#"
+            entry main() {
+                let a: u8 = 176;
+                let b: u16 = 1;
+                let c: u32 = 0;
+                let d: u64 = 0xFF;
+                let e: u128 = 1 << 100;
+                let f: u256 = 0xF0u256;
+                let ones: u32 = a.count_ones() + d.count_ones() + f.count_ones();
+                let leading: u32 = b.leading_zeros() + c.leading_zeros() + e.leading_zeros() + f.leading_zeros();
+                let trailing: u32 = a.trailing_zeros() + e.trailing_zeros() + f.trailing_zeros();
+                return ((ones as u64) * 1000000) + ((leading as u64) * 1000) + (trailing as u64)
+            }
+        "#;
+        // ones: 3 + 8 + 4, leading: 15 + 32 + 27 + 248, trailing: 4 + 100 + 4
+        test_code_expect_return(code, 15322108);
+    }
+
+    #[test]
+    fn test_checked_sub() {
+        // Underflow returns none instead of an error
+        for t in ["u8", "u16", "u32", "u64", "u128", "u256"] {
+            let code = format!("entry main() {{ let a: {t} = 3; let b: {t} = 5; return (a.checked_sub(b).is_none() && b.checked_sub(a).unwrap() == 2) as u64 }}");
+            test_code_expect_return(&code, 1);
+        }
+
+        test_code_expect_return("entry main() { let a: u64 = 7; return a.checked_sub(7).unwrap() }", 0);
+    }
+
+    #[test]
+    fn test_abs_diff() {
+        // Symmetric for every width
+        for t in ["u8", "u16", "u32", "u64", "u128", "u256"] {
+            let code = format!("entry main() {{ let a: {t} = 3; let b: {t} = 10; return ((a.abs_diff(b) == 7) && (b.abs_diff(a) == 7) && (a.abs_diff(3) == 0)) as u64 }}");
+            test_code_expect_return(&code, 1);
+        }
+
+        test_code_expect_return("entry main() { let a: u8 = 255; return a.abs_diff(0) as u64 }", 255);
+    }
+
+    #[test]
+    fn test_array_reductions() {
+        test_code_expect_return("entry main() { let a: u64[] = [4, 9, 2, 7]; return a.sum().unwrap() }", 22);
+        test_code_expect_return("entry main() { let a: u64[] = [4, 9, 2, 7]; return a.min().unwrap() }", 2);
+        test_code_expect_return("entry main() { let a: u64[] = [4, 9, 2, 7]; return a.max().unwrap() }", 9);
+
+        // Single element
+        test_code_expect_return("entry main() { let a: u64[] = [5]; return (a.sum().unwrap() + (a.min().unwrap() + a.max().unwrap())) }", 15);
+
+        // Empty array
+        test_code_expect_return("entry main() { let a: u64[] = []; return a.sum().unwrap_or(777) }", 777);
+        test_code_expect_return("entry main() { let a: u64[] = []; return a.min().unwrap_or(777) }", 777);
+        test_code_expect_return("entry main() { let a: u64[] = []; return a.max().unwrap_or(777) }", 777);
+
+        // Overflow
+        test_code_expect_return("entry main() { let a: u8[] = [200, 100]; return a.sum().unwrap_or(0) as u64 }", 0);
+    }
+
+    #[test]
+    fn test_array_safe_get() {
+        test_code_expect_return("entry main() { let a: u64[] = [4, 9]; let i: u32 = 1; return a.get(i).unwrap() }", 9);
+        test_code_expect_return("entry main() { let a: u64[] = [4, 9]; return a.get(1).unwrap() }", 9);
+        test_code_expect_return("entry main() { let a: u64[] = [4, 9]; return a.get(2).is_none() as u64 }", 1);
+        test_code_expect_return("entry main() { let a: u64[] = []; return a.get(0).unwrap_or(777) }", 777);
+
+        test_code_expect_return("entry main() { let a: u64[] = [4, 9]; return a.get_or(0, 777) }", 4);
+        test_code_expect_return("entry main() { let a: u64[] = [4, 9]; let i: u32 = 5; return a.get_or(i, 777) }", 777);
+
+        // The value is a copy, changing it doesn't change the array
+        let code = "struct P { x: u64 } entry main() { let a: P[] = [P { x: 1 }]; var p: P = a.get_or(0, P { x: 2 }); p.x = 3; return (a[0].x * 10) + p.x }";
+        test_code_expect_return(code, 13);
+    }
+
+    #[test]
+    fn test_array_search_and_dedup() {
+        test_code_expect_return("entry main() { let a: u64[] = [4, 9, 9, 7]; return (a.contains(9) && !a.contains(5)) as u64 }", 1);
+        test_code_expect_return("entry main() { let a: u64[] = [4, 9, 9, 7]; return a.index_of(9).unwrap() }", 1);
+        test_code_expect_return("entry main() { let a: u64[] = [4, 9, 9, 7]; return a.index_of(5).unwrap_or(777) }", 777);
+
+        // Only the consecutive duplicates are removed
+        test_code_expect_return("entry main() { let a: u64[] = [1, 1, 2, 2, 2, 1, 3, 3]; a.dedup(); assert(a == [1, 2, 1, 3]); return a.len() as u64 }", 4);
+        test_code_expect_return("entry main() { let a: u64[] = []; a.dedup(); return a.len() as u64 }", 0);
+
+        // Structs and nested arrays are compared by content
+        let code = "struct P { x: u64, y: u64[] } entry main() {
+            let a: P[] = [P { x: 1, y: [1] }, P { x: 1, y: [1] }, P { x: 1, y: [2] }, P { x: 2, y: [2] }];
+            assert(a.contains(P { x: 1, y: [2] }));
+            assert(!a.contains(P { x: 2, y: [1] }));
+            let i: u64 = a.index_of(P { x: 2, y: [2] }).unwrap();
+            a.dedup();
+            return a.len() as u64 + i
+        }";
+        test_code_expect_return(code, 6);
+
+        let code = "entry main() { let a: u64[][] = [[1, 2], [1, 2], [3]]; a.dedup(); return (a.len() as u64 * 10) + a.index_of([3]).unwrap() }";
+        test_code_expect_return(code, 21);
+    }
+
+    #[test]
+    fn test_array_search_cost() {
+        let env = EnvironmentBuilder::default();
+        let run = |call: &str, len: usize| {
+            let code = format!("entry main() {{ let a: u64[] = [{}]; {}; return 0 }}", vec!["1"; len].join(", "), call);
+            let mut state = State::new(None, None, None);
+            try_execute_with_state(&code, &env, &mut state).unwrap();
+            state.get_gas_usage()
+        };
+
+        // Each element of the array is charged on top of the base cost
+        for call in ["a.contains(2)", "a.index_of(2)", "a.dedup()"] {
+            assert_eq!(run(call, 100) - run(call, 1), 99);
+        }
+    }
+
+    #[test]
+    fn test_range_to_array() {
+        test_code_expect_return("entry main() { let a: u64[] = range_to_array(5..10); return (a.len() as u64 + a.sum().unwrap()) }", 40);
+        test_code_expect_return("entry main() { let a: u64[] = range_to_array(5..5); return a.len() as u64 }", 0);
+
+        // The elements are charged against the allocation budget before being created
+        let code = "entry main() { let r: range<u64> = 0..50000000; let a: u64[] = r.collect(); return a.len() as u64 }";
+        assert!(matches!(try_execute_with_max_allocations(code, 1000), Err(InterpreterError::AllocationLimitReached(1000))));
+        let code = "entry main() { let a: u64[] = range_to_array(0..50000000); return a.len() as u64 }";
+        assert!(matches!(try_execute_with_max_allocations(code, 1000), Err(InterpreterError::AllocationLimitReached(1000))));
+        let code = "entry main() { let a: u64[] = range_to_array(0..1000); return a.len() as u64 }";
+        assert_eq!(try_execute_with_max_allocations(code, 1000).unwrap(), 1000);
+
+        let code = "entry main() { let t: u256 = 12; let a: u256[] = range_to_array(10u256..13u256); return (a[2] == t) as u64 + a.len() as u64 }";
+        test_code_expect_return(code, 4);
+    }
+
+    #[test]
+    fn test_parse_numbers() {
+        test_code_expect_return("entry main() { return parse_u64(\"1234\").unwrap_or(777) }", 1234);
+        test_code_expect_return("entry main() { return parse_u8(\"255\").unwrap_or(0) as u64 }", 255);
+        test_code_expect_return("entry main() { return parse_u256(\"115792089237316195423570985008687907853269984665640564039457584007913129639935\").is_some() as u64 }", 1);
+
+        // Overflow
+        test_code_expect_return("entry main() { return parse_u8(\"256\").unwrap_or(0) as u64 }", 0);
+        test_code_expect_return("entry main() { return parse_u64(\"18446744073709551616\").unwrap_or(777) }", 777);
+        test_code_expect_return("entry main() { return parse_u256(\"115792089237316195423570985008687907853269984665640564039457584007913129639936\").is_none() as u64 }", 1);
+
+        // Malformed
+        for input in ["", " 1", "1 ", "+1", "-1", "1a", "0x10", "1_000"] {
+            let code = format!("entry main() {{ return parse_u64(\"{input}\").unwrap_or(777) }}");
+            test_code_expect_return(&code, 777);
+        }
+    }
+
+    #[test]
+    fn test_parse_bool() {
+        test_code_expect_return("entry main() { return parse_bool(\"true\").unwrap() as u64 }", 1);
+        test_code_expect_return("entry main() { return parse_bool(\"false\").unwrap() as u64 }", 0);
+
+        for input in ["", "True", " true", "1"] {
+            let code = format!("entry main() {{ return parse_bool(\"{input}\").is_none() as u64 }}");
+            test_code_expect_return(&code, 1);
+        }
+    }
+
+    #[test]
+    fn test_tuple() {
+        let code = r#"
+            fn pair(a: u64, b: bool) -> (u64, bool) {
+                return (a, b)
+            }
+
+            entry main() {
+                let t: (u64, bool) = pair(10, true);
+                let (x, y) = pair(5, false);
+                var r: u64 = t.0 + x;
+                if t.1 { r += 100 }
+                if y { r += 1000 }
+                let nested: ((u8, u64), string) = ((1, 2), "a");
+                let (_, s) = nested;
+                if s == "a" { r += 10000 }
+                return r + nested.0.1
+            }
+        "#;
+        test_code_expect_return(code, 10117);
+    }
+
+    // Execute the main entry and return its result with the number of executed expressions
+    fn try_execute_with_count(code: &str) -> (Result<u64, InterpreterError>, u64) {
+        let mut state = State::new(None, None, None);
+        let result = try_execute_with_state(code, &EnvironmentBuilder::default(), &mut state);
+        (result, state.get_expressions_executed())
+    }
+
+    fn try_execute_with_max_allocations(code: &str, max_allocations: u64) -> Result<u64, InterpreterError> {
+        let tokens = Lexer::new(code).get().unwrap();
+        let builder = EnvironmentBuilder::default();
+        let (program, mapper) = Parser::new(tokens, &builder).parse().unwrap();
+        let interpreter = Interpreter::with_max_allocations(&program, builder.environment(), max_allocations).unwrap();
+
+        let mut state = State::new(None, None, None);
+        let name = mapper.get(&Signature::new("main".to_string(), None, Vec::new())).unwrap();
+        interpreter.call_entry_function(&name, Vec::new(), &mut state)
+    }
+
+    #[test]
+    fn test_allocation_limit() {
+        // Huge repetition is rejected before allocating anything
+        let code = "entry main() { let a: u64[] = [0]; let b: u64[] = a * 1_000_000; return b.len() as u64 }";
+        assert!(matches!(try_execute_with_max_allocations(code, 1000), Err(InterpreterError::AllocationLimitReached(1000))));
+
+        // Elements pushed are counted too
+        let code = "entry main() { let a: u64[] = []; for i: u64 = 0; i < 2000; i += 1 { a.push(i); } return a.len() as u64 }";
+        assert!(matches!(try_execute_with_max_allocations(code, 1000), Err(InterpreterError::AllocationLimitReached(1000))));
+        assert_eq!(try_execute_with_max_allocations(code, 0).unwrap(), 2000);
+
+        // Struct fields count as elements
+        let code = "struct P { x: u64, y: u64 } entry main() { let a: P[] = [P { x: 1, y: 2 }, P { x: 3, y: 4 }]; return a.len() as u64 }";
+        assert_eq!(try_execute_with_max_allocations(code, 6).unwrap(), 2);
+        assert!(matches!(try_execute_with_max_allocations(code, 5), Err(InterpreterError::AllocationLimitReached(5))));
+    }
+
+    fn execute_with_count(code: &str) -> (u64, u64) {
+        let (result, count) = try_execute_with_count(code);
+        (result.unwrap(), count)
+    }
+
+    #[test]
+    fn test_int_loop_fast_path() {
+        // Same loops, the second condition isn't matched by the fast-path
+        let fast = execute_with_count("entry main() { var a: u64 = 0; for i: u64 = 20; i > 0; i -= 2 { a += i; } return a; }");
+        let general = execute_with_count("entry main() { var a: u64 = 0; for i: u64 = 20; 0 < i; i -= 2 { a += i; } return a; }");
+        assert_eq!(fast.0, 110);
+        assert_eq!(fast, general);
+
+        let fast = execute_with_count("entry main() { var i: u32 = 0; while i < 10 { i += 3; } return i as u64; }");
+        let general = execute_with_count("entry main() { var i: u32 = 0; while 10 > i { i += 3; } return i as u64; }");
+        assert_eq!(fast.0, 12);
+        assert_eq!(fast, general);
+
+        // The body can still update the loop variable
+        test_code_expect_return("entry main() { var a: u64 = 0; for i: u8 = 0; i < 10; i += 1 { i += 1; a += 1; } return a; }", 5);
+    }
+
+    #[test]
+    fn test_return_from_foreach() {
+        let code = "entry main() { let a: u64[] = [1, 2, 3, 4]; foreach v in a { if v == 3 { return v; } } return 0; }";
+        test_code_expect_return(code, 3);
+
+        // Nested in another loop
+        let code = "entry main() { let a: u64[] = [1, 2, 3]; for i: u64 = 0; i < 10; i += 1 { foreach v in a { if (v + i) == 5 { return i; } } } return 0; }";
+        test_code_expect_return(code, 2);
+
+        // Statements after the loop must not be executed
+        let code = "entry main() { let a: u64[] = [1, 2]; var b: u64 = 0; foreach v in a { b += v; return b; } b = 100; return b; }";
+        test_code_expect_return(code, 1);
+    }
+
+    #[test]
+    fn test_return_from_nested_scope() {
+        let code = "entry main() { var a: u64 = 1; { { { return a; } a = 2; } a = 3; } return a + 10; }";
+        test_code_expect_return(code, 1);
+
+        let code = "entry main() { var a: u64 = 0; while a < 10 { { if a == 5 { { return a; } } } a += 1; } return 100; }";
+        test_code_expect_return(code, 5);
+    }
+
+    #[test]
+    fn test_continue_in_for() {
+        // The increment must still be executed
+        let code = "entry main() { var a: u64 = 0; for i: u64 = 0; i < 10; i += 1 { if (i % 2) == 0 { continue; } a += i; } return a; }";
+        test_code_expect_return(code, 25);
+    }
+
+    #[test]
+    fn test_entry_contract() {
+        let code = "entry main(a: u64, b: u8) { return a + (b as u64); }";
+        let tokens = Lexer::new(code).get().unwrap();
+        let builder = EnvironmentBuilder::default();
+        let (program, mapper) = Parser::new(tokens, &builder).parse().unwrap();
+        let mut interpreter = Interpreter::new(&program, builder.environment()).unwrap();
+        let name = mapper.get(&Signature::new("main".to_string(), None, vec![Type::U64, Type::U8])).unwrap();
+
+        let params = || vec![Path::Owned(Value::U64(10)), Path::Owned(Value::U8(5))];
+
+        // Valid call
+        interpreter.set_entry_contract(Some(EntryContract::new(vec![Type::U64, Type::U8], Type::U64)));
+        let mut state = State::new(None, None, None);
+        assert_eq!(interpreter.call_entry_function(&name, params(), &mut state).unwrap(), 15);
+
+        // Wrong arity
+        let err = interpreter.call_entry_function(&name, vec![Path::Owned(Value::U64(10))], &mut state).unwrap_err();
+        assert!(matches!(err, InterpreterError::InvalidEntryArgumentsCount(2, 1)));
+
+        // Wrong argument type
+        let err = interpreter.call_entry_function(&name, vec![Path::Owned(Value::U64(10)), Path::Owned(Value::U64(5))], &mut state).unwrap_err();
+        assert!(matches!(err, InterpreterError::InvalidEntryArgumentType(1, Type::U8)));
+
+        // The entry function doesn't match the host expectations
+        interpreter.set_entry_contract(Some(EntryContract::new(vec![Type::U64], Type::U64)));
+        let err = interpreter.call_entry_function(&name, params(), &mut state).unwrap_err();
+        assert!(matches!(err, InterpreterError::EntryContractMismatch));
+    }
+
+    #[test]
+    fn test_emit_events() {
+        let code = r#"
+            struct Transfer { amount: u64 }
+
+            entry main() {
+                emit("transfer", Transfer { amount: 100 });
+                emit("done", "ok");
+                return 0
+            }
+        "#;
+        let tokens = Lexer::new(code).get().unwrap();
+        let builder = EnvironmentBuilder::default();
+        let (program, mapper) = Parser::new(tokens, &builder).parse().unwrap();
+        let interpreter = Interpreter::new(&program, builder.environment()).unwrap();
+        let name = mapper.get(&Signature::new("main".to_string(), None, Vec::new())).unwrap();
+
+        let mut state = State::new(None, None, None);
+        interpreter.call_entry_function(&name, Vec::new(), &mut state).unwrap();
+
+        let events = interpreter.take_events();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].0, "transfer");
+        assert!(matches!(&events[0].1, Value::Struct(fields, _) if *fields[0].handle() == Value::U64(100)));
+        assert_eq!(events[1], ("done".to_owned(), Value::String("ok".to_owned())));
+        assert!(interpreter.take_events().is_empty());
+    }
+
+    #[derive(Default)]
+    struct CollectSink(RefCell<Vec<String>>);
+
+    impl OutputSink for CollectSink {
+        fn write(&self, s: &str) {
+            self.0.borrow_mut().push(s.to_owned());
+        }
+    }
+
+    #[test]
+    fn test_print_output_sink() {
+        let sink = Rc::new(CollectSink::default());
+        let mut builder = EnvironmentBuilder::default();
+        builder.set_output_sink(sink.clone());
+
+        let code = r#"entry main() { print("value: "); println(42); println("done"); return 0 }"#;
+        test_code_expect_return_with_env(code, 0, builder);
+
+        assert_eq!(*sink.0.borrow(), vec!["value: ", "42\n", "done\n"]);
+    }
+
+    #[track_caller]
+    fn expect_aborted(code: &str, message: &str) {
+        match try_execute_with_count(code).0 {
+            Err(InterpreterError::EnvironmentError(EnvironmentError::Aborted(msg))) => assert_eq!(msg, message),
+            res => panic!("expected abort, got {:?}", res)
+        }
+    }
+
+    #[test]
+    fn test_assert_and_panic() {
+        // A passing assert doesn't stop the execution
+        test_code_expect_return("entry main() { let a: u64 = 10; assert(a > 5); assert(a == 10, \"a must be 10\"); return a; }", 10);
+
+        expect_aborted("entry main() { let a: u64 = 10; assert(a < 5); return a; }", "assertion failed");
+        expect_aborted("entry main() { let a: u64 = 10; assert(a < 5, \"a is too big\"); return a; }", "a is too big");
+        expect_aborted("entry main() { panic(\"custom message\"); return 0; }", "custom message");
+    }
+
+    #[test]
+    fn test_sha256() {
+        fn digest(input: &str) -> Value {
+            let code = format!("fn digest() -> u8[] {{ return sha256(\"{}\".to_bytes()) }}", input);
+            test_code_expect_value(&Signature::new("digest".to_string(), None, Vec::new()), &code)
+        }
+
+        fn bytes(hex: &str) -> Value {
+            Value::Array((0..hex.len()).step_by(2)
+                .map(|i| ValueOwnable::Owned(Box::new(Value::U8(u8::from_str_radix(&hex[i..i + 2], 16).unwrap()))))
+                .collect())
+        }
+
+        // Known answers from FIPS 180-2
+        assert_eq!(digest(""), bytes("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"));
+        assert_eq!(digest("abc"), bytes("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"));
+        assert_eq!(
+            digest("abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"),
+            bytes("248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1")
+        );
+    }
+
+    #[test]
+    fn test_sha256_cost() {
+        let env = EnvironmentBuilder::default();
+        let run = |len: usize| {
+            let code = format!("entry main() {{ let a: u8[] = [{}]; sha256(a); return 0 }}", vec!["1"; len].join(", "));
+            let mut state = State::new(None, None, None);
+            try_execute_with_state(&code, &env, &mut state).unwrap();
+            state.get_gas_usage()
+        };
+
+        // Each byte to hash is charged on top of the base cost
+        assert_eq!(run(100) - run(1), 99);
+    }
+
+    #[test]
+    fn test_assignment_copies_value() {
+        // Mutating a copied array doesn't affect the original
+        test_code_expect_return("entry main() { let a: u64[] = [1, 2]; var b: u64[] = a; b.push(3); b[0] = 10; return (a.len() as u64) + a[0]; }", 3);
+        test_code_expect_return("entry main() { let a: u64[] = [1, 2]; var b: u64[] = []; b = a; b[0] = 10; return a[0]; }", 1);
+
+        // Nested arrays are copied too
+        test_code_expect_return("entry main() { let a: u64[][] = [[1], [2]]; var b: u64[][] = a; b[0][0] = 10; b[1].push(3); return a[0][0] + (a[1].len() as u64); }", 2);
+
+        // Same for struct fields
+        let code = r#"
+            struct Point { x: u64, values: u64[] }
+
+            entry main() {
+                let p: Point = Point { x: 1, values: [1] };
+                var q: Point = p;
+                q.x = 10;
+                q.values.push(2);
+                let v: u64[] = p.values;
+                v.push(3);
+                return p.x + (p.values.len() as u64);
+            }
+        "#;
+        test_code_expect_return(code, 2);
+    }
+
+    #[test]
+    fn test_string_append_reuses_buffer() {
+        let state = State::new(None, None, None);
+        let mut s = String::with_capacity(64);
+        let ptr = s.as_ptr();
+        for i in 0..10u64 {
+            append_to_string(&mut s, &Value::U64(i), &state).unwrap();
+        }
+        assert_eq!(s, "0123456789");
+        assert_eq!(s.as_ptr(), ptr);
+
+        let code = "entry main() { var s: string = \"\"; for i: u64 = 0; i < 100; i += 1 { s += \"ab\"; } return s.len() as u64; }";
+        test_code_expect_return(code, 200);
+    }
+
+    #[test]
+    fn test_max_string_length() {
+        let code = "entry main() { var s: string = \"\"; for i: u64 = 0; i < 100; i += 1 { s += \"ab\"; } return s.len() as u64; }";
+        let mut state = State::new(None, None, None);
+        state.set_max_string_length(Some(50));
+        assert!(matches!(
+            try_execute_with_state(code, &EnvironmentBuilder::default(), &mut state),
+            Err(InterpreterError::ValueError(ValueError::MaxStringLengthReached(52, 50)))
+        ));
+    }
+
+    #[test]
+    fn test_array_concat_and_repeat() {
+        test_code_expect_return("entry main() { let a: u64[] = [1, 2] + [3, 4]; return ((a.len() as u64) * 10) + a[3]; }", 44);
+        test_code_expect_return("entry main() { let a: u64[] = [0, 1] * 3; return ((a.len() as u64) * 10) + a[5]; }", 61);
+        test_code_expect_return("entry main() { var a: u64[] = [1]; a += [2]; a *= 2u8; return ((a.len() as u64) * 10) + a[3]; }", 42);
+
+        // The values are copied and not shared with the operands
+        test_code_expect_return("entry main() { let a: u64[][] = [[1]]; var b: u64[][] = a * 2; b[0][0] = 5; return a[0][0] + b[1][0]; }", 2);
+
+        let code = "entry main() { let a: u64[] = [0] * 100; return a.len() as u64; }";
+        let env = EnvironmentBuilder::default();
+        let mut state = State::new(None, None, None);
+        state.set_max_array_length(Some(10));
+        assert!(matches!(
+            try_execute_with_state(code, &env, &mut state),
+            Err(InterpreterError::ValueError(ValueError::MaxArrayLengthReached(100, 10)))
+        ));
+
+        // Rejected by the default limits before allocating
+        let code = "entry main() { let a: u64[] = [0] * 100000000000000; return a.len() as u64; }";
+        assert!(matches!(
+            try_execute_with_count(code).0,
+            Err(InterpreterError::ValueError(ValueError::MaxArrayLengthReached(100000000000000, MAX_ARRAY_LENGTH)))
+        ));
+
+        let code = "entry main() { let a: u64[] = [0] * 2000; return a.len() as u64; }";
+        assert!(matches!(try_execute_with_max_allocations(code, 1000), Err(InterpreterError::AllocationLimitReached(1000))));
+
+        // The length limit is checked before the allocations are counted
+        let code = "entry main() { let a: u64[] = [0] * 100000000000000; return a.len() as u64; }";
+        assert!(matches!(
+            try_execute_with_max_allocations(code, 1000),
+            Err(InterpreterError::ValueError(ValueError::MaxArrayLengthReached(100000000000000, MAX_ARRAY_LENGTH)))
+        ));
+
+        // Without a length limit, the size is checked instead of aborting
+        let mut state = State::new(None, None, None);
+        state.set_max_array_length(None);
+        let code = "entry main() { let a: u64[] = [0, 1] * 9223372036854775807; return a.len() as u64; }";
+        assert!(try_execute_with_state(code, &env, &mut state).is_err());
+    }
+
+    #[test]
+    fn test_array_concat_type_mismatch() {
+        let env = EnvironmentBuilder::default();
+        let tokens = Lexer::new("entry main() { let a: u64[] = [1] + [true]; return 0; }").get().unwrap();
+        assert!(matches!(Parser::new(tokens, &env).parse(), Err(ParserError::InvalidOperationNotSameType(_, _))));
+
+        let tokens = Lexer::new("entry main() { let a: u64[] = [1] * \"a\"; return 0; }").get().unwrap();
+        assert!(matches!(Parser::new(tokens, &env).parse(), Err(ParserError::ExpectedNumberType(_))));
+    }
+
+    #[test]
+    fn test_execute_in_context() {
+        let program = Program::new();
+        let env = EnvironmentBuilder::default();
+        let interpreter = Interpreter::new(&program, env.environment()).unwrap();
+
+        // let a: u64 = 10
+        let first = vec![Statement::Variable(DeclarationStatement {
+            id: 0,
+            value_type: Type::U64,
+            value: Expression::Value(Value::U64(10)),
+            mutable: true
+        })];
+        // a += 5; return a
+        let second = vec![
+            Statement::Expression(Expression::Operator(
+                Operator::Assign(Some(Box::new(Operator::Plus))),
+                Box::new(Expression::Variable(0)),
+                Box::new(Expression::Value(Value::U64(5)))
+            )),
+            Statement::Return(Some(Expression::Variable(0)))
+        ];
+        let third = vec![Statement::Return(Some(Expression::Variable(0)))];
+
+        let mut context = ExecutionContext::new();
+        let mut state = State::new(None, None, None);
+        assert_eq!(interpreter.execute_in_context(&first, 1, &mut context, &mut state).unwrap(), None);
+        assert_eq!(interpreter.execute_in_context(&second, 1, &mut context, &mut state).unwrap(), Some(Value::U64(15)));
+        assert_eq!(interpreter.execute_in_context(&third, 1, &mut context, &mut state).unwrap(), Some(Value::U64(15)));
+
+        // A fresh context doesn't know the variable
+        let mut context = ExecutionContext::new();
+        assert!(matches!(
+            interpreter.execute_in_context(&third, 1, &mut context, &mut state),
+            Err(InterpreterError::VariableNotFound(0))
+        ));
+    }
+
+    #[test]
+    fn test_arithmetic_on_optional() {
+        let program = Program::new();
+        let env = EnvironmentBuilder::default();
+        let interpreter = Interpreter::new(&program, env.environment()).unwrap();
+
+        // The parser rejects it, so the statement is built by hand
+        let optional = Value::Optional(Some(ValueOwnable::Owned(Box::new(Value::U64(5)))));
+        let statements = [Operator::Plus, Operator::Minus, Operator::Divide, Operator::BitwiseLeft].map(|op| vec![
+            Statement::Return(Some(Expression::Operator(
+                op,
+                Box::new(Expression::Value(Value::U64(1))),
+                Box::new(Expression::Value(optional.clone()))
+            )))
+        ]);
+
+        let mut state = State::new(None, None, None);
+        for statements in statements.iter() {
+            let mut context = ExecutionContext::new();
+            assert!(matches!(
+                interpreter.execute_in_context(statements, 0, &mut context, &mut state),
+                Err(InterpreterError::ArithmeticOnOptional(Type::Optional(inner))) if *inner == Type::U64
+            ));
+        }
+
+        // Other values are still reported as not numbers
+        let statements = vec![Statement::Return(Some(Expression::Operator(
+            Operator::Minus,
+            Box::new(Expression::Value(Value::U64(1))),
+            Box::new(Expression::Value(Value::Boolean(true)))
+        )))];
+        let mut context = ExecutionContext::new();
+        assert!(matches!(
+            interpreter.execute_in_context(&statements, 0, &mut context, &mut state),
+            Err(InterpreterError::OperationNotNumberType)
+        ));
+    }
+
+    #[test]
+    fn test_operator_precedence() {
+        // Left associative, it was 10 - (2 - 3) before the precedence table
+        test_code_expect_return("entry main() { return 10 - 2 - 3 }", 5);
+        test_code_expect_return("entry main() { return 2 * 3 + 4 }", 10);
+        test_code_expect_return("entry main() { return 2 * (3 + 4) }", 14);
+        test_code_expect_return("entry main() { return 100 / 10 / 2 }", 5);
+    }
+
+    #[test]
+    fn test_let_not_assigned_but_mutable() {
+        // A let variable can't be assigned, but its value can still be modified
+        // by a method or by a function taking it as parameter
+        test_code_expect_return("entry main() { let a: u64[] = [1]; a.push(2); return a.len() as u64 }", 2);
+        test_code_expect_return("fn add(v: u64[]) { v.push(5); } entry main() { let a: u64[] = []; add(a); return a[0] }", 5);
+    }
+
+    #[test]
+    fn test_call_entry_many() {
+        let code = "entry main(a: u64, b: u64) { emit(\"input\", a); return a / b }";
+        let tokens = Lexer::new(code).get().unwrap();
+        let env = EnvironmentBuilder::default();
+        let (program, mapper) = Parser::new(tokens, &env).parse().unwrap();
+        let interpreter = Interpreter::new(&program, env.environment()).unwrap();
+        let main = mapper.get(&Signature::new("main".to_string(), None, vec![Type::U64, Type::U64])).unwrap();
+
+        // Each call executes 8 expressions, the limit holds as the counter is reset before each call
+        let mut state = State::new(Some(10), None, None);
+        let results = interpreter.call_entry_many(&main, vec![
+            vec![Value::U64(10), Value::U64(2)],
+            vec![Value::U64(10), Value::U64(0)],
+            vec![Value::U64(9), Value::U64(3)]
+        ], &mut state);
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].0.as_ref().unwrap(), &5);
+        assert!(matches!(results[1].0, Err(InterpreterError::DivByZero)));
+        assert_eq!(results[2].0.as_ref().unwrap(), &3);
+
+        // Each call only returns its own events, even the failing one
+        for ((_, events), a) in results.iter().zip([10, 10, 9]) {
+            assert_eq!(events, &vec![("input".to_owned(), Value::U64(a))]);
+        }
+        assert!(interpreter.take_events().is_empty());
+    }
+
+    #[test]
+    fn test_deadline() {
+        let code = "entry main() { var a: u64 = 0; while true { a += 1; } return a }";
+        let env = EnvironmentBuilder::default();
+        let mut state = State::new(None, None, None);
+        state.set_deadline(Some(std::time::Instant::now() + std::time::Duration::from_millis(20)));
+        state.set_deadline_check_interval(100);
+        assert!(matches!(
+            try_execute_with_state(code, &env, &mut state),
+            Err(InterpreterError::Timeout)
+        ));
+
+        // The deadline is only checked after the interval
+        let mut state = State::new(None, None, None);
+        state.set_deadline(Some(std::time::Instant::now()));
+        assert_eq!(try_execute_with_state("entry main() { return 1 }", &env, &mut state).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_random() {
+        // Fixed sequence of values, repeated
+        struct Sequence(Vec<u64>, usize);
+
+        impl xelis_environment::RandomSource for Sequence {
+            fn next_u64(&mut self) -> u64 {
+                let value = self.0[self.1 % self.0.len()];
+                self.1 += 1;
+                value
+            }
+        }
+
+        let run = |code: &str, random: bool| {
+            let mut env = EnvironmentBuilder::default();
+            if random {
+                env.set_random_source(Some(Rc::new(RefCell::new(Sequence(vec![7, 42, u64::MAX, 0, 5], 0)))));
+            }
+
+            try_execute_with_state(code, &env, &mut State::new(None, None, None))
+        };
+
+        // 2^64 % 3 = 1, so 0 is rejected by the second range to keep it uniform
+        let code = "entry main() { assert(random() == 7); assert(random() == 42); assert(random_range(10, 13) == 10); assert(random_range(0, 3) == 2); return random() }";
+        assert_eq!(run(code, true).unwrap(), 7);
+
+        assert!(matches!(
+            run("entry main() { return random_range(5, 5) }", true),
+            Err(InterpreterError::EnvironmentError(EnvironmentError::EmptyRandomRange(5, 5)))
+        ));
+        assert!(matches!(
+            run("entry main() { return random() }", false),
+            Err(InterpreterError::EnvironmentError(EnvironmentError::NoRandomSource))
+        ));
+    }
+
+    #[test]
+    fn test_clamp() {
+        // Below, within and above the bounds, which are included
+        test_code_expect_return("entry main() { return clamp(2, 5, 10) }", 5);
+        test_code_expect_return("entry main() { return clamp(7, 5, 10) }", 7);
+        test_code_expect_return("entry main() { return clamp(5, 5, 10) + clamp(10, 5, 10) }", 15);
+        test_code_expect_return("entry main() { return clamp(11, 5, 10) }", 10);
+
+        // Every number type is supported
+        test_code_expect_return("entry main() { let a: u8 = 200; let b: u8 = clamp(a, 0, 100); return b as u64 }", 100);
+        test_code_expect_return("entry main() { let a: u256 = 3; return clamp(a, 4, 8) as u64 }", 4);
+
+        let key = Signature::new("main".to_string(), None, Vec::new());
+        assert_eq!(test_code_expect_value(&key, "fn main() -> u128 { let a: u128 = 1; return clamp(a, 2, 3) }"), Value::U128(2));
+
+        assert!(matches!(
+            test_code_expect_error("entry main() { return clamp(7, 10, 5) }"),
+            InterpreterError::EnvironmentError(EnvironmentError::InvalidClampBounds(Value::U64(10), Value::U64(5)))
+        ));
+
+        // Only numbers can be clamped
+        let env = EnvironmentBuilder::default();
+        let tokens = Lexer::new("entry main() { let s: string = clamp(\"a\", \"b\", \"c\"); return 0 }").get().unwrap();
+        assert!(Parser::new(tokens, &env).parse().is_err());
+    }
+
+    #[test]
+    fn test_div_ceil_round() {
+        // Exact division
+        test_code_expect_return("entry main() { return div_ceil(10, 5) + div_round(10, 5) }", 4);
+        test_code_expect_return("entry main() { return div_ceil(0, 5) + div_round(0, 5) }", 0);
+
+        // Rounded up, or to the nearest with half rounded up
+        test_code_expect_return("entry main() { return div_ceil(11, 5) }", 3);
+        test_code_expect_return("entry main() { return div_round(12, 5) }", 2);
+        test_code_expect_return("entry main() { return div_round(13, 5) }", 3);
+        test_code_expect_return("entry main() { return div_round(5, 2) }", 3);
+
+        // a + b - 1 would overflow
+        test_code_expect_return("entry main() { let a: u8 = 255; let b: u8 = div_ceil(a, 2); return b as u64 }", 128);
+        test_code_expect_return("entry main() { let a: u8 = 255; let b: u8 = div_round(a, 254); return b as u64 }", 1);
+        test_code_expect_return("entry main() { let a: u64 = u64::MAX.unwrap(); return div_ceil(a, a - 1) }", 2);
+        test_code_expect_return("entry main() { let a: u256 = u256::MAX.unwrap(); let b: u256 = 2; let c: u256 = div_ceil(a, b); return c.trailing_zeros() as u64 }", 255);
+
+        assert!(matches!(
+            test_code_expect_error("entry main() { let a: u64 = 0; return div_ceil(10, a) }"),
+            InterpreterError::EnvironmentError(EnvironmentError::DivByZero)
+        ));
+    }
+
+    #[test]
+    fn test_native_cost_fn() {
+        fn sum(_: xelis_environment::FnInstance, params: xelis_environment::FnParams, _: &mut xelis_environment::Context) -> xelis_environment::FnReturnType {
+            let mut total = 0;
+            for value in params[0].as_ref().as_vec()? {
+                total += value.handle().as_u64()?;
+            }
+            Ok(Some(Value::U64(total)))
+        }
+
+        // Each element of the array costs 10 on top of the base cost
+        fn sum_cost(_: Option<&Path>, params: &[Path]) -> u64 {
+            params[0].as_ref().as_vec().map(|values| values.len() as u64 * 10).unwrap_or(0)
+        }
+
+        fn total(zelf: xelis_environment::FnInstance, _: xelis_environment::FnParams, _: &mut xelis_environment::Context) -> xelis_environment::FnReturnType {
+            let mut total = 0;
+            for value in zelf?.as_vec()? {
+                total += value.handle().as_u64()?;
+            }
+            Ok(Some(Value::U64(total)))
+        }
+
+        // Same, but priced from the instance
+        fn total_cost(instance: Option<&Path>, _: &[Path]) -> u64 {
+            instance.and_then(|v| v.as_ref().as_vec().ok().map(|values| values.len() as u64 * 10)).unwrap_or(0)
+        }
+
+        let mut env = EnvironmentBuilder::default();
+        env.register_native_function_with_descriptor(NativeFunctionDescriptor {
+            name: "sum",
+            for_type: None,
+            parameters: vec![Type::Array(Box::new(Type::U64))],
+            on_call: sum,
+            cost: 5,
+            cost_fn: Some(sum_cost),
+            return_type: Some(Type::U64)
+        });
+        env.register_native_function_with_descriptor(NativeFunctionDescriptor {
+            name: "total",
+            for_type: Some(Type::Array(Box::new(Type::U64))),
+            parameters: Vec::new(),
+            on_call: total,
+            cost: 5,
+            cost_fn: Some(total_cost),
+            return_type: Some(Type::U64)
+        });
+
+        let run = |call: &str, len: usize, max_gas: Option<u64>| {
+            let code = format!("entry main() {{ let a: u64[] = [{}]; return {} }}", vec!["1"; len].join(", "), call);
+            let mut state = State::new(None, None, max_gas);
+            let result = try_execute_with_state(&code, &env, &mut state);
+            (result, state.get_gas_usage())
+        };
+
+        for call in ["sum(a)", "a.total()"] {
+            let (result, gas) = run(call, 1, None);
+            assert_eq!(result.unwrap(), 1);
+            assert_eq!(gas, 15);
+
+            let (result, gas) = run(call, 100, None);
+            assert_eq!(result.unwrap(), 100);
+            assert_eq!(gas, 1005);
+
+            // The cost is paid before the native function is executed
+            let (result, _) = run(call, 100, Some(1000));
+            assert!(matches!(result, Err(InterpreterError::GasLimitReached)));
+        }
+    }
+
+    #[test]
+    fn test_function_value() {
+        let code = "fn double(a: u64) -> u64 { return a * 2 }
+        entry main() {
+            let f: fn(u64) -> u64 = double;
+            return f(21)
+        }";
+        test_code_expect_return(code, 42);
+
+        // A function value can be reassigned
+        let code = "fn double(a: u64) -> u64 { return a * 2 }
+        fn square(a: u64) -> u64 { return a * a }
+        entry main() {
+            var f: fn(u64) -> u64 = double;
+            let a: u64 = f(3);
+            f = square;
+            return a + f(3)
+        }";
+        test_code_expect_return(code, 15);
+    }
+
+    #[test]
+    fn test_function_value_map() {
+        let code = "fn map(values: u64[], f: fn(u64) -> u64) -> u64[] {
+            let result: u64[] = [];
+            foreach v in values {
+                result.push(f(v))
+            }
+            return result
+        }
+        fn increment(a: u64) -> u64 { return a + 1 }
+        entry main() {
+            let values: u64[] = map([1, 2, 3], increment);
+            return values[0] * 100 + values[1] * 10 + values[2]
+        }";
+        test_code_expect_return(code, 234);
+    }
+
+    #[test]
+    fn test_function_value_errors() {
+        let env = EnvironmentBuilder::default();
+        let parse = |code: &'static str| Parser::new(Lexer::new(code).get().unwrap(), &env).parse();
+
+        // Wrong arguments
+        assert!(matches!(
+            parse("fn double(a: u64) -> u64 { return a * 2 } entry main() { let f: fn(u64) -> u64 = double; return f(1, 2) }"),
+            Err(ParserError::InvalidValueCallArgumentsCount(1))
+        ));
+        assert!(matches!(
+            parse("fn double(a: u64) -> u64 { return a * 2 } entry main() { let f: fn(u64) -> u64 = double; return f(\"a\") }"),
+            Err(ParserError::InvalidValueType(Type::String, Type::U64))
+        ));
+
+        // The function type must match the declaration
+        assert!(matches!(
+            parse("fn double(a: u64) -> u64 { return a * 2 } entry main() { let f: fn(u8) -> u64 = double; return f(1) }"),
+            Err(ParserError::InvalidValueType(_, _))
+        ));
+
+        // Overloaded functions can't be used as a value
+        assert!(matches!(
+            parse("fn f(a: u64) -> u64 { return a } fn f(a: u8) -> u64 { return 0 } entry main() { let g: fn(u64) -> u64 = f; return g(1) }"),
+            Err(ParserError::AmbiguousFunctionValue(_))
+        ));
+    }
+
+    #[test]
+    fn test_entry_function_value() {
+        let code = "fn double(a: u64) -> u64 { return a * 2 }
+        fn h(s: string) -> u64 { return 0 }
+        fn add(a: u64, b: u64) -> u64 { return a + b }
+        entry main(f: fn(u64) -> u64) { return f(21) }";
+        let env = EnvironmentBuilder::default();
+        let (program, mapper) = Parser::new(Lexer::new(code).get().unwrap(), &env).parse().unwrap();
+        let interpreter = Interpreter::new(&program, env.environment()).unwrap();
+        let main = mapper.get(&Signature::new("main".to_string(), None, vec![Type::Function(vec![Type::U64], Box::new(Type::U64))])).unwrap();
+        let id = |name: &str, parameters: Vec<Type>| mapper.get(&Signature::new(name.to_string(), None, parameters)).unwrap();
+        let call = |f: IdentifierType| interpreter.call_entry_function(&main, vec![Path::Owned(Value::Function(f))], &mut State::new(None, None, None));
+
+        assert_eq!(call(id("double", vec![Type::U64])).unwrap(), 42);
+
+        // Only a function with the declared signature is accepted
+        for f in [main, id("h", vec![Type::String]), id("add", vec![Type::U64, Type::U64]), 0, IdentifierType::MAX] {
+            assert!(matches!(call(f), Err(InterpreterError::InvalidEntryArgumentType(0, _))));
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use xelis_ast::{Expression, Statement};
+use xelis_types::{IdentifierType, Path, Value};
+use crate::{
+    contract,
+    stack::Stack,
+    Function,
+    Interpreter,
+    InterpreterError,
+    State,
+    StatementResult
+};
+
+// Result of a resumable execution
+#[derive(Debug)]
+pub enum ExecutionResult {
+    // The program suspended itself with this value
+    Yield(Value),
+    // The program is done and returned this value
+    Done(Option<Value>)
+}
+
+// Entry function execution that can be suspended by the program using yield
+// Only the top level statements of the entry function can suspend it:
+// `yield(value)` or `let x: T = yield(value)` which receives the resumed value
+// A yield anywhere else fails when executed
+pub struct Resumable<'a> {
+    interpreter: &'a Interpreter<'a>,
+    statements: &'a [Statement],
+    stack: Stack<'a>,
+    // Identifier of the native yield function
+    yield_id: IdentifierType,
+    // Index of the next top level statement to execute
+    next: usize,
+    // Statement suspended, waiting for a resumed value
+    suspended: Option<&'a Statement>,
+    done: bool
+}
+
+impl<'a> Resumable<'a> {
+    // Prepare the execution of an entry function
+    pub fn new(interpreter: &'a Interpreter<'a>, function_name: &IdentifierType, parameters: Vec<Path<'a>>, yield_id: IdentifierType, state: &mut State) -> Result<Self, InterpreterError> {
+        let f = match interpreter.get_function(function_name)? {
+            Function::Program(f) if f.is_entry() => f,
+            _ => return Err(InterpreterError::FunctionEntry(true, false))
+        };
+
+        interpreter.verify_entry_call(f, &parameters)?;
+        interpreter.context.borrow_mut().reset_allocations();
+
+        let stack = interpreter.prepare_stack(None, f.get_parameters(), parameters, f.get_variables_count(), state)?;
+        Ok(Self {
+            interpreter,
+            statements: f.get_statements(),
+            stack,
+            yield_id,
+            next: 0,
+            suspended: None,
+            done: false
+        })
+    }
+
+    // Argument of the yield call made by this statement, if any
+    fn get_yield_argument(&self, statement: &'a Statement) -> Option<&'a Expression> {
+        let expr = match statement {
+            Statement::Expression(expr) => expr,
+            Statement::Variable(var) => &var.value,
+            _ => return None
+        };
+
+        match expr {
+            Expression::FunctionCall(None, id, parameters) if *id == self.yield_id => parameters.first(),
+            _ => None
+        }
+    }
+
+    // Execute the top level statements until a yield or the end of the function
+    pub fn run_until_yield(&mut self, state: &mut State) -> Result<ExecutionResult, InterpreterError> {
+        if self.done {
+            return Err(InterpreterError::ExecutionFinished)
+        }
+
+        if self.suspended.is_some() {
+            return Err(InterpreterError::ExpectedResume)
+        }
+
+        while let Some(statement) = self.statements.get(self.next) {
+            self.next += 1;
+
+            if let Some(expr) = self.get_yield_argument(statement) {
+                state.increase_expressions_executed()?;
+                let value = match self.interpreter.execute_expression_and_expect_value(expr, &mut self.stack, state) {
+                    Ok(value) => value.into_owned(),
+                    // The try operator in the argument returns from the function
+                    Err(unwind) => {
+                        let value = unwind.into_returned()?;
+                        self.done = true;
+                        return Ok(ExecutionResult::Done(value))
+                    }
+                };
+                self.suspended = Some(statement);
+                return Ok(ExecutionResult::Yield(value))
+            }
+
+            let value = match self.interpreter.execute_statements(std::slice::from_ref(statement), &mut self.stack, state) {
+                Ok(StatementResult::Return(v)) => v.map(Path::into_owned),
+                Ok(_) => continue,
+                Err(unwind) => unwind.into_returned()?
+            };
+
+            self.done = true;
+            return Ok(ExecutionResult::Done(value))
+        }
+
+        self.done = true;
+        Ok(ExecutionResult::Done(None))
+    }
+
+    // Provide the value returned by the suspended yield
+    // The execution continues on the next call to run_until_yield
+    pub fn resume(&mut self, value: Value) -> Result<(), InterpreterError> {
+        let statement = self.suspended.ok_or(InterpreterError::NotSuspended)?;
+        if let Statement::Variable(var) = statement {
+            // The host value must match the declared variable
+            if !contract::is_value_of_type(&value, &var.value_type) {
+                return Err(InterpreterError::InvalidType(var.value_type.clone()))
+            }
+
+            self.stack.register_variable(var.id, Path::Owned(value))?;
+        }
+
+        self.suspended = None;
+        Ok(())
+    }
+
+    // Check if the execution is waiting for a resumed value
+    pub fn is_suspended(&self) -> bool {
+        self.suspended.is_some()
+    }
+}
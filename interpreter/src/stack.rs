@@ -16,6 +16,14 @@ impl<'a> Stack<'a> {
         }
     }
 
+    // Grow the stack so it can hold at least this count of variables
+    pub fn reserve(&mut self, capacity: u16) {
+        let capacity = capacity as usize;
+        if self.stack.len() < capacity {
+            self.stack.resize(capacity, None);
+        }
+    }
+
     // Remove a variable from the stack
     #[inline(always)]
     pub fn remove_variable(&mut self, name: &IdentifierType) -> Result<Path<'a>, InterpreterError> {
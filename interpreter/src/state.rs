@@ -1,5 +1,21 @@
+use std::time::Instant;
+use xelis_types::ValueError;
 use crate::InterpreterError;
 
+// Default maximum depth of nested expressions being executed
+// Prevent a deeply nested expression from overflowing the native stack
+// A level nested by the parser can be executed as two nested expressions,
+// like a parenthesized expression, so anything the parser accepts is executed
+pub const MAX_EXPRESSION_DEPTH: u16 = 2 * xelis_parser::MAX_NESTING_DEPTH as u16;
+
+// Default count of expressions executed between two deadline checks
+// Reading the clock on every expression would slow down the execution
+pub const DEADLINE_CHECK_INTERVAL: u64 = 1024;
+
+// Default maximum length of an array built by the program
+// Prevent an operation like `[0] * n` from allocating more memory than available
+pub const MAX_ARRAY_LENGTH: usize = 1 << 20;
+
 // State is used to store the number of expressions executed and the number of recursive calls
 pub struct State {
     // Count the number of expressions executed
@@ -14,6 +30,20 @@ pub struct State {
     gas_usage: u64,
     // Program execution shouldn't exceed this limit
     max_gas_usage: Option<u64>,
+    // Current depth of nested expressions
+    expression_depth: u16,
+    // Maximum depth of nested expressions
+    max_expression_depth: Option<u16>,
+    // Maximum length of a string built by the program
+    max_string_length: Option<usize>,
+    // Maximum length of an array built by the program
+    max_array_length: Option<usize>,
+    // Wall-clock time at which the execution is stopped
+    deadline: Option<Instant>,
+    // Count of expressions executed between two deadline checks
+    deadline_check_interval: u64,
+    // Expressions count at which the deadline is checked next
+    next_deadline_check: u64,
 }
 
 impl State {
@@ -26,7 +56,99 @@ impl State {
             max_recursive,
             gas_usage: 0,
             max_gas_usage: max_cost,
+            expression_depth: 0,
+            max_expression_depth: Some(MAX_EXPRESSION_DEPTH),
+            max_string_length: None,
+            max_array_length: Some(MAX_ARRAY_LENGTH),
+            deadline: None,
+            deadline_check_interval: DEADLINE_CHECK_INTERVAL,
+            next_deadline_check: DEADLINE_CHECK_INTERVAL,
+        }
+    }
+
+    // Set the wall-clock time at which the execution is stopped
+    // It is only checked every deadline check interval expressions
+    // None disable the deadline
+    pub fn set_deadline(&mut self, deadline: Option<Instant>) {
+        self.deadline = deadline;
+    }
+
+    // Set the count of expressions executed between two deadline checks
+    // A lower interval stops the execution closer to the deadline but is slower
+    pub fn set_deadline_check_interval(&mut self, interval: u64) {
+        self.deadline_check_interval = interval.max(1);
+        self.next_deadline_check = self.count_expr + self.deadline_check_interval;
+    }
+
+    // Set the maximum length of a string built by the program
+    // None disable the limit
+    pub fn set_max_string_length(&mut self, max_string_length: Option<usize>) {
+        self.max_string_length = max_string_length;
+    }
+
+    // Set the maximum length of an array built by the program
+    // None disable the limit
+    pub fn set_max_array_length(&mut self, max_array_length: Option<usize>) {
+        self.max_array_length = max_array_length;
+    }
+
+    // Verify that an array of this length can be built
+    pub fn check_array_length(&self, len: usize) -> Result<(), InterpreterError> {
+        if let Some(max) = self.max_array_length {
+            if len > max {
+                return Err(ValueError::MaxArrayLengthReached(len, max).into())
+            }
+        }
+
+        Ok(())
+    }
+
+    // Verify that a string of this length can be built
+    pub fn check_string_length(&self, len: usize) -> Result<(), InterpreterError> {
+        if let Some(max) = self.max_string_length {
+            if len > max {
+                return Err(ValueError::MaxStringLengthReached(len, max).into())
+            }
+        }
+
+        Ok(())
+    }
+
+    // Set the maximum depth of nested expressions
+    // None disable the limit
+    pub fn set_max_expression_depth(&mut self, max_expression_depth: Option<u16>) {
+        self.max_expression_depth = max_expression_depth;
+    }
+
+    // increment the depth of nested expressions
+    pub fn increase_expression_depth(&mut self) -> Result<(), InterpreterError> {
+        self.expression_depth += 1;
+
+        if let Some(max) = self.max_expression_depth {
+            if self.expression_depth > max {
+                return Err(InterpreterError::ExpressionDepthLimitReached)
+            }
         }
+
+        Ok(())
+    }
+
+    // decrement the depth of nested expressions
+    #[inline(always)]
+    pub fn decrease_expression_depth(&mut self) {
+        self.expression_depth -= 1;
+    }
+
+    // Take the current depth of nested expressions and reset it
+    #[inline(always)]
+    pub fn take_expression_depth(&mut self) -> u16 {
+        std::mem::take(&mut self.expression_depth)
+    }
+
+    // Restore a depth of nested expressions
+    #[inline(always)]
+    pub fn set_expression_depth(&mut self, depth: u16) {
+        self.expression_depth = depth;
     }
 
     // increase the number of expressions executed
@@ -39,6 +161,13 @@ impl State {
             }
         }
 
+        if self.count_expr >= self.next_deadline_check {
+            self.next_deadline_check = self.count_expr + self.deadline_check_interval;
+            if self.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return Err(InterpreterError::Timeout)
+            }
+        }
+
         Ok(())
     }
 
@@ -75,7 +204,7 @@ impl State {
 
     // Increase the gas usage
     pub fn increase_gas_usage(&mut self, value: u64) -> Result<(), InterpreterError> {
-        self.gas_usage += value;
+        self.gas_usage = self.gas_usage.saturating_add(value);
 
         if let Some(max) = self.max_gas_usage {
             if self.gas_usage >= max {
@@ -102,7 +231,9 @@ impl State {
     #[inline(always)]
     pub fn reset(&mut self) {
         self.count_expr = 0;
+        self.next_deadline_check = self.deadline_check_interval;
         self.recursive = 0;
         self.gas_usage = 0;
+        self.expression_depth = 0;
     }
 }
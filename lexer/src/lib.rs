@@ -11,6 +11,20 @@ macro_rules! parse_number {
     };
 }
 
+// Same as parse_number, but a value that doesn't fit in the suffix type
+// is kept as an out of range literal, the parser decides how to handle it
+macro_rules! parse_suffixed_number {
+    ($self: expr, $t: ident, $l: ident, $s: expr, $radix: expr) => {
+        match $t::from_str_radix($s, $radix) {
+            Ok(value) => Token::Value(Literal::$l(value)),
+            Err(_) => match u128::from_str_radix($s, $radix) {
+                Ok(value) => Token::Value(Literal::OutOfRange(NumberType::$l, value)),
+                Err(_) => return Err(LexerError::ParseToNumber($self.line, $self.column))
+            }
+        }
+    };
+}
+
 #[derive(Debug)]
 pub enum LexerError { // left is line, right is column
     EndOfFile,
@@ -44,7 +58,13 @@ pub struct Lexer<'a> {
     // current line number we are reading
     line: usize,
     // current column number we are reading
-    column: usize
+    column: usize,
+    // the last token read is a break or a continue, it may be followed by a label
+    after_jump: bool,
+    // a statement can start at this position, after a {, a ; or a }
+    // or after the end of an expression, as statements may not be separated
+    // only there a loop can be labeled
+    statement_start: bool
 }
 
 impl<'a> Lexer<'a> {
@@ -55,7 +75,9 @@ impl<'a> Lexer<'a> {
             chars: input.chars().collect::<Vec<_>>().into(),
             pos: 0,
             line: 1,
-            column: 0
+            column: 0,
+            after_jump: false,
+            statement_start: true
         }
     }
 
@@ -102,13 +124,9 @@ impl<'a> Lexer<'a> {
     }
 
     // push a character back to the list
+    // the line is only increased once a new line is read as whitespace
     fn push_back(&mut self, c: char) {
-        if c == '\n' {
-            self.line -= 1;
-            self.column = 1;
-        } else {
-            self.column -= 1;
-        }
+        self.column -= 1;
         self.pos -= 1;
         self.chars.push_front(c);
     }
@@ -237,7 +255,7 @@ impl<'a> Lexer<'a> {
 
         // Default number type to use
         let mut number_type = None;
-        let is_hex = c == '0' && self.peek()? == 'x';
+        let is_hex = c == '0' && self.chars.front() == Some(&'x');
         let column_start = self.column;
 
         let mut init_pos = if is_hex {
@@ -289,10 +307,10 @@ impl<'a> Lexer<'a> {
         let radix = if is_hex { 16 } else { 10 };
         let token = match number_type {
             Some(t) => match t {
-                NumberType::U8 => parse_number!(self, u8, U8, v, radix),
-                NumberType::U16 => parse_number!(self, u16, U16, v, radix),
-                NumberType::U32 => parse_number!(self, u32, U32, v, radix),
-                NumberType::U64 => parse_number!(self, u64, U64, v, radix),
+                NumberType::U8 => parse_suffixed_number!(self, u8, U8, v, radix),
+                NumberType::U16 => parse_suffixed_number!(self, u16, U16, v, radix),
+                NumberType::U32 => parse_suffixed_number!(self, u32, U32, v, radix),
+                NumberType::U64 => parse_suffixed_number!(self, u64, U64, v, radix),
                 NumberType::U128 => parse_number!(self, u128, U128, v, radix),
                 NumberType::U256 => parse_number!(self, U256, U256, v, radix),
             }
@@ -329,18 +347,33 @@ impl<'a> Lexer<'a> {
         }, diff)?;
 
         let generic = TokenGeneric::value_of(value);
-        let token = if generic.is_some() && self.peek()? == '<' {
+        let token = if let Some(generic) = generic.filter(|_| matches!(self.peek(), Ok('<'))) {
             self.advance()?;
-            let inner = self.read_token(0)?;
-            if '>' != self.advance()? {
-                return Err(LexerError::ExpectedChar(self.line, self.column));
+            let mut inner_tokens = Vec::with_capacity(generic.inner_count());
+            for i in 0..generic.inner_count() {
+                // Inner types are separated by a comma
+                if i > 0 {
+                    if ',' != self.advance()? {
+                        return Err(LexerError::ExpectedChar(self.line, self.column));
+                    }
+
+                    while self.peek()? == ' ' {
+                        self.advance()?;
+                    }
+                }
+
+                let inner = self.read_token(0)?;
+                if !inner.token.is_type() {
+                    return Err(LexerError::ExpectedType);
+                }
+                inner_tokens.push(inner.token);
             }
 
-            if !inner.token.is_type() {
-                return Err(LexerError::ExpectedType);
+            if '>' != self.advance()? {
+                return Err(LexerError::ExpectedChar(self.line, self.column));
             }
 
-            generic.unwrap().to_token(inner.token)
+            generic.to_token(inner_tokens)
         } else {
             Token::value_of(&value).unwrap_or_else(|| Token::Identifier(value))
         };
@@ -353,27 +386,69 @@ impl<'a> Lexer<'a> {
         })
     }
 
+    // Length of the label starting after a quote, None if the quote starts a string
+    // A label follows a break or a continue, or declares a loop: 'outer: while
+    fn label_length(&self) -> Option<usize> {
+        if !self.chars.front().is_some_and(|c| *c == '_' || c.is_alphabetic()) {
+            return None
+        }
+
+        let len = self.chars.iter()
+            .take_while(|c| **c == '_' || c.is_ascii_alphanumeric())
+            .count();
+
+        // 'a' is a string
+        if self.after_jump {
+            return (self.chars.get(len) != Some(&'\'')).then_some(len)
+        }
+
+        if !self.statement_start || self.chars.get(len) != Some(&':') {
+            return None
+        }
+
+        let mut rest = self.chars.iter().skip(len + 1).skip_while(|c| **c == ' ').peekable();
+        let mut keyword = String::new();
+        while let Some(c) = rest.next_if(|c| c.is_ascii_alphabetic()) {
+            keyword.push(*c);
+        }
+
+        let is_loop = matches!(keyword.as_str(), "while" | "for" | "foreach");
+        (is_loop && rest.next().is_some_and(|c| c.is_whitespace())).then_some(len)
+    }
+
     // retrieve the next token available
     fn next_token(&mut self) -> Result<Option<TokenResult<'a>>, LexerError> {
         while let Some(c) = self.next_char() {
             let token: TokenResult<'a> = match c {
-                '\n' | '\r' | '\t' => {
+                '\n' => {
                     self.line += 1;
                     self.column = 0;
                     continue;
                 },
                 // skipped characters
-                ' ' | ';' => {
+                ' ' | ';' | '\r' | '\t' => {
                     // we just skip these characters
+                    if c == ';' {
+                        self.statement_start = true;
+                    }
                     continue;
                 },
                 // read a string value
                 // It supports escaped characters
+                // or a loop label
                 '"' | '\'' => {
                     let column_start = self.column;
-                    let value = self.read_string(c)?;
+                    let token = match self.label_length().filter(|_| c == '\'') {
+                        Some(len) => {
+                            let start = self.pos;
+                            self.advance_by(len)?;
+                            Token::Label(self.get_slice(start, self.pos)?)
+                        },
+                        None => Token::Value(Literal::String(self.read_string(c)?))
+                    };
+
                     TokenResult {
-                        token: Token::Value(Literal::String(value)),
+                        token,
                         line: self.line,
                         column_start,
                         column_end: self.column
@@ -406,6 +481,12 @@ impl<'a> Lexer<'a> {
                 }
             };
 
+            self.after_jump = matches!(token.token, Token::Break | Token::Continue);
+            self.statement_start = matches!(
+                token.token,
+                Token::BraceOpen | Token::BraceClose | Token::ParenthesisClose | Token::BracketClose
+                    | Token::Identifier(_) | Token::Value(_) | Token::Number(_) | Token::Bool | Token::String
+            );
             return Ok(Some(token));
         }
 
@@ -414,14 +495,17 @@ impl<'a> Lexer<'a> {
 
     // Parse the code into a list of tokens
     // This returns only the list of tokens without any other information
-    pub fn get(mut self) -> Result<VecDeque<Token<'a>>, LexerError> {
-        let mut tokens = VecDeque::new();
-        while let Some(token) = self.next_token()? {
-            // push the token to the list
-            tokens.push_back(token.token);
-        }
+    pub fn get(self) -> Result<VecDeque<Token<'a>>, LexerError> {
+        self.tokens().collect()
+    }
 
-        Ok(tokens)
+    // Lazily read the tokens one at a time
+    // Lexing stops at the first error
+    pub fn tokens(self) -> Tokens<'a> {
+        Tokens {
+            lexer: self,
+            done: false
+        }
     }
 }
 
@@ -433,10 +517,105 @@ impl<'a> Iterator for Lexer<'a> {
     }
 }
 
+// Iterator over the tokens of a Lexer without their position
+pub struct Tokens<'a> {
+    lexer: Lexer<'a>,
+    // set once the end of the input or an error is reached
+    done: bool
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = Result<Token<'a>, LexerError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        match self.lexer.next_token() {
+            Ok(Some(token)) => Some(Ok(token.token)),
+            Ok(None) => {
+                self.done = true;
+                None
+            },
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+impl std::iter::FusedIterator for Tokens<'_> {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_labels() {
+        let code = "'outer: while true { break 'outer; continue 'outer } 'a' 'b c'";
+        let tokens = Lexer::new(code).get().unwrap();
+        assert_eq!(tokens, vec![
+            Token::Label("outer"),
+            Token::Colon,
+            Token::While,
+            Token::Value(Literal::Bool(true)),
+            Token::BraceOpen,
+            Token::Break,
+            Token::Label("outer"),
+            Token::Continue,
+            Token::Label("outer"),
+            Token::BraceClose,
+            Token::Value(Literal::String(Cow::Borrowed("a"))),
+            Token::Value(Literal::String(Cow::Borrowed("b c"))),
+        ]);
+    }
+
+    #[test]
+    fn test_label_only_at_statement_start() {
+        let code = "let s: string = 'note: for more';";
+        let tokens = Lexer::new(code).get().unwrap();
+        assert_eq!(tokens, vec![
+            Token::Let,
+            Token::Identifier("s"),
+            Token::Colon,
+            Token::String,
+            Token::OperatorAssign,
+            Token::Value(Literal::String(Cow::Borrowed("note: for more"))),
+        ]);
+    }
+
+    #[test]
+    fn test_label_after_expression() {
+        let expected = |value| vec![
+            Token::Var,
+            Token::Identifier("s"),
+            Token::Colon,
+            Token::Number(NumberType::U64),
+            Token::OperatorAssign,
+            Token::Value(value),
+            Token::Label("outer"),
+            Token::Colon,
+            Token::While,
+            Token::Value(Literal::Bool(true)),
+            Token::BraceOpen,
+            Token::Break,
+            Token::Label("outer"),
+            Token::BraceClose,
+        ];
+
+        let tokens = Lexer::new("var s: u64 = 0\n'outer: while true { break 'outer }").get().unwrap();
+        assert_eq!(tokens, expected(Literal::Number(0)));
+
+        let tokens = Lexer::new("var s: u64 = 1u64 'outer: while true { break 'outer }").get().unwrap();
+        assert_eq!(tokens, expected(Literal::U64(1)));
+
+        // Still a string when an expression is expected
+        let tokens = Lexer::new("let s: string =\n'note: for more'").get().unwrap();
+        assert_eq!(tokens.back(), Some(&Token::Value(Literal::String(Cow::Borrowed("note: for more")))));
+    }
+
     #[test]
     fn test_operators() {
         let code = "+ - * / % ^ | & << >> == != > < >= <=";
@@ -639,6 +818,16 @@ mod tests {
         ]);
     }
 
+    #[test]
+    fn test_zero_at_end() {
+        let code = "0";
+        let lexer = Lexer::new(code);
+        let tokens = lexer.get().unwrap();
+        assert_eq!(tokens, vec![
+            Token::Value(Literal::Number(0))
+        ]);
+    }
+
     #[test]
     fn test_number_with_underscore() {
         let code = "10_000";
@@ -679,6 +868,56 @@ mod tests {
         ]);
     }
 
+    #[test]
+    fn test_number_suffixes() {
+        let code = "5u8 300u16 10u32 10u64 10u256";
+        let lexer = Lexer::new(code);
+        let tokens = lexer.get().unwrap();
+        assert_eq!(tokens, vec![
+            Token::Value(Literal::U8(5)),
+            Token::Value(Literal::U16(300)),
+            Token::Value(Literal::U32(10)),
+            Token::Value(Literal::U64(10)),
+            Token::Value(Literal::U256(U256::from(10u64)))
+        ]);
+    }
+
+    #[test]
+    fn test_number_suffix_overflow() {
+        // Out of range values are resolved by the parser
+        let lexer = Lexer::new("300u8 0x1_0000u16");
+        assert_eq!(lexer.get().unwrap(), vec![
+            Token::Value(Literal::OutOfRange(NumberType::U8, 300)),
+            Token::Value(Literal::OutOfRange(NumberType::U16, 0x10000))
+        ]);
+
+        let lexer = Lexer::new("340282366920938463463374607431768211456u8");
+        assert!(matches!(lexer.get(), Err(LexerError::ParseToNumber(_, _))));
+    }
+
+    #[test]
+    fn test_bitwise_not() {
+        let code = "~0u8 !true";
+        let lexer = Lexer::new(code);
+        let tokens = lexer.get().unwrap();
+        assert_eq!(tokens, vec![
+            Token::BitwiseNot,
+            Token::Value(Literal::U8(0)),
+            Token::IsNot,
+            Token::Value(Literal::Bool(true))
+        ]);
+    }
+
+    #[test]
+    fn test_result_type() {
+        let code = "result<u64, string>";
+        let lexer = Lexer::new(code);
+        let tokens = lexer.get().unwrap();
+        assert_eq!(tokens, vec![
+            Token::Result(Box::new(Token::Number(NumberType::U64)), Box::new(Token::String))
+        ]);
+    }
+
     #[test]
     fn test_function_with_args() {
         let code = "fn sum(a: u64, b: u64) -> u64 { return a + b; }";
@@ -891,4 +1130,28 @@ mod tests {
             Token::Identifier("MAX")
         ]);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_tokens_iterator() {
+        let code = "fn main(a: u64) -> u64 { let b: string = \"hello\"; return a + 10 }";
+        let tokens = Lexer::new(code).tokens().collect::<Result<VecDeque<_>, _>>().unwrap();
+        assert_eq!(tokens, Lexer::new(code).get().unwrap());
+
+        // tokens are produced one at a time
+        let mut iter = Lexer::new(code).tokens();
+        assert_eq!(iter.next().unwrap().unwrap(), Token::Function);
+        assert_eq!(iter.next().unwrap().unwrap(), Token::Identifier("main"));
+    }
+
+    #[test]
+    fn test_tokens_iterator_stops_on_error() {
+        let code = "let a = 10 $ let b = 20";
+        let mut iter = Lexer::new(code).tokens();
+        assert_eq!(iter.next().unwrap().unwrap(), Token::Let);
+        assert_eq!(iter.next().unwrap().unwrap(), Token::Identifier("a"));
+        assert_eq!(iter.next().unwrap().unwrap(), Token::OperatorAssign);
+        assert_eq!(iter.next().unwrap().unwrap(), Token::Value(Literal::Number(10)));
+        assert!(matches!(iter.next(), Some(Err(LexerError::NoTokenFound(_, _)))));
+        assert!(iter.next().is_none());
+    }
+}
@@ -0,0 +1,177 @@
+use std::collections::HashSet;
+use xelis_ast::{DeclarationStatement, Expression, Operator, Program, Statement};
+use xelis_types::{IdentifierType, Value};
+use crate::ParserError;
+
+// Variables that may not be assigned yet at the current point of the function
+// Variable ids are reused between sibling scopes, a declaration always resets its id state
+type Unassigned = HashSet<IdentifierType>;
+
+// Definite assignment analysis
+// Verify that no variable is read before being assigned on every possible path
+pub fn check_program<'a>(program: &Program) -> Result<(), ParserError<'a>> {
+    for function in program.functions() {
+        check_statements(function.get_statements(), Unassigned::new())?;
+    }
+
+    Ok(())
+}
+
+// A declaration without value is only initialized if its type accept null
+fn is_uninitialized(declaration: &DeclarationStatement) -> bool {
+    declaration.value == Expression::Value(Value::Null) && !declaration.value_type.allow_null()
+}
+
+fn check_declaration<'a>(declaration: &DeclarationStatement, unassigned: &mut Unassigned) -> Result<(), ParserError<'a>> {
+    check_expression(&declaration.value, unassigned)?;
+    if is_uninitialized(declaration) {
+        unassigned.insert(declaration.id);
+    } else {
+        unassigned.remove(&declaration.id);
+    }
+
+    Ok(())
+}
+
+// Returns None if the statements never reach their end (return, break, continue)
+fn check_statements<'a>(statements: &[Statement], mut unassigned: Unassigned) -> Result<Option<Unassigned>, ParserError<'a>> {
+    for statement in statements {
+        match statement {
+            Statement::Variable(declaration) => check_declaration(declaration, &mut unassigned)?,
+            Statement::TupleDeconstruction(variables, expr) => {
+                check_expression(expr, &mut unassigned)?;
+                for (id, _) in variables {
+                    unassigned.remove(id);
+                }
+            },
+            Statement::Expression(expr) => check_expression(expr, &mut unassigned)?,
+            Statement::Return(expr) => {
+                if let Some(expr) = expr {
+                    check_expression(expr, &mut unassigned)?;
+                }
+                return Ok(None)
+            },
+            Statement::Break(_) | Statement::Continue(_) => return Ok(None),
+            Statement::Scope(statements) => match check_statements(statements, unassigned)? {
+                Some(state) => unassigned = state,
+                None => return Ok(None)
+            },
+            Statement::If(condition, statements, else_statements) => {
+                check_expression(condition, &mut unassigned)?;
+                let then_state = check_statements(statements, unassigned.clone())?;
+                let else_state = match else_statements {
+                    Some(else_statements) => check_statements(else_statements, unassigned)?,
+                    None => Some(unassigned)
+                };
+
+                // A variable is assigned after the condition only if it is in every branch reaching the end
+                unassigned = match (then_state, else_state) {
+                    (Some(a), Some(b)) => a.union(&b).copied().collect(),
+                    (Some(state), None) | (None, Some(state)) => state,
+                    (None, None) => return Ok(None)
+                };
+            },
+            // Loop bodies may never be executed, assignments inside them are not kept
+            Statement::While(condition, statements) => {
+                check_expression(condition, &mut unassigned)?;
+                check_statements(statements, unassigned.clone())?;
+            },
+            Statement::ForEach(id, expr, statements) => {
+                check_expression(expr, &mut unassigned)?;
+                let mut state = unassigned.clone();
+                state.remove(id);
+                check_statements(statements, state)?;
+            },
+            Statement::For(declaration, condition, increment, statements) => {
+                let mut state = unassigned.clone();
+                check_declaration(declaration, &mut state)?;
+                check_expression(condition, &mut state)?;
+                if let Some(mut state) = check_statements(statements, state)? {
+                    check_expression(increment, &mut state)?;
+                }
+            }
+        }
+    }
+
+    Ok(Some(unassigned))
+}
+
+fn check_expression<'a>(expr: &Expression, unassigned: &mut Unassigned) -> Result<(), ParserError<'a>> {
+    match expr {
+        Expression::Variable(id) => {
+            if unassigned.contains(id) {
+                return Err(ParserError::PossiblyUninitializedVariable(*id))
+            }
+        },
+        // Only a direct assignment initialize the variable, others read it first
+        Expression::Operator(Operator::Assign(None), left, right) if matches!(left.as_ref(), Expression::Variable(_)) => {
+            check_expression(right, unassigned)?;
+            if let Expression::Variable(id) = left.as_ref() {
+                unassigned.remove(id);
+            }
+        },
+        // Right side may not be evaluated
+        Expression::Operator(Operator::And | Operator::Or, left, right) => {
+            check_expression(left, unassigned)?;
+            check_expression(right, &mut unassigned.clone())?;
+        },
+        Expression::Operator(_, left, right)
+        | Expression::ArrayCall(left, right)
+        | Expression::Range(left, right) => {
+            check_expression(left, unassigned)?;
+            check_expression(right, unassigned)?;
+        },
+        Expression::Ternary(condition, left, right) => {
+            check_expression(condition, unassigned)?;
+            let mut right_state = unassigned.clone();
+            check_expression(left, unassigned)?;
+            check_expression(right, &mut right_state)?;
+            unassigned.extend(right_state);
+        },
+        Expression::FunctionCall(path, _, parameters) => {
+            if let Some(path) = path {
+                check_expression(path, unassigned)?;
+            }
+            for param in parameters {
+                check_expression(param, unassigned)?;
+            }
+        },
+        Expression::ValueCall(function, parameters) => {
+            check_expression(function, unassigned)?;
+            for param in parameters {
+                check_expression(param, unassigned)?;
+            }
+        },
+        Expression::ArrayConstructor(values)
+        | Expression::StructConstructor(values, _)
+        | Expression::TupleConstructor(values) => {
+            for value in values {
+                check_expression(value, unassigned)?;
+            }
+        },
+        Expression::StructUpdate(expr, fields) => {
+            check_expression(expr, unassigned)?;
+            for (_, value) in fields {
+                check_expression(value, unassigned)?;
+            }
+        },
+        // A block returning from the function never produces its value
+        Expression::Block(statements, value, _) => {
+            if let Some(mut state) = check_statements(statements, unassigned.clone())? {
+                check_expression(value, &mut state)?;
+                *unassigned = state;
+            }
+        },
+        // Right side is the field accessed, not a variable
+        Expression::Path(expr, _)
+        | Expression::OptionalPath(expr, _)
+        | Expression::SubExpression(expr)
+        | Expression::IsNot(expr)
+        | Expression::BitwiseNot(expr)
+        | Expression::Try(expr)
+        | Expression::Cast(expr, _) => check_expression(expr, unassigned)?,
+        Expression::Value(_) => {}
+    }
+
+    Ok(())
+}
@@ -0,0 +1,858 @@
+use std::collections::HashMap;
+use xelis_ast::{
+    DeclarationStatement,
+    Expression,
+    FunctionType,
+    Operator,
+    Program,
+    Statement
+};
+use xelis_environment::{Environment, NativeFunction};
+use xelis_types::{IdentifierType, Type, Value};
+use crate::{optional_of, ParserError};
+
+// Expression annotated with its resolved type
+#[derive(Debug)]
+pub struct TypedExpression<'p> {
+    // The original expression
+    pub expression: &'p Expression,
+    // Resolved type, None for null values and expressions producing nothing
+    pub expr_type: Option<Type>,
+    // Typed sub expressions in evaluation order
+    pub children: Vec<TypedExpression<'p>>,
+    // Typed statements of a block expression, executed before its children
+    pub statements: Vec<TypedStatement<'p>>,
+}
+
+impl<'p> TypedExpression<'p> {
+    fn new(expression: &'p Expression, expr_type: Option<Type>, children: Vec<TypedExpression<'p>>) -> Self {
+        Self {
+            expression,
+            expr_type,
+            children,
+            statements: Vec::new()
+        }
+    }
+}
+
+// Same shape as Statement but with typed expressions
+#[derive(Debug)]
+pub enum TypedStatement<'p> {
+    If(TypedExpression<'p>, Vec<TypedStatement<'p>>, Option<Vec<TypedStatement<'p>>>),
+    While(TypedExpression<'p>, Vec<TypedStatement<'p>>),
+    ForEach(IdentifierType, TypedExpression<'p>, Vec<TypedStatement<'p>>),
+    For(IdentifierType, TypedExpression<'p>, TypedExpression<'p>, TypedExpression<'p>, Vec<TypedStatement<'p>>),
+    Expression(TypedExpression<'p>),
+    Return(Option<TypedExpression<'p>>),
+    Scope(Vec<TypedStatement<'p>>),
+    Break(u16),
+    Continue(u16),
+    Variable(IdentifierType, TypedExpression<'p>),
+    TupleDeconstruction(Vec<IdentifierType>, TypedExpression<'p>),
+}
+
+#[derive(Debug)]
+pub struct TypedFunction<'p> {
+    pub function: &'p FunctionType,
+    // Typed default values of the parameters, same order as the parameters
+    pub default_values: Vec<Option<TypedExpression<'p>>>,
+    pub statements: Vec<TypedStatement<'p>>,
+}
+
+#[derive(Debug)]
+pub struct TypedProgram<'p> {
+    pub constants: Vec<TypedExpression<'p>>,
+    pub functions: Vec<TypedFunction<'p>>,
+}
+
+enum Function<'p> {
+    Native(&'p NativeFunction),
+    Program(&'p FunctionType)
+}
+
+impl<'p> Function<'p> {
+    fn for_type(&self) -> Option<&'p Type> {
+        match self {
+            Function::Native(f) => f.get_for_type().as_ref(),
+            Function::Program(FunctionType::Declared(f)) => f.get_on_type().as_ref(),
+            Function::Program(FunctionType::Entry(_)) => None
+        }
+    }
+
+    fn return_type(&self) -> Option<&'p Type> {
+        match self {
+            Function::Native(f) => f.return_type().as_ref(),
+            Function::Program(f) => f.return_type().as_ref()
+        }
+    }
+}
+
+// Static type checker over a Program
+// Every expression type is inferred and validated ahead of any execution,
+// this is useful for programs built or rewritten outside of the parser
+pub struct TypeChecker<'p> {
+    program: &'p Program,
+    environment: &'p Environment,
+    // Type of each variable declared at the current point
+    // Ids are reused between sibling scopes, a declaration always resets its type
+    variables: HashMap<IdentifierType, Type>,
+    // Return type of the function being checked
+    return_type: Option<Type>,
+    // Accept a condition of any type, like the parser in the same mode
+    truthy_conditions: bool,
+}
+
+impl<'p> TypeChecker<'p> {
+    pub fn new(program: &'p Program, environment: &'p Environment) -> Self {
+        Self {
+            program,
+            environment,
+            variables: HashMap::new(),
+            return_type: None,
+            truthy_conditions: false
+        }
+    }
+
+    // Must match the mode used to parse the program
+    pub fn set_truthy_conditions(&mut self, truthy_conditions: bool) {
+        self.truthy_conditions = truthy_conditions;
+    }
+
+    // Check the whole program and build its typed AST
+    pub fn check<'a>(mut self) -> Result<TypedProgram<'p>, ParserError<'a>> {
+        let mut constants = Vec::with_capacity(self.program.constants().len());
+        for constant in self.program.constants() {
+            constants.push(self.check_declaration(constant)?);
+        }
+
+        let mut functions = Vec::with_capacity(self.program.functions().len());
+        for function in self.program.functions() {
+            functions.push(self.check_function(function)?);
+        }
+
+        Ok(TypedProgram {
+            constants,
+            functions
+        })
+    }
+
+    fn get_function<'a>(&self, id: IdentifierType) -> Result<Function<'p>, ParserError<'a>> {
+        let natives = self.environment.get_functions();
+        let id = id as usize;
+        match natives.get(id) {
+            Some(f) => Ok(Function::Native(f)),
+            None => self.program.functions()
+                .get(id - natives.len())
+                .map(Function::Program)
+                .ok_or(ParserError::FunctionNotFound)
+        }
+    }
+
+    fn check_function<'a>(&mut self, function: &'p FunctionType) -> Result<TypedFunction<'p>, ParserError<'a>> {
+        self.variables.clear();
+        self.return_type = function.return_type().clone();
+
+        if let (FunctionType::Declared(f), Some(id)) = (function, function.get_instance_name()) {
+            let for_type = f.get_on_type().clone().ok_or(ParserError::NoValueType)?;
+            self.variables.insert(*id, for_type);
+        }
+
+        let mut default_values = Vec::with_capacity(function.get_parameters().len());
+        for param in function.get_parameters() {
+            let default_value = match param.get_default_value() {
+                Some(expr) => {
+                    let typed = self.check_expression(expr, None)?;
+                    Self::check_assignable(typed.expr_type.as_ref(), param.get_type())?;
+                    Some(typed)
+                },
+                None => None
+            };
+            default_values.push(default_value);
+            self.variables.insert(*param.get_name(), param.get_type().clone());
+        }
+
+        let statements = self.check_statements(function.get_statements())?;
+        Ok(TypedFunction {
+            function,
+            default_values,
+            statements
+        })
+    }
+
+    // Verify that a value of the given type (None for null) can be stored in the expected type
+    fn check_assignable<'a>(value_type: Option<&Type>, expected: &Type) -> Result<(), ParserError<'a>> {
+        match value_type {
+            Some(t) if !t.is_compatible_with(expected) => Err(ParserError::InvalidValueType(t.clone(), expected.clone())),
+            None if !expected.allow_null() => Err(ParserError::IncompatibleNullWith(expected.clone())),
+            _ => Ok(())
+        }
+    }
+
+    fn check_declaration<'a>(&mut self, declaration: &'p DeclarationStatement) -> Result<TypedExpression<'p>, ParserError<'a>> {
+        let value = self.check_expression(&declaration.value, None)?;
+        // A declaration without value is set to null, its initialization is verified separately
+        if let Some(t) = &value.expr_type {
+            Self::check_assignable(Some(t), &declaration.value_type)?;
+        }
+        self.variables.insert(declaration.id, declaration.value_type.clone());
+
+        Ok(value)
+    }
+
+    fn check_condition<'a>(&mut self, condition: &'p Expression) -> Result<TypedExpression<'p>, ParserError<'a>> {
+        let typed = self.check_expression(condition, None)?;
+        if !self.truthy_conditions && typed.expr_type != Some(Type::Bool) {
+            return Err(ParserError::InvalidCondition(Type::Bool, condition.clone()))
+        }
+
+        Ok(typed)
+    }
+
+    fn check_statements<'a>(&mut self, statements: &'p [Statement]) -> Result<Vec<TypedStatement<'p>>, ParserError<'a>> {
+        let mut typed = Vec::with_capacity(statements.len());
+        for statement in statements {
+            typed.push(self.check_statement(statement)?);
+        }
+
+        Ok(typed)
+    }
+
+    fn check_statement<'a>(&mut self, statement: &'p Statement) -> Result<TypedStatement<'p>, ParserError<'a>> {
+        Ok(match statement {
+            Statement::If(condition, statements, else_statements) => {
+                let condition = self.check_condition(condition)?;
+                let statements = self.check_statements(statements)?;
+                let else_statements = match else_statements {
+                    Some(else_statements) => Some(self.check_statements(else_statements)?),
+                    None => None
+                };
+                TypedStatement::If(condition, statements, else_statements)
+            },
+            Statement::While(condition, statements) => {
+                let condition = self.check_condition(condition)?;
+                TypedStatement::While(condition, self.check_statements(statements)?)
+            },
+            Statement::ForEach(id, expr, statements) => {
+                let typed = self.check_expression(expr, None)?;
+                let expr_type = typed.expr_type.clone().ok_or(ParserError::NoValueType)?;
+                if !expr_type.is_iterable() {
+                    return Err(ParserError::NotIterable(expr_type))
+                }
+                self.variables.insert(*id, expr_type.get_inner_type().clone());
+                TypedStatement::ForEach(*id, typed, self.check_statements(statements)?)
+            },
+            Statement::For(declaration, condition, increment, statements) => {
+                let value = self.check_declaration(declaration)?;
+                let condition = self.check_condition(condition)?;
+                let increment = self.check_expression(increment, None)?;
+                TypedStatement::For(declaration.id, value, condition, increment, self.check_statements(statements)?)
+            },
+            Statement::Expression(expr) => TypedStatement::Expression(self.check_expression(expr, None)?),
+            Statement::Return(expr) => match (expr, self.return_type.clone()) {
+                (Some(expr), Some(return_type)) => {
+                    let typed = self.check_expression(expr, None)?;
+                    Self::check_assignable(typed.expr_type.as_ref(), &return_type)?;
+                    TypedStatement::Return(Some(typed))
+                },
+                (None, None) => TypedStatement::Return(None),
+                (Some(expr), None) => {
+                    let typed = self.check_expression(expr, None)?;
+                    return Err(ParserError::UnexpectedType(typed.expr_type.unwrap_or(Type::Any)))
+                },
+                (None, Some(_)) => return Err(ParserError::NoValueType)
+            },
+            Statement::Scope(statements) => TypedStatement::Scope(self.check_statements(statements)?),
+            Statement::Break(depth) => TypedStatement::Break(*depth),
+            Statement::Continue(depth) => TypedStatement::Continue(*depth),
+            Statement::Variable(declaration) => TypedStatement::Variable(declaration.id, self.check_declaration(declaration)?),
+            Statement::TupleDeconstruction(variables, expr) => {
+                let typed = self.check_expression(expr, None)?;
+                let expr_type = Self::value_type(&typed)?;
+                match &expr_type {
+                    Type::Tuple(types) if types.len() == variables.len() => {
+                        for ((id, var_type), value_type) in variables.iter().zip(types) {
+                            Self::check_assignable(Some(value_type), var_type)?;
+                            self.variables.insert(*id, var_type.clone());
+                        }
+                    },
+                    _ => return Err(ParserError::InvalidTupleDeconstruction(expr_type, variables.len()))
+                };
+
+                TypedStatement::TupleDeconstruction(variables.iter().map(|(id, _)| *id).collect(), typed)
+            },
+        })
+    }
+
+    // Get the type of an already typed expression, it must produce a value
+    fn value_type<'a>(typed: &TypedExpression) -> Result<Type, ParserError<'a>> {
+        typed.expr_type.clone().ok_or(ParserError::EmptyValue)
+    }
+
+    // Infer and validate the type of an expression
+    // on_type is the type of the left side of a path (struct.field or value.method())
+    fn check_expression<'a>(&mut self, expression: &'p Expression, on_type: Option<&Type>) -> Result<TypedExpression<'p>, ParserError<'a>> {
+        let (expr_type, children) = match expression {
+            // The signature of a function value is only known by its declaration
+            Expression::Value(Value::Function(id)) => {
+                let f = self.get_function(*id)?;
+                let return_type = f.return_type().ok_or(ParserError::FunctionNoReturnType)?;
+                let parameters = match f {
+                    Function::Native(f) => f.get_parameters().clone(),
+                    Function::Program(f) => f.get_parameters().iter().map(|p| p.get_type().clone()).collect()
+                };
+                (Some(Type::Function(parameters, Box::new(return_type.clone()))), Vec::new())
+            },
+            Expression::Value(value) => (Type::from_value(value), Vec::new()),
+            Expression::Variable(id) => {
+                let expr_type = match on_type {
+                    Some(Type::Struct(_type)) => _type.fields().get(*id as usize).cloned(),
+                    Some(Type::Tuple(types)) => types.get(*id as usize).cloned(),
+                    Some(_) => None,
+                    None => self.variables.get(id).cloned()
+                };
+                (Some(expr_type.ok_or(ParserError::UnexpectedMappedVariableId(*id))?), Vec::new())
+            },
+            Expression::FunctionCall(path, id, parameters) => self.check_function_call(path.as_deref(), *id, parameters, on_type)?,
+            Expression::ArrayCall(array, index) => {
+                let array = self.check_expression(array, on_type)?;
+                let index = self.check_expression(index, None)?;
+                let index_type = Self::value_type(&index)?;
+                if !index_type.is_number() {
+                    return Err(ParserError::InvalidArrayCallIndexType(index_type))
+                }
+
+                let expr_type = match Self::value_type(&array)? {
+                    Type::Array(inner) => *inner,
+                    _ => return Err(ParserError::InvalidArrayCall)
+                };
+                (Some(expr_type), vec![array, index])
+            },
+            Expression::ArrayConstructor(values) => {
+                let mut children = Vec::with_capacity(values.len());
+                for value in values {
+                    children.push(self.check_expression(value, None)?);
+                }
+
+                // An empty array takes the type of its destination
+                let expected = match children.first() {
+                    Some(first) => Self::value_type(first)?,
+                    None => Type::Any
+                };
+                for child in children.iter().skip(1) {
+                    let value_type = Self::value_type(child)?;
+                    if !value_type.is_compatible_with(&expected) {
+                        return Err(ParserError::InvalidTypeInArray(value_type, expected))
+                    }
+                }
+                (Some(Type::Array(Box::new(expected))), children)
+            },
+            Expression::StructConstructor(values, _type) => {
+                if values.len() != _type.fields().len() {
+                    return Err(ParserError::InvalidStructFieldOrder)
+                }
+
+                let mut children = Vec::with_capacity(values.len());
+                for (value, field_type) in values.iter().zip(_type.fields()) {
+                    let typed = self.check_expression(value, None)?;
+                    Self::check_assignable(typed.expr_type.as_ref(), field_type)?;
+                    children.push(typed);
+                }
+                (Some(Type::Struct(_type.clone())), children)
+            },
+            Expression::TupleConstructor(values) => {
+                let mut children = Vec::with_capacity(values.len());
+                let mut types = Vec::with_capacity(values.len());
+                for value in values {
+                    let typed = self.check_expression(value, None)?;
+                    types.push(Self::value_type(&typed)?);
+                    children.push(typed);
+                }
+                (Some(Type::Tuple(types)), children)
+            },
+            Expression::Operator(op, left, right) => self.check_operator(op, left, right, on_type)?,
+            Expression::SubExpression(expr) => {
+                let typed = self.check_expression(expr, on_type)?;
+                (typed.expr_type.clone(), vec![typed])
+            },
+            Expression::Path(left, right) => {
+                let left = self.check_expression(left, on_type)?;
+                let left_type = Self::value_type(&left)?;
+                let right = self.check_expression(right, Some(&left_type))?;
+                (right.expr_type.clone(), vec![left, right])
+            },
+            Expression::StructUpdate(expr, fields) => {
+                let base = self.check_expression(expr, on_type)?;
+                let base_type = Self::value_type(&base)?;
+                let Type::Struct(struct_type) = &base_type else {
+                    return Err(ParserError::UnexpectedType(base_type))
+                };
+
+                let mut children = vec![base];
+                for (id, value) in fields {
+                    let field_type = struct_type.fields().get(*id as usize)
+                        .ok_or_else(|| ParserError::StructureFieldNotFound(id.to_string()))?;
+                    let typed = self.check_expression(value, None)?;
+                    let value_type = Self::value_type(&typed)?;
+                    if !field_type.is_compatible_with(&value_type) {
+                        return Err(ParserError::InvalidValueType(value_type, field_type.clone()))
+                    }
+                    children.push(typed);
+                }
+                (Some(base_type), children)
+            },
+            Expression::OptionalPath(left, right) => {
+                let left = self.check_expression(left, on_type)?;
+                let left_type = Self::value_type(&left)?;
+                if !left_type.is_optional() {
+                    return Err(ParserError::InvalidOptionalChaining(left_type))
+                }
+                let right = self.check_expression(right, Some(left_type.get_inner_type()))?;
+                let right_type = Self::value_type(&right)?;
+                (Some(optional_of(right_type)), vec![left, right])
+            },
+            Expression::IsNot(expr) => {
+                let typed = self.check_expression(expr, on_type)?;
+                let expr_type = Self::value_type(&typed)?;
+                if expr_type != Type::Bool {
+                    return Err(ParserError::InvalidValueType(expr_type, Type::Bool))
+                }
+                (Some(Type::Bool), vec![typed])
+            },
+            Expression::BitwiseNot(expr) => {
+                let typed = self.check_expression(expr, on_type)?;
+                let expr_type = Self::value_type(&typed)?;
+                if !expr_type.is_number() {
+                    return Err(ParserError::InvalidOperationNotNumberType(expr_type))
+                }
+                (Some(expr_type), vec![typed])
+            },
+            Expression::Ternary(condition, left, right) => {
+                let condition = self.check_condition(condition)?;
+                let left = self.check_expression(left, on_type)?;
+                let right = self.check_expression(right, on_type)?;
+                let left_type = Self::value_type(&left)?;
+                let right_type = Self::value_type(&right)?;
+                if left_type != right_type {
+                    return Err(ParserError::InvalidValueType(right_type, left_type))
+                }
+                (Some(left_type), vec![condition, left, right])
+            },
+            Expression::Cast(expr, _type) => {
+                let typed = self.check_expression(expr, on_type)?;
+                let expr_type = Self::value_type(&typed)?;
+                if !expr_type.is_castable_to(_type) {
+                    return Err(ParserError::CastError(expr_type, _type.clone()))
+                }
+                (Some(_type.clone()), vec![typed])
+            },
+            Expression::Range(start, end) => {
+                let start = self.check_expression(start, on_type)?;
+                let end = self.check_expression(end, on_type)?;
+                let start_type = Self::value_type(&start)?;
+                let end_type = Self::value_type(&end)?;
+                if start_type != end_type {
+                    return Err(ParserError::InvalidRangeType(start_type, end_type))
+                }
+                if !start_type.is_number() {
+                    return Err(ParserError::InvalidRangeTypePrimitive(start_type))
+                }
+                (Some(Type::Range(Box::new(start_type))), vec![start, end])
+            },
+            Expression::Block(statements, value, value_type) => {
+                let statements = self.check_statements(statements)?;
+                let value = self.check_expression(value, None)?;
+                Self::check_assignable(value.expr_type.as_ref(), value_type)?;
+
+                let mut typed = TypedExpression::new(expression, Some(value_type.clone()), vec![value]);
+                typed.statements = statements;
+                return Ok(typed)
+            },
+            Expression::Try(expr) => {
+                let typed = self.check_expression(expr, on_type)?;
+                let expr_type = Self::value_type(&typed)?;
+                if !expr_type.is_result() {
+                    return Err(ParserError::InvalidTryOperator(expr_type))
+                }
+                // the error is returned by the current function
+                match &self.return_type {
+                    Some(t) if t.is_result() => {},
+                    _ => return Err(ParserError::InvalidTryOperator(expr_type))
+                };
+                (Some(expr_type.get_inner_type().clone()), vec![typed])
+            },
+            Expression::ValueCall(function, parameters) => {
+                let function = self.check_expression(function, on_type)?;
+                let (parameters_types, return_type) = match Self::value_type(&function)? {
+                    Type::Function(parameters_types, return_type) => (parameters_types, *return_type),
+                    _type => return Err(ParserError::ExpectedFunctionType(_type))
+                };
+
+                if parameters.len() != parameters_types.len() {
+                    return Err(ParserError::InvalidValueCallArgumentsCount(parameters_types.len()))
+                }
+
+                let mut children = Vec::with_capacity(parameters.len() + 1);
+                children.push(function);
+                for (param, param_type) in parameters.iter().zip(parameters_types.iter()) {
+                    let typed = self.check_expression(param, None)?;
+                    Self::check_assignable(typed.expr_type.as_ref(), param_type)?;
+                    children.push(typed);
+                }
+                (Some(return_type), children)
+            }
+        };
+
+        Ok(TypedExpression::new(expression, expr_type, children))
+    }
+
+    fn check_operator<'a>(&mut self, op: &Operator, left: &'p Expression, right: &'p Expression, on_type: Option<&Type>) -> Result<(Option<Type>, Vec<TypedExpression<'p>>), ParserError<'a>> {
+        let left = self.check_expression(left, on_type)?;
+        let right = self.check_expression(right, on_type)?;
+
+        let expr_type = match op {
+            Operator::Assign(op) => {
+                let left_type = Self::value_type(&left)?;
+                match op {
+                    None => Self::check_assignable(right.expr_type.as_ref(), &left_type)?,
+                    Some(op) => Self::check_binary(op, left_type, Self::value_type(&right)?)
+                        .map(|_| ())?
+                };
+                None
+            },
+            Operator::Equals | Operator::NotEquals => {
+                match (&left.expr_type, &right.expr_type) {
+                    (Some(l), Some(r)) => if !l.is_compatible_with(r) && !r.is_compatible_with(l) {
+                        return Err(ParserError::InvalidOperationNotSameType(l.clone(), r.clone()))
+                    },
+                    (Some(t), None) | (None, Some(t)) => if !t.allow_null() {
+                        return Err(ParserError::IncompatibleNullWith(t.clone()))
+                    },
+                    (None, None) => return Err(ParserError::EmptyValue)
+                };
+                Some(Type::Bool)
+            },
+            op => Some(Self::check_binary(op, Self::value_type(&left)?, Self::value_type(&right)?)?)
+        };
+
+        Ok((expr_type, vec![left, right]))
+    }
+
+    // Check a binary operator between two values and returns the type produced
+    fn check_binary<'a>(op: &Operator, left: Type, right: Type) -> Result<Type, ParserError<'a>> {
+        Ok(match op {
+            Operator::And | Operator::Or => {
+                if left != Type::Bool {
+                    return Err(ParserError::InvalidOperationNotSameType(left, Type::Bool))
+                }
+                if right != Type::Bool {
+                    return Err(ParserError::InvalidOperationNotSameType(right, Type::Bool))
+                }
+                Type::Bool
+            },
+            Operator::Plus if left == Type::String || right == Type::String => Type::String,
+            Operator::Plus if left.is_array() && right.is_array() => {
+                if !right.is_compatible_with(&left) {
+                    return Err(ParserError::InvalidOperationNotSameType(left, right))
+                }
+                left
+            },
+            Operator::Multiply if left.is_array() => {
+                if !right.is_number() {
+                    return Err(ParserError::ExpectedNumberType(right))
+                }
+                left
+            },
+            Operator::BitwiseAnd
+            | Operator::BitwiseOr
+            | Operator::BitwiseXor if left == Type::Bool && right == Type::Bool => Type::Bool,
+            _ => {
+                if !left.is_number() || left != right {
+                    return Err(ParserError::InvalidOperationNotSameType(left, right))
+                }
+
+                match op {
+                    Operator::GreaterThan
+                    | Operator::GreaterOrEqual
+                    | Operator::LessThan
+                    | Operator::LessOrEqual => Type::Bool,
+                    _ => left
+                }
+            }
+        })
+    }
+
+    fn check_function_call<'a>(&mut self, path: Option<&'p Expression>, id: IdentifierType, parameters: &'p [Expression], on_type: Option<&Type>) -> Result<(Option<Type>, Vec<TypedExpression<'p>>), ParserError<'a>> {
+        let f = self.get_function(id)?;
+        let mut children = Vec::with_capacity(parameters.len() + 1);
+
+        // Type of the value on which the function is called
+        let instance_type = match path {
+            Some(path) => {
+                let typed = self.check_expression(path, on_type)?;
+                let instance_type = Self::value_type(&typed)?;
+                children.push(typed);
+                Some(instance_type)
+            },
+            None => on_type.cloned()
+        };
+
+        match (f.for_type(), &instance_type) {
+            (Some(for_type), Some(instance_type)) => if !instance_type.is_compatible_with(for_type) {
+                return Err(ParserError::InvalidFunctionType(instance_type.clone()))
+            },
+            (None, None) => {},
+            (_, Some(instance_type)) => return Err(ParserError::InvalidFunctionType(instance_type.clone())),
+            (Some(_), None) => return Err(ParserError::FunctionNotFound)
+        };
+
+        let mut params = Vec::with_capacity(parameters.len());
+        for param in parameters {
+            params.push(self.check_expression(param, None)?);
+        }
+
+        // Type bound to T, from the instance first, then from the parameters
+        let mut generic_type = instance_type.as_ref()
+            .map(|t| f.for_type().and_then(|for_type| for_type.find_generic_type(t)).unwrap_or_else(|| t.get_inner_type().clone()));
+
+        match f {
+            Function::Native(native) => {
+                if native.get_parameters().len() != params.len() {
+                    return Err(ParserError::FunctionNotFound)
+                }
+
+                for (param_type, typed) in native.get_parameters().iter().zip(params.iter()) {
+                    if let Some(t) = typed.expr_type.as_ref() {
+                        if !param_type.unify_generic_type(t, &mut generic_type) {
+                            return Err(ParserError::InvalidValueType(t.clone(), param_type.clone()))
+                        }
+                    }
+                }
+
+                for (param_type, typed) in native.get_parameters().iter().zip(params.iter()) {
+                    let expected = match &generic_type {
+                        Some(t) => param_type.with_generic_type(t),
+                        None => param_type.clone()
+                    };
+                    Self::check_assignable(typed.expr_type.as_ref(), &expected)?;
+                }
+            },
+            Function::Program(function) => {
+                let declared = function.get_parameters();
+                if params.len() > declared.len() {
+                    return Err(ParserError::FunctionNotFound)
+                }
+
+                for (param, typed) in declared.iter().zip(params.iter()) {
+                    Self::check_assignable(typed.expr_type.as_ref(), param.get_type())?;
+                }
+
+                // omitted arguments must have a default value
+                if declared.iter().skip(params.len()).any(|p| p.get_default_value().is_none()) {
+                    return Err(ParserError::FunctionNotFound)
+                }
+            }
+        };
+
+        let return_type = match (f.return_type(), &generic_type) {
+            (Some(t), Some(generic)) if t.is_generic() => Some(t.with_generic_type(generic)),
+            (Some(t), None) if t.is_generic() => return Err(ParserError::InvalidTypeT),
+            (t, _) => t.cloned()
+        };
+
+        children.extend(params);
+        Ok((return_type, children))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+    use xelis_ast::{EntryFunction, Token};
+    use xelis_builder::EnvironmentBuilder;
+    use xelis_types::Value;
+    use crate::Parser;
+    use super::*;
+
+    fn entry_program(statements: Vec<Statement>) -> Program {
+        let mut program = Program::new();
+        program.add_function(FunctionType::Entry(EntryFunction::new(Vec::new(), statements, 1)));
+        program
+    }
+
+    fn declaration(value_type: Type, value: Expression) -> Statement {
+        Statement::Variable(DeclarationStatement {
+            id: 0,
+            value_type,
+            value,
+            mutable: false
+        })
+    }
+
+    fn check(program: &Program) -> Result<(), String> {
+        let env = EnvironmentBuilder::default();
+        TypeChecker::new(program, env.environment())
+            .check()
+            .map(|_| ())
+            .map_err(|e| format!("{:?}", e))
+    }
+
+    #[test]
+    fn test_binary_operation_not_same_type() {
+        // let a: u64 = 10 - true; return 0
+        let program = entry_program(vec![
+            declaration(Type::U64, Expression::Operator(
+                Operator::Minus,
+                Box::new(Expression::Value(Value::U64(10))),
+                Box::new(Expression::Value(Value::Boolean(true)))
+            )),
+            Statement::Return(Some(Expression::Value(Value::U64(0))))
+        ]);
+
+        assert_eq!(check(&program).unwrap_err(), "InvalidOperationNotSameType(U64, Bool)");
+    }
+
+    #[test]
+    fn test_wrong_typed_assignment() {
+        // let a: u64 = "hello"; return 0
+        let program = entry_program(vec![
+            declaration(Type::U64, Expression::Value(Value::String("hello".to_owned()))),
+            Statement::Return(Some(Expression::Value(Value::U64(0))))
+        ]);
+        assert_eq!(check(&program).unwrap_err(), "InvalidValueType(String, U64)");
+
+        // let a: u64 = 10; a = "hello"; return a
+        let program = entry_program(vec![
+            declaration(Type::U64, Expression::Value(Value::U64(10))),
+            Statement::Expression(Expression::Operator(
+                Operator::Assign(None),
+                Box::new(Expression::Variable(0)),
+                Box::new(Expression::Value(Value::String("hello".to_owned())))
+            )),
+            Statement::Return(Some(Expression::Variable(0)))
+        ]);
+        assert_eq!(check(&program).unwrap_err(), "InvalidValueType(String, U64)");
+    }
+
+    #[test]
+    fn test_typed_program() {
+        // fn add(a: u64, b: u64) -> u64 { return a + b }
+        // entry main() { let values: u64[] = [1, 2]; return add(values[0], values.len() as u64) }
+        let tokens = vec![
+            Token::Function,
+            Token::Identifier("add"),
+            Token::ParenthesisOpen,
+            Token::Identifier("a"),
+            Token::Colon,
+            Token::Number(xelis_ast::NumberType::U64),
+            Token::Comma,
+            Token::Identifier("b"),
+            Token::Colon,
+            Token::Number(xelis_ast::NumberType::U64),
+            Token::ParenthesisClose,
+            Token::ReturnType,
+            Token::Number(xelis_ast::NumberType::U64),
+            Token::BraceOpen,
+            Token::Return,
+            Token::Identifier("a"),
+            Token::OperatorPlus,
+            Token::Identifier("b"),
+            Token::BraceClose,
+
+            Token::Entry,
+            Token::Identifier("main"),
+            Token::ParenthesisOpen,
+            Token::ParenthesisClose,
+            Token::BraceOpen,
+            Token::Let,
+            Token::Identifier("values"),
+            Token::Colon,
+            Token::Number(xelis_ast::NumberType::U64),
+            Token::BracketOpen,
+            Token::BracketClose,
+            Token::OperatorAssign,
+            Token::BracketOpen,
+            Token::Value(xelis_ast::Literal::Number(1)),
+            Token::Comma,
+            Token::Value(xelis_ast::Literal::Number(2)),
+            Token::BracketClose,
+            Token::Return,
+            Token::Identifier("add"),
+            Token::ParenthesisOpen,
+            Token::Identifier("values"),
+            Token::BracketOpen,
+            Token::Value(xelis_ast::Literal::Number(0)),
+            Token::BracketClose,
+            Token::Comma,
+            Token::Identifier("values"),
+            Token::Dot,
+            Token::Identifier("len"),
+            Token::ParenthesisOpen,
+            Token::ParenthesisClose,
+            Token::As,
+            Token::Number(xelis_ast::NumberType::U64),
+            Token::ParenthesisClose,
+            Token::BraceClose,
+        ];
+
+        let env = EnvironmentBuilder::default();
+        let (program, _) = Parser::new(VecDeque::from(tokens), &env).parse().unwrap();
+        let typed = TypeChecker::new(&program, env.environment()).check().unwrap();
+
+        let TypedStatement::Variable(_, values) = &typed.functions[1].statements[0] else {
+            panic!("expected a variable declaration")
+        };
+        assert_eq!(values.expr_type, Some(Type::Array(Box::new(Type::U64))));
+
+        let TypedStatement::Return(Some(call)) = &typed.functions[1].statements[1] else {
+            panic!("expected a return")
+        };
+        assert_eq!(call.expr_type, Some(Type::U64));
+        assert_eq!(call.children.len(), 2);
+        assert_eq!(call.children[1].expr_type, Some(Type::U64));
+        assert_eq!(call.children[1].children[0].expr_type, Some(Type::U32));
+    }
+
+    #[test]
+    fn test_truthy_condition() {
+        let code = "entry main() { let a: u64[] = [1]; if a { return 1 } return 0 }";
+        let env = EnvironmentBuilder::default();
+        let mut parser = Parser::new(xelis_lexer::Lexer::new(code).get().unwrap(), &env);
+        parser.set_truthy_conditions(true);
+        let (program, _) = parser.parse().unwrap();
+
+        // Rejected in the strict mode
+        assert!(check(&program).unwrap_err().contains("InvalidCondition"));
+
+        let mut checker = TypeChecker::new(&program, env.environment());
+        checker.set_truthy_conditions(true);
+        assert!(checker.check().is_ok());
+    }
+
+    #[test]
+    fn test_typed_function_value() {
+        let code = "fn double(a: u64) -> u64 { return a * 2 } entry main() { let f: fn(u64) -> u64 = double; return f(21) }";
+        let env = EnvironmentBuilder::default();
+        let tokens = xelis_lexer::Lexer::new(code).get().unwrap();
+        let (program, _) = Parser::new(tokens, &env).parse().unwrap();
+        let typed = TypeChecker::new(&program, env.environment()).check().unwrap();
+
+        let function_type = Type::Function(vec![Type::U64], Box::new(Type::U64));
+        let TypedStatement::Variable(_, value) = &typed.functions[1].statements[0] else {
+            panic!("expected a variable declaration")
+        };
+        assert_eq!(value.expr_type, Some(function_type.clone()));
+
+        let TypedStatement::Return(Some(call)) = &typed.functions[1].statements[1] else {
+            panic!("expected a return")
+        };
+        assert_eq!(call.expr_type, Some(Type::U64));
+        assert_eq!(call.children[0].expr_type, Some(function_type));
+
+        // Calling a value that isn't a function
+        let program = entry_program(vec![Statement::Return(Some(Expression::ValueCall(
+            Box::new(Expression::Value(Value::U64(1))),
+            Vec::new()
+        )))]);
+        assert!(check(&program).unwrap_err().contains("ExpectedFunctionType"));
+    }
+}
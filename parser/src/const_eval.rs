@@ -0,0 +1,246 @@
+use xelis_ast::{Expression, Operator};
+use xelis_types::{NoHashMap, Type, Value, ValueOwnable, U256};
+
+use crate::ParserError;
+
+// Apply a checked operation on two numbers of the same type
+macro_rules! checked_op {
+    ($left: expr, $right: expr, $op: ident) => {
+        match ($left, $right) {
+            (Value::U8(a), Value::U8(b)) => a.$op(*b).map(Value::U8),
+            (Value::U16(a), Value::U16(b)) => a.$op(*b).map(Value::U16),
+            (Value::U32(a), Value::U32(b)) => a.$op(*b).map(Value::U32),
+            (Value::U64(a), Value::U64(b)) => a.$op(*b).map(Value::U64),
+            (Value::U128(a), Value::U128(b)) => a.$op(*b).map(Value::U128),
+            (Value::U256(a), Value::U256(b)) => a.$op(*b).map(Value::U256),
+            _ => None
+        }
+    };
+}
+
+// Shift a number by a value of the same type
+// The shift is rejected if it's greater or equal to the bits count
+macro_rules! checked_shift {
+    ($left: expr, $right: expr, $op: ident, $shift: tt) => {
+        match ($left, $right) {
+            (Value::U8(a), Value::U8(b)) => a.$op(*b as u32).map(Value::U8),
+            (Value::U16(a), Value::U16(b)) => a.$op(*b as u32).map(Value::U16),
+            (Value::U32(a), Value::U32(b)) => a.$op(*b).map(Value::U32),
+            (Value::U64(a), Value::U64(b)) => u32::try_from(*b).ok().and_then(|b| a.$op(b)).map(Value::U64),
+            (Value::U128(a), Value::U128(b)) => u32::try_from(*b).ok().and_then(|b| a.$op(b)).map(Value::U128),
+            (Value::U256(a), Value::U256(b)) => if *b < U256::from(256u32) {
+                Some(Value::U256(*a $shift (b.low_u64() as u32)))
+            } else {
+                None
+            },
+            _ => None
+        }
+    };
+}
+
+// Apply a bitwise operation on two numbers or two booleans
+macro_rules! bitwise_op {
+    ($left: expr, $right: expr, $op: tt) => {
+        match ($left, $right) {
+            (Value::U8(a), Value::U8(b)) => Some(Value::U8(a $op b)),
+            (Value::U16(a), Value::U16(b)) => Some(Value::U16(a $op b)),
+            (Value::U32(a), Value::U32(b)) => Some(Value::U32(a $op b)),
+            (Value::U64(a), Value::U64(b)) => Some(Value::U64(a $op b)),
+            (Value::U128(a), Value::U128(b)) => Some(Value::U128(a $op b)),
+            (Value::U256(a), Value::U256(b)) => Some(Value::U256(*a $op *b)),
+            (Value::Boolean(a), Value::Boolean(b)) => Some(Value::Boolean(a $op b)),
+            _ => None
+        }
+    };
+}
+
+// Evaluate a pure constant expression at parse time
+// Only literals, operators on numbers, booleans and string concatenation,
+// casts, ternaries and array/struct constructors of constants are accepted
+pub fn const_eval<'a>(expr: &Expression) -> Result<Value, ParserError<'a>> {
+    const_eval_with(expr, &NoHashMap::default())
+}
+
+// Same as const_eval, but variables can reference the already evaluated constants
+pub fn const_eval_with<'a>(expr: &Expression, constants: &NoHashMap<Value>) -> Result<Value, ParserError<'a>> {
+    Ok(match expr {
+        Expression::Value(v) => v.clone(),
+        Expression::Variable(id) => constants.get(id)
+            .cloned()
+            .ok_or_else(|| ParserError::NotConstantExpression(expr.clone()))?,
+        Expression::SubExpression(expr) => const_eval_with(expr, constants)?,
+        Expression::IsNot(expr) => Value::Boolean(!const_eval_with(expr, constants)?.as_bool()?),
+        Expression::BitwiseNot(expr) => const_eval_with(expr, constants)?.bitwise_not()?,
+        Expression::Ternary(condition, left, right) => if const_eval_with(condition, constants)?.as_bool()? {
+            const_eval_with(left, constants)?
+        } else {
+            const_eval_with(right, constants)?
+        },
+        Expression::Cast(expr, cast_type) => {
+            let value = const_eval_with(expr, constants)?;
+            match cast_type {
+                Type::U8 => Value::U8(value.cast_to_u8()?),
+                Type::U16 => Value::U16(value.cast_to_u16()?),
+                Type::U32 => Value::U32(value.cast_to_u32()?),
+                Type::U64 => Value::U64(value.cast_to_u64()?),
+                Type::U128 => Value::U128(value.cast_to_u128()?),
+                Type::U256 => Value::U256(value.cast_to_u256()?),
+                Type::String => Value::String(value.cast_to_string()?),
+                _ => return Err(ParserError::NotConstantExpression(expr.as_ref().clone()))
+            }
+        },
+        Expression::ArrayConstructor(values) => Value::Array(const_eval_values(values, constants)?),
+        Expression::StructConstructor(fields, struct_type) => Value::Struct(const_eval_values(fields, constants)?, struct_type.clone()),
+        Expression::Operator(op, left, right) => {
+            let left = const_eval_with(left, constants)?;
+            // Keep the short-circuit of the logical operators
+            match (op, &left) {
+                (Operator::And, Value::Boolean(false)) => return Ok(Value::Boolean(false)),
+                (Operator::Or, Value::Boolean(true)) => return Ok(Value::Boolean(true)),
+                _ => {}
+            };
+
+            let right = const_eval_with(right, constants)?;
+            const_eval_operator(op, &left, &right)?
+        },
+        expr => return Err(ParserError::NotConstantExpression(expr.clone()))
+    })
+}
+
+fn const_eval_values<'a>(expressions: &[Expression], constants: &NoHashMap<Value>) -> Result<Vec<ValueOwnable>, ParserError<'a>> {
+    expressions.iter()
+        .map(|expr| const_eval_with(expr, constants).map(|v| ValueOwnable::Owned(Box::new(v))))
+        .collect()
+}
+
+fn const_eval_operator<'a>(op: &Operator, left: &Value, right: &Value) -> Result<Value, ParserError<'a>> {
+    let value = match op {
+        Operator::Equals => Some(Value::Boolean(left.is_equal(right)?)),
+        Operator::NotEquals => Some(Value::Boolean(!left.is_equal(right)?)),
+        Operator::Plus if left.is_string() || right.is_string() => Some(Value::String(format!("{}{}", left, right))),
+        Operator::Plus => checked_op!(left, right, checked_add),
+        Operator::Minus => checked_op!(left, right, checked_sub),
+        Operator::Multiply => checked_op!(left, right, checked_mul),
+        Operator::Divide => checked_op!(left, right, checked_div),
+        Operator::Rem => checked_op!(left, right, checked_rem),
+        Operator::BitwiseLeft => checked_shift!(left, right, checked_shl, <<),
+        Operator::BitwiseRight => checked_shift!(left, right, checked_shr, >>),
+        Operator::BitwiseAnd => bitwise_op!(left, right, &),
+        Operator::BitwiseOr => bitwise_op!(left, right, |),
+        Operator::BitwiseXor => bitwise_op!(left, right, ^),
+        Operator::And | Operator::Or => Some(Value::Boolean(right.as_bool()?)),
+        Operator::GreaterThan => left.partial_cmp(right).map(|o| Value::Boolean(o.is_gt())),
+        Operator::GreaterOrEqual => left.partial_cmp(right).map(|o| Value::Boolean(o.is_ge())),
+        Operator::LessThan => left.partial_cmp(right).map(|o| Value::Boolean(o.is_lt())),
+        Operator::LessOrEqual => left.partial_cmp(right).map(|o| Value::Boolean(o.is_le())),
+        Operator::Assign(_) => None
+    };
+
+    value.ok_or_else(|| ParserError::InvalidConstantOperation(op.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+    use xelis_ast::{Literal, NumberType, Token};
+    use xelis_builder::EnvironmentBuilder;
+    use crate::Parser;
+    use super::*;
+
+    fn number(value: u64) -> Box<Expression> {
+        Box::new(Expression::Value(Value::U64(value)))
+    }
+
+    #[test]
+    fn test_const_eval() {
+        // (2 + 3) * 4 == 20
+        let expr = Expression::Operator(
+            Operator::Equals,
+            Box::new(Expression::Operator(Operator::Multiply, Box::new(Expression::SubExpression(Box::new(Expression::Operator(Operator::Plus, number(2), number(3))))), number(4))),
+            number(20)
+        );
+        assert_eq!(const_eval(&expr).unwrap(), Value::Boolean(true));
+
+        // "a" + 1
+        let expr = Expression::Operator(Operator::Plus, Box::new(Expression::Value(Value::String("a".to_owned()))), number(1));
+        assert_eq!(const_eval(&expr).unwrap(), Value::String("a1".to_owned()));
+
+        // Overflow and division by zero are rejected
+        let expr = Expression::Operator(Operator::Plus, number(u64::MAX), number(1));
+        assert!(matches!(const_eval(&expr), Err(ParserError::InvalidConstantOperation(Operator::Plus))));
+        let expr = Expression::Operator(Operator::Divide, number(1), number(0));
+        assert!(matches!(const_eval(&expr), Err(ParserError::InvalidConstantOperation(Operator::Divide))));
+
+        // Unknown variable and function call are not constant
+        assert!(matches!(const_eval(&Expression::Variable(0)), Err(ParserError::NotConstantExpression(_))));
+        assert!(matches!(const_eval(&Expression::FunctionCall(None, 0, Vec::new())), Err(ParserError::NotConstantExpression(_))));
+    }
+
+    // const NAME: u64 = <value tokens>
+    fn constant_tokens<'a>(name: &'a str, value: Vec<Token<'a>>) -> Vec<Token<'a>> {
+        let mut tokens = vec![
+            Token::Const,
+            Token::Identifier(name),
+            Token::Colon,
+            Token::Number(NumberType::U64),
+            Token::OperatorAssign,
+        ];
+        tokens.extend(value);
+        tokens
+    }
+
+    #[test]
+    fn test_foldable_constant() {
+        // const A: u64 = 10 * 3
+        // const B: u64 = A + 2
+        let mut tokens = constant_tokens("A", vec![
+            Token::Value(Literal::U64(10)),
+            Token::OperatorMultiply,
+            Token::Value(Literal::U64(3)),
+        ]);
+        tokens.extend(constant_tokens("B", vec![
+            Token::Identifier("A"),
+            Token::OperatorPlus,
+            Token::Value(Literal::U64(2)),
+        ]));
+
+        let env = EnvironmentBuilder::default();
+        let (program, _) = Parser::new(VecDeque::from(tokens), &env).parse().unwrap();
+        let mut values: Vec<_> = program.constants().iter()
+            .map(|c| (c.id, c.value.clone()))
+            .collect();
+        values.sort_by_key(|(id, _)| *id);
+
+        assert_eq!(values, vec![
+            (0, Expression::Value(Value::U64(30))),
+            (1, Expression::Value(Value::U64(32))),
+        ]);
+    }
+
+    #[test]
+    fn test_non_foldable_constant() {
+        // fn f() -> u64 { return 1 }
+        // const A: u64 = f()
+        let mut tokens = vec![
+            Token::Function,
+            Token::Identifier("f"),
+            Token::ParenthesisOpen,
+            Token::ParenthesisClose,
+            Token::ReturnType,
+            Token::Number(NumberType::U64),
+            Token::BraceOpen,
+            Token::Return,
+            Token::Value(Literal::U64(1)),
+            Token::BraceClose,
+        ];
+        tokens.extend(constant_tokens("A", vec![
+            Token::Identifier("f"),
+            Token::ParenthesisOpen,
+            Token::ParenthesisClose,
+        ]));
+
+        let env = EnvironmentBuilder::default();
+        let err = Parser::new(VecDeque::from(tokens), &env).parse().unwrap_err();
+        assert!(matches!(err, ParserError::NotConstantExpression(Expression::FunctionCall(..))));
+    }
+}
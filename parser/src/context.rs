@@ -3,14 +3,20 @@ use crate::ParserError;
 
 #[derive(Clone, Debug)]
 pub struct Context<'a> {
-    // scopes are used to store variables
-    scopes: Vec<(&'a str, Type)>,
+    // scopes are used to store variables and if they are mutable
+    scopes: Vec<(&'a str, Type, bool)>,
     // checkpoints are used to manage scopes
     checkpoints: Vec<usize>,
     // max variables count for each scope depth
     max_variables_count: usize,
     // is_in_loop is used to allow the use of the break and continue keywords
     is_in_loop: bool,
+    // labels of the loops being read, the innermost loop is the last
+    loop_labels: Vec<Option<&'a str>>,
+    // label declared for the next loop
+    next_loop_label: Option<&'a str>,
+    // return type of the function being parsed, used by the try operator
+    return_type: Option<Type>,
 }
 
 impl<'a> Context<'a> {
@@ -22,6 +28,9 @@ impl<'a> Context<'a> {
             checkpoints: Vec::new(),
             max_variables_count: 0,
             is_in_loop: false,
+            loop_labels: Vec::new(),
+            next_loop_label: None,
+            return_type: None,
         }
     }
 
@@ -32,6 +41,19 @@ impl<'a> Context<'a> {
             .ok_or_else(|| ParserError::UnexpectedMappedVariableId(key.clone()))
     }
 
+    // returns true if this variable can be assigned after its declaration
+    pub fn is_variable_mutable(&self, key: &IdentifierType) -> bool {
+        self.scopes.get(*key as usize)
+            .is_some_and(|v| v.2)
+    }
+
+    // prevent any assignment to this variable
+    pub fn set_variable_immutable(&mut self, key: &IdentifierType) {
+        if let Some(v) = self.scopes.get_mut(*key as usize) {
+            v.2 = false;
+        }
+    }
+
     // returns true if this variable name is registered in scopes
     #[inline]
     pub fn has_variable(&self, key: &str) -> bool {
@@ -44,7 +66,7 @@ impl<'a> Context<'a> {
     }
 
     pub fn get_variable_id(&self, key: &str) -> Option<IdentifierType> {
-        self.scopes.iter().position(|(k, _)| *k == key).map(|v| v as IdentifierType)
+        self.scopes.iter().position(|(k, _, _)| *k == key).map(|v| v as IdentifierType)
     }
 
     // register a variable in the current scope
@@ -57,8 +79,9 @@ impl<'a> Context<'a> {
     }
 
     // register a variable in the current scope unchecked
+    // it is mutable until set as immutable
     pub fn register_variable_unchecked(&mut self, key: &'a str, var_type: Type) -> IdentifierType {
-        self.scopes.push((key, var_type));
+        self.scopes.push((key, var_type, true));
 
         (self.scopes.len() - 1) as IdentifierType
     }
@@ -87,4 +110,53 @@ impl<'a> Context<'a> {
     pub fn set_in_a_loop(&mut self, is_in_loop: bool) {
         self.is_in_loop = is_in_loop;
     }
+
+    // set the label of the next loop read
+    pub fn set_next_loop_label(&mut self, label: &'a str) {
+        self.next_loop_label = Some(label);
+    }
+
+    // returns true if a loop being read has this label
+    pub fn has_loop_label(&self, label: &str) -> bool {
+        self.loop_labels.contains(&Some(label))
+    }
+
+    // enter the body of a loop, it takes the label declared for it
+    pub fn begin_loop(&mut self) {
+        let label = self.next_loop_label.take();
+        self.loop_labels.push(label);
+    }
+
+    // leave the body of a loop
+    pub fn end_loop(&mut self) {
+        self.loop_labels.pop();
+    }
+
+    // count of loops between the innermost loop and the loop with this label
+    pub fn get_loop_depth(&self, label: &str) -> Option<u16> {
+        self.loop_labels.iter()
+            .rev()
+            .position(|l| *l == Some(label))
+            .map(|depth| depth as u16)
+    }
+
+    // take the labels of the loops being read, a block expression can't leave them
+    pub fn take_loop_labels(&mut self) -> Vec<Option<&'a str>> {
+        core::mem::take(&mut self.loop_labels)
+    }
+
+    // restore the labels of the loops being read
+    pub fn set_loop_labels(&mut self, labels: Vec<Option<&'a str>>) {
+        self.loop_labels = labels;
+    }
+
+    // returns the return type of the function being parsed
+    pub fn get_return_type(&self) -> Option<&Type> {
+        self.return_type.as_ref()
+    }
+
+    // set the return type of the function being parsed
+    pub fn set_return_type(&mut self, return_type: Option<Type>) {
+        self.return_type = return_type;
+    }
 }
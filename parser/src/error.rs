@@ -1,5 +1,7 @@
-use xelis_ast::{Expression, Token};
+use std::fmt;
+use xelis_ast::{Expression, Operator, Token};
 use xelis_builder::BuilderError;
+use xelis_lexer::LexerError;
 use xelis_types::{Type, ValueError, IdentifierType};
 
 #[derive(Debug)]
@@ -8,6 +10,7 @@ pub enum ParserError<'a> {
     NotIterable(Type),
     InvalidRangeType(Type, Type),
     InvalidRangeTypePrimitive(Type),
+    InvalidForTypePrimitive(Type),
     ValueError(ValueError),
     BuilderError(BuilderError),
     InvalidStructFieldOrder,
@@ -15,6 +18,8 @@ pub enum ParserError<'a> {
     InvalidImport,
     InvalidImportPath(String),
     ImportNotFound(String),
+    ImportLexerError(String, LexerError),
+    CyclicImport(Vec<String>),
     MappingExists(IdentifierType),
     ConstantNameNotUppercase(String),
     StructNotFound(&'a str),
@@ -22,10 +27,17 @@ pub enum ParserError<'a> {
     AssignReturnNothing,
     EntryFunctionCannotHaveForType,
     ExpectedToken,
+    MaxDepthReached(usize),
+    TooManyParameters(usize),
+    TooManyFunctions(usize),
+    UnknownLabel(String),
+    LabelAlreadyUsed(String),
+    ExpectedLoopAfterLabel(String),
     VariableTooLong(String),
     VariableMustStartWithAlphabetic(String),
     ExpectedIdentifierToken(Token<'a>),
     UnexpectedToken(Token<'a>),
+    ExpectedExpression(Token<'a>),
     InvalidToken(Token<'a>, Token<'a>),
     TypeNotFound(Token<'a>),
     NoIfBeforeElse,
@@ -35,16 +47,25 @@ pub enum ParserError<'a> {
     FunctionSignatureAlreadyExist,
     UnexpectedVariable(String),
     UnexpectedMappedVariableId(IdentifierType),
+    PossiblyUninitializedVariable(IdentifierType),
+    InvalidTryOperator(Type),
+    InvalidOptionalChaining(Type),
+    InvalidOperationNotNumberType(Type),
     MappingNotFound(String),
     UnexpectedType(Type),
     InvalidStructField(String),
+    StructureFieldNotFound(String),
     InvalidStructureName(String),
     FunctionNotFound,
+    UnknownNamedArgument(&'a str),
+    DuplicateNamedArgument(&'a str),
+    PositionalArgumentAfterNamed,
+    DefaultParameterNotTrailing(&'a str),
     LastFunction,
     FunctionNoReturnType,
     InvalidTypeT,
     NoScopeFound,
-    NoReturnFound,
+    NoReturnFound(&'a str),
     ReturnAlreadyInElse,
     EmptyValue,
     IncompatibleNullWith(Type),
@@ -64,11 +85,26 @@ pub enum ParserError<'a> {
     InvalidTypeInArray(Type, Type),
     InvalidValueType(Type, Type),
     NoValueType,
+    ExpectedBlockValue,
     ExpectedArrayType,
     InvalidFunctionType(Type),
     EmptyArrayConstructor,
     ExpectedNumberType(Type),
-    InvalidNumberValueForType
+    InvalidNumberValueForType,
+    NotConstantExpression(Expression),
+    InvalidConstantOperation(Operator),
+    InvalidTupleDeconstruction(Type, usize),
+    InvalidTupleSize(usize),
+    InvalidTupleIndex(u64, usize),
+    ChainedComparisonNotAllowed,
+    AssignToConstant(IdentifierType),
+    AssignToImmutable(IdentifierType),
+    AmbiguousFunctionValue(String),
+    ExpectedFunctionType(Type),
+    InvalidValueCallArgumentsCount(usize),
+    DuplicateStructField(String),
+    MissingStructField(String),
+    InvalidStringOperation(Operator)
 }
 
 impl<'a> From<ValueError> for ParserError<'a> {
@@ -81,4 +117,38 @@ impl<'a> From<BuilderError> for ParserError<'a> {
     fn from(e: BuilderError) -> Self {
         ParserError::BuilderError(e)
     }
-}
\ No newline at end of file
+}
+
+// Tokens are rendered as written in the source code,
+// other variants fallback on their debug representation
+impl fmt::Display for ParserError<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnexpectedToken(token) => write!(f, "unexpected token `{}`", token),
+            Self::InvalidToken(token, expected) => write!(f, "invalid token `{}`, expected `{}`", token, expected),
+            Self::ExpectedIdentifierToken(token) => write!(f, "expected an identifier, got `{}`", token),
+            Self::ExpectedExpression(token) => write!(f, "expected an expression, got `{}`", token),
+            Self::TypeNotFound(token) => write!(f, "type not found for token `{}`", token),
+            Self::OperatorNotFound(token) => write!(f, "operator not found for token `{}`", token),
+            Self::ChainedComparisonNotAllowed => write!(f, "comparisons can't be chained, combine them with `&&` instead: `0 < x && x < 10`"),
+            e => write!(f, "{:?}", e)
+        }
+    }
+}
+
+impl std::error::Error for ParserError<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use xelis_ast::Literal;
+    use super::*;
+
+    #[test]
+    fn test_display_tokens() {
+        assert_eq!(ParserError::UnexpectedToken(Token::BraceClose).to_string(), "unexpected token `}`");
+        assert_eq!(ParserError::InvalidToken(Token::OperatorPlusAssign, Token::OperatorAssign).to_string(), "invalid token `+=`, expected `=`");
+        assert_eq!(ParserError::ExpectedIdentifierToken(Token::Value(Literal::Number(5))).to_string(), "expected an identifier, got `5`");
+        assert_eq!(ParserError::ExpectedToken.to_string(), "ExpectedToken");
+        assert!(ParserError::ChainedComparisonNotAllowed.to_string().contains("`0 < x && x < 10`"));
+    }
+}
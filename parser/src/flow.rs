@@ -0,0 +1,40 @@
+use xelis_ast::{Expression, Statement};
+use xelis_types::{IdentifierType, Value};
+
+// Control flow analysis
+// Verify that the statements never reach their end: every path returns from the function,
+// calls a function that never returns (such as panic) or loops forever
+// Only the last statement is checked, a dead code after it would not be allowed
+// diverging contains the ids of the functions that never return
+pub fn always_returns(statements: &[Statement], diverging: &[IdentifierType]) -> bool {
+    statements.last().is_some_and(|statement| diverges(statement, diverging))
+}
+
+fn diverges(statement: &Statement, diverging: &[IdentifierType]) -> bool {
+    match statement {
+        Statement::Return(_) => true,
+        // An if without else may fall through
+        Statement::If(_, statements, Some(else_statements)) => always_returns(statements, diverging)
+            && always_returns(else_statements, diverging),
+        Statement::Scope(statements) => always_returns(statements, diverging),
+        // Only a break can leave an infinite loop
+        Statement::While(Expression::Value(Value::Boolean(true)), statements) => !has_break(statements, 0),
+        Statement::Expression(Expression::FunctionCall(None, id, _)) => diverging.contains(id),
+        _ => false
+    }
+}
+
+// Search a break leaving the current loop
+// depth is the count of nested loops entered, a labeled break in them may leave the current loop
+fn has_break(statements: &[Statement], depth: u16) -> bool {
+    statements.iter().any(|statement| match statement {
+        Statement::Break(target) => *target == depth,
+        Statement::If(_, statements, else_statements) => has_break(statements, depth)
+            || else_statements.as_deref().is_some_and(|statements| has_break(statements, depth)),
+        Statement::Scope(statements) => has_break(statements, depth),
+        Statement::While(_, statements)
+        | Statement::ForEach(_, _, statements)
+        | Statement::For(_, _, _, statements) => has_break(statements, depth + 1),
+        _ => false
+    })
+}
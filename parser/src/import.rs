@@ -0,0 +1,96 @@
+use std::{
+    cell::OnceCell,
+    collections::HashMap,
+    fs,
+    io,
+    path::PathBuf
+};
+
+// Fetch the source code of an imported file
+// Allow the host to resolve imports from memory, a database or a virtual filesystem
+pub trait ImportResolver {
+    fn resolve(&self, path: &str) -> io::Result<String>;
+}
+
+// Resolve the imports relative to a root directory
+#[derive(Debug, Clone)]
+pub struct FileSystemResolver {
+    root: PathBuf
+}
+
+impl FileSystemResolver {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into()
+        }
+    }
+}
+
+impl Default for FileSystemResolver {
+    fn default() -> Self {
+        Self::new(".")
+    }
+}
+
+impl ImportResolver for FileSystemResolver {
+    fn resolve(&self, path: &str) -> io::Result<String> {
+        fs::read_to_string(self.root.join(path))
+    }
+}
+
+// In-memory resolver, the key is the import path
+impl ImportResolver for HashMap<String, String> {
+    fn resolve(&self, path: &str) -> io::Result<String> {
+        self.get(path)
+            .cloned()
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))
+    }
+}
+
+// Append-only list of the loaded sources
+// A source is never moved nor dropped once loaded,
+// so the tokens can borrow it as long as the loader lives
+struct Source {
+    code: String,
+    next: OnceCell<Box<Source>>
+}
+
+// Load the imported files using a resolver
+// The loader must outlive the parser as the tokens borrow the loaded sources
+pub struct ImportLoader {
+    resolver: Box<dyn ImportResolver>,
+    sources: OnceCell<Box<Source>>
+}
+
+impl ImportLoader {
+    pub fn new(resolver: impl ImportResolver + 'static) -> Self {
+        Self {
+            resolver: Box::new(resolver),
+            sources: OnceCell::new()
+        }
+    }
+
+    // Resolve the path and keep its source alive
+    pub fn load(&self, path: &str) -> io::Result<&str> {
+        let code = self.resolver.resolve(path)?;
+
+        let mut cell = &self.sources;
+        while let Some(source) = cell.get() {
+            cell = &source.next;
+        }
+
+        let source = cell.get_or_init(|| Box::new(Source {
+            code,
+            next: OnceCell::new()
+        }));
+
+        Ok(&source.code)
+    }
+}
+
+// Resolve the imports from the current directory
+impl Default for ImportLoader {
+    fn default() -> Self {
+        Self::new(FileSystemResolver::default())
+    }
+}
@@ -1,5 +1,11 @@
 mod context;
 mod error;
+mod assignment;
+mod checker;
+mod const_eval;
+mod flow;
+mod import;
+mod span;
 
 use std::{
     borrow::Cow,
@@ -13,9 +19,65 @@ use xelis_builder::{
 use xelis_ast::*;
 use xelis_environment::NativeFunction;
 use xelis_types::*;
+use xelis_lexer::{Lexer, TokenResult};
+use span::SpanRecorder;
 use context::Context;
 
 pub use error::ParserError;
+pub use checker::*;
+pub use const_eval::*;
+pub use import::*;
+pub use span::{Span, Spans};
+
+// Default maximum nesting depth of expressions and blocks
+// Prevent a deeply nested input from overflowing the native stack
+pub const MAX_NESTING_DEPTH: usize = 256;
+
+// Default maximum count of parameters of a function
+pub const MAX_PARAMETERS_COUNT: usize = 255;
+
+// Default maximum count of functions declared by a program, imports included
+// Each function takes an identifier, shared with the environment functions
+pub const MAX_FUNCTIONS_COUNT: usize = 4096;
+
+// Behavior when an integer literal doesn't fit in its type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LiteralOverflow {
+    // Reject the literal with an error
+    #[default]
+    Error,
+    // Clamp the literal to the maximum value of the type
+    Saturate,
+    // Keep only the low bits that fit in the type
+    Wrap
+}
+
+// Convert a literal to a smaller integer type using the overflow behavior
+macro_rules! convert_literal {
+    ($mode: expr, $t: ty, $value: expr) => {
+        match <$t>::try_from($value) {
+            Ok(v) => v,
+            Err(_) => match $mode {
+                LiteralOverflow::Error => return Err(ParserError::InvalidNumberValueForType),
+                LiteralOverflow::Saturate => <$t>::MAX,
+                LiteralOverflow::Wrap => $value as $t
+            }
+        }
+    };
+}
+
+// Type produced by an optional chaining
+// An optional field is not wrapped a second time
+fn optional_of(_type: Type) -> Type {
+    if _type.is_optional() {
+        _type
+    } else {
+        Type::Optional(Box::new(_type))
+    }
+}
+
+// Parameter read from a function declaration: its name, type and default value
+type ParameterDeclaration<'a> = (&'a str, Type, Option<Expression>);
 
 enum Function<'a> {
     Native(&'a NativeFunction),
@@ -36,6 +98,25 @@ impl<'a> Function<'a> {
             _ => false
         }
     }
+
+    fn for_type(&self) -> Option<&Type> {
+        match self {
+            Function::Native(f) => f.get_for_type().as_ref(),
+            Function::Program(FunctionType::Declared(f)) => f.get_on_type().as_ref(),
+            Function::Program(FunctionType::Entry(_)) => None
+        }
+    }
+
+    // Type of this function used as a value, it must return a value
+    fn value_type(&self) -> Option<Type> {
+        let parameters = match self {
+            Function::Native(f) => f.get_parameters().clone(),
+            Function::Program(f) => f.get_parameters().iter().map(|p| p.get_type().clone()).collect()
+        };
+
+        self.return_type().as_ref()
+            .map(|return_type| Type::Function(parameters, Box::new(return_type.clone())))
+    }
 }
 
 pub struct Parser<'a> {
@@ -43,44 +124,173 @@ pub struct Parser<'a> {
     tokens: VecDeque<Token<'a>>,
     // All constants declared
     constants: HashSet<DeclarationStatement>,
+    // Values of the constants folded at parse time
+    constants_values: NoHashMap<Value>,
     // All functions registered by the program
     functions: Vec<FunctionType>,
     // Functions mapper
     // It will contains all the functions declared in the program
     // with the matching id <-> function signature
     functions_mapper: FunctionMapper<'a>,
+    // Name and parameters names of each function registered by the program
+    // Same order as the functions, used to resolve named arguments
+    functions_parameters_names: Vec<(&'a str, Vec<&'a str>)>,
     // Struct manager
     struct_manager: StructManager<'a>,
+    // Default values of the fields of each struct declared by the program
+    // Same order as the fields, None for a required field
+    struct_defaults: NoHashMap<Vec<Option<Expression>>>,
     // Environment contains all the library linked to the program
     environment: &'a EnvironmentBuilder<'a>,
-    // TODO: Path to use to import files
-    // _path: Option<&'a str>
+    // Ids of the environment functions that never return
+    // Resolved once as they are checked for each function declared
+    diverging_functions: Vec<IdentifierType>,
+    // Current nesting depth of expressions and blocks
+    depth: usize,
+    // Maximum nesting depth allowed
+    max_depth: usize,
+    // Maximum count of parameters of a function
+    max_parameters: usize,
+    // Maximum count of functions declared by the program
+    max_functions: usize,
+    // Behavior when an integer literal doesn't fit in its type
+    literal_overflow: LiteralOverflow,
+    // Accept a condition of any type, its truthiness is used
+    truthy_conditions: bool,
+    // Loader used to fetch the imported files
+    // Borrowed as the tokens of the imported files borrow its sources
+    import_loader: Option<&'a ImportLoader>,
+    // Files currently being imported, used to detect a cycle
+    import_stack: Vec<String>,
+    // Files already imported, each file is imported only once
+    imported: HashSet<String>,
+    // Spans of the nodes read, only if the tokens positions are known
+    spans: Option<SpanRecorder>
 }
 
 impl<'a> Parser<'a> {
+    // The environment is borrowed and its mappers are only used as parents:
+    // a new parser can be created for each parse of the same environment,
+    // such as when the source is parsed again after each edit
     pub fn new(tokens: VecDeque<Token<'a>>, environment: &'a EnvironmentBuilder) -> Self {
         let functions_mapper = FunctionMapper::with_parent(environment.get_functions_mapper());
 
         Parser {
             tokens,
             constants: HashSet::new(),
+            constants_values: NoHashMap::default(),
             functions: Vec::new(),
             functions_mapper,
+            functions_parameters_names: Vec::new(),
             struct_manager: StructManager::with_parent(environment.get_struct_manager()),
-            environment
+            struct_defaults: NoHashMap::default(),
+            environment,
+            diverging_functions: Self::get_diverging_functions(environment),
+            depth: 0,
+            max_depth: MAX_NESTING_DEPTH,
+            max_parameters: MAX_PARAMETERS_COUNT,
+            max_functions: MAX_FUNCTIONS_COUNT,
+            literal_overflow: LiteralOverflow::default(),
+            truthy_conditions: false,
+            import_loader: None,
+            import_stack: Vec::new(),
+            imported: HashSet::new(),
+            spans: None
+        }
+    }
+
+    // Same as new, but the span of each statement and expression is recorded
+    // using the position of the tokens, they are returned by parse_with_spans
+    pub fn with_spans(tokens: VecDeque<TokenResult<'a>>, environment: &'a EnvironmentBuilder) -> Self {
+        let positions = tokens.iter().map(Span::of_token).collect();
+        let mut parser = Self::new(tokens.into_iter().map(|t| t.token).collect(), environment);
+        parser.spans = Some(SpanRecorder::new(positions));
+        parser
+    }
+
+    // Set the maximum nesting depth of expressions and blocks
+    pub fn set_max_depth(&mut self, max_depth: usize) {
+        self.max_depth = max_depth;
+    }
+
+    // Set the maximum count of parameters of a function
+    pub fn set_max_parameters(&mut self, max_parameters: usize) {
+        self.max_parameters = max_parameters;
+    }
+
+    // Set the maximum count of functions declared by the program
+    pub fn set_max_functions(&mut self, max_functions: usize) {
+        self.max_functions = max_functions;
+    }
+
+    // Set the loader used to fetch the imported files
+    // Without it, no file can be imported
+    // ImportLoader::default() reads the files from the current directory
+    pub fn set_import_loader(&mut self, import_loader: &'a ImportLoader) {
+        self.import_loader = Some(import_loader);
+    }
+
+    // Set the behavior when an integer literal doesn't fit in its type
+    pub fn set_literal_overflow(&mut self, literal_overflow: LiteralOverflow) {
+        self.literal_overflow = literal_overflow;
+    }
+
+    // Accept a condition of any type in if, while, for and ternary
+    // The interpreter must be configured the same way to execute the program
+    pub fn set_truthy_conditions(&mut self, truthy_conditions: bool) {
+        self.truthy_conditions = truthy_conditions;
+    }
+
+    // Enter a nested expression or block
+    fn increase_depth(&mut self) -> Result<(), ParserError<'a>> {
+        self.depth += 1;
+        if self.depth > self.max_depth {
+            return Err(ParserError::MaxDepthReached(self.max_depth))
         }
+
+        Ok(())
     }
 
     // Consume the next token
     #[inline(always)]
     fn advance(&mut self) -> Result<Token<'a>, ParserError<'a>> {
-        self.tokens.pop_front().ok_or(ParserError::ExpectedToken)
+        self.next().ok_or(ParserError::ExpectedToken)
     }
 
     // Consume the next token without error
     #[inline(always)]
     fn next(&mut self) -> Option<Token<'a>> {
-        self.tokens.pop_front()
+        let token = self.tokens.pop_front()?;
+        if let Some(spans) = self.spans.as_mut() {
+            spans.advance();
+        }
+        Some(token)
+    }
+
+    // Push back the last token consumed
+    fn push_back(&mut self, token: Token<'a>) {
+        self.tokens.push_front(token);
+        if let Some(spans) = self.spans.as_mut() {
+            spans.push_back();
+        }
+    }
+
+    // Span of the next token, used as the start of the node being read
+    fn next_span(&self) -> Option<Span> {
+        self.spans.as_ref().and_then(SpanRecorder::next_token)
+    }
+
+    // Record the span of an expression read from the start to the last token consumed
+    fn record_expression(&mut self, expression: &Expression, start: Option<Span>) {
+        if let (Some(spans), Some(start)) = (self.spans.as_mut(), start) {
+            spans.expression(expression, start);
+        }
+    }
+
+    fn record_statement(&mut self, statement: &Statement, start: Option<Span>) {
+        if let (Some(spans), Some(start)) = (self.spans.as_mut(), start) {
+            spans.statement(statement, start);
+        }
     }
 
     // Peek the next token without consuming it
@@ -119,6 +329,18 @@ impl<'a> Parser<'a> {
         }).is_some()
     }
 
+    // Consume the optional comma separating two items of a list
+    // A trailing comma is accepted, but two consecutive commas are rejected
+    fn read_separator(&mut self) -> Result<(), ParserError<'a>> {
+        if self.peek_is(Token::Comma) {
+            self.advance()?;
+            if self.peek_is(Token::Comma) {
+                return Err(ParserError::UnexpectedToken(Token::Comma))
+            }
+        }
+        Ok(())
+    }
+
     // Require a specific token
     fn expect_token(&mut self, expected: Token<'a>) -> Result<(), ParserError<'a>> {
         let token = self.advance()?;
@@ -142,6 +364,7 @@ impl<'a> Parser<'a> {
             Token::Bool => Type::Bool,
             Token::Optional(inner) => Type::Optional(Box::new(self.get_type_from_token(*inner)?)),
             Token::Range(inner) => Type::Range(Box::new(self.get_type_from_token(*inner)?)),
+            Token::Result(ok, err) => Type::Result(Box::new(self.get_type_from_token(*ok)?), Box::new(self.get_type_from_token(*err)?)),
             Token::Identifier(id) => {
                 if let Ok(v) = self.struct_manager.get_by_name(id) {
                     Type::Struct(v.inner().clone())
@@ -165,29 +388,76 @@ impl<'a> Parser<'a> {
      * - bool
      * - Struct (Structure with name that starts with a uppercase letter)
      * - T[] (where T is any above Type)
+     * - (T, T) (tuple of at least two types)
      */
     fn read_type(&mut self) -> Result<Type, ParserError<'a>> {
         let token = self.advance()?;
-        let mut _type = self.get_type_from_token(token)?;
+        let mut _type = if token == Token::ParenthesisOpen {
+            self.read_tuple_type()?
+        } else if token == Token::Function {
+            self.read_function_type()?
+        } else {
+            self.get_type_from_token(token)?
+        };
 
         // support multi dimensional arrays
+        let mut dimensions = 0;
         loop {
             let token = self.advance()?;
             if token != Token::BracketOpen {
                 // Push back
                 // This allow us to economize one read per iteration on array type
                 // by simply pushing back the token that we don't need
-                self.tokens.push_front(token);
+                self.push_back(token);
                 break;
             }
 
             self.expect_token(Token::BracketClose)?;
+            dimensions += 1;
+            if dimensions > self.max_depth {
+                return Err(ParserError::MaxDepthReached(self.max_depth))
+            }
             _type = Type::Array(Box::new(_type));
         }
 
         Ok(_type)
     }
 
+    // Read the types of a tuple until the closing parenthesis
+    fn read_tuple_type(&mut self) -> Result<Type, ParserError<'a>> {
+        self.increase_depth()?;
+        let mut types = vec![self.read_type()?];
+        while self.peek_is(Token::Comma) {
+            self.expect_token(Token::Comma)?;
+            types.push(self.read_type()?);
+        }
+        self.expect_token(Token::ParenthesisClose)?;
+        self.depth -= 1;
+
+        if types.len() < 2 {
+            return Err(ParserError::InvalidTupleSize(types.len()))
+        }
+
+        Ok(Type::Tuple(types))
+    }
+
+    // Read the type of a function value: fn(u64, string) -> bool
+    fn read_function_type(&mut self) -> Result<Type, ParserError<'a>> {
+        self.increase_depth()?;
+        self.expect_token(Token::ParenthesisOpen)?;
+        let mut parameters = Vec::new();
+        while self.peek_is_not(Token::ParenthesisClose) {
+            parameters.push(self.read_type()?);
+            self.read_separator()?;
+        }
+        self.expect_token(Token::ParenthesisClose)?;
+        self.expect_token(Token::ReturnType)?;
+        let return_type = self.read_type()?;
+        self.depth -= 1;
+
+        Ok(Type::Function(parameters, Box::new(return_type)))
+    }
+
     // get the type of an expression
     fn get_type_from_expression<'b>(&'b self, on_type: Option<&Type>, expression: &'b Expression, context: &'b Context<'a>) -> Result<Cow<'b, Type>, ParserError<'a>> {
         match self.get_type_from_expression_internal(on_type, expression, context)? {
@@ -206,46 +476,41 @@ impl<'a> Parser<'a> {
             },
             Expression::Variable(ref var_name) => match on_type {
                 Some(t) => {
-                    if let Type::Struct(_type) = t {
-                        let index = *var_name as usize;
-                        if let Some(field_type) = _type.fields().get(index) {
-                            Cow::Owned(field_type.clone())
-                        } else {
-                            return Err(ParserError::UnexpectedMappedVariableId(var_name.clone()))
-                        }
-                    } else {
-                        return Err(ParserError::UnexpectedMappedVariableId(var_name.clone()))
+                    let index = *var_name as usize;
+                    let field_type = match t {
+                        Type::Struct(_type) => _type.fields().get(index),
+                        Type::Tuple(types) => types.get(index),
+                        _ => None
+                    };
+
+                    match field_type {
+                        Some(field_type) => Cow::Owned(field_type.clone()),
+                        None => return Err(ParserError::UnexpectedMappedVariableId(*var_name))
                     }
                 },
                 None => Cow::Borrowed(context.get_type_of_variable(var_name)?),
             },
-            Expression::FunctionCall(path, name, _) => {
+            Expression::FunctionCall(path, name, parameters) => {
                 let f = self.get_function(*name)?;
                 let return_type = f.return_type();
                 match return_type {
-                    Some(ref v) => match v {
-                        Type::T => match on_type {
-                            Some(t) => Cow::Owned(t.get_inner_type().clone()),
-                            None => match path {
-                                Some(p) => Cow::Owned(self.get_type_from_expression(on_type, p, context)?.get_inner_type().clone()),
-                                None => return Err(ParserError::InvalidTypeT)
-                            }
-                        },
-                        Type::Optional(inner) => match inner.as_ref() {
-                            Type::T => match on_type {
-                                Some(t) => Cow::Owned(Type::Optional(Box::new(t.get_inner_type().clone()))),
-                                None => match path {
-                                    Some(p) => Cow::Owned(Type::Optional(Box::new(self.get_type_from_expression(on_type, p, context)?.get_inner_type().clone()))),
-                                    None => return Err(ParserError::InvalidTypeT)
-                                }
-                            },
-                            _ => Cow::Owned(v.clone())
-                        },
-                        _ => Cow::Owned(v.clone())
+                    Some(ref v) if v.is_generic() => {
+                        let t = self.get_generic_type_for_call(&f, on_type, path.as_deref(), parameters, context)?;
+                        Cow::Owned(v.with_generic_type(&t))
                     },
+                    Some(ref v) => Cow::Owned(v.clone()),
                     None => return Err(ParserError::FunctionNoReturnType)
                 }
             },
+            // The signature of a function value is only known by its declaration
+            Expression::Value(Value::Function(id)) => match self.get_function(*id)?.value_type() {
+                Some(_type) => Cow::Owned(_type),
+                None => return Err(ParserError::FunctionNoReturnType)
+            },
+            Expression::ValueCall(function, _) => match self.get_type_from_expression(on_type, function, context)?.into_owned() {
+                Type::Function(_, return_type) => Cow::Owned(*return_type),
+                _type => return Err(ParserError::ExpectedFunctionType(_type))
+            },
             // we have to clone everything due to this
             Expression::Value(ref val) => match Type::from_value(val) {
                 Some(v) => Cow::Owned(v),
@@ -259,10 +524,25 @@ impl<'a> Parser<'a> {
             },
             Expression::SubExpression(expr) => self.get_type_from_expression(on_type, expr, context)?,
             Expression::StructConstructor(_, _type) => Cow::Owned(Type::Struct(_type.clone())),
+            Expression::TupleConstructor(values) => {
+                let mut types = Vec::with_capacity(values.len());
+                for value in values {
+                    types.push(self.get_type_from_expression(on_type, value, context)?.into_owned());
+                }
+                Cow::Owned(Type::Tuple(types))
+            },
             Expression::Path(left, right) => {
                 let var_type = self.get_type_from_expression(on_type, left, context)?;
                 self.get_type_from_expression(Some(&var_type), right, context)?
             },
+            Expression::StructUpdate(expr, _) => self.get_type_from_expression(on_type, expr, context)?,
+            // The variables declared in the block are out of scope, its type is saved
+            Expression::Block(_, _, _type) => Cow::Borrowed(_type),
+            Expression::OptionalPath(left, right) => {
+                let var_type = self.get_type_from_expression(on_type, left, context)?;
+                let right_type = self.get_type_from_expression(Some(var_type.get_inner_type()), right, context)?;
+                Cow::Owned(optional_of(right_type.into_owned()))
+            },
             // Compatibility checks are done when constructing the expression
             Expression::Operator(op, left, right) => match op {
                 // Condition operators
@@ -299,6 +579,11 @@ impl<'a> Parser<'a> {
                     let left_type = self.get_type_from_expression(on_type, left, context)?;
                     let right_type = self.get_type_from_expression(on_type, right, context)?;
 
+                    // array * count
+                    if *op == Operator::Multiply && left_type.is_array() && right_type.is_number() {
+                        return Ok(Some(left_type))
+                    }
+
                     if !left_type.is_number() || !right_type.is_number() || left_type != right_type {
                         return Err(ParserError::InvalidOperationNotSameType(left_type.into_owned(), right_type.into_owned()))
                     }
@@ -306,37 +591,222 @@ impl<'a> Parser<'a> {
                 }
             },
             Expression::IsNot(_) => Cow::Owned(Type::Bool),
-            Expression::Ternary(_, expr, _) => self.get_type_from_expression(on_type, expr, context)?,
+            Expression::BitwiseNot(expr) => self.get_type_from_expression(on_type, expr, context)?,
+            // A diverging branch takes the type of the other one
+            Expression::Ternary(_, expr, else_expr) => match self.get_type_from_expression(on_type, expr, context)? {
+                _type if *_type == Type::Never => self.get_type_from_expression(on_type, else_expr, context)?,
+                _type => _type
+            },
             Expression::Cast(_, _type) => Cow::Borrowed(_type),
             Expression::Range(start, _) => Cow::Owned(Type::Range(Box::new(self.get_type_from_expression(on_type, start, context)?.into_owned()))),
+            Expression::Try(expr) => Cow::Owned(self.get_type_from_expression(on_type, expr, context)?.get_inner_type().clone()),
         };
 
         Ok(Some(_type))
     }
 
+    // Resolve the type T used by a generic function call
+    // It is bound from the instance type if any, otherwise from the parameters
+    fn get_generic_type_for_call(&self, f: &Function, on_type: Option<&Type>, path: Option<&Expression>, parameters: &[Expression], context: &Context<'a>) -> Result<Type, ParserError<'a>> {
+        let instance_type = match (on_type, path) {
+            (Some(t), _) => Some(Cow::Borrowed(t)),
+            (None, Some(p)) => Some(self.get_type_from_expression(on_type, p, context)?),
+            (None, None) => None
+        };
+
+        if let Some(instance_type) = instance_type {
+            return Ok(f.for_type()
+                .and_then(|t| t.find_generic_type(&instance_type))
+                .unwrap_or_else(|| instance_type.get_inner_type().clone()))
+        }
+
+        if let Function::Native(f) = f {
+            for (param_type, expr) in f.get_parameters().iter().zip(parameters) {
+                if let Some(expr_type) = self.get_type_from_expression_internal(None, expr, context)? {
+                    if let Some(t) = param_type.find_generic_type(&expr_type) {
+                        return Ok(t)
+                    }
+                }
+            }
+        }
+
+        Err(ParserError::InvalidTypeT)
+    }
+
+    // Verify that the instance and the arguments of a native function call agree on the type bound to T
+    // Example: push on a u64[] only accepts a u64
+    fn verify_generic_arguments(&self, f: &NativeFunction, on_type: Option<&Type>, parameters: &[Expression], context: &Context<'a>) -> Result<(), ParserError<'a>> {
+        let mut bound = None;
+        if let (Some(for_type), Some(instance_type)) = (f.get_for_type(), on_type) {
+            for_type.unify_generic_type(instance_type, &mut bound);
+        }
+
+        for (param_type, expr) in f.get_parameters().iter().zip(parameters) {
+            let Some(expr_type) = self.get_type_from_expression_internal(None, expr, context)? else {
+                continue
+            };
+
+            if !param_type.unify_generic_type(&expr_type, &mut bound) {
+                let expected = match &bound {
+                    Some(t) => param_type.with_generic_type(t),
+                    None => param_type.clone()
+                };
+                return Err(ParserError::InvalidValueType(expr_type.into_owned(), expected))
+            }
+        }
+
+        Ok(())
+    }
+
+    // Check if the expression is a result, in this case '?' is the try operator and not a ternary
+    fn is_result_expression(&self, on_type: Option<&Type>, expr: Option<&Expression>, context: &Context<'a>) -> bool {
+        expr.and_then(|expr| self.get_type_from_expression(on_type, expr, context).ok())
+            .is_some_and(|t| t.is_result())
+    }
+
+    // Check if the expression is an optional, in this case '?.' is an optional chaining
+    fn is_optional_expression(&self, on_type: Option<&Type>, expr: Option<&Expression>, context: &Context<'a>) -> bool {
+        expr.and_then(|expr| self.get_type_from_expression(on_type, expr, context).ok())
+                .is_some_and(|t| t.is_optional())
+    }
+
+    // Check if the token is the ternary operator
+    // '?' is the try operator after a result and starts an optional chaining as '?.'
+    fn is_ternary_operator(&self, token: &Token, on_type: Option<&Type>, expr: Option<&Expression>, context: &Context<'a>) -> bool {
+        *token == Token::OperatorTernary
+            && !self.is_result_expression(on_type, expr, context)
+            && !(self.tokens.get(1) == Some(&Token::Dot) && self.is_optional_expression(on_type, expr, context))
+    }
+
+    // Check if the next tokens are a named argument: name: value
+    // A type constant (name::CONSTANT) is not a named argument
+    fn peek_is_named_argument(&self) -> bool {
+        matches!(
+            (self.tokens.front(), self.tokens.get(1), self.tokens.get(2)),
+            (Some(Token::Identifier(_)), Some(Token::Colon), next) if next != Some(&Token::Colon)
+        )
+    }
+
+    // Resolve a function call declared in the program that doesn't match exactly a signature
+    // Named arguments are reordered to match the declared parameters order
+    // and are appended after the positional arguments
+    // Omitted arguments must be trailing parameters with a default value
+    fn resolve_function_call(&self, name: &str, on_type: Option<&Type>, parameters: Vec<Expression>, types: Vec<Type>, named: Vec<(&'a str, Expression, Type)>) -> Result<(IdentifierType, Vec<Expression>), ParserError<'a>> {
+        let positional = parameters.len();
+        let offset = self.environment.get_functions().len();
+
+        let mut error = ParserError::FunctionNotFound;
+        'main: for (i, (function_name, names)) in self.functions_parameters_names.iter().enumerate() {
+            if *function_name != name || names.len() < positional + named.len() {
+                continue;
+            }
+
+            // Find the position of each named argument in the declared parameters
+            let mut ordered: Vec<Option<(&Expression, &Type)>> = vec![None; names.len() - positional];
+            for (arg_name, expr, t) in named.iter() {
+                match names.iter().position(|n| n == arg_name) {
+                    Some(index) if index < positional => {
+                        error = ParserError::DuplicateNamedArgument(arg_name);
+                        continue 'main;
+                    },
+                    Some(index) => ordered[index - positional] = Some((expr, t)),
+                    None => {
+                        error = ParserError::UnknownNamedArgument(arg_name);
+                        continue 'main;
+                    }
+                }
+            }
+
+            // Every omitted parameter must have a default value
+            let declared = self.functions[i].get_parameters();
+            let provided = positional + ordered.iter().rposition(Option::is_some).map_or(0, |i| i + 1);
+            let mut candidate_parameters = parameters.clone();
+            let mut candidate_types = types.clone();
+            for (param, arg) in declared[positional..].iter().zip(ordered) {
+                let (expr, t) = match arg {
+                    Some(arg) => arg,
+                    None => match param.get_default_value() {
+                        Some(expr) => (expr, param.get_type()),
+                        None => continue 'main
+                    }
+                };
+                candidate_parameters.push(expr.clone());
+                candidate_types.push(t.clone());
+            }
+
+            let signature = Signature::new(name.to_owned(), on_type.cloned(), candidate_types);
+            if let Ok(id) = self.functions_mapper.get_compatible(signature, &mut candidate_parameters) {
+                if id as usize == offset + i {
+                    // Trailing default values are evaluated by the callee
+                    // Those skipped before a named argument are passed directly as they can't reference any variable
+                    candidate_parameters.truncate(provided);
+                    return Ok((id, candidate_parameters))
+                }
+            }
+        }
+
+        Err(error)
+    }
+
     // Read a function call with the following syntax:
     // function_name(param1, param2, ...)
+    // Arguments can also be named, after the positional ones:
+    // function_name(param1, name: param2, ...)
     fn read_function_call(&mut self, path: Option<Expression>, on_type: Option<&Type>, name: &str, context: &mut Context<'a>) -> Result<Expression, ParserError<'a>> {
         // we remove the token from the list
         self.expect_token(Token::ParenthesisOpen)?;
         let mut parameters: Vec<Expression> = Vec::new();
         let mut types: Vec<Type> = Vec::new();
+        let mut named: Vec<(&'a str, Expression, Type)> = Vec::new();
 
         // read parameters for function call
         while self.peek_is_not(Token::ParenthesisClose) {
+            let arg_name = if self.peek_is_named_argument() {
+                let arg_name = self.next_identifier()?;
+                self.expect_token(Token::Colon)?;
+                if named.iter().any(|(n, _, _)| *n == arg_name) {
+                    return Err(ParserError::DuplicateNamedArgument(arg_name))
+                }
+                Some(arg_name)
+            } else {
+                None
+            };
+
             let expr = self.read_expression(context)?;
             // We are forced to clone the type because we can't borrow it from the expression
             // I prefer to do this than doing an iteration below
             let t = self.get_type_from_expression(None, &expr, context)?.into_owned();
-            types.push(t);
-            parameters.push(expr);
-
-            if self.peek_is(Token::Comma) {
-                self.expect_token(Token::Comma)?;
+            match arg_name {
+                Some(arg_name) => named.push((arg_name, expr, t)),
+                None => {
+                    // positional arguments must be before the named ones
+                    if !named.is_empty() {
+                        return Err(ParserError::PositionalArgumentAfterNamed)
+                    }
+                    types.push(t);
+                    parameters.push(expr);
+                }
             }
+
+            self.read_separator()?;
         }
 
-        let id = self.functions_mapper.get_compatible(Signature::new(name.to_owned(), on_type.cloned(), types), &mut parameters)?;
+        let id = if named.is_empty() {
+            match self.functions_mapper.get_compatible(Signature::new(name.to_owned(), on_type.cloned(), types.clone()), &mut parameters) {
+                Ok(id) => id,
+                // Maybe some arguments are omitted
+                Err(e) => {
+                    let (id, resolved) = self.resolve_function_call(name, on_type, parameters, types, named)
+                        .map_err(|_| ParserError::from(e))?;
+                    parameters = resolved;
+                    id
+                }
+            }
+        } else {
+            let (id, resolved) = self.resolve_function_call(name, on_type, parameters, types, named)?;
+            parameters = resolved;
+            id
+        };
 
         // Entry are only callable by external
         let f = self.get_function(id)?;
@@ -344,47 +814,217 @@ impl<'a> Parser<'a> {
             return Err(ParserError::FunctionNotFound)
         }
 
+        if let Function::Native(f) = f {
+            self.verify_generic_arguments(f, on_type, &parameters, context)?;
+        }
+
         self.expect_token(Token::ParenthesisClose)?;
         Ok(Expression::FunctionCall(path.map(Box::new), id, parameters))
     }
 
+    // Find the function used as a value by its name
+    // Only the functions declared before and not being a method can be used
+    // A name shared by several functions is ambiguous
+    fn get_function_value(&self, name: &str) -> Result<Option<IdentifierType>, ParserError<'a>> {
+        let offset = self.environment.get_functions().len();
+        let mut candidates = self.functions_parameters_names.iter()
+            .enumerate()
+            .filter(|(i, (function_name, _))| *function_name == name && matches!(self.functions[*i], FunctionType::Declared(ref f) if f.get_on_type().is_none()))
+            .map(|(i, _)| (offset + i) as IdentifierType);
+
+        match (candidates.next(), candidates.next()) {
+            (Some(_), Some(_)) => Err(ParserError::AmbiguousFunctionValue(name.to_owned())),
+            (id, _) => Ok(id)
+        }
+    }
+
+    // Variable holding a function value with its parameters types
+    fn get_function_variable(name: &str, context: &Context<'a>) -> Option<(IdentifierType, Vec<Type>)> {
+        let id = context.get_variable_id(name)?;
+        match context.get_type_of_variable(&id).ok()? {
+            Type::Function(parameters, _) => Some((id, parameters.clone())),
+            _ => None
+        }
+    }
+
+    // Field of a struct holding a function value with its parameters types
+    fn get_function_field(&self, on_type: Option<&Type>, name: &str) -> Result<Option<(IdentifierType, Vec<Type>)>, ParserError<'a>> {
+        let Some(Type::Struct(_type)) = on_type else {
+            return Ok(None)
+        };
+
+        let field = self.struct_manager.get_by_ref(_type)?.get_id_for_field(name);
+        Ok(field.and_then(|id| match _type.fields().get(id as usize) {
+            Some(Type::Function(parameters, _)) => Some((id, parameters.clone())),
+            _ => None
+        }))
+    }
+
+    // Read the call of a function value with the following syntax:
+    // variable(param1, param2, ...)
+    // Arguments are positional only as the parameters names aren't part of the type
+    fn read_value_call(&mut self, function: Expression, parameters_types: &[Type], context: &mut Context<'a>) -> Result<Expression, ParserError<'a>> {
+        self.expect_token(Token::ParenthesisOpen)?;
+        let mut parameters = Vec::with_capacity(parameters_types.len());
+        while self.peek_is_not(Token::ParenthesisClose) {
+            let param_type = parameters_types.get(parameters.len())
+                .ok_or(ParserError::InvalidValueCallArgumentsCount(parameters_types.len()))?;
+            parameters.push(self.read_value_for_type(param_type, context)?);
+            self.read_separator()?;
+        }
+        self.expect_token(Token::ParenthesisClose)?;
+
+        if parameters.len() != parameters_types.len() {
+            return Err(ParserError::InvalidValueCallArgumentsCount(parameters_types.len()))
+        }
+
+        Ok(Expression::ValueCall(Box::new(function), parameters))
+    }
+
     // Read a struct constructor with the following syntax:
     // struct_name { field_name: value1, field2: value2 }
     // If we have a field that has the same name as a variable we can pass it as following:
     // Example: struct_name { field_name, field2: value2 }
     fn read_struct_constructor(&mut self, on_type: Option<&Type>, struct_type: StructType, context: &mut Context<'a>) -> Result<Expression, ParserError<'a>> {
         self.expect_token(Token::BraceOpen)?;
-        let mut fields = Vec::with_capacity(struct_type.fields().len());
-        for t in struct_type.fields() {
+        let mut fields: Vec<Option<Expression>> = vec![None; struct_type.fields().len()];
+        while self.peek_is_not(Token::BraceClose) {
             let field_name = self.next_identifier()?;
-            let field_value = match self.advance()? {
-                Token::Comma => {
+            let id = self.struct_manager.get_by_ref(&struct_type)?
+                .get_id_for_field(field_name)
+                .ok_or_else(|| ParserError::StructureFieldNotFound(field_name.to_owned()))? as usize;
+
+            if fields[id].is_some() {
+                return Err(ParserError::DuplicateStructField(field_name.to_owned()))
+            }
+
+            let t = &struct_type.fields()[id];
+            let field_value = match self.peek()? {
+                Token::Comma | Token::BraceClose => {
                     let id = context.get_variable_id(field_name)
                         .ok_or_else(|| ParserError::UnexpectedVariable(field_name.to_owned()))?;
                     Expression::Variable(id)
                 }
                 Token::Colon => {
-                    let value = self.read_expr(on_type, true, true, Some(t), context)?;
-                    if self.peek_is(Token::Comma) {
-                        self.advance()?;
-                    }
-                    value
+                    self.advance()?;
+                    self.read_expr(on_type, true, true, Some(t), context)?
                 }
-                token => return Err(ParserError::UnexpectedToken(token))
+                _ => return Err(ParserError::UnexpectedToken(self.advance()?))
             };
+            self.read_separator()?;
 
             let field_type = self.get_type_from_expression(on_type, &field_value, context)?;
             if !t.is_compatible_with(&field_type) {
                 return Err(ParserError::InvalidValueType(field_type.into_owned(), t.clone()))
             }
 
-            fields.push(field_value);
+            fields[id] = Some(field_value);
         }
 
         self.expect_token(Token::BraceClose)?;
+
+        // The omitted fields take their default value, evaluated on each construction
+        let defaults = self.struct_defaults.get(&struct_type.id());
+        let names = self.struct_manager.get_field_names(&struct_type)?;
+        let fields = fields.into_iter()
+            .enumerate()
+            .map(|(i, field)| field
+                .or_else(|| defaults.and_then(|defaults| defaults[i].clone()))
+                .ok_or_else(|| ParserError::MissingStructField(names[i].to_owned()))
+            )
+            .collect::<Result<Vec<_>, _>>()?;
+
         Ok(Expression::StructConstructor(fields, struct_type))
     }
 
+    // Read the fields replaced by a struct update with the following syntax:
+    // expr with { field_name: value1, field2: value2 }
+    // The fields not set are copied from the base struct
+    fn read_struct_update(&mut self, on_type: Option<&Type>, struct_type: &StructType, context: &mut Context<'a>) -> Result<Vec<(IdentifierType, Expression)>, ParserError<'a>> {
+        self.expect_token(Token::BraceOpen)?;
+        let mut fields = Vec::new();
+        while self.peek_is_not(Token::BraceClose) {
+            let field_name = self.next_identifier()?;
+            let id = self.struct_manager.get_by_ref(struct_type)?
+                .get_id_for_field(field_name)
+                .ok_or_else(|| ParserError::StructureFieldNotFound(field_name.to_owned()))?;
+
+            self.expect_token(Token::Colon)?;
+            let t = &struct_type.fields()[id as usize];
+            let field_value = self.read_expr(on_type, true, true, Some(t), context)?;
+            self.read_separator()?;
+
+            let field_type = self.get_type_from_expression(on_type, &field_value, context)?;
+            if !t.is_compatible_with(&field_type) {
+                return Err(ParserError::InvalidValueType(field_type.into_owned(), t.clone()))
+            }
+
+            fields.push((id, field_value));
+        }
+
+        self.expect_token(Token::BraceClose)?;
+        Ok(fields)
+    }
+
+    // Read a block expression with the following syntax:
+    // { statements; value }
+    // The value is the last expression of the block
+    // A return inside the block returns from the function
+    fn read_block_expression(&mut self, expected_type: Option<&Type>, context: &mut Context<'a>) -> Result<Expression, ParserError<'a>> {
+        self.increase_depth()?;
+        // break and continue can't leave a block expression
+        let in_loop = context.is_in_a_loop();
+        let loop_labels = context.take_loop_labels();
+        context.set_in_a_loop(false);
+        context.begin_scope();
+
+        let return_type = context.get_return_type().cloned();
+        let mut statements = Vec::new();
+        let value = loop {
+            let is_statement = match self.peek()? {
+                Token::For
+                | Token::ForEach
+                | Token::While
+                | Token::If
+                | Token::BraceOpen
+                | Token::Let
+                | Token::Var
+                | Token::Return
+                | Token::Continue
+                | Token::Break
+                | Token::Label(_) => true,
+                Token::BraceClose => return Err(ParserError::ExpectedBlockValue),
+                _ => false
+            };
+
+            if is_statement {
+                let statement = self.read_statement(context, &return_type)?
+                    .ok_or(ParserError::ExpectedBlockValue)?;
+                statements.push(statement);
+                continue;
+            }
+
+            let start = self.next_span();
+            let expr = self.read_expr(None, true, true, expected_type, context)?;
+            if self.peek_is(Token::BraceClose) {
+                break expr;
+            }
+
+            let statement = Statement::Expression(expr);
+            self.record_statement(&statement, start);
+            statements.push(statement);
+        };
+        self.expect_token(Token::BraceClose)?;
+
+        let value_type = self.get_type_from_expression(None, &value, context)?.into_owned();
+        context.end_scope();
+        context.set_in_a_loop(in_loop);
+        context.set_loop_labels(loop_labels);
+        self.depth -= 1;
+
+        Ok(Expression::Block(statements, Box::new(value), value_type))
+    }
+
     // Read a constant from the environment
     fn read_type_constant(&mut self, token: Token<'a>) -> Result<Expression, ParserError<'a>> {
         let _type = self.get_type_from_token(token)?;
@@ -406,12 +1046,40 @@ impl<'a> Parser<'a> {
     // Read an expression with the possibility to accept operators
     // number_type is used to force the type of a number
     fn read_expr(&mut self, on_type: Option<&Type>, allow_ternary: bool, accept_operator: bool, expected_type: Option<&Type>, context: &mut Context<'a>) -> Result<Expression, ParserError<'a>> {
+        self.read_expr_with_precedence(on_type, allow_ternary, accept_operator, expected_type, 0, context)
+    }
+
+    // Same as read_expr but stops at the first operator with a lower precedence than min_precedence
+    fn read_expr_with_precedence(&mut self, on_type: Option<&Type>, allow_ternary: bool, accept_operator: bool, expected_type: Option<&Type>, min_precedence: u8, context: &mut Context<'a>) -> Result<Expression, ParserError<'a>> {
+        self.increase_depth()?;
+        let expr = self.read_expr_internal(on_type, allow_ternary, accept_operator, expected_type, min_precedence, context)?;
+        self.depth -= 1;
+        Ok(expr)
+    }
+
+    // Read the right operand of a binary operator
+    // It contains only the operators binding tighter, so the next ones are applied to the whole operation
+    fn read_operand(&mut self, op: &Operator, on_type: Option<&Type>, expected_type: Option<&Type>, context: &mut Context<'a>) -> Result<Expression, ParserError<'a>> {
+        let min_precedence = match op.associativity() {
+            Associativity::Left => op.precedence() + 1,
+            Associativity::Right => op.precedence()
+        };
+
+        self.read_expr_with_precedence(on_type, false, true, expected_type, min_precedence, context)
+    }
+
+    fn read_expr_internal(&mut self, on_type: Option<&Type>, allow_ternary: bool, accept_operator: bool, expected_type: Option<&Type>, min_precedence: u8, context: &mut Context<'a>) -> Result<Expression, ParserError<'a>> {
         let mut required_operator = false;
         let mut last_expression: Option<Expression> = None;
+        // Each expression built on the previous one nests it deeper, like a + b + c
+        // It is counted in the depth even if it isn't read recursively
+        let mut chain_depth = 0;
+        // Start of the last expression, an operator applied to it starts at the same position
+        let mut last_start: Option<Span> = None;
         while self.peek()
             .ok()
             .filter(|peek| {
-                if !allow_ternary && **peek == Token::OperatorTernary {
+                if !allow_ternary && self.is_ternary_operator(peek, on_type, last_expression.as_ref(), context) {
                     return false
                 }
 
@@ -419,14 +1087,21 @@ impl<'a> Parser<'a> {
                     return false
                 }
 
-                !peek.should_stop()
+                // An operator binding less ends the operand being read
+                if required_operator && Operator::precedence_of(peek).is_some_and(|precedence| precedence < min_precedence) {
+                    return false
+                }
+
+                // A brace at the start of an expression opens a block expression
+                (!peek.should_stop() || (**peek == Token::BraceOpen && last_expression.is_none()))
                 && (
                     required_operator == peek.is_operator()
                     || (**peek == Token::BracketOpen && last_expression.is_none())
                 )
             }).is_some()
         {
-
+            let on_previous = last_expression.is_some();
+            let start = if on_previous { last_start } else { self.next_span() };
             let token = self.advance()?;
             let expr: Expression = match token {
                 Token::BracketOpen => {
@@ -465,9 +1140,7 @@ impl<'a> Parser<'a> {
                                 };
                                 expressions.push(expr);
 
-                                if self.peek_is(Token::Comma) {
-                                    self.expect_token(Token::Comma)?;
-                                }
+                                self.read_separator()?;
                             }
 
                             self.expect_token(Token::BracketClose)?;
@@ -475,15 +1148,47 @@ impl<'a> Parser<'a> {
                         }
                     }
                 },
+                Token::BraceOpen => self.read_block_expression(expected_type, context)?,
                 Token::ParenthesisOpen => {
-                    let expr = self.read_expr(None, true, true, expected_type, context)?;
-                    self.expect_token(Token::ParenthesisClose)?;
-                    Expression::SubExpression(Box::new(expr))
+                    // Each value of a tuple has its own expected type
+                    let expected_types = match expected_type {
+                        Some(Type::Tuple(types)) => Some(types),
+                        _ => None
+                    };
+                    let first_expected = match expected_types {
+                        Some(types) => types.first(),
+                        None => expected_type
+                    };
+
+                    let expr = self.read_expr(None, true, true, first_expected, context)?;
+                    if self.peek_is(Token::Comma) {
+                        let mut values = vec![expr];
+                        while self.peek_is(Token::Comma) {
+                            self.expect_token(Token::Comma)?;
+                            let expected = expected_types.and_then(|types| types.get(values.len()));
+                            values.push(self.read_expr(None, true, true, expected, context)?);
+                        }
+                        self.expect_token(Token::ParenthesisClose)?;
+
+                        // Every value must have a known type
+                        for value in values.iter() {
+                            self.get_type_from_expression(None, value, context)?;
+                        }
+
+                        Expression::TupleConstructor(values)
+                    } else {
+                        self.expect_token(Token::ParenthesisClose)?;
+                        Expression::SubExpression(Box::new(expr))
+                    }
                 },
                 Token::Identifier(id) => {
                     match self.peek()? {
-                        // function call
-                        Token::ParenthesisOpen => self.read_function_call(last_expression.take(), on_type, id, context)?,
+                        Token::ParenthesisOpen => match Self::get_function_variable(id, context).filter(|_| on_type.is_none()).or(self.get_function_field(on_type, id)?) {
+                            // call of a variable or a struct field holding a function value
+                            Some((var_id, parameters_types)) => self.read_value_call(Expression::Variable(var_id), &parameters_types, context)?,
+                            // function call
+                            None => self.read_function_call(last_expression.take(), on_type, id, context)?
+                        },
                         Token::Colon => self.read_type_constant(Token::Identifier(id))?,
                         _ => {
                             match on_type {
@@ -502,8 +1207,12 @@ impl<'a> Parser<'a> {
                                 None => {
                                     if let Some(id) = context.get_variable_id(id) {
                                         Expression::Variable(id)
+                                    } else if let Some(value) = self.environment.get_global_constant(id) {
+                                        Expression::Value(value.clone())
                                     } else if let Ok(id) = self.struct_manager.get_by_name(&id) {
                                         self.read_struct_constructor(on_type, id.inner().clone(), context)?
+                                    } else if let Some(id) = self.get_function_value(id)? {
+                                        Expression::Value(Value::Function(id))
                                     } else {
                                         return Err(ParserError::UnexpectedVariable(id.to_owned()))
                                     }
@@ -520,11 +1229,21 @@ impl<'a> Parser<'a> {
                         Literal::U64(n) => Value::U64(n),
                         Literal::U128(n) => Value::U128(n),
                         Literal::U256(n) => Value::U256(n),
+                        // Suffixed literal too big for its type
+                        Literal::OutOfRange(number_type, n) => match number_type {
+                            NumberType::U8 => Value::U8(convert_literal!(self.literal_overflow, u8, n)),
+                            NumberType::U16 => Value::U16(convert_literal!(self.literal_overflow, u16, n)),
+                            NumberType::U32 => Value::U32(convert_literal!(self.literal_overflow, u32, n)),
+                            NumberType::U64 => Value::U64(convert_literal!(self.literal_overflow, u64, n)),
+                            NumberType::U128 => Value::U128(n),
+                            NumberType::U256 => Value::U256(U256::from(n))
+                        },
+                        // Infer the type from the context, the literal must fit in it
                         Literal::Number(n) => match expected_type {
-                            Some(Type::U8) => Value::U8(n as u8),
-                            Some(Type::U16) => Value::U16(n as u16),
-                            Some(Type::U32) => Value::U32(n as u32),
-                            Some(Type::U64) => Value::U64(n as u64),
+                            Some(Type::U8) => Value::U8(convert_literal!(self.literal_overflow, u8, n)),
+                            Some(Type::U16) => Value::U16(convert_literal!(self.literal_overflow, u16, n)),
+                            Some(Type::U32) => Value::U32(convert_literal!(self.literal_overflow, u32, n)),
+                            Some(Type::U64) => Value::U64(n),
                             Some(Type::U128) => Value::U128(n as u128),
                             Some(Type::U256) => Value::U256(U256::from(n)),
                             _ => Value::U64(n)
@@ -556,6 +1275,18 @@ impl<'a> Parser<'a> {
                                 }
 
                                 Expression::Range(Box::new(value), Box::new(end_expr))
+                            } else if let (Type::Tuple(types), Ok(Token::Value(Literal::Number(index)))) = (&_type, self.peek()) {
+                                // tuple.0 access the value at index
+                                let index = *index;
+                                if index >= types.len() as u64 {
+                                    return Err(ParserError::InvalidTupleIndex(index, types.len()))
+                                }
+                                let index_start = self.next_span();
+                                self.advance()?;
+
+                                let index = Expression::Variable(index as IdentifierType);
+                                self.record_expression(&index, index_start);
+                                Expression::Path(Box::new(value), Box::new(index))
                             } else {
                                 let right_expr = self.read_expr(Some(&_type), false, false, expected_type, context)?;
 
@@ -564,6 +1295,9 @@ impl<'a> Parser<'a> {
                                         return Err(ParserError::UnexpectedPathInFunctionCall)
                                     }
                                     Expression::FunctionCall(Some(Box::new(value)), name, params)
+                                } else if let Expression::ValueCall(field, params) = right_expr {
+                                    // The function value is read from the struct field
+                                    Expression::ValueCall(Box::new(Expression::Path(Box::new(value), field)), params)
                                 } else {
                                     Expression::Path(Box::new(value), Box::new(right_expr))
                                 }
@@ -581,28 +1315,79 @@ impl<'a> Parser<'a> {
 
                     Expression::IsNot(Box::new(expr))
                 },
-                Token::OperatorTernary => match last_expression { // condition ? expr : expr
-                    Some(expr) => {
-                        if *self.get_type_from_expression(on_type, &expr, context)? != Type::Bool {
-                            return Err(ParserError::InvalidCondition(Type::Bool, expr))
-                        }
+                // Only the next value is complemented, not the whole expression
+                Token::BitwiseNot => {
+                    let expr = self.read_expr(on_type, false, false, expected_type, context)?;
+                    let expr_type = self.get_type_from_expression(on_type, &expr, context)?;
+                    if !expr_type.is_number() {
+                        return Err(ParserError::InvalidOperationNotNumberType(expr_type.into_owned()))
+                    }
 
-                        let valid_expr = self.read_expr(on_type, true, true, expected_type, context)?;
-                        let first_type = self.get_type_from_expression(on_type, &valid_expr, context)?.into_owned();
-                        self.expect_token(Token::Colon)?;
-                        let else_expr = self.read_expr(on_type, true, true, expected_type, context)?;
-                        let else_type = self.get_type_from_expression(on_type, &else_expr, context)?;
-                        
-                        if first_type != *else_type { // both expr should have the SAME type.
-                            return Err(ParserError::InvalidValueType(else_type.into_owned(), first_type))
-                        }
-                        required_operator = !required_operator;
-                        Expression::Ternary(Box::new(expr), Box::new(valid_expr), Box::new(else_expr))
-                    },
+                    Expression::BitwiseNot(Box::new(expr))
+                },
+                // expr? propagates the error of a result to the caller
+                Token::OperatorTernary if self.is_result_expression(on_type, last_expression.as_ref(), context) => {
+                    let Some(expr) = last_expression else {
+                        return Err(ParserError::InvalidTernaryNoPreviousExpression)
+                    };
+                    let expr_type = self.get_type_from_expression(on_type, &expr, context)?;
+                    let Type::Result(_, err_type) = expr_type.as_ref() else {
+                        return Err(ParserError::InvalidTryOperator(expr_type.into_owned()))
+                    };
+
+                    // The error must be compatible with the error type returned by the function
+                    match context.get_return_type() {
+                        Some(Type::Result(_, return_err)) if err_type.is_compatible_with(return_err) => {},
+                        _ => return Err(ParserError::InvalidTryOperator(expr_type.into_owned()))
+                    };
+
+                    required_operator = !required_operator;
+                    Expression::Try(Box::new(expr))
+                },
+                // optional?.field is none if the optional is null
+                Token::OperatorTernary if self.peek_is(Token::Dot) && self.is_optional_expression(on_type, last_expression.as_ref(), context) => {
+                    let Some(expr) = last_expression else {
+                        return Err(ParserError::InvalidTernaryNoPreviousExpression)
+                    };
+                    self.expect_token(Token::Dot)?;
+
+                    let expr_type = self.get_type_from_expression(on_type, &expr, context)?.into_owned();
+                    let inner_type = expr_type.get_inner_type();
+                    if !matches!(inner_type, Type::Struct(_)) {
+                        return Err(ParserError::InvalidOptionalChaining(expr_type))
+                    }
+
+                    let field = self.read_expr(Some(inner_type), false, false, None, context)?;
+                    if !matches!(field, Expression::Variable(_)) {
+                        return Err(ParserError::InvalidOptionalChaining(expr_type))
+                    }
+
+                    required_operator = !required_operator;
+                    Expression::OptionalPath(Box::new(expr), Box::new(field))
+                },
+                Token::OperatorTernary => match last_expression { // condition ? expr : expr
+                    Some(expr) => {
+                        if !self.truthy_conditions && *self.get_type_from_expression(on_type, &expr, context)? != Type::Bool {
+                            return Err(ParserError::InvalidCondition(Type::Bool, expr))
+                        }
+
+                        let valid_expr = self.read_expr(on_type, true, true, expected_type, context)?;
+                        let first_type = self.get_type_from_expression(on_type, &valid_expr, context)?.into_owned();
+                        self.expect_token(Token::Colon)?;
+                        let else_expr = self.read_expr(on_type, true, true, expected_type, context)?;
+                        let else_type = self.get_type_from_expression(on_type, &else_expr, context)?;
+                        
+                        // both expr should have the SAME type, unless one of them never returns
+                        if first_type != *else_type && first_type != Type::Never && *else_type != Type::Never {
+                            return Err(ParserError::InvalidValueType(else_type.into_owned(), first_type))
+                        }
+                        required_operator = !required_operator;
+                        Expression::Ternary(Box::new(expr), Box::new(valid_expr), Box::new(else_expr))
+                    },
                     None => return Err(ParserError::InvalidTernaryNoPreviousExpression)
                 },
                 Token::As => {
-                    let previous_expr = last_expression.ok_or_else(|| ParserError::InvalidOperation)?;
+                    let previous_expr = last_expression.ok_or(ParserError::InvalidOperation)?;
                     let left_type = self.get_type_from_expression(on_type, &previous_expr, context)?.into_owned();
                     let right_type = self.read_type()?;
 
@@ -617,8 +1402,32 @@ impl<'a> Parser<'a> {
                     required_operator = !required_operator;
                     Expression::Cast(Box::new(previous_expr), right_type)
                 },
+                Token::With => {
+                    let previous_expr = last_expression.ok_or(ParserError::InvalidOperation)?;
+                    let left_type = self.get_type_from_expression(on_type, &previous_expr, context)?.into_owned();
+                    let Type::Struct(struct_type) = &left_type else {
+                        return Err(ParserError::UnexpectedType(left_type))
+                    };
+
+                    let fields = self.read_struct_update(on_type, struct_type, context)?;
+                    required_operator = !required_operator;
+                    Expression::StructUpdate(Box::new(previous_expr), fields)
+                },
                 token => {
                     match last_expression {
+                        // null on the left side can only be compared to a value accepting null
+                        Some(previous_expr @ Expression::Value(Value::Null)) if matches!(Operator::value_of(&token), Some(Operator::Equals | Operator::NotEquals)) => {
+                            required_operator = !required_operator;
+
+                            let op = Operator::value_of(&token).ok_or(ParserError::OperatorNotFound(token))?;
+                            let expr = self.read_operand(&op, on_type, None, context)?;
+                            let right_type = self.get_type_from_expression(on_type, &expr, context)?;
+                            if !right_type.allow_null() {
+                                return Err(ParserError::IncompatibleNullWith(right_type.into_owned()))
+                            }
+
+                            Expression::Operator(op, Box::new(previous_expr), Box::new(expr))
+                        },
                         Some(mut previous_expr) => {
                             required_operator = !required_operator;
 
@@ -629,9 +1438,46 @@ impl<'a> Parser<'a> {
                                 None => return Err(ParserError::OperatorNotFound(token))
                             };
 
-                            let mut expr = self.read_expr(on_type, false, true, Some(&left_type), context)?;
+                            // Constants and 'let' variables are immutable, including their fields and values
+                            if op.is_assignation() {
+                                if let Some(id) = Self::get_assigned_variable(&previous_expr) {
+                                    if self.constants_values.contains_key(&id) {
+                                        return Err(ParserError::AssignToConstant(id))
+                                    }
+
+                                    if !context.is_variable_mutable(&id) {
+                                        return Err(ParserError::AssignToImmutable(id))
+                                    }
+                                }
+                            }
+
+                            // a < b < c would compare the bool result of a < b with c
+                            if op.is_comparison() && matches!(&previous_expr, Expression::Operator(previous_op, _, _) if previous_op.is_comparison()) {
+                                return Err(ParserError::ChainedComparisonNotAllowed)
+                            }
+
+                            // The count of an array repetition is a number
+                            let is_repeat = left_type.is_array() && match &op {
+                                Operator::Multiply => true,
+                                Operator::Assign(Some(op)) => **op == Operator::Multiply,
+                                _ => false
+                            };
+                            let expected = if is_repeat { None } else { Some(&left_type) };
+                            let mut expr = self.read_operand(&op, on_type, expected, context)?;
                             if let Some(right_type) = self.get_type_from_expression_internal(on_type, &expr, context)? {
                                 match &op {
+                                    // array * count repeats the values of the array
+                                    _ if is_repeat => {
+                                        if !right_type.is_number() {
+                                            return Err(ParserError::ExpectedNumberType(right_type.into_owned()))
+                                        }
+                                    },
+                                    // Only += is overloaded for strings, the value is appended using its display format
+                                    Operator::Assign(Some(inner)) if left_type == Type::String => {
+                                        if **inner != Operator::Plus {
+                                            return Err(ParserError::InvalidStringOperation(op))
+                                        }
+                                    },
                                     Operator::Minus | Operator::Rem | Operator::Divide | Operator::Multiply
                                     | Operator::Assign(_) | Operator::BitwiseLeft | Operator::BitwiseRight
                                     | Operator::GreaterThan | Operator::LessThan | Operator::LessOrEqual
@@ -647,6 +1493,12 @@ impl<'a> Parser<'a> {
                                             }
                                         }
                                     },
+                                    // Arrays are concatenated if their values are compatible
+                                    Operator::Plus if left_type.is_array() && right_type.is_array() => {
+                                        if !right_type.is_compatible_with(&left_type) {
+                                            return Err(ParserError::InvalidOperationNotSameType(left_type, right_type.into_owned()))
+                                        }
+                                    },
                                     Operator::Plus => {
                                         if left_type != *right_type && !(left_type == Type::String || *right_type == Type::String) {
                                             return Err(ParserError::InvalidOperationNotSameType(left_type, right_type.into_owned()))
@@ -686,10 +1538,17 @@ impl<'a> Parser<'a> {
                 }
             };
 
+            self.record_expression(&expr, start);
             last_expression = Some(expr);
+            if on_previous {
+                chain_depth += 1;
+                self.increase_depth()?;
+            }
+            last_start = start;
             required_operator = !required_operator;
         }
 
+        self.depth -= chain_depth;
         match last_expression {
             Some(v) => Ok(v),
             None => Err(ParserError::NotImplemented)
@@ -702,21 +1561,50 @@ impl<'a> Parser<'a> {
      * }
      */
     fn read_body(&mut self, context: &mut Context<'a>, return_type: &Option<Type>) -> Result<Vec<Statement>, ParserError<'a>> {
+        self.increase_depth()?;
         context.begin_scope();
         let statements = self.read_statements(context, return_type)?;
         context.end_scope();
+        self.depth -= 1;
         Ok(statements)
     }
 
+    // Read an expression that must be assignable to the expected type
+    fn read_value_for_type(&mut self, value_type: &Type, context: &mut Context<'a>) -> Result<Expression, ParserError<'a>> {
+        let expr = self.read_expr(None, true, true, Some(value_type.get_inner_type()), context)?;
+
+        let expr_type = match self.get_type_from_expression_internal(None, &expr, context) {
+            Ok(opt_type) => match opt_type {
+                Some(v) => v,
+                None => if value_type.is_optional() {
+                    Cow::Owned(value_type.clone())
+                } else {
+                    return Err(ParserError::NoValueType)
+                }
+            },
+            Err(e) => match e { // support empty array declaration
+                ParserError::EmptyArrayConstructor if value_type.is_array() => Cow::Owned(value_type.clone()),
+                _ => return Err(e)
+            }
+        };
+
+        if !expr_type.is_compatible_with(value_type) {
+            return Err(ParserError::InvalidValueType(expr_type.into_owned(), value_type.clone()))
+        }
+
+        Ok(expr)
+    }
+
     /**
      * Example: let hello: string = "hello";
      * Rules:
-     * - Every variable must be declared with 'let' keyword
+     * - Every variable must be declared with 'let' or 'var' keyword
+     * - Only a 'var' variable can be assigned after its declaration
      * - Variable name must be alphanumeric characters
      * - Must provide a value type
      * - If no value is set, Null is set by default
      */
-    fn read_variable(&mut self, context: &mut Context<'a>, is_const: bool) -> Result<DeclarationStatement, ParserError<'a>> {
+    fn read_variable(&mut self, context: &mut Context<'a>, is_const: bool, mutable: bool) -> Result<DeclarationStatement, ParserError<'a>> {
         let name: &'a str = self.next_identifier()?;
 
         // Constants must be uppercase
@@ -735,28 +1623,7 @@ impl<'a> Parser<'a> {
         let value_type = self.read_type()?;
         let value: Expression = if self.peek_is(Token::OperatorAssign) {
             self.expect_token(Token::OperatorAssign)?;
-            let expr = self.read_expr(None, true, true, Some(value_type.get_inner_type()), context)?;
-
-            let expr_type = match self.get_type_from_expression_internal(None, &expr, context) {
-                Ok(opt_type) => match opt_type {
-                    Some(v) => v,
-                    None => if value_type.is_optional() {
-                        Cow::Owned(value_type.clone())
-                    } else {
-                        return Err(ParserError::NoValueType)
-                    }
-                },
-                Err(e) => match e { // support empty array declaration
-                    ParserError::EmptyArrayConstructor if value_type.is_array() => Cow::Owned(value_type.clone()),
-                    _ => return Err(e)
-                }
-            };
-
-            if !expr_type.is_compatible_with(&value_type) {
-                return Err(ParserError::InvalidValueType(expr_type.into_owned(), value_type))
-            }
-
-            expr
+            self.read_value_for_type(&value_type, context)?
         } else {
             Expression::Value(Value::Null)
         };
@@ -767,35 +1634,100 @@ impl<'a> Parser<'a> {
             context.register_variable(name, value_type.clone())?
         };
 
+        if !mutable {
+            context.set_variable_immutable(&id);
+        }
+
         Ok(DeclarationStatement {
             id,
             value_type,
-            value
+            value,
+            mutable
         })
     }
 
+    /**
+     * Example: let (a, b) = f();
+     * Rules:
+     * - Types are inferred from the tuple
+     * - The tuple must have exactly one value per variable
+     * - `_` can be used to ignore a value
+     * - Variables declared with 'var' are mutable
+     */
+    fn read_tuple_deconstruction(&mut self, context: &mut Context<'a>, mutable: bool) -> Result<Statement, ParserError<'a>> {
+        self.expect_token(Token::ParenthesisOpen)?;
+        let mut names = vec![self.next_identifier()?];
+        while self.peek_is(Token::Comma) {
+            self.expect_token(Token::Comma)?;
+            names.push(self.next_identifier()?);
+        }
+        self.expect_token(Token::ParenthesisClose)?;
+        self.expect_token(Token::OperatorAssign)?;
+
+        let expr = self.read_expr(None, true, true, None, context)?;
+        let types = match self.get_type_from_expression(None, &expr, context)?.into_owned() {
+            Type::Tuple(types) if types.len() == names.len() => types,
+            t => return Err(ParserError::InvalidTupleDeconstruction(t, names.len()))
+        };
+
+        let mut variables = Vec::with_capacity(names.len());
+        for (name, value_type) in names.into_iter().zip(types) {
+            let id = if name == "_" {
+                context.register_variable_unchecked(name, value_type.clone())
+            } else if !name.starts_with(char::is_alphabetic) {
+                return Err(ParserError::VariableMustStartWithAlphabetic(name.to_owned()))
+            } else {
+                context.register_variable(name, value_type.clone())?
+            };
+
+            if !mutable {
+                context.set_variable_immutable(&id);
+            }
+            variables.push((id, value_type));
+        }
+
+        Ok(Statement::TupleDeconstruction(variables, expr))
+    }
+
     fn read_loop_body(&mut self, context: &mut Context<'a>, return_type: &Option<Type>) -> Result<Vec<Statement>, ParserError<'a>> {
         // support nested loop
         let old_value = context.is_in_a_loop();
         context.set_in_a_loop(true);
+        context.begin_loop();
         self.expect_token(Token::BraceOpen)?;
         let statements = self.read_body(context, return_type)?;
+        context.end_loop();
         context.set_in_a_loop(old_value);
 
         Ok(statements)
     }
 
+    // Read the optional label of a break or continue
+    // Returns the count of loops to leave before the labeled one
+    fn read_loop_depth(&mut self, context: &Context<'a>) -> Result<u16, ParserError<'a>> {
+        let Ok(Token::Label(label)) = self.peek() else {
+            return Ok(0)
+        };
+
+        let label = *label;
+        self.advance()?;
+        context.get_loop_depth(label)
+            .ok_or_else(|| ParserError::UnknownLabel(label.to_owned()))
+    }
+
     // Read a single statement
     fn read_statement(&mut self, context: &mut Context<'a>, return_type: &Option<Type>) -> Result<Option<Statement>, ParserError<'a>> {
+        let start = self.next_span();
         if let Some(token) = self.next() {
             let statement: Statement = match token {
                 Token::BraceClose => return Ok(None),
                 Token::For => { // Example: for i: u64 = 0; i < 10; i += 1 {}
                     context.begin_scope();
-                    let var = self.read_variable(context, false)?;
+                    // The loop variable is updated by the increment
+                    let var = self.read_variable(context, false, true)?;
                     let condition = self.read_expression(context)?;
                     let condition_type = self.get_type_from_expression(None, &condition, context)?;
-                    if  *condition_type != Type::Bool {
+                    if !self.truthy_conditions && *condition_type != Type::Bool {
                         return Err(ParserError::InvalidCondition(condition_type.into_owned(), condition))
                     }
 
@@ -831,7 +1763,7 @@ impl<'a> Parser<'a> {
                 Token::While => { // Example: while i < 10 {}
                     let condition = self.read_expression(context)?;
                     let condition_type = self.get_type_from_expression(None, &condition, context)?;
-                    if  *condition_type != Type::Bool {
+                    if !self.truthy_conditions && *condition_type != Type::Bool {
                         return Err(ParserError::InvalidCondition(condition_type.into_owned(), condition))
                     }
 
@@ -842,7 +1774,7 @@ impl<'a> Parser<'a> {
                 Token::If => {
                     let condition = self.read_expression(context)?;
                     let condition_type = self.get_type_from_expression(None, &condition, context)?;
-                    if *condition_type != Type::Bool {
+                    if !self.truthy_conditions && *condition_type != Type::Bool {
                         return Err(ParserError::InvalidCondition(condition_type.into_owned(), condition))
                     }
 
@@ -864,7 +1796,9 @@ impl<'a> Parser<'a> {
                     Statement::If(condition, body, else_statement)
                 },
                 Token::BraceOpen => Statement::Scope(self.read_body(context, return_type)?),
-                Token::Let => Statement::Variable(self.read_variable(context, false)?),
+                Token::Let | Token::Var if self.peek_is(Token::ParenthesisOpen) => self.read_tuple_deconstruction(context, token == Token::Var)?,
+                Token::Let => Statement::Variable(self.read_variable(context, false, false)?),
+                Token::Var => Statement::Variable(self.read_variable(context, false, true)?),
                 Token::Return => {
                     let opt: Option<Expression> = if let Some(return_type) = return_type {
                         let expr = self.read_expr(None, true, true, Some(return_type), context)?;
@@ -889,30 +1823,47 @@ impl<'a> Parser<'a> {
                         return Err(ParserError::UnexpectedToken(Token::Continue));
                     }
 
+                    let depth = self.read_loop_depth(context)?;
                     // we can't have anything after a continue
                     if self.peek_is_not(Token::BraceClose) {
                         return Err(ParserError::DeadCodeNotAllowed);
                     }
 
-                    Statement::Continue
+                    Statement::Continue(depth)
                 },
                 Token::Break => {
                     if !context.is_in_a_loop() {
                         return Err(ParserError::UnexpectedToken(Token::Break));
                     }
 
+                    let depth = self.read_loop_depth(context)?;
                     // we can't have anything after a break
                     if self.peek_is_not(Token::BraceClose) {
                         return Err(ParserError::DeadCodeNotAllowed);
                     }
 
-                    Statement::Break
+                    Statement::Break(depth)
+                },
+                Token::Label(label) => { // Example: 'outer: while true {}
+                    if context.has_loop_label(label) {
+                        return Err(ParserError::LabelAlreadyUsed(label.to_owned()))
+                    }
+
+                    self.expect_token(Token::Colon)?;
+                    if !matches!(self.peek()?, Token::While | Token::For | Token::ForEach) {
+                        return Err(ParserError::ExpectedLoopAfterLabel(label.to_owned()))
+                    }
+
+                    context.set_next_loop_label(label);
+                    return self.read_statement(context, return_type)
                 },
                 token => {
-                    self.tokens.push_front(token);
+                    self.push_back(token);
                     Statement::Expression(self.read_expression(context)?)
                 }
             };
+
+            self.record_statement(&statement, start);
             Ok(Some(statement))
         } else {
             Ok(None)
@@ -932,14 +1883,32 @@ impl<'a> Parser<'a> {
     }
 
     // Read the parameters for a function
-    fn read_parameters(&mut self) -> Result<Vec<(&'a str, Type)>, ParserError<'a>> {
-        let mut parameters = Vec::new();
+    // If allowed, a parameter can have a default value: name: type = value
+    // Only the trailing parameters can have a default value
+    // The default value can't reference any variable
+    fn read_parameters(&mut self, allow_default: bool) -> Result<Vec<ParameterDeclaration<'a>>, ParserError<'a>> {
+        let mut parameters: Vec<ParameterDeclaration<'a>> = Vec::new();
         while self.peek_is_identifier() {
             let name = self.next_identifier()?;
             self.expect_token(Token::Colon)?;
             let value_type = self.read_type()?;
 
-            parameters.push((name, value_type));
+            let default_value = if allow_default && self.peek_is(Token::OperatorAssign) {
+                self.advance()?;
+                let mut context = Context::new();
+                context.begin_scope();
+                Some(self.read_value_for_type(&value_type, &mut context)?)
+            } else {
+                if parameters.last().is_some_and(|(_, _, default)| default.is_some()) {
+                    return Err(ParserError::DefaultParameterNotTrailing(name))
+                }
+                None
+            };
+
+            if parameters.len() >= self.max_parameters {
+                return Err(ParserError::TooManyParameters(self.max_parameters))
+            }
+            parameters.push((name, value_type, default_value));
 
             if self.peek_is_not(Token::Comma) {
                 break;
@@ -951,31 +1920,23 @@ impl<'a> Parser<'a> {
         Ok(parameters)
     }
 
-    // Verify that the last statement is a return
-    // We don't check the last statement directly has it would allow
-    // to have dead code after a return
-    fn ends_with_return(statements: &Vec<Statement>) -> Result<bool, ParserError<'a>> {
-        let mut ok = false;
-        if let Some(statement) = statements.last() {
-            match statement {
-                Statement::If(_, statements, else_statements) => {
-                    // if its the last statement
-                    ok = Self::ends_with_return(statements)?;
-                    // if it ends with a return, else must also end with a return
-                    if let Some(statements) = else_statements.as_ref().filter(|_| ok) {
-                        ok = Self::ends_with_return(statements)?;
-                    } else {
-                        ok = false;
-                    }
-                }
-                Statement::Return(Some(_)) => {
-                    ok = true;
-                },
-                _ => {}
-            }
+    // Variable at the root of an assignment target such as a, a.b or a[0]
+    fn get_assigned_variable(expr: &Expression) -> Option<IdentifierType> {
+        match expr {
+            Expression::Variable(id) => Some(*id),
+            Expression::Path(left, _) | Expression::ArrayCall(left, _) => Self::get_assigned_variable(left),
+            _ => None
         }
+    }
 
-        Ok(ok)
+    // Ids of the environment functions that never return
+    fn get_diverging_functions(environment: &EnvironmentBuilder) -> Vec<IdentifierType> {
+        let panic = Signature::new("panic".to_owned(), None, vec![Type::Any]);
+        environment.get_functions_mapper()
+            .get(&panic)
+            .ok()
+            .into_iter()
+            .collect()
     }
 
     /**
@@ -985,17 +1946,27 @@ impl<'a> Parser<'a> {
      * - fn foo() -> u64 { ... }
      * - fn foo(a: u64, b: u64) { ... }
      * - fn (f Foo) bar() { ... }
+     * - fn (self Foo) bar() { ... }
      * Rules:
      * - Signature is based on function name, and parameters
      * - Entry function is a "public callable" function and must return a u64 value
+     * - Receiver type of a method can't be a primitive type
      */
     fn read_function(&mut self, entry: bool, context: &mut Context<'a>) -> Result<(), ParserError<'a>> {
+        if self.functions.len() >= self.max_functions {
+            return Err(ParserError::TooManyFunctions(self.max_functions))
+        }
+
         context.begin_scope();
 
         let token = self.advance()?;
         let (instance_name, for_type, name) = if !entry && token == Token::ParenthesisOpen {
             let instance_name = self.next_identifier()?;
             let for_type = self.read_type()?;
+            // methods can only be declared on non-primitive types
+            if for_type.is_primitive() {
+                return Err(ParserError::InvalidForTypePrimitive(for_type))
+            }
             let id = context.register_variable(instance_name, for_type.clone())?;
             self.expect_token(Token::ParenthesisClose)?;
 
@@ -1008,7 +1979,7 @@ impl<'a> Parser<'a> {
         };
 
         self.expect_token(Token::ParenthesisOpen)?;
-        let parameters = self.read_parameters()?;
+        let parameters = self.read_parameters(true)?;
         self.expect_token(Token::ParenthesisClose)?;
 
         // all entries must return a u64 value without being specified
@@ -1032,22 +2003,26 @@ impl<'a> Parser<'a> {
             return Err(ParserError::FunctionSignatureAlreadyExist) 
         }
 
+        let names = parameters.iter().map(|p| p.0).collect();
+
         let has_return_type = return_type.is_some();
 
         let mut new_params = Vec::with_capacity(parameters.len());
-        for (name, param_type) in parameters {
+        for (name, param_type, default_value) in parameters {
             let id = context.register_variable(name, param_type.clone())?;
-            new_params.push(Parameter::new(id, param_type));
+            new_params.push(Parameter::with_default_value(id, param_type, default_value));
         }
 
         self.expect_token(Token::BraceOpen)?;
+        context.set_return_type(return_type.clone());
         let statements = self.read_body(context, &return_type)?;
+        context.set_return_type(None);
 
         context.end_scope();
 
-        // verify that the function ends with a return
-        if has_return_type && !Self::ends_with_return(&statements)? {
-            return Err(ParserError::NoReturnFound)
+        // verify that every path of the function returns a value
+        if has_return_type && !flow::always_returns(&statements, &self.diverging_functions) {
+            return Err(ParserError::NoReturnFound(name))
         }
 
         let function = match entry {
@@ -1064,6 +2039,7 @@ impl<'a> Parser<'a> {
 
         // push function before reading statements to allow recursive calls
         self.functions.push(function);
+        self.functions_parameters_names.push((name, names));
 
         Ok(())
     }
@@ -1072,7 +2048,7 @@ impl<'a> Parser<'a> {
     // import "filename.xel";
     // or with an alias:
     // import "filename.xel" as alias;
-    fn read_import(&mut self) -> Result<(), ParserError<'a>> {
+    fn read_import(&mut self, context: &mut Context<'a>) -> Result<(), ParserError<'a>> {
         let path = self.advance()?;
 
         let Token::Value(Literal::String(path)) = path else {
@@ -1090,7 +2066,39 @@ impl<'a> Parser<'a> {
             return Err(ParserError::NotImplemented)
         }
 
-        Ok(())
+        let path = path.into_owned();
+        if let Some(index) = self.import_stack.iter().position(|p| *p == path) {
+            let mut cycle = self.import_stack[index..].to_vec();
+            cycle.push(path);
+            return Err(ParserError::CyclicImport(cycle))
+        }
+
+        // Already imported through another file
+        if self.imported.contains(&path) {
+            return Ok(())
+        }
+
+        let source = self.import_loader
+            .ok_or_else(|| ParserError::ImportNotFound(path.clone()))?
+            .load(&path)
+            .map_err(|_| ParserError::ImportNotFound(path.clone()))?;
+        let tokens = Lexer::new(source).get()
+            .map_err(|e| ParserError::ImportLexerError(path.clone(), e))?;
+
+        // Declarations of the imported file are read before continuing the current one
+        // The imported nodes have no span as they come from another source
+        self.import_stack.push(path);
+        let tokens = std::mem::replace(&mut self.tokens, tokens);
+        let spans = self.spans.take();
+        let res = self.read_declarations(context);
+        self.tokens = tokens;
+        self.spans = spans;
+
+        if let Some(path) = self.import_stack.pop() {
+            self.imported.insert(path);
+        }
+
+        res
     }
 
     // check if a function with the same signature exists
@@ -1137,45 +2145,116 @@ impl<'a> Parser<'a> {
         };
 
         self.expect_token(Token::BraceOpen)?;
-        let params = self.read_parameters()?;
-        let mut fields = Vec::with_capacity(params.len());
-        for (name, param_type) in params {
-            fields.push((name, param_type));
+        let mut fields = Vec::new();
+        let mut defaults = Vec::new();
+        while self.peek_is_identifier() {
+            let field_name = self.next_identifier()?;
+            self.expect_token(Token::Colon)?;
+            let field_type = self.read_type()?;
+
+            // A default value can't reference any variable
+            let default_value = if self.peek_is(Token::OperatorAssign) {
+                self.advance()?;
+                let mut context = Context::new();
+                context.begin_scope();
+                Some(self.read_value_for_type(&field_type, &mut context)?)
+            } else {
+                None
+            };
+
+            fields.push((field_name, field_type));
+            defaults.push(default_value);
+
+            if self.peek_is_not(Token::Comma) {
+                break;
+            }
+            self.expect_token(Token::Comma)?;
         }
 
         self.expect_token(Token::BraceClose)?;
 
         self.struct_manager.add(Cow::Borrowed(name), fields)?;
+        if defaults.iter().any(Option::is_some) {
+            let id = self.struct_manager.get_by_name(name)?.inner().id();
+            self.struct_defaults.insert(id, defaults);
+        }
 
         Ok(())
     }
 
-    // Parse the tokens and return a Program
-    // The function mapper is also returned for external calls
-    pub fn parse(mut self) -> Result<(Program, FunctionMapper<'a>), ParserError<'a>> {
-        let mut context: Context = Context::new();
+    // Read all the top-level declarations until the end of the tokens
+    fn read_declarations(&mut self, context: &mut Context<'a>) -> Result<(), ParserError<'a>> {
         while let Some(token) = self.next() {
             match token {
-                Token::Import => {
-                    self.read_import()?;
-                    continue;
-                }
+                Token::Import => self.read_import(context)?,
                 Token::Const => {
-                    let var = self.read_variable(&mut context, true)?;
+                    let mut var = self.read_variable(context, true, false)?;
                     let id = var.id;
+
+                    // Constants must be known at parse time
+                    let value = const_eval_with(&var.value, &self.constants_values)?;
+                    var.value = Expression::Value(value.clone());
+                    self.constants_values.insert(id, value);
+
                     if !self.constants.insert(var) {
                         return Err(ParserError::VariableIdAlreadyUsed(id))
                     }
                 },
-                Token::Function => self.read_function(false, &mut context)?,
-                Token::Entry => self.read_function(true, &mut context)?,
+                Token::Function => self.read_function(false, context)?,
+                Token::Entry => self.read_function(true, context)?,
                 Token::Struct => self.read_struct()?,
                 token => return Err(ParserError::UnexpectedToken(token))
             };
         }
 
+        Ok(())
+    }
+
+    // Parse the tokens and return a Program
+    // The function mapper is also returned for external calls
+    pub fn parse(self) -> Result<(Program, FunctionMapper<'a>), ParserError<'a>> {
+        self.parse_with_spans()
+            .map(|(program, mapper, _)| (program, mapper))
+    }
+
+    // Same as parse, but also returns the spans of the statements and expressions
+    // The spans are empty if the parser wasn't created using with_spans
+    pub fn parse_with_spans(mut self) -> Result<(Program, FunctionMapper<'a>, Spans), ParserError<'a>> {
+        let mut context: Context = Context::new();
+        self.read_declarations(&mut context)?;
+
         let program = Program::with(self.constants, self.struct_manager.finalize(), self.functions);
-        Ok((program, self.functions_mapper))
+        assignment::check_program(&program)?;
+
+        let spans = self.spans
+            .map(|spans| spans.resolve(&program))
+            .unwrap_or_default();
+
+        Ok((program, self.functions_mapper, spans))
+    }
+
+    // Parse the tokens as a single expression, such as a line typed in a REPL
+    // No variable is in scope and the expression must produce a value
+    // Statements and declarations are rejected
+    pub fn parse_expression(mut self) -> Result<(Expression, Type), ParserError<'a>> {
+        match self.peek()? {
+            Token::Import | Token::Const | Token::Function | Token::Entry | Token::Struct
+            | Token::Let | Token::Var | Token::Return | Token::If | Token::Else | Token::For
+            | Token::ForEach | Token::While | Token::Break | Token::Continue => {
+                return Err(ParserError::ExpectedExpression(self.advance()?))
+            },
+            _ => {}
+        }
+
+        let mut context: Context = Context::new();
+        context.begin_scope();
+        let expression = self.read_expression(&mut context)?;
+        if let Some(token) = self.next() {
+            return Err(ParserError::UnexpectedToken(token))
+        }
+
+        let _type = self.get_type_from_expression(None, &expression, &context)?.into_owned();
+        Ok((expression, _type))
     }
 }
 
@@ -1214,6 +2293,18 @@ mod tests {
         parser.read_statements(&mut context, return_type).unwrap()
     }
 
+    #[track_caller]
+    fn test_parser_statement_error(tokens: Vec<Token>, variables: Vec<(&str, Type)>, env: EnvironmentBuilder) {
+        let mut parser = Parser::new(VecDeque::from(tokens), &env);
+        let mut context = Context::new();
+        context.begin_scope();
+        for (name, t) in variables {
+            context.register_variable(name, t).unwrap();
+        }
+
+        assert!(parser.read_statements(&mut context, &None).is_err());
+    }
+
     fn test_parser_statement_with_return_type(tokens: Vec<Token>, variables: Vec<(&str, Type)>, return_type: Type) -> Vec<Statement> {
         let env = EnvironmentBuilder::new();
         test_parser_statement_with(tokens, variables, &Some(return_type), env)
@@ -1245,7 +2336,8 @@ mod tests {
                     value: Expression::Range(
                         Box::new(Expression::Value(Value::U64(0))),
                         Box::new(Expression::Value(Value::U64(10))),
-                    )
+                    ),
+                    mutable: false
                 }
             )
         );
@@ -1347,7 +2439,8 @@ mod tests {
                         Expression::Value(Value::U64(1)),
                         Expression::Value(Value::U64(2)),
                         Expression::Value(Value::U64(3))
-                    ])
+                    ]),
+                    mutable: false
                 }
             ),
             Statement::Return(Some(Expression::Operator(
@@ -1668,27 +2761,27 @@ mod tests {
     fn test_ends_with_return() {
         const RETURN: Statement = Statement::Return(Some(Expression::Value(Value::U64(0))));
         let statements = vec![RETURN];
-        assert!(Parser::ends_with_return(&statements).unwrap());
+        assert!(flow::always_returns(&statements, &[]));
 
         let statements = vec![RETURN, Statement::Expression(Expression::Value(Value::U64(0)))];
-        assert!(!Parser::ends_with_return(&statements).unwrap());
+        assert!(!flow::always_returns(&statements, &[]));
 
         // if ... return
         let statements = vec![Statement::If(Expression::Value(Value::Boolean(true)), Vec::new(), None), RETURN];
-        assert!(Parser::ends_with_return(&statements).unwrap());
+        assert!(flow::always_returns(&statements, &[]));
 
         let statements = vec![Statement::If(Expression::Value(Value::Boolean(true)), Vec::new(), None)];
-        assert!(!Parser::ends_with_return(&statements).unwrap());
+        assert!(!flow::always_returns(&statements, &[]));
 
         // if else
         let statements = vec![Statement::If(Expression::Value(Value::Boolean(true)), Vec::new(), Some(Vec::new()))];
-        assert!(!Parser::ends_with_return(&statements).unwrap());
+        assert!(!flow::always_returns(&statements, &[]));
 
         // if return else return
         let statements = vec![
             Statement::If(Expression::Value(Value::Boolean(true)), vec![RETURN], Some(vec![RETURN]))
         ];
-        assert!(Parser::ends_with_return(&statements).unwrap());
+        assert!(flow::always_returns(&statements, &[]));
 
         // if return else if return else no return
         let statements = vec![
@@ -1700,7 +2793,7 @@ mod tests {
                 ])
             )
         ];
-        assert!(!Parser::ends_with_return(&statements).unwrap());
+        assert!(!flow::always_returns(&statements, &[]));
 
         // if return else if return else return
         let statements = vec![
@@ -1712,7 +2805,118 @@ mod tests {
                 ])
             )
         ];
-        assert!(Parser::ends_with_return(&statements).unwrap());
+        assert!(flow::always_returns(&statements, &[]));
+
+        // scope return
+        let statements = vec![Statement::Scope(vec![RETURN])];
+        assert!(flow::always_returns(&statements, &[]));
+
+        // infinite loop without break
+        let statements = vec![Statement::While(Expression::Value(Value::Boolean(true)), Vec::new())];
+        assert!(flow::always_returns(&statements, &[]));
+
+        let statements = vec![
+            Statement::While(
+                Expression::Value(Value::Boolean(true)),
+                vec![Statement::If(Expression::Value(Value::Boolean(true)), vec![Statement::Break(0)], None)]
+            )
+        ];
+        assert!(!flow::always_returns(&statements, &[]));
+
+        // call to a diverging function
+        let statements = vec![Statement::Expression(Expression::FunctionCall(None, 0, Vec::new()))];
+        assert!(flow::always_returns(&statements, &[0]));
+        assert!(!flow::always_returns(&statements, &[1]));
+    }
+
+    #[test]
+    fn test_function_all_paths_return() {
+        let env = EnvironmentBuilder::default();
+
+        let code = "fn f(a: u64) -> u64 { if a > 0 { return 1 } else { return 0 } }";
+        let tokens = Lexer::new(code).get().unwrap();
+        assert!(Parser::new(tokens, &env).parse().is_ok());
+
+        let code = "fn f(a: u64) -> u64 { if a > 0 { return 1 } }";
+        let tokens = Lexer::new(code).get().unwrap();
+        assert!(matches!(Parser::new(tokens, &env).parse(), Err(ParserError::NoReturnFound("f"))));
+
+        let code = "fn f(a: u64) -> u64 { if a > 0 { return 1 } panic(\"zero\") }";
+        let tokens = Lexer::new(code).get().unwrap();
+        assert!(Parser::new(tokens, &env).parse().is_ok());
+    }
+
+    #[test]
+    fn test_operator_precedence() {
+        let env = EnvironmentBuilder::new();
+        let variables = vec![("a", Type::U64), ("b", Type::U64), ("c", Type::U64)];
+
+        // a + b * c is a + (b * c), the brace ends the statements
+        let tokens = Lexer::new("a + b * c }").get().unwrap().into();
+        let statements = test_parser_statement_with(tokens, variables.clone(), &None, env);
+        assert_eq!(statements, vec![
+            Statement::Expression(Expression::Operator(
+                Operator::Plus,
+                Box::new(Expression::Variable(0)),
+                Box::new(Expression::Operator(Operator::Multiply, Box::new(Expression::Variable(1)), Box::new(Expression::Variable(2))))
+            ))
+        ]);
+
+        // a - b - c is (a - b) - c
+        let env = EnvironmentBuilder::new();
+        let tokens = Lexer::new("a - b - c }").get().unwrap().into();
+        let statements = test_parser_statement_with(tokens, variables.clone(), &None, env);
+        assert_eq!(statements, vec![
+            Statement::Expression(Expression::Operator(
+                Operator::Minus,
+                Box::new(Expression::Operator(Operator::Minus, Box::new(Expression::Variable(0)), Box::new(Expression::Variable(1)))),
+                Box::new(Expression::Variable(2))
+            ))
+        ]);
+
+        // a = b + c is a = (b + c)
+        let env = EnvironmentBuilder::new();
+        let tokens = Lexer::new("a = b + c }").get().unwrap().into();
+        let statements = test_parser_statement_with(tokens, variables.clone(), &None, env);
+        assert_eq!(statements, vec![
+            Statement::Expression(Expression::Operator(
+                Operator::Assign(None),
+                Box::new(Expression::Variable(0)),
+                Box::new(Expression::Operator(Operator::Plus, Box::new(Expression::Variable(1)), Box::new(Expression::Variable(2))))
+            ))
+        ]);
+
+        // a = b = c is a = (b = c), an assignment has no value
+        let env = EnvironmentBuilder::new();
+        let mut parser = Parser::new(Lexer::new("a = b = c }").get().unwrap(), &env);
+        let mut context = Context::new();
+        context.begin_scope();
+        for (name, t) in variables {
+            context.register_variable(name, t).unwrap();
+        }
+        assert!(matches!(parser.read_statements(&mut context, &None), Err(ParserError::AssignReturnNothing)));
+    }
+
+    #[test]
+    fn test_chained_comparison() {
+        let env = EnvironmentBuilder::new();
+
+        let code = "entry main() { let x: u64 = 5; return (0 < x < 10) as u64 }";
+        let tokens = Lexer::new(code).get().unwrap();
+        assert!(matches!(Parser::new(tokens, &env).parse(), Err(ParserError::ChainedComparisonNotAllowed)));
+
+        let code = "entry main() { let x: u64 = 5; let b: bool = x == 5 != false; return 0 }";
+        let tokens = Lexer::new(code).get().unwrap();
+        assert!(matches!(Parser::new(tokens, &env).parse(), Err(ParserError::ChainedComparisonNotAllowed)));
+
+        // Combined with && or parenthesized
+        let code = "entry main() { let x: u64 = 5; return (0 < x && x < 10) as u64 }";
+        let tokens = Lexer::new(code).get().unwrap();
+        assert!(Parser::new(tokens, &env).parse().is_ok());
+
+        let code = "entry main() { let x: u64 = 5; return ((x == 5) != false) as u64 }";
+        let tokens = Lexer::new(code).get().unwrap();
+        assert!(Parser::new(tokens, &env).parse().is_ok());
     }
 
     #[test]
@@ -1731,6 +2935,41 @@ mod tests {
         assert_eq!(statements.len(), 1);
     }
 
+    #[test]
+    fn test_number_type_inference() {
+        // let a: u16 = 300;
+        let tokens = vec![
+            Token::Let,
+            Token::Identifier("a"),
+            Token::Colon,
+            Token::Number(NumberType::U16),
+            Token::OperatorAssign,
+            Token::Value(Literal::Number(300)),
+        ];
+
+        let statements = test_parser_statement(tokens, Vec::new());
+        let Statement::Variable(declaration) = &statements[0] else {
+            panic!("expected a variable declaration");
+        };
+        assert_eq!(declaration.value, Expression::Value(Value::U16(300)));
+
+        // let a: u8 = 300;
+        let tokens = vec![
+            Token::Let,
+            Token::Identifier("a"),
+            Token::Colon,
+            Token::Number(NumberType::U8),
+            Token::OperatorAssign,
+            Token::Value(Literal::Number(300)),
+        ];
+
+        let env = EnvironmentBuilder::new();
+        let mut parser = Parser::new(VecDeque::from(tokens), &env);
+        let mut context = Context::new();
+        context.begin_scope();
+        assert!(matches!(parser.read_statements(&mut context, &None), Err(ParserError::InvalidNumberValueForType)));
+    }
+
     #[test]
     fn test_ternary() {
         // i < 10 ? 1 : 0
@@ -1818,4 +3057,1178 @@ mod tests {
         let statements = test_parser_statement_with(tokens, Vec::new(), &None, env);
         assert_eq!(statements.len(), 1);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_array_trailing_comma() {
+        // let a: u64[] = [1, 2, 3,];
+        let tokens = vec![
+            Token::Let,
+            Token::Identifier("a"),
+            Token::Colon,
+            Token::Number(NumberType::U64),
+            Token::BracketOpen,
+            Token::BracketClose,
+            Token::OperatorAssign,
+            Token::BracketOpen,
+            Token::Value(Literal::U64(1)),
+            Token::Comma,
+            Token::Value(Literal::U64(2)),
+            Token::Comma,
+            Token::Value(Literal::U64(3)),
+            Token::Comma,
+            Token::BracketClose,
+        ];
+
+        let statements = test_parser_statement(tokens, Vec::new());
+        assert_eq!(statements, vec![
+            Statement::Variable(DeclarationStatement {
+                id: 0,
+                value_type: Type::Array(Box::new(Type::U64)),
+                value: Expression::ArrayConstructor(vec![
+                    Expression::Value(Value::U64(1)),
+                    Expression::Value(Value::U64(2)),
+                    Expression::Value(Value::U64(3))
+                ]),
+                mutable: false
+            })
+        ]);
+    }
+
+    #[test]
+    fn test_array_invalid_commas() {
+        // let a: u64[] = [,];
+        let tokens = vec![
+            Token::Let,
+            Token::Identifier("a"),
+            Token::Colon,
+            Token::Number(NumberType::U64),
+            Token::BracketOpen,
+            Token::BracketClose,
+            Token::OperatorAssign,
+            Token::BracketOpen,
+            Token::Comma,
+            Token::BracketClose,
+        ];
+        test_parser_statement_error(tokens, Vec::new(), EnvironmentBuilder::new());
+
+        // let a: u64[] = [1,, 2];
+        let tokens = vec![
+            Token::Let,
+            Token::Identifier("a"),
+            Token::Colon,
+            Token::Number(NumberType::U64),
+            Token::BracketOpen,
+            Token::BracketClose,
+            Token::OperatorAssign,
+            Token::BracketOpen,
+            Token::Value(Literal::U64(1)),
+            Token::Comma,
+            Token::Comma,
+            Token::Value(Literal::U64(2)),
+            Token::BracketClose,
+        ];
+        test_parser_statement_error(tokens, Vec::new(), EnvironmentBuilder::new());
+
+        // let a: u64[] = [1, 2,,];
+        let tokens = vec![
+            Token::Let,
+            Token::Identifier("a"),
+            Token::Colon,
+            Token::Number(NumberType::U64),
+            Token::BracketOpen,
+            Token::BracketClose,
+            Token::OperatorAssign,
+            Token::BracketOpen,
+            Token::Value(Literal::U64(1)),
+            Token::Comma,
+            Token::Value(Literal::U64(2)),
+            Token::Comma,
+            Token::Comma,
+            Token::BracketClose,
+        ];
+        test_parser_statement_error(tokens, Vec::new(), EnvironmentBuilder::new());
+    }
+
+    #[test]
+    fn test_struct_constructor_trailing_comma() {
+        let env = || {
+            let mut env = EnvironmentBuilder::new();
+            env.register_structure("Message", vec![
+                ("message_id", Type::U64),
+                ("message", Type::String)
+            ]);
+            env
+        };
+
+        // let msg: Message = Message { message_id: 0, message: "hello", };
+        let tokens = vec![
+            Token::Let,
+            Token::Identifier("msg"),
+            Token::Colon,
+            Token::Identifier("Message"),
+            Token::OperatorAssign,
+            Token::Identifier("Message"),
+            Token::BraceOpen,
+            Token::Identifier("message_id"),
+            Token::Colon,
+            Token::Value(Literal::U64(0)),
+            Token::Comma,
+            Token::Identifier("message"),
+            Token::Colon,
+            Token::Value(Literal::String(Cow::Borrowed("hello"))),
+            Token::Comma,
+            Token::BraceClose
+        ];
+
+        let statements = test_parser_statement_with(tokens, Vec::new(), &None, env());
+        assert_eq!(statements.len(), 1);
+
+        // Shorthand fields with and without a trailing comma
+        // let msg: Message = Message { message_id, message };
+        let mut tokens = vec![
+            Token::Let,
+            Token::Identifier("msg"),
+            Token::Colon,
+            Token::Identifier("Message"),
+            Token::OperatorAssign,
+            Token::Identifier("Message"),
+            Token::BraceOpen,
+            Token::Identifier("message_id"),
+            Token::Comma,
+            Token::Identifier("message"),
+            Token::BraceClose
+        ];
+        let variables = vec![("message_id", Type::U64), ("message", Type::String)];
+
+        let statements = test_parser_statement_with(tokens.clone(), variables.clone(), &None, env());
+        assert_eq!(statements.len(), 1);
+
+        tokens.insert(tokens.len() - 1, Token::Comma);
+        let statements = test_parser_statement_with(tokens, variables, &None, env());
+        assert_eq!(statements.len(), 1);
+    }
+
+    #[test]
+    fn test_struct_constructor_invalid_commas() {
+        let env = || {
+            let mut env = EnvironmentBuilder::new();
+            env.register_structure("Message", vec![
+                ("message_id", Type::U64),
+                ("message", Type::String)
+            ]);
+            env
+        };
+
+        // let msg: Message = Message { message_id: 0,, message: "hello" };
+        let tokens = vec![
+            Token::Let,
+            Token::Identifier("msg"),
+            Token::Colon,
+            Token::Identifier("Message"),
+            Token::OperatorAssign,
+            Token::Identifier("Message"),
+            Token::BraceOpen,
+            Token::Identifier("message_id"),
+            Token::Colon,
+            Token::Value(Literal::U64(0)),
+            Token::Comma,
+            Token::Comma,
+            Token::Identifier("message"),
+            Token::Colon,
+            Token::Value(Literal::String(Cow::Borrowed("hello"))),
+            Token::BraceClose
+        ];
+        test_parser_statement_error(tokens, Vec::new(), env());
+
+        // let msg: Message = Message { message_id: 0, message: "hello",, };
+        let tokens = vec![
+            Token::Let,
+            Token::Identifier("msg"),
+            Token::Colon,
+            Token::Identifier("Message"),
+            Token::OperatorAssign,
+            Token::Identifier("Message"),
+            Token::BraceOpen,
+            Token::Identifier("message_id"),
+            Token::Colon,
+            Token::Value(Literal::U64(0)),
+            Token::Comma,
+            Token::Identifier("message"),
+            Token::Colon,
+            Token::Value(Literal::String(Cow::Borrowed("hello"))),
+            Token::Comma,
+            Token::Comma,
+            Token::BraceClose
+        ];
+        test_parser_statement_error(tokens, Vec::new(), env());
+    }
+
+    #[test]
+    fn test_function_trailing_comma() {
+        // fn foo(a: u64, b: u64,) {}
+        // entry main() { foo(1, 2,); return 0 }
+        let tokens = vec![
+            Token::Function,
+            Token::Identifier("foo"),
+            Token::ParenthesisOpen,
+            Token::Identifier("a"),
+            Token::Colon,
+            Token::Number(NumberType::U64),
+            Token::Comma,
+            Token::Identifier("b"),
+            Token::Colon,
+            Token::Number(NumberType::U64),
+            Token::Comma,
+            Token::ParenthesisClose,
+            Token::BraceOpen,
+            Token::BraceClose,
+            Token::Entry,
+            Token::Identifier("main"),
+            Token::ParenthesisOpen,
+            Token::ParenthesisClose,
+            Token::BraceOpen,
+            Token::Identifier("foo"),
+            Token::ParenthesisOpen,
+            Token::Value(Literal::U64(1)),
+            Token::Comma,
+            Token::Value(Literal::U64(2)),
+            Token::Comma,
+            Token::ParenthesisClose,
+            Token::Return,
+            Token::Value(Literal::U64(0)),
+            Token::BraceClose
+        ];
+
+        let program = test_parser(tokens);
+        assert_eq!(program.functions().len(), 2);
+    }
+
+    #[test]
+    fn test_function_invalid_commas() {
+        let env = EnvironmentBuilder::default();
+
+        // fn foo(,) {}
+        let tokens = vec![
+            Token::Function,
+            Token::Identifier("foo"),
+            Token::ParenthesisOpen,
+            Token::Comma,
+            Token::ParenthesisClose,
+            Token::BraceOpen,
+            Token::BraceClose,
+        ];
+        assert!(Parser::new(VecDeque::from(tokens), &env).parse().is_err());
+
+        // fn foo(a: u64,,) {}
+        let tokens = vec![
+            Token::Function,
+            Token::Identifier("foo"),
+            Token::ParenthesisOpen,
+            Token::Identifier("a"),
+            Token::Colon,
+            Token::Number(NumberType::U64),
+            Token::Comma,
+            Token::Comma,
+            Token::ParenthesisClose,
+            Token::BraceOpen,
+            Token::BraceClose,
+        ];
+        assert!(Parser::new(VecDeque::from(tokens), &env).parse().is_err());
+
+        // fn foo(a: u64) {}
+        // entry main() { foo(1,,); return 0 }
+        let tokens = vec![
+            Token::Function,
+            Token::Identifier("foo"),
+            Token::ParenthesisOpen,
+            Token::Identifier("a"),
+            Token::Colon,
+            Token::Number(NumberType::U64),
+            Token::ParenthesisClose,
+            Token::BraceOpen,
+            Token::BraceClose,
+            Token::Entry,
+            Token::Identifier("main"),
+            Token::ParenthesisOpen,
+            Token::ParenthesisClose,
+            Token::BraceOpen,
+            Token::Identifier("foo"),
+            Token::ParenthesisOpen,
+            Token::Value(Literal::U64(1)),
+            Token::Comma,
+            Token::Comma,
+            Token::ParenthesisClose,
+            Token::Return,
+            Token::Value(Literal::U64(0)),
+            Token::BraceClose
+        ];
+        assert!(Parser::new(VecDeque::from(tokens), &env).parse().is_err());
+    }
+
+    // fn sub(a: u64, b: u64) -> u64 { return a }
+    // entry main() { return sub(<args>) }
+    fn test_parser_named_arguments(args: Vec<Token>) -> Result<Program, String> {
+        let mut tokens = vec![
+            Token::Function,
+            Token::Identifier("sub"),
+            Token::ParenthesisOpen,
+            Token::Identifier("a"),
+            Token::Colon,
+            Token::Number(NumberType::U64),
+            Token::Comma,
+            Token::Identifier("b"),
+            Token::Colon,
+            Token::Number(NumberType::U64),
+            Token::ParenthesisClose,
+            Token::ReturnType,
+            Token::Number(NumberType::U64),
+            Token::BraceOpen,
+            Token::Return,
+            Token::Identifier("a"),
+            Token::BraceClose,
+            Token::Entry,
+            Token::Identifier("main"),
+            Token::ParenthesisOpen,
+            Token::ParenthesisClose,
+            Token::BraceOpen,
+            Token::Return,
+            Token::Identifier("sub"),
+            Token::ParenthesisOpen,
+        ];
+        tokens.extend(args);
+        tokens.push(Token::ParenthesisClose);
+        tokens.push(Token::BraceClose);
+
+        let env = EnvironmentBuilder::new();
+        Parser::new(VecDeque::from(tokens), &env).parse()
+            .map(|(program, _)| program)
+            .map_err(|e| format!("{:?}", e))
+    }
+
+    #[test]
+    fn test_named_arguments() {
+        // sub(b: 2, a: 10)
+        let program = test_parser_named_arguments(vec![
+            Token::Identifier("b"),
+            Token::Colon,
+            Token::Value(Literal::U64(2)),
+            Token::Comma,
+            Token::Identifier("a"),
+            Token::Colon,
+            Token::Value(Literal::U64(10)),
+        ]).unwrap();
+        let FunctionType::Entry(entry) = &program.functions()[1] else {
+            panic!("expected entry function");
+        };
+
+        // Arguments are reordered to match the declaration
+        assert_eq!(*entry.get_statements(), vec![
+            Statement::Return(Some(Expression::FunctionCall(
+                None,
+                0,
+                vec![
+                    Expression::Value(Value::U64(10)),
+                    Expression::Value(Value::U64(2))
+                ]
+            )))
+        ]);
+
+        // sub(10, b: 2)
+        assert!(test_parser_named_arguments(vec![
+            Token::Value(Literal::U64(10)),
+            Token::Comma,
+            Token::Identifier("b"),
+            Token::Colon,
+            Token::Value(Literal::U64(2)),
+        ]).is_ok());
+    }
+
+    #[test]
+    fn test_named_arguments_errors() {
+        // sub(a: 10, a: 2)
+        let err = test_parser_named_arguments(vec![
+            Token::Identifier("a"),
+            Token::Colon,
+            Token::Value(Literal::U64(10)),
+            Token::Comma,
+            Token::Identifier("a"),
+            Token::Colon,
+            Token::Value(Literal::U64(2)),
+        ]).unwrap_err();
+        assert_eq!(err, "DuplicateNamedArgument(\"a\")");
+
+        // sub(10, a: 2)
+        let err = test_parser_named_arguments(vec![
+            Token::Value(Literal::U64(10)),
+            Token::Comma,
+            Token::Identifier("a"),
+            Token::Colon,
+            Token::Value(Literal::U64(2)),
+        ]).unwrap_err();
+        assert_eq!(err, "DuplicateNamedArgument(\"a\")");
+
+        // sub(a: 10, c: 2)
+        let err = test_parser_named_arguments(vec![
+            Token::Identifier("a"),
+            Token::Colon,
+            Token::Value(Literal::U64(10)),
+            Token::Comma,
+            Token::Identifier("c"),
+            Token::Colon,
+            Token::Value(Literal::U64(2)),
+        ]).unwrap_err();
+        assert_eq!(err, "UnknownNamedArgument(\"c\")");
+
+        // sub(a: 10, 2)
+        let err = test_parser_named_arguments(vec![
+            Token::Identifier("a"),
+            Token::Colon,
+            Token::Value(Literal::U64(10)),
+            Token::Comma,
+            Token::Value(Literal::U64(2)),
+        ]).unwrap_err();
+        assert_eq!(err, "PositionalArgumentAfterNamed");
+    }
+
+    // fn f(<params>) {}
+    fn test_parser_default_parameters(params: Vec<Token>) -> Result<Program, String> {
+        let mut tokens = vec![
+            Token::Function,
+            Token::Identifier("f"),
+            Token::ParenthesisOpen,
+        ];
+        tokens.extend(params);
+        tokens.extend([
+            Token::ParenthesisClose,
+            Token::BraceOpen,
+            Token::BraceClose,
+        ]);
+
+        let env = EnvironmentBuilder::new();
+        Parser::new(VecDeque::from(tokens), &env).parse()
+            .map(|(program, _)| program)
+            .map_err(|e| format!("{:?}", e))
+    }
+
+    #[test]
+    fn test_default_parameters() {
+        // fn f(x: u64, y: u64 = 10) {}
+        let program = test_parser_default_parameters(vec![
+            Token::Identifier("x"),
+            Token::Colon,
+            Token::Number(NumberType::U64),
+            Token::Comma,
+            Token::Identifier("y"),
+            Token::Colon,
+            Token::Number(NumberType::U64),
+            Token::OperatorAssign,
+            Token::Value(Literal::U64(10)),
+        ]).unwrap();
+
+        let parameters = program.functions()[0].get_parameters();
+        assert!(parameters[0].get_default_value().is_none());
+        assert_eq!(parameters[1].get_default_value(), Some(&Expression::Value(Value::U64(10))));
+    }
+
+    #[test]
+    fn test_default_parameters_errors() {
+        // fn f(x: u64 = 10, y: u64) {}
+        let err = test_parser_default_parameters(vec![
+            Token::Identifier("x"),
+            Token::Colon,
+            Token::Number(NumberType::U64),
+            Token::OperatorAssign,
+            Token::Value(Literal::U64(10)),
+            Token::Comma,
+            Token::Identifier("y"),
+            Token::Colon,
+            Token::Number(NumberType::U64),
+        ]).unwrap_err();
+        assert_eq!(err, "DefaultParameterNotTrailing(\"y\")");
+
+        // fn f(x: u64 = "hello") {}
+        assert!(test_parser_default_parameters(vec![
+            Token::Identifier("x"),
+            Token::Colon,
+            Token::Number(NumberType::U64),
+            Token::OperatorAssign,
+            Token::Value(Literal::String("hello".into())),
+        ]).is_err());
+
+        // fn f(x: u64 = y) {}
+        assert!(test_parser_default_parameters(vec![
+            Token::Identifier("x"),
+            Token::Colon,
+            Token::Number(NumberType::U64),
+            Token::OperatorAssign,
+            Token::Identifier("y"),
+        ]).is_err());
+    }
+
+    // fn f(c: bool) -> u64 { var a: u64; <statements> return a }
+    fn test_parser_uninitialized(statements: Vec<Token>) -> Result<Program, String> {
+        let mut tokens = vec![
+            Token::Function,
+            Token::Identifier("f"),
+            Token::ParenthesisOpen,
+            Token::Identifier("c"),
+            Token::Colon,
+            Token::Bool,
+            Token::ParenthesisClose,
+            Token::ReturnType,
+            Token::Number(NumberType::U64),
+            Token::BraceOpen,
+            Token::Var,
+            Token::Identifier("a"),
+            Token::Colon,
+            Token::Number(NumberType::U64),
+        ];
+        tokens.extend(statements);
+        tokens.extend([
+            Token::Return,
+            Token::Identifier("a"),
+            Token::BraceClose,
+        ]);
+
+        let env = EnvironmentBuilder::new();
+        Parser::new(VecDeque::from(tokens), &env).parse()
+            .map(|(program, _)| program)
+            .map_err(|e| format!("{:?}", e))
+    }
+
+    #[test]
+    fn test_uninitialized_variable() {
+        // if c { a = 1 } else { a = 2 }
+        assert!(test_parser_uninitialized(vec![
+            Token::If,
+            Token::Identifier("c"),
+            Token::BraceOpen,
+            Token::Identifier("a"),
+            Token::OperatorAssign,
+            Token::Value(Literal::U64(1)),
+            Token::BraceClose,
+            Token::Else,
+            Token::BraceOpen,
+            Token::Identifier("a"),
+            Token::OperatorAssign,
+            Token::Value(Literal::U64(2)),
+            Token::BraceClose,
+        ]).is_ok());
+
+        // if c { a = 1 } else { return 0 }
+        assert!(test_parser_uninitialized(vec![
+            Token::If,
+            Token::Identifier("c"),
+            Token::BraceOpen,
+            Token::Identifier("a"),
+            Token::OperatorAssign,
+            Token::Value(Literal::U64(1)),
+            Token::BraceClose,
+            Token::Else,
+            Token::BraceOpen,
+            Token::Return,
+            Token::Value(Literal::U64(0)),
+            Token::BraceClose,
+        ]).is_ok());
+
+        // Not assigned at all
+        let err = test_parser_uninitialized(Vec::new()).unwrap_err();
+        assert_eq!(err, "PossiblyUninitializedVariable(1)");
+
+        // if c { a = 1 }
+        let err = test_parser_uninitialized(vec![
+            Token::If,
+            Token::Identifier("c"),
+            Token::BraceOpen,
+            Token::Identifier("a"),
+            Token::OperatorAssign,
+            Token::Value(Literal::U64(1)),
+            Token::BraceClose,
+        ]).unwrap_err();
+        assert_eq!(err, "PossiblyUninitializedVariable(1)");
+
+        // while c { a = 1 }
+        let err = test_parser_uninitialized(vec![
+            Token::While,
+            Token::Identifier("c"),
+            Token::BraceOpen,
+            Token::Identifier("a"),
+            Token::OperatorAssign,
+            Token::Value(Literal::U64(1)),
+            Token::BraceClose,
+        ]).unwrap_err();
+        assert_eq!(err, "PossiblyUninitializedVariable(1)");
+
+        // a += 1
+        let err = test_parser_uninitialized(vec![
+            Token::Identifier("a"),
+            Token::OperatorPlusAssign,
+            Token::Value(Literal::U64(1)),
+        ]).unwrap_err();
+        assert_eq!(err, "PossiblyUninitializedVariable(1)");
+    }
+
+    #[test]
+    fn test_try_operator_requires_result_function() {
+        // fn f(r: result<u64, string>) -> u64 { return r? }
+        let tokens = vec![
+            Token::Function,
+            Token::Identifier("f"),
+            Token::ParenthesisOpen,
+            Token::Identifier("r"),
+            Token::Colon,
+            Token::Result(Box::new(Token::Number(NumberType::U64)), Box::new(Token::String)),
+            Token::ParenthesisClose,
+            Token::ReturnType,
+            Token::Number(NumberType::U64),
+            Token::BraceOpen,
+            Token::Return,
+            Token::Identifier("r"),
+            Token::OperatorTernary,
+            Token::BraceClose,
+        ];
+
+        let env = EnvironmentBuilder::default();
+        let err = Parser::new(VecDeque::from(tokens), &env).parse().unwrap_err();
+        assert!(matches!(err, ParserError::InvalidTryOperator(_)));
+    }
+
+    // fn f() -> u64 { let a: u64[]...[] = [[...[1]...]]; return 0 }
+    fn nested_array_tokens(depth: usize) -> Vec<Token<'static>> {
+        let mut tokens = vec![
+            Token::Function,
+            Token::Identifier("f"),
+            Token::ParenthesisOpen,
+            Token::ParenthesisClose,
+            Token::ReturnType,
+            Token::Number(NumberType::U64),
+            Token::BraceOpen,
+            Token::Let,
+            Token::Identifier("a"),
+            Token::Colon,
+            Token::Number(NumberType::U64),
+        ];
+        for _ in 0..depth {
+            tokens.extend([Token::BracketOpen, Token::BracketClose]);
+        }
+        tokens.push(Token::OperatorAssign);
+        tokens.extend(std::iter::repeat_n(Token::BracketOpen, depth));
+        tokens.push(Token::Value(Literal::U64(1)));
+        tokens.extend(std::iter::repeat_n(Token::BracketClose, depth));
+        tokens.extend([
+            Token::Return,
+            Token::Value(Literal::U64(0)),
+            Token::BraceClose
+        ]);
+        tokens
+    }
+
+    // The depth limits are sized for release builds,
+    // debug builds use larger frames than the default test thread stack allows
+    fn with_large_stack<F: FnOnce() + Send + 'static>(f: F) {
+        std::thread::Builder::new()
+            .stack_size(64 * 1024 * 1024)
+            .spawn(f)
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+
+    #[test]
+    fn test_max_nesting_depth() {
+        with_large_stack(|| {
+            let env = EnvironmentBuilder::default();
+            assert!(Parser::new(VecDeque::from(nested_array_tokens(8)), &env).parse().is_ok());
+
+            let err = Parser::new(VecDeque::from(nested_array_tokens(100_000)), &env).parse().unwrap_err();
+            assert!(matches!(err, ParserError::MaxDepthReached(MAX_NESTING_DEPTH)));
+
+            // Limit is configurable
+            let mut parser = Parser::new(VecDeque::from(nested_array_tokens(8)), &env);
+            parser.set_max_depth(4);
+            assert!(matches!(parser.parse(), Err(ParserError::MaxDepthReached(4))));
+        });
+    }
+
+    #[test]
+    fn test_max_nesting_depth_chain() {
+        with_large_stack(|| {
+            let env = EnvironmentBuilder::default();
+            // fn f(a: u64) -> u64 { return a + a + ... + a }
+            let code = |count: usize| format!("fn f(a: u64) -> u64 {{ return {} }}", vec!["a"; count].join(" + "));
+
+            // Each operator nests the previous expression in the one built
+            let source = code(200);
+            assert!(Parser::new(Lexer::new(&source).get().unwrap(), &env).parse().is_ok());
+
+            let source = code(100_000);
+            let err = Parser::new(Lexer::new(&source).get().unwrap(), &env).parse().unwrap_err();
+            assert!(matches!(err, ParserError::MaxDepthReached(MAX_NESTING_DEPTH)));
+
+            let source = code(8);
+            let mut parser = Parser::new(Lexer::new(&source).get().unwrap(), &env);
+            parser.set_max_depth(4);
+            assert!(matches!(parser.parse(), Err(ParserError::MaxDepthReached(4))));
+        });
+    }
+
+    #[test]
+    fn test_max_parameters() {
+        let env = EnvironmentBuilder::default();
+        let code = |count: usize| {
+            let parameters = (0..count).map(|i| format!("p{i}: u64")).collect::<Vec<_>>().join(", ");
+            format!("fn f({parameters}) {{}} entry main() {{ return 0 }}")
+        };
+
+        let source = code(MAX_PARAMETERS_COUNT);
+        assert!(Parser::new(Lexer::new(&source).get().unwrap(), &env).parse().is_ok());
+
+        let source = code(MAX_PARAMETERS_COUNT + 1);
+        assert!(matches!(Parser::new(Lexer::new(&source).get().unwrap(), &env).parse(), Err(ParserError::TooManyParameters(MAX_PARAMETERS_COUNT))));
+
+        // Limit is configurable
+        let source = code(3);
+        let mut parser = Parser::new(Lexer::new(&source).get().unwrap(), &env);
+        parser.set_max_parameters(2);
+        assert!(matches!(parser.parse(), Err(ParserError::TooManyParameters(2))));
+    }
+
+    #[test]
+    fn test_max_functions() {
+        let env = EnvironmentBuilder::default();
+        let code = |count: usize| (0..count).map(|i| format!("fn f{i}() {{}}")).collect::<Vec<_>>().join(" ");
+
+        let source = code(MAX_FUNCTIONS_COUNT);
+        assert!(Parser::new(Lexer::new(&source).get().unwrap(), &env).parse().is_ok());
+
+        let source = code(MAX_FUNCTIONS_COUNT + 1);
+        assert!(matches!(Parser::new(Lexer::new(&source).get().unwrap(), &env).parse(), Err(ParserError::TooManyFunctions(MAX_FUNCTIONS_COUNT))));
+
+        let source = code(3);
+        let mut parser = Parser::new(Lexer::new(&source).get().unwrap(), &env);
+        parser.set_max_functions(2);
+        assert!(matches!(parser.parse(), Err(ParserError::TooManyFunctions(2))));
+    }
+
+    #[test]
+    fn test_max_nesting_depth_blocks() {
+        with_large_stack(|| {
+            // fn f() -> u64 { { { ... return 0 } } }
+            let depth = 100_000;
+            let mut tokens = vec![
+                Token::Function,
+                Token::Identifier("f"),
+                Token::ParenthesisOpen,
+                Token::ParenthesisClose,
+                Token::ReturnType,
+                Token::Number(NumberType::U64),
+                Token::BraceOpen,
+            ];
+            tokens.extend(std::iter::repeat_n(Token::BraceOpen, depth));
+            tokens.extend([Token::Return, Token::Value(Literal::U64(0))]);
+            tokens.extend(std::iter::repeat_n(Token::BraceClose, depth + 1));
+
+            let env = EnvironmentBuilder::default();
+            let err = Parser::new(VecDeque::from(tokens), &env).parse().unwrap_err();
+            assert!(matches!(err, ParserError::MaxDepthReached(_)));
+        });
+    }
+
+    #[test]
+    fn test_method_primitive_receiver() {
+        // fn (self u64) double() -> u64 { return self * 2 }
+        let tokens = vec![
+            Token::Function,
+            Token::ParenthesisOpen,
+            Token::Identifier("self"),
+            Token::Number(NumberType::U64),
+            Token::ParenthesisClose,
+            Token::Identifier("double"),
+            Token::ParenthesisOpen,
+            Token::ParenthesisClose,
+            Token::ReturnType,
+            Token::Number(NumberType::U64),
+            Token::BraceOpen,
+            Token::Return,
+            Token::Identifier("self"),
+            Token::OperatorMultiply,
+            Token::Value(Literal::U64(2)),
+            Token::BraceClose,
+        ];
+
+        let env = EnvironmentBuilder::default();
+        let err = Parser::new(VecDeque::from(tokens), &env).parse().unwrap_err();
+        assert!(matches!(err, ParserError::InvalidForTypePrimitive(Type::U64)));
+    }
+
+    // let a: u8 = 256u8;
+    fn literal_overflow_statement<'a>(env: &'a EnvironmentBuilder<'a>, mode: LiteralOverflow) -> Result<Vec<Statement>, ParserError<'a>> {
+        let tokens = vec![
+            Token::Let,
+            Token::Identifier("a"),
+            Token::Colon,
+            Token::Number(NumberType::U8),
+            Token::OperatorAssign,
+            Token::Value(Literal::OutOfRange(NumberType::U8, 256)),
+        ];
+
+        let mut parser = Parser::new(VecDeque::from(tokens), env);
+        parser.set_literal_overflow(mode);
+        let mut context = Context::new();
+        context.begin_scope();
+        parser.read_statements(&mut context, &None)
+    }
+
+    #[test]
+    fn test_literal_overflow() {
+        let expected = |value: u8| vec![
+            Statement::Variable(DeclarationStatement {
+                id: 0,
+                value_type: Type::U8,
+                value: Expression::Value(Value::U8(value)),
+                mutable: false
+            })
+        ];
+
+        let env = EnvironmentBuilder::default();
+        // Rejected by default
+        assert!(matches!(literal_overflow_statement(&env, LiteralOverflow::default()), Err(ParserError::InvalidNumberValueForType)));
+        assert_eq!(literal_overflow_statement(&env, LiteralOverflow::Saturate).unwrap(), expected(u8::MAX));
+        assert_eq!(literal_overflow_statement(&env, LiteralOverflow::Wrap).unwrap(), expected(0));
+    }
+
+    #[test]
+    fn test_tuple_errors() {
+        let env = EnvironmentBuilder::new();
+        let tuple_type = Type::Tuple(vec![Type::U64, Type::Bool]);
+        let read = |tokens: Vec<Token<'static>>| {
+            let mut parser = Parser::new(VecDeque::from(tokens), &env);
+            let mut context = Context::new();
+            context.begin_scope();
+            context.register_variable("t", tuple_type.clone()).unwrap();
+            parser.read_statements(&mut context, &None).map(|_| ())
+        };
+
+        // let (a, b, c) = (1, true)
+        let err = read(vec![
+            Token::Let,
+            Token::ParenthesisOpen,
+            Token::Identifier("a"),
+            Token::Comma,
+            Token::Identifier("b"),
+            Token::Comma,
+            Token::Identifier("c"),
+            Token::ParenthesisClose,
+            Token::OperatorAssign,
+            Token::ParenthesisOpen,
+            Token::Value(Literal::Number(1)),
+            Token::Comma,
+            Token::Value(Literal::Bool(true)),
+            Token::ParenthesisClose,
+        ]).unwrap_err();
+        assert!(matches!(err, ParserError::InvalidTupleDeconstruction(Type::Tuple(_), 3)));
+
+        // t.2 = 1
+        let err = read(vec![
+            Token::Identifier("t"),
+            Token::Dot,
+            Token::Value(Literal::Number(2)),
+            Token::OperatorAssign,
+            Token::Value(Literal::Number(1)),
+        ]).unwrap_err();
+        assert!(matches!(err, ParserError::InvalidTupleIndex(2, 2)));
+    }
+
+    // let v: <declared> = o.unwrap()
+    fn unwrap_statement<'a>(env: &'a EnvironmentBuilder<'a>, declared: Token<'a>, optional: Type) -> Result<Vec<Statement>, ParserError<'a>> {
+        let tokens = vec![
+            Token::Let,
+            Token::Identifier("v"),
+            Token::Colon,
+            declared,
+            Token::OperatorAssign,
+            Token::Identifier("o"),
+            Token::Dot,
+            Token::Identifier("unwrap"),
+            Token::ParenthesisOpen,
+            Token::ParenthesisClose,
+        ];
+
+        let mut parser = Parser::new(VecDeque::from(tokens), env);
+        let mut context = Context::new();
+        context.begin_scope();
+        context.register_variable("o", optional).unwrap();
+        parser.read_statements(&mut context, &None)
+    }
+
+    #[test]
+    fn test_generic_unwrap() {
+        let env = EnvironmentBuilder::default();
+        let optional = |t: Type| Type::Optional(Box::new(t));
+        assert!(unwrap_statement(&env, Token::Number(NumberType::U64), optional(Type::U64)).is_ok());
+        assert!(unwrap_statement(&env, Token::String, optional(Type::String)).is_ok());
+
+        // T is bound to the inner type of the optional
+        assert!(matches!(unwrap_statement(&env, Token::String, optional(Type::U64)), Err(ParserError::InvalidValueType(Type::U64, Type::String))));
+        assert!(matches!(unwrap_statement(&env, Token::Number(NumberType::U64), optional(Type::String)), Err(ParserError::InvalidValueType(Type::String, Type::U64))));
+    }
+
+    #[test]
+    fn test_generic_argument_conflict() {
+        // a.push("hello") with a: u64[]
+        let tokens = vec![
+            Token::Identifier("a"),
+            Token::Dot,
+            Token::Identifier("push"),
+            Token::ParenthesisOpen,
+            Token::Value(Literal::String(Cow::Borrowed("hello"))),
+            Token::ParenthesisClose,
+        ];
+
+        let env = EnvironmentBuilder::default();
+        let mut parser = Parser::new(VecDeque::from(tokens), &env);
+        let mut context = Context::new();
+        context.begin_scope();
+        context.register_variable("a", Type::Array(Box::new(Type::U64))).unwrap();
+        assert!(matches!(parser.read_statements(&mut context, &None), Err(ParserError::InvalidValueType(Type::String, Type::U64))));
+    }
+
+    #[test]
+    fn test_import_in_memory() {
+        let mut files = std::collections::HashMap::new();
+        files.insert("math.xel".to_owned(), "fn double(a: u64) -> u64 { return a * 2 }".to_owned());
+        let loader = ImportLoader::new(files);
+
+        let env = EnvironmentBuilder::default();
+        let tokens = Lexer::new("import \"math.xel\" entry main() { return double(21) }").get().unwrap();
+        let mut parser = Parser::new(tokens, &env);
+        parser.set_import_loader(&loader);
+
+        let (program, _) = parser.parse().unwrap();
+        assert_eq!(program.functions().len(), 2);
+
+        // Unknown file
+        let tokens = Lexer::new("import \"unknown.xel\" entry main() { return 0 }").get().unwrap();
+        let mut parser = Parser::new(tokens, &env);
+        parser.set_import_loader(&loader);
+        assert!(matches!(parser.parse(), Err(ParserError::ImportNotFound(path)) if path == "unknown.xel"));
+    }
+
+    #[test]
+    fn test_import_file_system() {
+        let dir = std::env::temp_dir().join(format!("xelis-import-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("math.xel"), "fn double(a: u64) -> u64 { return a * 2 }").unwrap();
+
+        let code = "import \"math.xel\" entry main() { return double(21) }";
+        let env = EnvironmentBuilder::default();
+        let loader = ImportLoader::new(FileSystemResolver::new(&dir));
+        let res = parse_with_imports(&loader, &env, code).map(|program| program.functions().len());
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(res.unwrap(), 2);
+
+        // Nothing can be imported without a loader
+        let res = Parser::new(Lexer::new(code).get().unwrap(), &env).parse();
+        assert!(matches!(res, Err(ParserError::ImportNotFound(path)) if path == "math.xel"));
+    }
+
+    fn memory_loader(files: &[(&str, &str)]) -> ImportLoader {
+        let files: std::collections::HashMap<String, String> = files.iter()
+            .map(|(path, code)| (path.to_string(), code.to_string()))
+            .collect();
+        ImportLoader::new(files)
+    }
+
+    fn parse_with_imports<'a>(loader: &'a ImportLoader, env: &'a EnvironmentBuilder<'a>, code: &'a str) -> Result<Program, ParserError<'a>> {
+        let mut parser = Parser::new(Lexer::new(code).get().unwrap(), env);
+        parser.set_import_loader(loader);
+        parser.parse().map(|(program, _)| program)
+    }
+
+    #[test]
+    fn test_direct_cyclic_import() {
+        let env = EnvironmentBuilder::default();
+        let loader = memory_loader(&[
+            ("a.xel", "import \"b.xel\""),
+            ("b.xel", "import \"a.xel\""),
+        ]);
+
+        let err = parse_with_imports(&loader, &env, "import \"a.xel\" entry main() { return 0 }").unwrap_err();
+        assert!(matches!(err, ParserError::CyclicImport(cycle) if cycle == ["a.xel", "b.xel", "a.xel"]));
+    }
+
+    #[test]
+    fn test_indirect_cyclic_import() {
+        let env = EnvironmentBuilder::default();
+        let loader = memory_loader(&[
+            ("a.xel", "import \"b.xel\""),
+            ("b.xel", "import \"c.xel\""),
+            ("c.xel", "import \"b.xel\""),
+        ]);
+
+        let err = parse_with_imports(&loader, &env, "import \"a.xel\" entry main() { return 0 }").unwrap_err();
+        assert!(matches!(err, ParserError::CyclicImport(cycle) if cycle == ["b.xel", "c.xel", "b.xel"]));
+    }
+
+    #[test]
+    fn test_diamond_import() {
+        let env = EnvironmentBuilder::default();
+        let loader = memory_loader(&[
+            ("b.xel", "import \"d.xel\" fn b() -> u64 { return d() }"),
+            ("c.xel", "import \"d.xel\" fn c() -> u64 { return d() }"),
+            ("d.xel", "fn d() -> u64 { return 1 }"),
+        ]);
+
+        // d is imported once, otherwise its function would be declared twice
+        let program = parse_with_imports(&loader, &env, "import \"b.xel\" import \"c.xel\" entry main() { return b() + c() }").unwrap();
+        assert_eq!(program.functions().len(), 4);
+    }
+
+    #[test]
+    fn test_reparse_identical_program() {
+        let code = r#"
+            struct Point { x: u64, y: u64 }
+            const MAX: u64 = 10
+            fn (p Point) sum() -> u64 {
+                return p.x + p.y
+            }
+            fn total(list: u64[], p: Point) -> u64 {
+                var total: u64 = 0;
+                foreach v in list {
+                    if v % 2 == 0 {
+                        total += v * p.sum()
+                    } else {
+                        total -= 1
+                    }
+                }
+                return total
+            }
+            entry main() {
+                return total([1, 2, 3], Point { x: 1, y: MAX })
+            }
+        "#;
+
+        let env = EnvironmentBuilder::default();
+        let parse = || {
+            let tokens = Lexer::new(code).get().unwrap();
+            Parser::new(tokens, &env).parse().unwrap().0
+        };
+
+        assert_eq!(parse(), parse());
+    }
+
+    #[test]
+    fn test_unused_variable_warnings() {
+        let env = EnvironmentBuilder::default();
+        let code = "entry main() { var a: u64 = 1; let unused: u64 = 2; let arr: u64[] = [1, 2]; foreach v in arr { a += v; } for i: u64 = 0; i < 2; i += 1 { let x: u64 = i; } return a }";
+        let (program, _) = Parser::new(Lexer::new(code).get().unwrap(), &env).parse().unwrap();
+
+        // unused is the variable 1, the slot of v is reused by i once the foreach is done
+        // so x is the variable 4
+        assert_eq!(xelis_ast::unused_variable_warnings(&program), vec![
+            xelis_ast::VariableWarning::Unused(0, 1),
+            xelis_ast::VariableWarning::Unused(0, 4)
+        ]);
+        assert!(xelis_ast::VariableUsage::of_function(&program.functions()[0]).free_variables().is_empty());
+    }
+
+    #[test]
+    fn test_loop_labels() {
+        let env = EnvironmentBuilder::default();
+        let parse = |code: &'static str| Parser::new(Lexer::new(code).get().unwrap(), &env).parse();
+
+        let (program, _) = parse("entry main() { 'a: while true { 'b: while true { break 'a; } } return 0 }").unwrap();
+        let Statement::While(_, statements) = &program.functions()[0].get_statements()[0] else {
+            panic!("expected a while loop")
+        };
+        assert!(matches!(&statements[0], Statement::While(_, inner) if inner[0] == Statement::Break(1)));
+
+        assert!(matches!(parse("entry main() { while true { break 'a; } return 0 }"), Err(ParserError::UnknownLabel(label)) if label == "a"));
+        assert!(matches!(parse("entry main() { 'a: while true { 'a: while true { break; } } return 0 }"), Err(ParserError::LabelAlreadyUsed(_))));
+        // A label belongs to its loop only
+        assert!(matches!(parse("entry main() { 'a: while true { break; } while true { break 'a; } return 0 }"), Err(ParserError::UnknownLabel(_))));
+        // break can't leave a block expression
+        assert!(matches!(parse("entry main() { 'a: while true { let x: u64 = { break 'a; 1 }; } return 0 }"), Err(ParserError::UnexpectedToken(Token::Break))));
+
+        // The labeled break is the only way out of the infinite loop
+        assert!(parse("fn f() -> u64 { 'a: while true { while true { break 'a; } } }").is_err());
+        assert!(parse("fn f() -> u64 { 'a: while true { while true { break; } } }").is_ok());
+    }
+
+    #[test]
+    fn test_assign_to_constant() {
+        let env = EnvironmentBuilder::default();
+        let parse = |code: &'static str| Parser::new(Lexer::new(code).get().unwrap(), &env).parse();
+
+        assert!(matches!(parse("const MAX: u64 = 10 entry main() { MAX = 5; return MAX }"), Err(ParserError::AssignToConstant(_))));
+        assert!(matches!(parse("const MAX: u64 = 10 entry main() { MAX += 5; return MAX }"), Err(ParserError::AssignToConstant(_))));
+        assert!(matches!(parse("const VALUES: u64[] = [1, 2] entry main() { VALUES[0] = 5; return VALUES[0] }"), Err(ParserError::AssignToConstant(_))));
+
+        // Reading a constant is still allowed
+        assert!(parse("const MAX: u64 = 10 entry main() { var a: u64 = MAX; a += MAX; return a }").is_ok());
+    }
+
+    #[test]
+    fn test_assign_to_immutable() {
+        let env = EnvironmentBuilder::default();
+        let parse = |code: &'static str| Parser::new(Lexer::new(code).get().unwrap(), &env).parse();
+        let is_immutable = |code: &'static str| matches!(parse(code), Err(ParserError::AssignToImmutable(_)));
+
+        assert!(parse("entry main() { var a: u64 = 1; a = 2; a += 3; return a }").is_ok());
+        assert!(parse("entry main() { var (a, b) = (1, 2); a = b; return a }").is_ok());
+
+        assert!(is_immutable("entry main() { let a: u64 = 1; a = 2; return a }"));
+        assert!(is_immutable("entry main() { let a: u64 = 1; a += 2; return a }"));
+        assert!(is_immutable("entry main() { let a: u64[] = [1]; a[0] = 2; return a[0] }"));
+        assert!(is_immutable("entry main() { let (a, b) = (1, 2); a = b; return a }"));
+        assert!(is_immutable("struct P { x: u64 } entry main() { let p: P = P { x: 1 }; p.x = 2; return p.x }"));
+
+        // Immutability belongs to the variable, the same value can be mutated through a copy
+        assert!(parse("entry main() { let a: u64[] = [1]; var b: u64[] = a; b[0] = 2; return a[0] }").is_ok());
+        assert!(is_immutable("entry main() { var a: u64[] = [1]; let b: u64[] = a; b[0] = 2; return a[0] }"));
+        assert!(parse("entry main() { { let a: u64 = 1; } var a: u64 = 2; a = 3; return a }").is_ok());
+
+        // Parameters and loop variables are mutable
+        assert!(parse("fn f(a: u64) -> u64 { a += 1; return a } entry main() { return f(1) }").is_ok());
+        assert!(parse("entry main() { var s: u64 = 0; for i: u64 = 0; i < 10; i += 1 { s += i; } return s }").is_ok());
+    }
+
+    #[test]
+    fn test_spans() {
+        let code = "fn f(v: u64 = 2) -> u64 {\n    return v\n}\nentry main() {\n    let a: u8 = 10;\n    return (a + 2) as u64 * f()\n}";
+        let tokens = Lexer::new(code).collect::<Result<VecDeque<_>, _>>().unwrap();
+        let env = EnvironmentBuilder::default();
+        let (program, _, spans) = Parser::with_spans(tokens, &env).parse_with_spans().unwrap();
+        let span = |line_start, column_start, line_end, column_end| Some(Span { line_start, column_start, line_end, column_end });
+
+        let main = &program.functions()[1];
+        let statements = main.get_statements();
+        assert_eq!(spans.statement(&statements[0]), span(5, 5, 5, 18));
+
+        let Statement::Return(Some(expr)) = &statements[1] else { unreachable!() };
+        assert_eq!(spans.statement(&statements[1]), span(6, 5, 6, 31));
+        assert_eq!(spans.expression(expr), span(6, 12, 6, 31));
+
+        // (a + 2) as u64 * f()
+        let Expression::Operator(Operator::Multiply, left, right) = expr else { unreachable!() };
+        assert_eq!(spans.expression(right), span(6, 29, 6, 31));
+        let Expression::Cast(sub, _) = left.as_ref() else { unreachable!() };
+        assert_eq!(spans.expression(left), span(6, 12, 6, 25));
+        assert_eq!(spans.expression(sub), span(6, 12, 6, 18));
+
+        let Expression::SubExpression(inner) = sub.as_ref() else { unreachable!() };
+        let Expression::Operator(Operator::Plus, a, two) = inner.as_ref() else { unreachable!() };
+        assert_eq!(spans.expression(inner), span(6, 13, 6, 17));
+        assert_eq!(spans.expression(a), span(6, 13, 6, 13));
+        assert_eq!(spans.expression(two), span(6, 17, 6, 17));
+
+        // The default value keeps its own span
+        let default_value = program.functions()[0].get_parameters()[0].get_default_value().unwrap();
+        assert_eq!(spans.expression(default_value), span(1, 15, 1, 15));
+
+        // Without the positions, no span is recorded
+        let tokens = Lexer::new(code).get().unwrap();
+        let (program, _, spans) = Parser::new(tokens, &env).parse_with_spans().unwrap();
+        assert_eq!(spans.statement(&program.functions()[1].get_statements()[0]), None);
+    }
+}
@@ -0,0 +1,253 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap, VecDeque},
+    hash::{Hash, Hasher},
+    mem
+};
+use xelis_ast::{walk_expression, walk_statement, Expression, Program, Statement, Visitor};
+use xelis_lexer::TokenResult;
+
+// Position of a node in the source code
+// Lines and columns start at 1 and both ends are included, like the tokens of the lexer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub line_start: usize,
+    pub column_start: usize,
+    pub line_end: usize,
+    pub column_end: usize
+}
+
+impl Span {
+    pub fn of_token(token: &TokenResult) -> Self {
+        Self {
+            line_start: token.line,
+            column_start: token.column_start,
+            line_end: token.line,
+            column_end: token.column_end
+        }
+    }
+
+    // Span from the start of this one to the end of the other one
+    pub fn to(&self, end: &Span) -> Self {
+        Self {
+            line_start: self.line_start,
+            column_start: self.column_start,
+            line_end: end.line_end,
+            column_end: end.column_end
+        }
+    }
+
+    // returns true if the other span is fully inside this one
+    pub fn contains(&self, other: &Span) -> bool {
+        (self.line_start, self.column_start) <= (other.line_start, other.column_start)
+            && (other.line_end, other.column_end) <= (self.line_end, self.column_end)
+    }
+}
+
+// Spans of the statements and expressions of a parsed program
+// Nodes are identified by their address: the spans are only valid for the program
+// parsed with them and as long as it is not modified, a cloned node has no span
+#[derive(Debug, Default)]
+pub struct Spans {
+    statements: HashMap<usize, Span>,
+    expressions: HashMap<usize, Span>
+}
+
+impl Spans {
+    pub fn statement(&self, statement: &Statement) -> Option<Span> {
+        self.statements.get(&(statement as *const Statement as usize)).copied()
+    }
+
+    pub fn expression(&self, expression: &Expression) -> Option<Span> {
+        self.expressions.get(&(expression as *const Expression as usize)).copied()
+    }
+}
+
+// Hash of a node and all its children
+// Number literals are hashed by value as the parser can convert them to another number type
+struct Fingerprint(DefaultHasher);
+
+impl Fingerprint {
+    fn of_statement(statement: &Statement) -> u64 {
+        let mut fingerprint = Self(DefaultHasher::new());
+        fingerprint.visit_statement(statement);
+        fingerprint.0.finish()
+    }
+
+    fn of_expression(expression: &Expression) -> u64 {
+        let mut fingerprint = Self(DefaultHasher::new());
+        fingerprint.visit_expression(expression);
+        fingerprint.0.finish()
+    }
+}
+
+impl Visitor for Fingerprint {
+    // Only the data of the node itself is hashed, the children are hashed by the walk
+    // Lengths are included so two different trees never give the same sequence
+    fn visit_statement(&mut self, statement: &Statement) {
+        mem::discriminant(statement).hash(&mut self.0);
+        match statement {
+            Statement::If(_, statements, else_statements) => {
+                statements.len().hash(&mut self.0);
+                else_statements.as_ref().map(Vec::len).hash(&mut self.0);
+            },
+            Statement::While(_, statements)
+            | Statement::For(_, _, _, statements)
+            | Statement::Scope(statements) => statements.len().hash(&mut self.0),
+            Statement::ForEach(id, _, statements) => (id, statements.len()).hash(&mut self.0),
+            Statement::Return(value) => value.is_some().hash(&mut self.0),
+            Statement::Break(depth) | Statement::Continue(depth) => depth.hash(&mut self.0),
+            Statement::Variable(declaration) => declaration.id.hash(&mut self.0),
+            Statement::TupleDeconstruction(variables, _) => variables.hash(&mut self.0),
+            Statement::Expression(_) => {}
+        }
+        walk_statement(self, statement);
+    }
+
+    fn visit_expression(&mut self, expression: &Expression) {
+        mem::discriminant(expression).hash(&mut self.0);
+        match expression {
+            Expression::FunctionCall(path, id, parameters) => (path.is_some(), id, parameters.len()).hash(&mut self.0),
+            Expression::ArrayConstructor(values)
+            | Expression::TupleConstructor(values)
+            | Expression::ValueCall(_, values) => values.len().hash(&mut self.0),
+            Expression::StructConstructor(values, _type) => (values.len(), _type.id()).hash(&mut self.0),
+            Expression::Variable(id) => id.hash(&mut self.0),
+            Expression::Value(value) if value.is_number() => value.to_string().hash(&mut self.0),
+            Expression::Value(value) => format!("{:?}", value).hash(&mut self.0),
+            Expression::Operator(op, _, _) => op.hash(&mut self.0),
+            Expression::Cast(_, _type) => _type.hash(&mut self.0),
+            Expression::StructUpdate(_, fields) => fields.iter().map(|(id, _)| *id).collect::<Vec<_>>().hash(&mut self.0),
+            Expression::Block(statements, _, _) => statements.len().hash(&mut self.0),
+            _ => {}
+        }
+        walk_expression(self, expression);
+    }
+}
+
+// Record the span of the nodes while they are built by the parser
+// A node is moved until the program is complete, so its span is kept with its fingerprint
+// and matched against the final nodes once the program is parsed
+pub(crate) struct SpanRecorder {
+    // Span of each token not read yet
+    positions: VecDeque<Span>,
+    // Span of each token read, the last one is the end of the node being read
+    consumed: Vec<Span>,
+    // Spans recorded for each fingerprint, in the order the nodes were read
+    recorded: HashMap<u64, Vec<(Span, bool)>>
+}
+
+impl SpanRecorder {
+    pub fn new(positions: VecDeque<Span>) -> Self {
+        Self {
+            positions,
+            consumed: Vec::new(),
+            recorded: HashMap::new()
+        }
+    }
+
+    // The next token was read
+    pub fn advance(&mut self) {
+        if let Some(span) = self.positions.pop_front() {
+            self.consumed.push(span);
+        }
+    }
+
+    // The last token read was pushed back
+    pub fn push_back(&mut self) {
+        if let Some(span) = self.consumed.pop() {
+            self.positions.push_front(span);
+        }
+    }
+
+    pub fn next_token(&self) -> Option<Span> {
+        self.positions.front().copied()
+    }
+
+    // Span from the start to the last token read
+    fn span_from(&self, start: Span) -> Option<Span> {
+        self.consumed.last().map(|end| start.to(end))
+    }
+
+    pub fn statement(&mut self, statement: &Statement, start: Span) {
+        if let Some(span) = self.span_from(start) {
+            self.record(Fingerprint::of_statement(statement), span);
+        }
+    }
+
+    pub fn expression(&mut self, expression: &Expression, start: Span) {
+        if let Some(span) = self.span_from(start) {
+            self.record(Fingerprint::of_expression(expression), span);
+        }
+    }
+
+    fn record(&mut self, fingerprint: u64, span: Span) {
+        self.recorded.entry(fingerprint)
+            .or_default()
+            .push((span, false));
+    }
+
+    // Find the node of each recorded span in the program
+    pub fn resolve(self, program: &Program) -> Spans {
+        let mut resolver = Resolver {
+            recorded: self.recorded,
+            parents: Vec::new(),
+            spans: Spans::default()
+        };
+        resolver.visit_program(program);
+        resolver.spans
+    }
+}
+
+// Match the nodes of the program with their recorded span
+// A node takes the first span recorded for the same fingerprint inside the span of its parent,
+// so the copy of a node, such as a default value, doesn't take the span of the original one
+struct Resolver {
+    recorded: HashMap<u64, Vec<(Span, bool)>>,
+    parents: Vec<Span>,
+    spans: Spans
+}
+
+impl Resolver {
+    fn take(&mut self, fingerprint: u64) -> Option<Span> {
+        let parent = self.parents.last();
+        let (span, used) = self.recorded.get_mut(&fingerprint)?
+            .iter_mut()
+            .find(|(span, used)| !*used && parent.is_none_or(|parent| parent.contains(span)))?;
+
+        *used = true;
+        Some(*span)
+    }
+}
+
+impl Visitor for Resolver {
+    fn visit_program(&mut self, program: &Program) {
+        // Constants are folded to a value when they are read, only functions have spans
+        for function in program.functions() {
+            self.visit_function(function);
+        }
+    }
+
+    fn visit_statement(&mut self, statement: &Statement) {
+        match self.take(Fingerprint::of_statement(statement)) {
+            Some(span) => {
+                self.spans.statements.insert(statement as *const Statement as usize, span);
+                self.parents.push(span);
+                walk_statement(self, statement);
+                self.parents.pop();
+            },
+            None => walk_statement(self, statement)
+        }
+    }
+
+    fn visit_expression(&mut self, expression: &Expression) {
+        match self.take(Fingerprint::of_expression(expression)) {
+            Some(span) => {
+                self.spans.expressions.insert(expression as *const Expression as usize, span);
+                self.parents.push(span);
+                walk_expression(self, expression);
+                self.parents.pop();
+            },
+            None => walk_expression(self, expression)
+        }
+    }
+}
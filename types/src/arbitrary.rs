@@ -0,0 +1,218 @@
+use alloc::{boxed::Box, string::String, vec::Vec};
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+use crate::{InnerValue, StructType, Type, U256, Value, ValueOwnable};
+
+// Maximum nesting of a generated type or value
+// Keep it low to avoid a stack overflow in the recursive routines
+const MAX_DEPTH: usize = 4;
+
+// Maximum count of values generated for an array, a struct or a tuple
+const MAX_LEN: usize = 4;
+
+const PRIMITIVES: [Type; 8] = [
+    Type::U8,
+    Type::U16,
+    Type::U32,
+    Type::U64,
+    Type::U128,
+    Type::U256,
+    Type::Bool,
+    Type::String,
+];
+
+const NUMBERS: [Type; 6] = [
+    Type::U8,
+    Type::U16,
+    Type::U32,
+    Type::U64,
+    Type::U128,
+    Type::U256,
+];
+
+fn arbitrary_types(u: &mut Unstructured, depth: usize, concrete: bool) -> Result<Vec<Type>> {
+    let len = u.int_in_range(0..=MAX_LEN)?;
+    (0..len).map(|_| arbitrary_type(u, depth, concrete)).collect()
+}
+
+// Generate a type, a concrete type has no generic type and can be used by a value
+fn arbitrary_type(u: &mut Unstructured, depth: usize, concrete: bool) -> Result<Type> {
+    if depth >= MAX_DEPTH {
+        return Ok(u.choose(&PRIMITIVES)?.clone())
+    }
+
+    let depth = depth + 1;
+    Ok(match u.int_in_range(0..=8)? {
+        0 => Type::Struct(StructType::new(u.arbitrary()?, arbitrary_types(u, depth, concrete)?)),
+        1 => Type::Array(Box::new(arbitrary_type(u, depth, concrete)?)),
+        2 => Type::Optional(Box::new(arbitrary_type(u, depth, concrete)?)),
+        3 => Type::Range(Box::new(u.choose(&NUMBERS)?.clone())),
+        4 => Type::Result(Box::new(arbitrary_type(u, depth, concrete)?), Box::new(arbitrary_type(u, depth, concrete)?)),
+        5 => Type::Tuple(arbitrary_types(u, depth, concrete)?),
+        6 if !concrete => u.choose(&[Type::Any, Type::AnyStruct, Type::T, Type::Never])?.clone(),
+        _ => u.choose(&PRIMITIVES)?.clone()
+    })
+}
+
+fn arbitrary_values(u: &mut Unstructured, types: &[Type]) -> Result<Vec<ValueOwnable>> {
+    types.iter()
+        .map(|_type| arbitrary_ownable(u, _type))
+        .collect()
+}
+
+// Values are either owned or shared to cover both handles
+fn arbitrary_ownable(u: &mut Unstructured, _type: &Type) -> Result<ValueOwnable> {
+    let value = arbitrary_value_of_type(u, _type)?;
+    Ok(if u.arbitrary()? {
+        ValueOwnable::Rc(InnerValue::new(value))
+    } else {
+        ValueOwnable::Owned(Box::new(value))
+    })
+}
+
+// Generate a value of the concrete type
+// The values of an array, a struct or a range always have the expected type
+fn arbitrary_value_of_type(u: &mut Unstructured, _type: &Type) -> Result<Value> {
+    Ok(match _type {
+        Type::U8 => Value::U8(u.arbitrary()?),
+        Type::U16 => Value::U16(u.arbitrary()?),
+        Type::U32 => Value::U32(u.arbitrary()?),
+        Type::U64 => Value::U64(u.arbitrary()?),
+        Type::U128 => Value::U128(u.arbitrary()?),
+        Type::U256 => Value::U256(u.arbitrary()?),
+        Type::Bool => Value::Boolean(u.arbitrary()?),
+        Type::String => Value::String(u.arbitrary::<String>()?),
+        Type::Struct(struct_type) => Value::Struct(arbitrary_values(u, struct_type.fields())?, struct_type.clone()),
+        Type::Array(inner) => {
+            let len = u.int_in_range(0..=MAX_LEN)?;
+            let types = alloc::vec![inner.as_ref().clone(); len];
+            Value::Array(arbitrary_values(u, &types)?)
+        },
+        Type::Optional(inner) => Value::Optional(if u.arbitrary()? {
+            Some(arbitrary_ownable(u, inner)?)
+        } else {
+            None
+        }),
+        Type::Range(inner) => {
+            let start = arbitrary_value_of_type(u, inner)?;
+            let end = arbitrary_value_of_type(u, inner)?;
+            Value::Range(Box::new(start), Box::new(end), inner.as_ref().clone())
+        },
+        Type::Result(ok, err) => if u.arbitrary()? {
+            Value::Ok(arbitrary_ownable(u, ok)?)
+        } else {
+            Value::Err(arbitrary_ownable(u, err)?)
+        },
+        Type::Tuple(types) => Value::Tuple(arbitrary_values(u, types)?),
+        // arbitrary_type never returns a function type, only reached for a type given by the caller
+        Type::Function(_, _) => Value::Function(u.arbitrary()?),
+        // Generic types are never generated for a value
+        Type::Any | Type::AnyStruct | Type::T | Type::Never => Value::Null
+    })
+}
+
+impl<'a> Arbitrary<'a> for U256 {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(U256::new(u.arbitrary()?, u.arbitrary()?, u.arbitrary()?, u.arbitrary()?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for Type {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        arbitrary_type(u, 0, false)
+    }
+}
+
+impl<'a> Arbitrary<'a> for Value {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        if u.ratio(1, 16)? {
+            return Ok(Value::Null)
+        }
+
+        let _type = arbitrary_type(u, 0, true)?;
+        arbitrary_value_of_type(u, &_type)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Deterministic pseudo random input
+    fn random_bytes(len: usize) -> Vec<u8> {
+        let mut seed = 0x2545F4914F6CDD1Du64;
+        (0..len).map(|_| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed as u8
+        }).collect()
+    }
+
+    fn is_of_type(value: &Value, _type: &Type) -> bool {
+        let all_of_type = |values: &[ValueOwnable], types: &[Type]| values.len() == types.len()
+            && values.iter().zip(types).all(|(v, t)| is_of_type(&v.handle(), t));
+
+        match (value, _type) {
+            (Value::Struct(fields, struct_type), Type::Struct(expected)) => struct_type == expected
+                && all_of_type(fields, struct_type.fields()),
+            (Value::Array(values), Type::Array(inner)) => values.iter().all(|v| is_of_type(&v.handle(), inner)),
+            (Value::Optional(value), Type::Optional(inner)) => value.as_ref().is_none_or(|v| is_of_type(&v.handle(), inner)),
+            (Value::Range(start, end, range_type), Type::Range(inner)) => range_type == inner.as_ref()
+                && is_of_type(start, inner)
+                && is_of_type(end, inner),
+            (Value::Ok(value), Type::Result(ok, _)) => is_of_type(&value.handle(), ok),
+            (Value::Err(value), Type::Result(_, err)) => is_of_type(&value.handle(), err),
+            (Value::Tuple(values), Type::Tuple(types)) => all_of_type(values, types),
+            (Value::Function(_), Type::Function(_, _)) => true,
+            (value, _type) => _type.is_primitive() && Type::from_value(value).as_ref() == Some(_type)
+        }
+    }
+
+    #[test]
+    fn test_arbitrary_value_of_type() {
+        let data = random_bytes(1 << 16);
+        let mut u = Unstructured::new(&data);
+        while !u.is_empty() {
+            let _type = arbitrary_type(&mut u, 0, true).unwrap();
+            let value = arbitrary_value_of_type(&mut u, &_type).unwrap();
+            assert!(is_of_type(&value, &_type), "{:?} is not {:?}", value, _type);
+        }
+    }
+
+    #[test]
+    fn test_arbitrary_function_field() {
+        let data = random_bytes(1 << 12);
+        let mut u = Unstructured::new(&data);
+        let _type = Type::Struct(StructType::new(0, alloc::vec![
+            Type::U64,
+            Type::Function(alloc::vec![Type::U64], Box::new(Type::U64))
+        ]));
+
+        while !u.is_empty() {
+            let value = arbitrary_value_of_type(&mut u, &_type).unwrap();
+            assert!(is_of_type(&value, &_type), "{:?} is not {:?}", value, _type);
+
+            let mut buf = Vec::new();
+            value.serialize(&mut buf);
+            let decoded = Value::deserialize(&mut buf.as_slice()).unwrap();
+            assert!(decoded.is_equal(&value).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_arbitrary_serialize_round_trip() {
+        let data = random_bytes(1 << 16);
+        let mut u = Unstructured::new(&data);
+        while !u.is_empty() {
+            let value = Value::arbitrary(&mut u).unwrap();
+            let mut buf = Vec::new();
+            value.serialize(&mut buf);
+
+            let mut reader = buf.as_slice();
+            let decoded = Value::deserialize(&mut reader).unwrap();
+            assert!(reader.is_empty());
+            assert!(decoded.is_equal(&value).unwrap());
+        }
+    }
+}
@@ -0,0 +1,211 @@
+use alloc::{boxed::Box, vec::Vec};
+
+use crate::{Value, ValueError, ValueOwnable};
+
+// Step from a value to one of its inner values
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DiffSegment {
+    // Field index in a struct or a tuple
+    Field(usize),
+    // Element index in an array
+    Index(usize),
+    // Inner value of an optional or a result
+    Inner
+}
+
+// Difference between two value trees at a given path
+// old is None for an added array element, new is None for a removed one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValueDiff {
+    pub path: Vec<DiffSegment>,
+    pub old: Option<Value>,
+    pub new: Option<Value>
+}
+
+impl ValueDiff {
+    fn new(path: &[DiffSegment], old: Option<Value>, new: Option<Value>) -> Self {
+        Self {
+            path: path.to_vec(),
+            old,
+            new
+        }
+    }
+}
+
+fn owned(value: &ValueOwnable) -> Value {
+    value.handle().as_value().clone()
+}
+
+fn diff_fields(path: &mut Vec<DiffSegment>, old: &[ValueOwnable], new: &[ValueOwnable], diffs: &mut Vec<ValueDiff>) {
+    for (i, (a, b)) in old.iter().zip(new).enumerate() {
+        path.push(DiffSegment::Field(i));
+        diff_values(path, &a.handle(), &b.handle(), diffs);
+        path.pop();
+    }
+}
+
+fn diff_values(path: &mut Vec<DiffSegment>, old: &Value, new: &Value, diffs: &mut Vec<ValueDiff>) {
+    match (old, new) {
+        (Value::Struct(a, a_type), Value::Struct(b, b_type)) if a_type == b_type && a.len() == b.len() => {
+            diff_fields(path, a, b, diffs);
+        },
+        (Value::Tuple(a), Value::Tuple(b)) if a.len() == b.len() => {
+            diff_fields(path, a, b, diffs);
+        },
+        (Value::Array(a), Value::Array(b)) => {
+            for (i, (a, b)) in a.iter().zip(b).enumerate() {
+                path.push(DiffSegment::Index(i));
+                diff_values(path, &a.handle(), &b.handle(), diffs);
+                path.pop();
+            }
+
+            // Elements appended at the end
+            for (i, value) in b.iter().enumerate().skip(a.len()) {
+                path.push(DiffSegment::Index(i));
+                diffs.push(ValueDiff::new(path, None, Some(owned(value))));
+                path.pop();
+            }
+
+            // Elements removed, reported from the last one
+            // so the diffs can be applied in order
+            for (i, value) in a.iter().enumerate().skip(b.len()).rev() {
+                path.push(DiffSegment::Index(i));
+                diffs.push(ValueDiff::new(path, Some(owned(value)), None));
+                path.pop();
+            }
+        },
+        (Value::Optional(Some(a)), Value::Optional(Some(b)))
+        | (Value::Ok(a), Value::Ok(b))
+        | (Value::Err(a), Value::Err(b)) => {
+            path.push(DiffSegment::Inner);
+            diff_values(path, &a.handle(), &b.handle(), diffs);
+            path.pop();
+        },
+        (a, b) => if a != b {
+            diffs.push(ValueDiff::new(path, Some(a.clone()), Some(b.clone())));
+        }
+    }
+}
+
+fn apply_diff(value: &mut Value, path: &[DiffSegment], diff: &ValueDiff) -> Result<(), ValueError> {
+    match path {
+        [] => {
+            *value = diff.new.clone().ok_or(ValueError::SubValue)?;
+        },
+        [DiffSegment::Index(index)] if diff.old.is_none() => {
+            let values = value.as_mut_vec()?;
+            if *index != values.len() {
+                return Err(ValueError::OutOfBounds(*index, values.len()))
+            }
+
+            let new = diff.new.clone().ok_or(ValueError::SubValue)?;
+            values.push(ValueOwnable::Owned(Box::new(new)));
+        },
+        [DiffSegment::Index(index)] if diff.new.is_none() => {
+            let values = value.as_mut_vec()?;
+            if *index + 1 != values.len() {
+                return Err(ValueError::OutOfBounds(*index, values.len()))
+            }
+
+            values.pop();
+        },
+        [segment, rest @ ..] => {
+            let inner = match (segment, value) {
+                (DiffSegment::Field(i), Value::Struct(values, _) | Value::Tuple(values))
+                | (DiffSegment::Index(i), Value::Array(values)) => {
+                    let len = values.len();
+                    values.get_mut(*i).ok_or(ValueError::OutOfBounds(*i, len))?
+                },
+                (DiffSegment::Inner, Value::Optional(Some(v)) | Value::Ok(v) | Value::Err(v)) => v,
+                _ => return Err(ValueError::SubValue)
+            };
+
+            let mut handle = inner.handle_mut();
+            apply_diff(handle.as_value_mut(), rest, diff)?;
+        }
+    }
+
+    Ok(())
+}
+
+impl Value {
+    // Compute the structural differences to go from this value to the other one
+    // Structs, tuples, arrays, optionals and results are compared recursively,
+    // any other mismatch is reported on the leaf value
+    pub fn diff(&self, other: &Value) -> Vec<ValueDiff> {
+        let mut diffs = Vec::new();
+        diff_values(&mut Vec::new(), self, other, &mut diffs);
+        diffs
+    }
+
+    // Apply the diffs produced by Value::diff, in order
+    pub fn patch(&mut self, diffs: &[ValueDiff]) -> Result<(), ValueError> {
+        for diff in diffs {
+            apply_diff(self, &diff.path, diff)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{StructType, Type};
+    use super::*;
+
+    fn owned(value: Value) -> ValueOwnable {
+        ValueOwnable::Owned(Box::new(value))
+    }
+
+    // struct Account { balance: u64, owner: struct Owner { name: string, id: u64 } }
+    fn account(balance: u64, id: u64) -> Value {
+        let owner_type = StructType::new(1, vec![Type::String, Type::U64]);
+        let account_type = StructType::new(0, vec![Type::U64, Type::Struct(owner_type.clone())]);
+
+        Value::Struct(vec![
+            owned(Value::U64(balance)),
+            owned(Value::Struct(vec![owned(Value::String("xelis".to_owned())), owned(Value::U64(id))], owner_type))
+        ], account_type)
+    }
+
+    #[test]
+    fn test_diff_nested_struct() {
+        let old = account(100, 1);
+        let new = account(100, 2);
+
+        assert!(old.diff(&old.clone()).is_empty());
+        assert_eq!(old.diff(&new), vec![
+            ValueDiff {
+                path: vec![DiffSegment::Field(1), DiffSegment::Field(1)],
+                old: Some(Value::U64(1)),
+                new: Some(Value::U64(2))
+            }
+        ]);
+
+        let mut patched = old.clone();
+        patched.patch(&old.diff(&new)).unwrap();
+        assert_eq!(patched, new);
+    }
+
+    #[test]
+    fn test_diff_array_length() {
+        let array = |values: &[u64]| Value::Array(values.iter().map(|v| owned(Value::U64(*v))).collect());
+        let old = array(&[1, 2, 3, 4]);
+        let new = array(&[1, 5]);
+
+        let diffs = old.diff(&new);
+        assert_eq!(diffs, vec![
+            ValueDiff { path: vec![DiffSegment::Index(1)], old: Some(Value::U64(2)), new: Some(Value::U64(5)) },
+            ValueDiff { path: vec![DiffSegment::Index(3)], old: Some(Value::U64(4)), new: None },
+            ValueDiff { path: vec![DiffSegment::Index(2)], old: Some(Value::U64(3)), new: None },
+        ]);
+
+        let mut patched = old.clone();
+        patched.patch(&diffs).unwrap();
+        assert_eq!(patched, new);
+
+        let mut patched = new.clone();
+        patched.patch(&new.diff(&old)).unwrap();
+        assert_eq!(patched, old);
+    }
+}
@@ -1,20 +1,32 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
 mod types;
 mod values;
 mod path;
 mod u256;
-
-use std::{
-    collections::HashMap,
-    hash::{
-        BuildHasherDefault,
-        Hasher
-    }
+mod diff;
+mod serialize;
+#[cfg(any(test, feature = "arbitrary"))]
+mod arbitrary;
+
+use core::hash::{
+    BuildHasherDefault,
+    Hasher
 };
 
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+#[cfg(not(feature = "std"))]
+use hashbrown::HashMap;
+
 pub use types::*;
 pub use values::*;
 pub use path::*;
 pub use u256::U256;
+pub use diff::*;
+pub use serialize::{serialize_map, deserialize_map};
 
 // Variable identifier used in the parser and interpreter
 // This is used to optimize the memory usage by using a smaller type
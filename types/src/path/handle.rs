@@ -1,4 +1,4 @@
-use std::{
+use core::{
     fmt,
     cell::{Ref, RefMut},
     ops::{Deref, DerefMut}
@@ -33,7 +33,7 @@ impl<'a> Path<'a> {
     pub fn shareable(&mut self) -> Path<'a> {
         match self {
             Self::Owned(v) => {
-                let dst = std::mem::replace(v, Value::Null);
+                let dst = core::mem::replace(v, Value::Null);
                 let inner = InnerValue::new(dst);
                 let shared = ValueOwnable::Rc(inner);
                 *self = Self::Wrapper(shared.clone());
@@ -0,0 +1,547 @@
+use alloc::{boxed::Box, collections::BTreeMap, string::String, vec::Vec};
+
+use crate::{StructType, Type, U256, Value, ValueError, ValueOwnable, MAX_VALUE_DEPTH};
+
+// Tags of the values, the primitives reuse their primitive byte
+const VALUE_NULL: u8 = 8;
+const VALUE_STRUCT: u8 = 9;
+const VALUE_ARRAY: u8 = 10;
+const VALUE_OPTIONAL: u8 = 11;
+const VALUE_RANGE: u8 = 12;
+const VALUE_OK: u8 = 13;
+const VALUE_ERR: u8 = 14;
+const VALUE_TUPLE: u8 = 15;
+const VALUE_FUNCTION: u8 = 16;
+
+// Tags of the types, the primitives reuse their primitive byte
+const TYPE_ANY: u8 = 8;
+const TYPE_T: u8 = 9;
+const TYPE_STRUCT: u8 = 10;
+const TYPE_ARRAY: u8 = 11;
+const TYPE_OPTIONAL: u8 = 12;
+const TYPE_RANGE: u8 = 13;
+const TYPE_RESULT: u8 = 14;
+const TYPE_TUPLE: u8 = 15;
+const TYPE_FUNCTION: u8 = 16;
+const TYPE_NEVER: u8 = 17;
+const TYPE_ANY_STRUCT: u8 = 18;
+
+fn read_bytes<'a>(reader: &mut &'a [u8], len: usize) -> Result<&'a [u8], ValueError> {
+    if reader.len() < len {
+        return Err(ValueError::UnexpectedEndOfInput)
+    }
+
+    let (bytes, rest) = reader.split_at(len);
+    *reader = rest;
+    Ok(bytes)
+}
+
+fn read_array<const N: usize>(reader: &mut &[u8]) -> Result<[u8; N], ValueError> {
+    let mut array = [0; N];
+    array.copy_from_slice(read_bytes(reader, N)?);
+    Ok(array)
+}
+
+fn read_u8(reader: &mut &[u8]) -> Result<u8, ValueError> {
+    Ok(read_bytes(reader, 1)?[0])
+}
+
+fn read_len(reader: &mut &[u8]) -> Result<usize, ValueError> {
+    Ok(u32::from_be_bytes(read_array(reader)?) as usize)
+}
+
+fn write_len(buf: &mut Vec<u8>, len: usize) {
+    buf.extend_from_slice(&(len as u32).to_be_bytes());
+}
+
+fn serialize_values(buf: &mut Vec<u8>, values: &[ValueOwnable]) {
+    write_len(buf, values.len());
+    for value in values {
+        value.handle().serialize(buf);
+    }
+}
+
+fn deserialize_owned(reader: &mut &[u8], depth: usize, max_depth: usize) -> Result<ValueOwnable, ValueError> {
+    Ok(ValueOwnable::Owned(Box::new(Value::deserialize_internal(reader, depth, max_depth)?)))
+}
+
+fn deserialize_values(reader: &mut &[u8], depth: usize, max_depth: usize) -> Result<Vec<ValueOwnable>, ValueError> {
+    let len = read_len(reader)?;
+    // Don't trust the length to reserve the memory, each value takes at least one byte
+    let mut values = Vec::with_capacity(len.min(reader.len()));
+    for _ in 0..len {
+        values.push(deserialize_owned(reader, depth, max_depth)?);
+    }
+
+    Ok(values)
+}
+
+fn serialize_types(buf: &mut Vec<u8>, types: &[Type]) {
+    write_len(buf, types.len());
+    for _type in types {
+        _type.serialize(buf);
+    }
+}
+
+fn deserialize_types(reader: &mut &[u8], depth: usize, max_depth: usize) -> Result<Vec<Type>, ValueError> {
+    let len = read_len(reader)?;
+    let mut types = Vec::with_capacity(len.min(reader.len()));
+    for _ in 0..len {
+        types.push(Type::deserialize_internal(reader, depth, max_depth)?);
+    }
+
+    Ok(types)
+}
+
+impl Type {
+    // Write the self-describing binary representation of the type
+    pub fn serialize(&self, buf: &mut Vec<u8>) {
+        if let Some(byte) = self.primitive_byte() {
+            buf.push(byte);
+            return
+        }
+
+        match self {
+            Type::Any => buf.push(TYPE_ANY),
+            Type::T => buf.push(TYPE_T),
+            Type::Never => buf.push(TYPE_NEVER),
+            Type::AnyStruct => buf.push(TYPE_ANY_STRUCT),
+            Type::Struct(struct_type) => {
+                buf.push(TYPE_STRUCT);
+                buf.extend_from_slice(&struct_type.id().to_be_bytes());
+                serialize_types(buf, struct_type.fields());
+            },
+            Type::Array(inner) => {
+                buf.push(TYPE_ARRAY);
+                inner.serialize(buf);
+            },
+            Type::Optional(inner) => {
+                buf.push(TYPE_OPTIONAL);
+                inner.serialize(buf);
+            },
+            Type::Range(inner) => {
+                buf.push(TYPE_RANGE);
+                inner.serialize(buf);
+            },
+            Type::Result(ok, err) => {
+                buf.push(TYPE_RESULT);
+                ok.serialize(buf);
+                err.serialize(buf);
+            },
+            Type::Tuple(types) => {
+                buf.push(TYPE_TUPLE);
+                serialize_types(buf, types);
+            },
+            Type::Function(parameters, return_type) => {
+                buf.push(TYPE_FUNCTION);
+                serialize_types(buf, parameters);
+                return_type.serialize(buf);
+            },
+            // Handled by the primitive byte
+            _ => unreachable!()
+        }
+    }
+
+    // Read a type written by Type::serialize, the reader is advanced past it
+    // The input may be untrusted, it fails if the type is nested deeper than the default maximum depth
+    #[inline]
+    pub fn deserialize(reader: &mut &[u8]) -> Result<Type, ValueError> {
+        Self::deserialize_with_max_depth(reader, MAX_VALUE_DEPTH)
+    }
+
+    // Same as deserialize but fails if the type is nested deeper than max_depth
+    pub fn deserialize_with_max_depth(reader: &mut &[u8], max_depth: usize) -> Result<Type, ValueError> {
+        Self::deserialize_internal(reader, 0, max_depth)
+    }
+
+    fn deserialize_internal(reader: &mut &[u8], depth: usize, max_depth: usize) -> Result<Type, ValueError> {
+        let depth = depth + 1;
+        if depth > max_depth {
+            return Err(ValueError::MaxDepthReached(max_depth))
+        }
+
+        let tag = read_u8(reader)?;
+        if let Some(_type) = Type::primitive_type_from_byte(tag) {
+            return Ok(_type)
+        }
+
+        Ok(match tag {
+            TYPE_ANY => Type::Any,
+            TYPE_T => Type::T,
+            TYPE_NEVER => Type::Never,
+            TYPE_ANY_STRUCT => Type::AnyStruct,
+            TYPE_STRUCT => {
+                let id = u16::from_be_bytes(read_array(reader)?);
+                Type::Struct(StructType::new(id, deserialize_types(reader, depth, max_depth)?))
+            },
+            TYPE_ARRAY => Type::Array(Box::new(Self::deserialize_internal(reader, depth, max_depth)?)),
+            TYPE_OPTIONAL => Type::Optional(Box::new(Self::deserialize_internal(reader, depth, max_depth)?)),
+            TYPE_RANGE => Type::Range(Box::new(Self::deserialize_internal(reader, depth, max_depth)?)),
+            TYPE_RESULT => Type::Result(
+                Box::new(Self::deserialize_internal(reader, depth, max_depth)?),
+                Box::new(Self::deserialize_internal(reader, depth, max_depth)?)
+            ),
+            TYPE_TUPLE => Type::Tuple(deserialize_types(reader, depth, max_depth)?),
+            TYPE_FUNCTION => Type::Function(
+                deserialize_types(reader, depth, max_depth)?,
+                Box::new(Self::deserialize_internal(reader, depth, max_depth)?)
+            ),
+            tag => return Err(ValueError::InvalidTag(tag))
+        })
+    }
+}
+
+impl Value {
+    // Write a compact self-describing binary representation of the value
+    // Each value starts with a tag byte, numbers and lengths are big-endian
+    // Shared values are written as owned ones
+    pub fn serialize(&self, buf: &mut Vec<u8>) {
+        match self {
+            Value::Null => buf.push(VALUE_NULL),
+            Value::U8(n) => {
+                buf.push(0);
+                buf.push(*n);
+            },
+            Value::U16(n) => {
+                buf.push(1);
+                buf.extend_from_slice(&n.to_be_bytes());
+            },
+            Value::U32(n) => {
+                buf.push(2);
+                buf.extend_from_slice(&n.to_be_bytes());
+            },
+            Value::U64(n) => {
+                buf.push(3);
+                buf.extend_from_slice(&n.to_be_bytes());
+            },
+            Value::U128(n) => {
+                buf.push(4);
+                buf.extend_from_slice(&n.to_be_bytes());
+            },
+            Value::U256(n) => {
+                buf.push(5);
+                buf.extend_from_slice(&n.to_be_bytes());
+            },
+            Value::Boolean(b) => {
+                buf.push(6);
+                buf.push(*b as u8);
+            },
+            Value::String(s) => {
+                buf.push(7);
+                write_len(buf, s.len());
+                buf.extend_from_slice(s.as_bytes());
+            },
+            Value::Struct(fields, struct_type) => {
+                buf.push(VALUE_STRUCT);
+                Type::Struct(struct_type.clone()).serialize(buf);
+                serialize_values(buf, fields);
+            },
+            Value::Array(values) => {
+                buf.push(VALUE_ARRAY);
+                serialize_values(buf, values);
+            },
+            Value::Optional(value) => {
+                buf.push(VALUE_OPTIONAL);
+                match value {
+                    Some(value) => {
+                        buf.push(1);
+                        value.handle().serialize(buf);
+                    },
+                    None => buf.push(0)
+                }
+            },
+            Value::Range(start, end, _type) => {
+                buf.push(VALUE_RANGE);
+                start.serialize(buf);
+                end.serialize(buf);
+                _type.serialize(buf);
+            },
+            Value::Ok(value) => {
+                buf.push(VALUE_OK);
+                value.handle().serialize(buf);
+            },
+            Value::Err(value) => {
+                buf.push(VALUE_ERR);
+                value.handle().serialize(buf);
+            },
+            Value::Tuple(values) => {
+                buf.push(VALUE_TUPLE);
+                serialize_values(buf, values);
+            },
+            Value::Function(id) => {
+                buf.push(VALUE_FUNCTION);
+                buf.extend_from_slice(&id.to_be_bytes());
+            }
+        }
+    }
+
+    // Read a value written by Value::serialize, the reader is advanced past it
+    // The input may be untrusted, it fails if the value is nested deeper than the default maximum depth
+    #[inline]
+    pub fn deserialize(reader: &mut &[u8]) -> Result<Value, ValueError> {
+        Self::deserialize_with_max_depth(reader, MAX_VALUE_DEPTH)
+    }
+
+    // Same as deserialize but fails if the value is nested deeper than max_depth
+    // The types written in the value count in its depth
+    pub fn deserialize_with_max_depth(reader: &mut &[u8], max_depth: usize) -> Result<Value, ValueError> {
+        Self::deserialize_internal(reader, 0, max_depth)
+    }
+
+    // The nested values are read by small helpers to keep the frame of each level small
+    fn deserialize_internal(reader: &mut &[u8], depth: usize, max_depth: usize) -> Result<Value, ValueError> {
+        let depth = depth + 1;
+        if depth > max_depth {
+            return Err(ValueError::MaxDepthReached(max_depth))
+        }
+
+        let tag = read_u8(reader)?;
+        Ok(match tag {
+            VALUE_STRUCT => {
+                let Type::Struct(struct_type) = Type::deserialize_internal(reader, depth, max_depth)? else {
+                    return Err(ValueError::InvalidTag(VALUE_STRUCT))
+                };
+                Value::Struct(deserialize_values(reader, depth, max_depth)?, struct_type)
+            },
+            VALUE_ARRAY => Value::Array(deserialize_values(reader, depth, max_depth)?),
+            VALUE_OPTIONAL => match read_u8(reader)? {
+                0 => Value::Optional(None),
+                1 => Value::Optional(Some(deserialize_owned(reader, depth, max_depth)?)),
+                tag => return Err(ValueError::InvalidTag(tag))
+            },
+            VALUE_RANGE => {
+                let start = deserialize_owned(reader, depth, max_depth)?.into_inner();
+                let end = deserialize_owned(reader, depth, max_depth)?.into_inner();
+                Value::Range(Box::new(start), Box::new(end), Type::deserialize_internal(reader, depth, max_depth)?)
+            },
+            VALUE_OK => Value::Ok(deserialize_owned(reader, depth, max_depth)?),
+            VALUE_ERR => Value::Err(deserialize_owned(reader, depth, max_depth)?),
+            VALUE_TUPLE => Value::Tuple(deserialize_values(reader, depth, max_depth)?),
+            tag => deserialize_primitive(tag, reader)?
+        })
+    }
+}
+
+// Read a value without any nested value
+fn deserialize_primitive(tag: u8, reader: &mut &[u8]) -> Result<Value, ValueError> {
+    Ok(match tag {
+        0 => Value::U8(read_u8(reader)?),
+        1 => Value::U16(u16::from_be_bytes(read_array(reader)?)),
+        2 => Value::U32(u32::from_be_bytes(read_array(reader)?)),
+        3 => Value::U64(u64::from_be_bytes(read_array(reader)?)),
+        4 => Value::U128(u128::from_be_bytes(read_array(reader)?)),
+        5 => Value::U256(U256::from_be_bytes(read_array(reader)?)),
+        6 => match read_u8(reader)? {
+            0 => Value::Boolean(false),
+            1 => Value::Boolean(true),
+            tag => return Err(ValueError::InvalidTag(tag))
+        },
+        7 => {
+            let len = read_len(reader)?;
+            let bytes = read_bytes(reader, len)?;
+            Value::String(String::from_utf8(bytes.to_vec()).map_err(|_| ValueError::InvalidUtf8)?)
+        },
+        VALUE_NULL => Value::Null,
+        VALUE_FUNCTION => Value::Function(u16::from_be_bytes(read_array(reader)?)),
+        tag => return Err(ValueError::InvalidTag(tag))
+    })
+}
+
+// Serialization contract of the maps
+// A map is written as its entries count followed by each key and its value
+// The entries are sorted by the serialized bytes of their keys, so the same logical map
+// always produces the same bytes whatever its insertion order, as required by the hashing
+// If a key is present more than once, its last value is kept
+pub fn serialize_map<'a, I>(entries: I, buf: &mut Vec<u8>)
+where
+    I: IntoIterator<Item = (&'a Value, &'a Value)>
+{
+    let mut sorted = BTreeMap::new();
+    for (key, value) in entries {
+        let mut key_bytes = Vec::new();
+        key.serialize(&mut key_bytes);
+        sorted.insert(key_bytes, value);
+    }
+
+    write_len(buf, sorted.len());
+    for (key_bytes, value) in sorted {
+        buf.extend_from_slice(&key_bytes);
+        value.serialize(buf);
+    }
+}
+
+// Read a map written by serialize_map, the entries are returned in their serialized order
+// Only the canonical form is accepted: keys must be sorted and unique
+pub fn deserialize_map(reader: &mut &[u8]) -> Result<Vec<(Value, Value)>, ValueError> {
+    let len = read_len(reader)?;
+    let mut entries = Vec::with_capacity(len.min(reader.len()));
+    let mut previous_key: Option<&[u8]> = None;
+    for _ in 0..len {
+        let start = *reader;
+        let key = Value::deserialize(reader)?;
+        let key_bytes = &start[..start.len() - reader.len()];
+        if previous_key.is_some_and(|previous| previous >= key_bytes) {
+            return Err(ValueError::NonCanonicalMap)
+        }
+        previous_key = Some(key_bytes);
+
+        entries.push((key, Value::deserialize(reader)?));
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use super::*;
+
+    fn owned(value: Value) -> ValueOwnable {
+        ValueOwnable::Owned(Box::new(value))
+    }
+
+    fn round_trip(value: &Value) -> Value {
+        let mut buf = Vec::new();
+        value.serialize(&mut buf);
+
+        let mut reader = buf.as_slice();
+        let decoded = Value::deserialize(&mut reader).unwrap();
+        assert!(reader.is_empty());
+        decoded
+    }
+
+    #[test]
+    fn test_primitives_round_trip() {
+        for value in [
+            Value::Null,
+            Value::U8(u8::MAX),
+            Value::U16(0x1234),
+            Value::U32(u32::MAX),
+            Value::U64(42),
+            Value::U128(u128::MAX - 1),
+            Value::U256(U256::new(1, 2, 3, 4)),
+            Value::Boolean(true),
+            Value::String("hello xelis".into()),
+        ] {
+            assert_eq!(round_trip(&value), value);
+        }
+
+        // U256 is written as 32 big-endian bytes
+        let mut buf = Vec::new();
+        Value::U256(U256::from(1u32)).serialize(&mut buf);
+        assert_eq!(buf.len(), 33);
+        assert_eq!(buf[0], Type::U256.primitive_byte().unwrap());
+        assert_eq!(buf[32], 1);
+    }
+
+    #[test]
+    fn test_nested_round_trip() {
+        let inner_type = StructType::new(1, vec![Type::String, Type::Optional(Box::new(Type::U64))]);
+        let outer_type = StructType::new(0, vec![Type::Array(Box::new(Type::Struct(inner_type.clone()))), Type::Range(Box::new(Type::U32))]);
+
+        let inner = |name: &str, value: Option<u64>| Value::Struct(vec![
+            owned(Value::String(name.into())),
+            owned(Value::Optional(value.map(|v| owned(Value::U64(v)))))
+        ], inner_type.clone());
+
+        let mut value = Value::Struct(vec![
+            owned(Value::Array(vec![owned(inner("a", Some(1))), owned(inner("b", None))])),
+            owned(Value::Range(Box::new(Value::U32(0)), Box::new(Value::U32(10)), Type::U32))
+        ], outer_type);
+
+        // Wrap it a few more times
+        for _ in 0..16 {
+            value = Value::Tuple(vec![
+                owned(Value::Ok(owned(value))),
+                owned(Value::Err(owned(Value::Array(Vec::new())))),
+                ValueOwnable::Rc(crate::InnerValue::new(Value::Optional(None)))
+            ]);
+        }
+
+        // Shared values are decoded as owned ones, compare their content
+        assert!(round_trip(&value).is_equal(&value).unwrap());
+    }
+
+    #[test]
+    fn test_truncated_input() {
+        let value = Value::Array(vec![owned(Value::String("hello".into())), owned(Value::U64(7))]);
+        let mut buf = Vec::new();
+        value.serialize(&mut buf);
+
+        for len in 0..buf.len() {
+            let mut reader = &buf[..len];
+            assert!(matches!(Value::deserialize(&mut reader), Err(ValueError::UnexpectedEndOfInput)), "len {}", len);
+        }
+
+        let mut reader: &[u8] = &[42];
+        assert!(matches!(Value::deserialize(&mut reader), Err(ValueError::InvalidTag(42))));
+    }
+
+    #[test]
+    fn test_max_depth() {
+        // Only tags are needed to nest the values and types, without any end
+        let mut reader: &[u8] = &[VALUE_OK; 100_000];
+        assert!(matches!(Value::deserialize(&mut reader), Err(ValueError::MaxDepthReached(MAX_VALUE_DEPTH))));
+
+        let mut reader: &[u8] = &[TYPE_ARRAY; 100_000];
+        assert!(matches!(Type::deserialize(&mut reader), Err(ValueError::MaxDepthReached(MAX_VALUE_DEPTH))));
+
+        // The types of a value count in its depth
+        let mut buf = vec![VALUE_RANGE, 3, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 1];
+        buf.extend_from_slice(&[TYPE_OPTIONAL; 100_000]);
+        let mut reader = buf.as_slice();
+        assert!(matches!(Value::deserialize(&mut reader), Err(ValueError::MaxDepthReached(MAX_VALUE_DEPTH))));
+
+        // The limit is included
+        let mut value = Value::Null;
+        for _ in 0..9 {
+            value = Value::Optional(Some(owned(value)));
+        }
+        let mut buf = Vec::new();
+        value.serialize(&mut buf);
+        assert_eq!(Value::deserialize_with_max_depth(&mut buf.as_slice(), 10).unwrap(), value);
+        assert!(matches!(Value::deserialize_with_max_depth(&mut buf.as_slice(), 9), Err(ValueError::MaxDepthReached(9))));
+    }
+
+    #[test]
+    fn test_map_insertion_order() {
+        let entries = [
+            (Value::String("b".into()), Value::U64(2)),
+            (Value::U8(7), Value::Boolean(true)),
+            (Value::String("a".into()), Value::U64(1)),
+            (Value::Null, Value::Array(Vec::new())),
+        ];
+
+        let mut forward = Vec::new();
+        serialize_map(entries.iter().map(|(k, v)| (k, v)), &mut forward);
+
+        let mut backward = Vec::new();
+        serialize_map(entries.iter().rev().map(|(k, v)| (k, v)), &mut backward);
+        assert_eq!(forward, backward);
+
+        // The last value of a duplicated key is kept
+        let updated = Value::U64(3);
+        let mut duplicated = Vec::new();
+        serialize_map(entries.iter().map(|(k, v)| (k, v)).chain([(&entries[0].0, &updated)]), &mut duplicated);
+
+        let mut reader = duplicated.as_slice();
+        let decoded = deserialize_map(&mut reader).unwrap();
+        assert!(reader.is_empty());
+        assert_eq!(decoded.len(), entries.len());
+        assert!(decoded.contains(&(Value::String("b".into()), Value::U64(3))));
+    }
+
+    #[test]
+    fn test_map_non_canonical() {
+        // Two entries written in the wrong order
+        let mut buf = Vec::new();
+        write_len(&mut buf, 2);
+        for key in [2u8, 1] {
+            Value::U8(key).serialize(&mut buf);
+            Value::Null.serialize(&mut buf);
+        }
+
+        let mut reader = buf.as_slice();
+        assert!(matches!(deserialize_map(&mut reader), Err(ValueError::NonCanonicalMap)));
+    }
+}
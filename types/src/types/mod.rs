@@ -1,21 +1,30 @@
 mod r#struct;
+mod parse;
 
 pub use r#struct::*;
+pub use parse::*;
 
 use crate::{
-    values::Value,
-    ValueOwnable,
+    values::{Value, MAX_VALUE_DEPTH},
+    ValueError,
 };
-use std::{
-    collections::{HashMap, HashSet},
+use alloc::{boxed::Box, string::{String, ToString}, vec::Vec};
+use core::{
     fmt,
     hash::{BuildHasher, Hash},
 };
 
+#[cfg(feature = "std")]
+use std::collections::{HashMap, HashSet};
+#[cfg(not(feature = "std"))]
+use hashbrown::{HashMap, HashSet};
+
 
 #[derive(Clone, Hash, PartialEq, Eq, Debug)]
 pub enum Type {
     Any,
+    // Any struct type, used by the parameters of the native functions
+    AnyStruct,
     T,
 
     U8,
@@ -32,6 +41,15 @@ pub enum Type {
     Array(Box<Type>),
     Optional(Box<Type>),
     Range(Box<Type>),
+    // ok type, error type
+    Result(Box<Type>, Box<Type>),
+    // fixed list of values with their own type
+    Tuple(Vec<Type>),
+    // parameters types, returned type
+    Function(Vec<Type>, Box<Type>),
+    // Type of an expression that never produces a value, like a panic
+    // It is compatible with every type
+    Never,
 }
 
 impl Type {
@@ -68,7 +86,7 @@ impl Type {
     // check if the type has an inner type
     pub fn has_inner_type(&self) -> bool {
         match self {
-            Type::Array(_) | Type::Optional(_) | Type::Range(_) => true,
+            Type::Array(_) | Type::Optional(_) | Type::Range(_) | Type::Result(_, _) => true,
             _ => false
         }
     }
@@ -78,9 +96,35 @@ impl Type {
         self.primitive_byte().is_some()
     }
 
+    // Retrieve the type of a value, None if it can't be determined
+    // or if the value is nested deeper than the default maximum depth
+    #[inline]
     pub fn from_value(value: &Value) -> Option<Self> {
+        Self::from_value_with_max_depth(value, MAX_VALUE_DEPTH).ok().flatten()
+    }
+
+    // Same as from_value but fails if the value is nested deeper than max_depth
+    pub fn from_value_with_max_depth(value: &Value, max_depth: usize) -> Result<Option<Self>, ValueError> {
+        Self::from_value_internal(value, 0, max_depth)
+    }
+
+    fn from_value_internal(value: &Value, depth: usize, max_depth: usize) -> Result<Option<Self>, ValueError> {
+        let depth = depth + 1;
+        if depth > max_depth {
+            return Err(ValueError::MaxDepthReached(max_depth))
+        }
+
+        macro_rules! inner {
+            ($value: expr) => {
+                match Self::from_value_internal($value, depth, max_depth)? {
+                    Some(_type) => _type,
+                    None => return Ok(None)
+                }
+            };
+        }
+
         let _type = match value {
-            Value::Null => return None,
+            Value::Null => return Ok(None),
             Value::U8(_) => Type::U8,
             Value::U16(_) => Type::U16,
             Value::U32(_) => Type::U32,
@@ -89,16 +133,31 @@ impl Type {
             Value::U256(_) => Type::U256,
             Value::String(_) => Type::String,
             Value::Boolean(_) => Type::Bool,
-            Value::Optional(value) => Type::Optional(Box::new(match value.as_ref()? {
-                ValueOwnable::Owned(v) => Type::from_value(&v)?,
-                ValueOwnable::Rc(v) => Type::from_value(&v.borrow())?,
-            })),
-            Value::Array(values) => Type::Array(Box::new(Type::from_value(&values.first()?.handle())?)),
+            Value::Optional(value) => match value {
+                Some(value) => Type::Optional(Box::new(inner!(&value.handle()))),
+                None => return Ok(None)
+            },
+            Value::Array(values) => match values.first() {
+                Some(value) => Type::Array(Box::new(inner!(&value.handle()))),
+                None => return Ok(None)
+            },
             Value::Struct(_, _type) => Type::Struct(_type.clone()),
             Value::Range(_, _, _type) => Type::Range(Box::new(_type.clone())),
+            // Only one side of the result is known from its value
+            Value::Ok(value) => Type::Result(Box::new(inner!(&value.handle())), Box::new(Type::Any)),
+            Value::Err(value) => Type::Result(Box::new(Type::Any), Box::new(inner!(&value.handle()))),
+            Value::Tuple(values) => {
+                let mut types = Vec::with_capacity(values.len());
+                for value in values {
+                    types.push(inner!(&value.handle()));
+                }
+                Type::Tuple(types)
+            },
+            // The signature of a function isn't known from its id
+            Value::Function(_) => return Ok(None),
         };
 
-        Some(_type)
+        Ok(Some(_type))
     }
 
     pub fn get_inner_type(&self) -> &Type {
@@ -106,6 +165,7 @@ impl Type {
             Type::Array(ref _type) => _type,
             Type::Optional(ref _type) => _type,
             Type::Range(ref _type) => _type,
+            Type::Result(ref _type, _) => _type,
             _ => &self
         }
     }
@@ -118,12 +178,25 @@ impl Type {
     }
 
     pub fn is_compatible_with(&self, other: &Type) -> bool {
+        if *self == Type::Never {
+            return true
+        }
+
         match other {
             Type::Range(inner) => match self {
                 Type::Range(inner2) => inner.is_compatible_with(inner2),
                 _ => false
             },
             Type::Any | Type::T => true,
+            Type::AnyStruct => self.is_struct() || *self == Type::AnyStruct || *self == Type::T || *self == Type::Any,
+            Type::Result(ok, err) => match self {
+                Type::Result(ok2, err2) => ok2.is_compatible_with(ok) && err2.is_compatible_with(err),
+                _ => *self == Type::T || *self == Type::Any
+            },
+            Type::Tuple(types) => match self {
+                Type::Tuple(types2) => types.len() == types2.len() && types2.iter().zip(types).all(|(a, b)| a.is_compatible_with(b)),
+                _ => *self == Type::T || *self == Type::Any
+            },
             Type::Array(sub_type) => match self {
                 Type::Array(sub) => sub.is_compatible_with(sub_type.as_ref()),
                 _ => *self == *other || self.is_compatible_with(sub_type.as_ref()),
@@ -132,7 +205,7 @@ impl Type {
                 Type::Optional(sub) => sub.is_compatible_with(sub_type.as_ref()),
                 _ => *self == *other || self.is_compatible_with(sub_type.as_ref()),
             },
-            o => *o == *self || *self == Type::T || *self == Type::Any
+            o => *o == *self || *self == Type::T || *self == Type::Any || (*self == Type::AnyStruct && o.is_struct())
         }
     }
 
@@ -233,12 +306,91 @@ impl Type {
             _ => false
         }
     }
+
+    pub fn is_result(&self) -> bool {
+        matches!(self, Type::Result(_, _))
+    }
+
+    // Check if T is used in this type
+    pub fn is_generic(&self) -> bool {
+        match self {
+            Type::T => true,
+            Type::Array(inner) | Type::Optional(inner) | Type::Range(inner) => inner.is_generic(),
+            Type::Result(ok, err) => ok.is_generic() || err.is_generic(),
+            Type::Tuple(types) => types.iter().any(Type::is_generic),
+            Type::Function(parameters, return_type) => parameters.iter().any(Type::is_generic) || return_type.is_generic(),
+            _ => false
+        }
+    }
+
+    // Find the type bound to T by matching this generic type against a concrete one
+    // Example: result<any, T> against result<u64, string> gives string
+    pub fn find_generic_type(&self, concrete: &Type) -> Option<Type> {
+        match (self, concrete) {
+            (Type::T, t) => Some(t.clone()),
+            (Type::Array(a), Type::Array(b))
+            | (Type::Optional(a), Type::Optional(b))
+            | (Type::Range(a), Type::Range(b)) => a.find_generic_type(b),
+            (Type::Result(ok, err), Type::Result(ok2, err2)) => ok.find_generic_type(ok2)
+                .or_else(|| err.find_generic_type(err2)),
+            (Type::Tuple(types), Type::Tuple(types2)) => types.iter()
+                .zip(types2)
+                .find_map(|(a, b)| a.find_generic_type(b)),
+            _ => None
+        }
+    }
+
+    // Bind T by matching this generic type against a concrete one
+    // T may already be bound by a previous match, in this case the types must agree
+    // Returns false on a conflict
+    // Example: T[] against u64[], then T against string is a conflict
+    pub fn unify_generic_type(&self, concrete: &Type, bound: &mut Option<Type>) -> bool {
+        match (self, concrete) {
+            (Type::T, t) => match bound {
+                Some(b) if t.is_compatible_with(b) => {
+                    // Refine a type bound from an untyped value
+                    if *b == Type::Any {
+                        *bound = Some(t.clone());
+                    }
+                    true
+                },
+                Some(_) => false,
+                None => {
+                    *bound = Some(t.clone());
+                    true
+                }
+            },
+            (Type::Array(a), Type::Array(b))
+            | (Type::Optional(a), Type::Optional(b))
+            | (Type::Range(a), Type::Range(b)) => a.unify_generic_type(b, bound),
+            (Type::Result(ok, err), Type::Result(ok2, err2)) => ok.unify_generic_type(ok2, bound)
+                && err.unify_generic_type(err2, bound),
+            (Type::Tuple(types), Type::Tuple(types2)) => types.len() == types2.len()
+                && types.iter().zip(types2).all(|(a, b)| a.unify_generic_type(b, bound)),
+            // Structure mismatches are reported by the compatibility checks
+            _ => true
+        }
+    }
+
+    // Replace T by the given type
+    pub fn with_generic_type(&self, t: &Type) -> Type {
+        match self {
+            Type::T => t.clone(),
+            Type::Array(inner) => Type::Array(Box::new(inner.with_generic_type(t))),
+            Type::Optional(inner) => Type::Optional(Box::new(inner.with_generic_type(t))),
+            Type::Range(inner) => Type::Range(Box::new(inner.with_generic_type(t))),
+            Type::Result(ok, err) => Type::Result(Box::new(ok.with_generic_type(t)), Box::new(err.with_generic_type(t))),
+            Type::Tuple(types) => Type::Tuple(types.iter().map(|v| v.with_generic_type(t)).collect()),
+            _ => self.clone()
+        }
+    }
 }
 
 impl fmt::Display for Type {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Type::Any => write!(f, "any"),
+            Type::AnyStruct => write!(f, "any_struct"),
             Type::T => write!(f, "T"),
             Type::U8 => write!(f, "u8"),
             Type::U16 => write!(f, "u16"),
@@ -252,6 +404,16 @@ impl fmt::Display for Type {
             Type::Array(_type) => write!(f, "{}[]", _type),
             Type::Optional(_type) => write!(f, "optional<{}>", _type),
             Type::Range(_type) => write!(f, "range<{}>", _type),
+            Type::Result(ok, err) => write!(f, "result<{}, {}>", ok, err),
+            Type::Tuple(types) => {
+                let s: Vec<String> = types.iter().map(|t| t.to_string()).collect();
+                write!(f, "({})", s.join(", "))
+            },
+            Type::Function(parameters, return_type) => {
+                let s: Vec<String> = parameters.iter().map(|t| t.to_string()).collect();
+                write!(f, "fn({}) -> {}", s.join(", "), return_type)
+            },
+            Type::Never => write!(f, "never")
         }
     }
 }
@@ -260,7 +422,7 @@ pub trait HasKey<K> {
     fn has(&self, key: &K) -> bool;
 }
 
-impl<K: Hash + Eq> HasKey<K> for HashSet<K> {
+impl<K: Hash + Eq, S: BuildHasher> HasKey<K> for HashSet<K, S> {
     fn has(&self, key: &K) -> bool {
         self.contains(key)
     }
@@ -270,4 +432,30 @@ impl<K: Hash + Eq, V, S: BuildHasher> HasKey<K> for HashMap<K, V, S> {
     fn has(&self, key: &K) -> bool {
         self.contains_key(key)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unify_generic_type() {
+        let optional_array = Type::Optional(Box::new(Type::Array(Box::new(Type::T))));
+        let mut bound = None;
+        assert!(optional_array.unify_generic_type(&Type::Optional(Box::new(Type::Array(Box::new(Type::U64)))), &mut bound));
+        assert_eq!(bound, Some(Type::U64));
+
+        // T is already bound to u64
+        assert!(Type::T.unify_generic_type(&Type::U64, &mut bound));
+        assert!(!Type::T.unify_generic_type(&Type::String, &mut bound));
+
+        // (T, T) against (u64, string) is a conflict
+        let tuple = Type::Tuple(vec![Type::T, Type::T]);
+        assert!(!tuple.unify_generic_type(&Type::Tuple(vec![Type::U64, Type::String]), &mut None));
+
+        // An untyped value is refined by the next one
+        let mut bound = Some(Type::Any);
+        assert!(Type::T.unify_generic_type(&Type::String, &mut bound));
+        assert_eq!(bound, Some(Type::String));
+    }
+}
@@ -0,0 +1,249 @@
+use alloc::{boxed::Box, string::{String, ToString}, vec::Vec};
+use core::str::FromStr;
+use thiserror::Error;
+
+use super::{StructType, Type};
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum TypeParseError {
+    #[error("unexpected end of type")]
+    UnexpectedEnd,
+    #[error("unexpected character '{0}' at position {1}")]
+    UnexpectedChar(char, usize),
+    #[error("unknown type '{0}'")]
+    UnknownType(String),
+    #[error("struct types can only be parsed by name with a struct resolver")]
+    UnsupportedStruct,
+}
+
+// Recursive descent parser over the Display format of a type
+struct TypeParser<'a, F> {
+    input: &'a str,
+    pos: usize,
+    resolve_struct: F
+}
+
+impl<'a, F: Fn(&str) -> Option<StructType>> TypeParser<'a, F> {
+    fn skip_whitespaces(&mut self) {
+        let rest = &self.input[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespaces();
+        self.input[self.pos..].chars().next()
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), TypeParseError> {
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.pos += c.len_utf8();
+                Ok(())
+            },
+            Some(c) => Err(TypeParseError::UnexpectedChar(c, self.pos)),
+            None => Err(TypeParseError::UnexpectedEnd)
+        }
+    }
+
+    fn read_identifier(&mut self) -> Result<&'a str, TypeParseError> {
+        self.skip_whitespaces();
+        let rest = &self.input[self.pos..];
+        let len = rest.find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+
+        if len == 0 {
+            return Err(match rest.chars().next() {
+                Some(c) => TypeParseError::UnexpectedChar(c, self.pos),
+                None => TypeParseError::UnexpectedEnd
+            })
+        }
+
+        self.pos += len;
+        Ok(&rest[..len])
+    }
+
+    // Read the inner type of a generic such as optional<T>
+    fn read_generic(&mut self) -> Result<Box<Type>, TypeParseError> {
+        self.expect('<')?;
+        let inner = self.read_type()?;
+        self.expect('>')?;
+        Ok(Box::new(inner))
+    }
+
+    fn read_base(&mut self) -> Result<Type, TypeParseError> {
+        if self.peek() == Some('(') {
+            self.pos += 1;
+            let mut types = Vec::new();
+            if self.peek() != Some(')') {
+                loop {
+                    types.push(self.read_type()?);
+                    if self.peek() != Some(',') {
+                        break;
+                    }
+                    self.pos += 1;
+                }
+            }
+            self.expect(')')?;
+            return Ok(Type::Tuple(types))
+        }
+
+        Ok(match self.read_identifier()? {
+            "any" => Type::Any,
+            "any_struct" => Type::AnyStruct,
+            "T" => Type::T,
+            "u8" => Type::U8,
+            "u16" => Type::U16,
+            "u32" => Type::U32,
+            "u64" => Type::U64,
+            "u128" => Type::U128,
+            "u256" => Type::U256,
+            "string" => Type::String,
+            "bool" => Type::Bool,
+            "never" => Type::Never,
+            "fn" => {
+                self.expect('(')?;
+                let mut parameters = Vec::new();
+                if self.peek() != Some(')') {
+                    loop {
+                        parameters.push(self.read_type()?);
+                        if self.peek() != Some(',') {
+                            break;
+                        }
+                        self.pos += 1;
+                    }
+                }
+                self.expect(')')?;
+                self.expect('-')?;
+                self.expect('>')?;
+                Type::Function(parameters, Box::new(self.read_type()?))
+            },
+            "optional" => Type::Optional(self.read_generic()?),
+            "range" => Type::Range(self.read_generic()?),
+            "result" => {
+                self.expect('<')?;
+                let ok = self.read_type()?;
+                self.expect(',')?;
+                let err = self.read_type()?;
+                self.expect('>')?;
+                Type::Result(Box::new(ok), Box::new(err))
+            },
+            // The struct Display output doesn't contain its name
+            "struct" => return Err(TypeParseError::UnsupportedStruct),
+            name => match (self.resolve_struct)(name) {
+                Some(struct_type) => Type::Struct(struct_type),
+                None => return Err(TypeParseError::UnknownType(name.to_string()))
+            }
+        })
+    }
+
+    fn read_type(&mut self) -> Result<Type, TypeParseError> {
+        let mut _type = self.read_base()?;
+        while self.peek() == Some('[') {
+            self.pos += 1;
+            self.expect(']')?;
+            _type = Type::Array(Box::new(_type));
+        }
+
+        Ok(_type)
+    }
+}
+
+impl Type {
+    // Parse a type from its Display format
+    // Struct names are resolved using the provided function
+    pub fn parse_with<F: Fn(&str) -> Option<StructType>>(input: &str, resolve_struct: F) -> Result<Type, TypeParseError> {
+        let mut parser = TypeParser {
+            input,
+            pos: 0,
+            resolve_struct
+        };
+
+        let _type = parser.read_type()?;
+        match parser.peek() {
+            Some(c) => Err(TypeParseError::UnexpectedChar(c, parser.pos)),
+            None => Ok(_type)
+        }
+    }
+}
+
+// Parse a type without any struct
+impl FromStr for Type {
+    type Err = TypeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Type::parse_with(s, |_| None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use super::*;
+
+    fn round_trip(_type: Type) {
+        let display = _type.to_string();
+        assert_eq!(display.parse::<Type>(), Ok(_type), "{}", display);
+    }
+
+    fn round_trip_error(_type: Type, err: TypeParseError) {
+        assert_eq!(_type.to_string().parse::<Type>(), Err(err));
+    }
+
+    #[test]
+    fn test_round_trip_display() {
+        let primitives = [
+            Type::Any,
+            Type::AnyStruct,
+            Type::T,
+            Type::U8,
+            Type::U16,
+            Type::U32,
+            Type::U64,
+            Type::U128,
+            Type::U256,
+            Type::String,
+            Type::Bool,
+            Type::Never,
+        ];
+
+        for _type in primitives.iter().cloned() {
+            round_trip(_type.clone());
+            round_trip(Type::Array(Box::new(_type.clone())));
+            round_trip(Type::Optional(Box::new(_type.clone())));
+            round_trip(Type::Range(Box::new(_type)));
+        }
+
+        round_trip(Type::Array(Box::new(Type::Array(Box::new(Type::Optional(Box::new(Type::U64)))))));
+        round_trip(Type::Optional(Box::new(Type::Array(Box::new(Type::Range(Box::new(Type::U8)))))));
+        round_trip(Type::Result(Box::new(Type::Array(Box::new(Type::String))), Box::new(Type::Optional(Box::new(Type::Bool)))));
+        round_trip(Type::Tuple(vec![Type::U64, Type::Tuple(vec![Type::String, Type::Bool]), Type::Array(Box::new(Type::T))]));
+        round_trip(Type::Array(Box::new(Type::Tuple(Vec::new()))));
+        round_trip(Type::Function(vec![Type::U64, Type::Array(Box::new(Type::String))], Box::new(Type::Bool)));
+        round_trip(Type::Function(Vec::new(), Box::new(Type::Function(vec![Type::U8], Box::new(Type::U8)))));
+    }
+
+    #[test]
+    fn test_parse_struct() {
+        let struct_type = StructType::new(0, vec![Type::U64, Type::String]);
+        let resolve = |name: &str| (name == "Message").then(|| struct_type.clone());
+
+        assert_eq!(
+            Type::parse_with("optional< Message[] >", resolve),
+            Ok(Type::Optional(Box::new(Type::Array(Box::new(Type::Struct(struct_type.clone()))))))
+        );
+        assert_eq!(Type::parse_with("Unknown", resolve), Err(TypeParseError::UnknownType("Unknown".to_string())));
+
+        // The struct Display output can't be resolved back
+        round_trip_error(Type::Struct(struct_type), TypeParseError::UnsupportedStruct);
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert_eq!("".parse::<Type>(), Err(TypeParseError::UnexpectedEnd));
+        assert_eq!("optional<u64".parse::<Type>(), Err(TypeParseError::UnexpectedEnd));
+        assert_eq!("u64[".parse::<Type>(), Err(TypeParseError::UnexpectedEnd));
+        assert_eq!("u64 u8".parse::<Type>(), Err(TypeParseError::UnexpectedChar('u', 4)));
+        assert_eq!("result<u8>".parse::<Type>(), Err(TypeParseError::UnexpectedChar('>', 9)));
+        assert_eq!("u63".parse::<Type>(), Err(TypeParseError::UnknownType("u63".to_string())));
+    }
+}
@@ -1,23 +1,65 @@
-use std::rc::Rc;
+use alloc::{rc::Rc, string::String, vec::Vec};
+use core::hash::{Hash, Hasher};
 use crate::IdentifierType;
 use super::Type;
 
 // Represents a struct in the language
-#[derive(Clone, Hash, PartialEq, Eq, Debug)]
+#[derive(Clone, Eq, Debug)]
 pub struct Struct {
     // Unique identifier for serialization
     id: IdentifierType,
     // Fields of the struct
-    fields: Vec<Type>
+    fields: Vec<Type>,
+    // Name of the struct and of its fields, in the same order
+    // Empty when the names aren't known, like for a deserialized type
+    name: String,
+    fields_names: Vec<String>
 }
 
-#[derive(Clone, Hash, PartialEq, Eq, Debug)]
+// The names are only informative, they are not serialized
+// and don't change the identity of the struct
+impl PartialEq for Struct {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id && self.fields == other.fields
+    }
+}
+
+impl Hash for Struct {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+        self.fields.hash(state);
+    }
+}
+
+// The definition is shared between all the types and values of this struct
+// Cloning it only increments a reference counter
+#[derive(Clone, Eq, Debug)]
 pub struct StructType(Rc<Struct>);
 
+// Clones of the same struct type share their definition,
+// comparing the pointers first avoids comparing all the fields
+impl PartialEq for StructType {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0) || self.0 == other.0
+    }
+}
+
+impl Hash for StructType {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
+
 impl StructType {
     /// Create a new struct type
     pub fn new(id: IdentifierType, fields: Vec<Type>) -> Self {
-        Self(Rc::new(Struct { id, fields }))
+        Self(Rc::new(Struct { id, fields, name: String::new(), fields_names: Vec::new() }))
+    }
+
+    /// Create a new struct type knowing its name and the names of its fields
+    pub fn with_names(id: IdentifierType, name: String, fields: Vec<Type>, fields_names: Vec<String>) -> Self {
+        debug_assert_eq!(fields.len(), fields_names.len());
+        Self(Rc::new(Struct { id, fields, name, fields_names }))
     }
 
     /// Get the unique identifier of the struct
@@ -31,4 +73,57 @@ impl StructType {
     pub fn fields(&self) -> &Vec<Type> {
         &self.0.fields
     }
-}
\ No newline at end of file
+
+    /// Get the name of the struct, empty if it is unknown
+    #[inline(always)]
+    pub fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    /// Get the names of the fields, empty if they are unknown
+    #[inline(always)]
+    pub fn fields_names(&self) -> &[String] {
+        &self.0.fields_names
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{boxed::Box, vec};
+    use crate::{Value, ValueOwnable};
+    use super::*;
+
+    #[test]
+    fn test_struct_values_share_type() {
+        let struct_type = StructType::new(0, vec![Type::U64, Type::String]);
+        let values: Vec<ValueOwnable> = (0..100_000u64)
+            .map(|i| ValueOwnable::Owned(Box::new(Value::Struct(vec![
+                ValueOwnable::Owned(Box::new(Value::U64(i))),
+                ValueOwnable::Owned(Box::new(Value::String("a".into())))
+            ], struct_type.clone()))))
+            .collect();
+
+        // Only one definition is allocated for all the values
+        assert_eq!(Rc::strong_count(&struct_type.0), 100_001);
+        let Value::Struct(_, last) = &*values[99_999].handle() else {
+            panic!("expected a struct")
+        };
+        assert!(Rc::ptr_eq(&struct_type.0, &last.0));
+
+        // A type built separately is still equal
+        assert_eq!(struct_type, StructType::new(0, vec![Type::U64, Type::String]));
+        assert_ne!(struct_type, StructType::new(1, vec![Type::U64, Type::String]));
+    }
+
+    #[test]
+    fn test_fields_names_not_compared() {
+        let named = StructType::with_names(0, "P".into(), vec![Type::U64], vec!["x".into()]);
+        let unnamed = StructType::new(0, vec![Type::U64]);
+
+        assert_eq!(named.name(), "P");
+        assert_eq!(named.fields_names(), &["x"]);
+        assert!(unnamed.name().is_empty());
+        assert!(unnamed.fields_names().is_empty());
+        assert_eq!(named, unnamed);
+    }
+}
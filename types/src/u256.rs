@@ -1,5 +1,6 @@
 use core::fmt;
-use std::{
+use alloc::string::String;
+use core::{
     cmp::Ordering,
     ops::{
         Add,
@@ -14,6 +15,7 @@ use std::{
         DivAssign,
         Mul,
         MulAssign,
+        Not,
         Rem,
         RemAssign,
         Shl,
@@ -67,14 +69,18 @@ impl U256 {
     }
 
     /// Create a new U256 from a string and a radix.
+    /// Fails on an empty string, an invalid digit or an overflow.
     pub fn from_str_radix(s: &str, radix: u32) -> Result<U256, ()> {
+        if s.is_empty() {
+            return Err(());
+        }
+
         let mut result = U256::ZERO;
         for c in s.chars() {
-            if let Some(digit) = c.to_digit(radix) {
-                result = result * U256::from(radix) + U256::from(digit as u64);
-            } else {
-                return Err(());
-            }
+            let digit = c.to_digit(radix).ok_or(())?;
+            result = result.checked_mul(U256::from(radix))
+                .and_then(|v| v.checked_add(U256::from(digit as u64)))
+                .ok_or(())?;
         }
 
         Ok(result)
@@ -158,23 +164,23 @@ impl U256 {
         let mut remainder = self;
 
         // We start by aligning the divisor with the most significant bit of `self`
-        let mut shift = 0;
-        let mut divisor_shifted = divisor;
-        while remainder >= divisor_shifted {
-            divisor_shifted = divisor_shifted << 1;
-            shift += 1;
-        }
+        // The shift is computed from the bits so the shifted divisor never overflows
+        let mut shift = divisor.leading_zeros() - self.leading_zeros();
+        let mut divisor_shifted = divisor << shift;
 
         // Perform the division bit by bit
-        while shift > 0 {
-            divisor_shifted = divisor_shifted >> 1;
-            shift -= 1;
-
+        loop {
             // If the remainder is greater than or equal to divisor_shifted, subtract and update quotient
             if remainder >= divisor_shifted {
                 remainder = remainder - divisor_shifted;
                 quotient = quotient | (U256::ONE << shift);
             }
+
+            if shift == 0 {
+                break;
+            }
+            divisor_shifted >>= 1;
+            shift -= 1;
         }
 
         (quotient, false)
@@ -199,22 +205,21 @@ impl U256 {
         let mut remainder = self;
 
         // We start by aligning the divisor with the most significant bit of `self`
-        let mut shift = 0;
-        let mut divisor_shifted = divisor;
-        while remainder >= divisor_shifted {
-            divisor_shifted = divisor_shifted << 1;
-            shift += 1;
-        }
+        let mut shift = divisor.leading_zeros() - self.leading_zeros();
+        let mut divisor_shifted = divisor << shift;
 
         // Perform the division bit by bit
-        while shift > 0 {
-            divisor_shifted = divisor_shifted >> 1;
-            shift -= 1;
-
+        loop {
             // If the remainder is greater than or equal to divisor_shifted, subtract
             if remainder >= divisor_shifted {
                 remainder = remainder - divisor_shifted;
             }
+
+            if shift == 0 {
+                break;
+            }
+            divisor_shifted >>= 1;
+            shift -= 1;
         }
 
         (remainder, false)
@@ -230,6 +235,15 @@ impl U256 {
         }
     }
 
+    /// Absolute difference between two values, never overflows
+    pub fn abs_diff(self, other: U256) -> U256 {
+        if self >= other {
+            self - other
+        } else {
+            other - self
+        }
+    }
+
     /// Checked addition with overflow handling
     pub fn checked_add(self, other: U256) -> Option<U256> {
         let (result, overflow) = self.overflowing_add(other);
@@ -297,7 +311,7 @@ impl U256 {
     pub fn from_be_bytes(bytes: [u8; 32]) -> Self {
         let mut data = [0u64; 4];
         for i in 0..4 {
-            data[i] = u64::from_be_bytes(bytes[i * 8..(i + 1) * 8].try_into().unwrap());
+            data[3 - i] = u64::from_be_bytes(bytes[i * 8..(i + 1) * 8].try_into().unwrap());
         }
 
         U256(data)
@@ -354,6 +368,37 @@ impl U256 {
     pub fn low_u128(&self) -> u128 {
         (self.0[0] as u128) | ((self.0[1] as u128) << 64)
     }
+
+    /// Returns the number of ones in the binary representation
+    pub fn count_ones(&self) -> u32 {
+        self.0.iter().map(|x| x.count_ones()).sum()
+    }
+
+    /// Returns the number of leading zeros in the binary representation
+    pub fn leading_zeros(&self) -> u32 {
+        let mut zeros = 0;
+        for x in self.0.iter().rev() {
+            zeros += x.leading_zeros();
+            if *x != 0 {
+                break;
+            }
+        }
+
+        zeros
+    }
+
+    /// Returns the number of trailing zeros in the binary representation
+    pub fn trailing_zeros(&self) -> u32 {
+        let mut zeros = 0;
+        for x in self.0.iter() {
+            zeros += x.trailing_zeros();
+            if *x != 0 {
+                break;
+            }
+        }
+
+        zeros
+    }
 }
 
 impl FromStr for U256 {
@@ -471,7 +516,7 @@ impl Shl<u32> for U256 {
 
         for i in (word_shift..4).rev() {
             result[i] = self.0[i - word_shift] << bit_shift;
-            if bit_shift > 0 && i > 0 {
+            if bit_shift > 0 && i > word_shift {
                 result[i] |= self.0[i - word_shift - 1] >> (64 - bit_shift);
             }
         }
@@ -521,7 +566,7 @@ impl Shl for U256 {
         let word_shift = word_shift as usize;
         for i in (word_shift..4).rev() {
             result[i] = self.0[i - word_shift] << bit_shift;
-            if bit_shift > 0 && i > 0 {
+            if bit_shift > 0 && i > word_shift {
                 result[i] |= self.0[i - word_shift - 1] >> (64 - bit_shift);
             }
         }
@@ -542,6 +587,14 @@ impl BitXor for U256 {
     }
 }
 
+impl Not for U256 {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        U256(self.0.map(|v| !v))
+    }
+}
+
 impl BitOr for U256 {
     type Output = Self;
 
@@ -722,6 +775,11 @@ mod tests {
         assert_eq!(U256::from_str_radix("1", 10).unwrap(), U256::ONE);
         assert_eq!(U256::from_str_radix("1234567890", 10).unwrap(), U256::from(1234567890u64));
         assert_eq!(U256::from_str_radix("ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff", 16).unwrap(), U256::MAX);
+
+        assert!(U256::from_str_radix("", 10).is_err());
+        assert!(U256::from_str_radix("12a", 10).is_err());
+        // One more than the maximum value
+        assert!(U256::from_str_radix("1ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff", 16).is_err());
     }
 
     #[test]
@@ -740,6 +798,22 @@ mod tests {
         assert_eq!(U256::from_str("115792089237316195423570985008687907853269984665640564039457584007913129639935").unwrap(), U256::MAX);
     }
 
+    #[test]
+    fn test_bit_counts() {
+        assert_eq!(U256::ZERO.count_ones(), 0);
+        assert_eq!(U256::ZERO.leading_zeros(), 256);
+        assert_eq!(U256::ZERO.trailing_zeros(), 256);
+
+        assert_eq!(U256::MAX.count_ones(), 256);
+        assert_eq!(U256::MAX.leading_zeros(), 0);
+        assert_eq!(U256::MAX.trailing_zeros(), 0);
+
+        let value = U256::new(0, 0b1010 << 4, 0, 1);
+        assert_eq!(value.count_ones(), 3);
+        assert_eq!(value.leading_zeros(), 63);
+        assert_eq!(value.trailing_zeros(), 64 + 5);
+    }
+
     #[test]
     fn test_add_overflow() {
         let a = U256([1, 0, 0, 0]);
@@ -803,6 +877,20 @@ mod tests {
         assert_eq!(a | b, U256([1, 1, 0, 0]));
     }
 
+    #[test]
+    fn test_be_bytes() {
+        // The most significant limb is written first
+        let value = U256::new(1, 2, 3, 4);
+        let mut expected = [0u8; 32];
+        expected[7] = 4;
+        expected[15] = 3;
+        expected[23] = 2;
+        expected[31] = 1;
+        assert_eq!(value.to_be_bytes(), expected);
+        assert_eq!(U256::from_be_bytes(expected), value);
+        assert_eq!(U256::from_be_bytes(U256::from(0x0102u16).to_be_bytes()), U256::from(0x0102u16));
+    }
+
     #[test]
     fn test_shl() {
         let a = U256([1, 0, 0, 0]);
@@ -810,6 +898,10 @@ mod tests {
 
         let a = U256([1, 0, 0, 0]);
         assert_eq!(a.shl(128), U256([0, 0, 1, 0]));
+
+        let a = U256([3, 0, 0, 0]);
+        assert_eq!(a.shl(127), U256([0, 1 << 63, 1, 0]));
+        assert_eq!(a.shl(U256::from(127u8)), U256([0, 1 << 63, 1, 0]));
     }
 
     #[test]
@@ -850,5 +942,12 @@ mod tests {
         let a = U256([0, 0, 0, 1]);
         let b = U256([0, 0, 0, 2]);
         assert_eq!(a / b, U256::ZERO);
+
+        // The shifted divisor must not overflow when the top bit of the value is set
+        assert_eq!(U256::MAX / U256::from(2u8), U256([u64::MAX, u64::MAX, u64::MAX, u64::MAX >> 1]));
+        assert_eq!(U256::MAX % U256::from(2u8), U256::ONE);
+        assert_eq!(U256::MAX / U256::MAX, U256::ONE);
+        assert_eq!(U256::MAX % (U256::MAX - U256::ONE), U256::ONE);
+        assert_eq!(U256([0, 0, 0, 1 << 63]) / U256::from(3u8), U256([0xaaaaaaaaaaaaaaaa, 0xaaaaaaaaaaaaaaaa, 0xaaaaaaaaaaaaaaaa, 0x2aaaaaaaaaaaaaaa]));
     }
 }
\ No newline at end of file
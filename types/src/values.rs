@@ -1,6 +1,12 @@
-use std::{cell::{Ref, RefCell, RefMut}, cmp::Ordering, hash::Hash, rc::Rc};
+use alloc::{borrow::ToOwned, boxed::Box, format, rc::Rc, string::{String, ToString}, vec::Vec};
+use core::{cell::{Ref, RefCell, RefMut}, cmp::Ordering, hash::Hash};
 use thiserror::Error;
-use crate::{types::Type, StructType, ValueHandle, ValueHandleMut, U256};
+use crate::{types::Type, IdentifierType, StructType, ValueHandle, ValueHandleMut, U256};
+
+// Default maximum nesting of a value walked by the recursive routines
+// A deeper value, or a cycle created through shared values, is rejected
+// instead of overflowing the stack
+pub const MAX_VALUE_DEPTH: usize = 256;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct InnerValue(Rc<RefCell<Value>>);
@@ -28,7 +34,7 @@ impl InnerValue {
 }
 
 impl Hash for InnerValue {
-    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
         self.0.borrow().hash(state)
     }
 }
@@ -70,6 +76,26 @@ pub enum ValueError {
     InvalidPrimitiveType,
     #[error("Invalid unknown type")]
     UnknownType,
+    #[error("String length {0} exceeds the maximum of {1}")]
+    MaxStringLengthReached(usize, usize),
+    #[error("Array length {0} exceeds the maximum of {1}")]
+    MaxArrayLengthReached(usize, usize),
+    #[error("Values can't be compared: {0:?} and {1:?}")]
+    Incomparable(Value, Value),
+    #[error("Unexpected end of input")]
+    UnexpectedEndOfInput,
+    #[error("Invalid tag: {0}")]
+    InvalidTag(u8),
+    #[error("Invalid UTF-8 string")]
+    InvalidUtf8,
+    #[error("Map keys are not sorted or not unique")]
+    NonCanonicalMap,
+    #[error("Value exceeds the maximum depth of {0}")]
+    MaxDepthReached(usize),
+    #[error("Number {0} doesn't fit in a u128")]
+    NumberTooLarge(U256),
+    #[error("Integer overflow")]
+    Overflow,
 }
 
 #[derive(Debug, Hash, Clone, PartialEq, Eq)]
@@ -84,16 +110,22 @@ impl ValueOwnable {
             ValueOwnable::Owned(v) => *v,
             ValueOwnable::Rc(v) => match Rc::try_unwrap(v.into_inner()) {
                 Ok(value) => value.into_inner(),
-                Err(rc) => rc.borrow().clone()
+                // Still shared, copy it so the result doesn't alias the other owners
+                Err(rc) => rc.borrow().deep_clone()
             }
         }
     }
 
+    // Copy the inner value, shared values included
+    pub fn deep_clone(&self) -> ValueOwnable {
+        ValueOwnable::Owned(Box::new(self.handle().deep_clone()))
+    }
+
     // Transform the value into a shared value
     pub fn transform(&mut self) -> ValueOwnable {
         match self {
             ValueOwnable::Owned(v) => {
-                let dst = std::mem::replace(v, Box::new(Value::Null));
+                let dst = core::mem::replace(v, Box::new(Value::Null));
                 let shared = Self::Rc(InnerValue::new(*dst));
                 *self = shared.clone();
                 shared
@@ -135,6 +167,13 @@ pub enum Value {
     Optional(Option<ValueOwnable>),
     // Use box directly because the range are primitive only
     Range(Box<Value>, Box<Value>, Type),
+    // Result payloads
+    Ok(ValueOwnable),
+    Err(ValueOwnable),
+    // Fixed list of values, each with its own type
+    Tuple(Vec<ValueOwnable>),
+    // Identifier of a function used as a value
+    Function(IdentifierType),
 }
 
 impl PartialOrd for Value {
@@ -222,6 +261,27 @@ impl Value {
         }
     }
 
+    // Widen any unsigned number to a u128
+    // Fails if the value isn't a number or is a u256 greater than u128::MAX
+    pub fn as_u128_lossless(&self) -> Result<u128, ValueError> {
+        self.as_number().map(|(n, _)| n)
+    }
+
+    // Same as as_u128_lossless, but also returns the type of the number
+    // so the result can be converted back once computed
+    pub fn as_number(&self) -> Result<(u128, Type), ValueError> {
+        Ok(match self {
+            Value::U8(n) => (*n as u128, Type::U8),
+            Value::U16(n) => (*n as u128, Type::U16),
+            Value::U32(n) => (*n as u128, Type::U32),
+            Value::U64(n) => (*n as u128, Type::U64),
+            Value::U128(n) => (*n, Type::U128),
+            Value::U256(n) if n.leading_zeros() >= 128 => (n.low_u128(), Type::U256),
+            Value::U256(n) => return Err(ValueError::NumberTooLarge(*n)),
+            _ => return Err(ValueError::OperationNotNumberType)
+        })
+    }
+
     #[inline]
     pub fn as_string(&self) -> Result<&String, ValueError> {
         match self {
@@ -238,6 +298,26 @@ impl Value {
         }
     }
 
+    // Value of a condition when any type is accepted in it:
+    // numbers are true when non zero, strings and arrays when non empty
+    // optionals when they contain a value, and any other value except null
+    pub fn truthy(&self) -> bool {
+        match self {
+            Value::Null => false,
+            Value::U8(n) => *n != 0,
+            Value::U16(n) => *n != 0,
+            Value::U32(n) => *n != 0,
+            Value::U64(n) => *n != 0,
+            Value::U128(n) => *n != 0,
+            Value::U256(n) => !n.is_zero(),
+            Value::String(s) => !s.is_empty(),
+            Value::Boolean(b) => *b,
+            Value::Array(values) => !values.is_empty(),
+            Value::Optional(value) => value.is_some(),
+            _ => true
+        }
+    }
+
     #[inline]
     pub fn as_map(&self) -> Result<&Vec<ValueOwnable>, ValueError> {
         match self {
@@ -295,6 +375,38 @@ impl Value {
         }
     }
 
+    // Bitwise complement of an unsigned number
+    #[inline]
+    pub fn bitwise_not(&self) -> Result<Value, ValueError> {
+        Ok(match self {
+            Value::U8(n) => Value::U8(!n),
+            Value::U16(n) => Value::U16(!n),
+            Value::U32(n) => Value::U32(!n),
+            Value::U64(n) => Value::U64(!n),
+            Value::U128(n) => Value::U128(!n),
+            Value::U256(n) => Value::U256(!*n),
+            _ => return Err(ValueError::OperationNotNumberType)
+        })
+    }
+
+    #[inline]
+    pub fn as_result(&self) -> Result<Result<&ValueOwnable, &ValueOwnable>, ValueError> {
+        match self {
+            Value::Ok(value) => Ok(Ok(value)),
+            Value::Err(value) => Ok(Err(value)),
+            v => Err(ValueError::InvalidValue(v.clone(), Type::Result(Box::new(Type::Any), Box::new(Type::Any))))
+        }
+    }
+
+    #[inline]
+    pub fn to_result(self) -> Result<Result<ValueOwnable, ValueOwnable>, ValueError> {
+        match self {
+            Value::Ok(value) => Ok(Ok(value)),
+            Value::Err(value) => Ok(Err(value)),
+            v => Err(ValueError::InvalidValue(v, Type::Result(Box::new(Type::Any), Box::new(Type::Any))))
+        }
+    }
+
     #[inline]
     pub fn to_u8(self) -> Result<u8, ValueError> {
         match self {
@@ -379,7 +491,7 @@ impl Value {
     pub fn to_sub_vec(self) -> Result<Vec<ValueOwnable>, ValueError> {
         match self {
             Value::Array(values) => Ok(values),
-            Value::Struct(fields, _) => Ok(fields),
+            Value::Struct(fields, _) | Value::Tuple(fields) => Ok(fields),
             _ => Err(ValueError::SubValue)
         }
     }
@@ -388,7 +500,7 @@ impl Value {
     pub fn as_sub_vec(&self) -> Result<&Vec<ValueOwnable>, ValueError> {
         match self {
             Value::Array(values) => Ok(values),
-            Value::Struct(fields, _) => Ok(fields),
+            Value::Struct(fields, _) | Value::Tuple(fields) => Ok(fields),
             _ => Err(ValueError::SubValue)
         }
     }
@@ -397,7 +509,7 @@ impl Value {
     pub fn as_mut_sub_vec(&mut self) -> Result<&mut Vec<ValueOwnable>, ValueError> {
         match self {
             Value::Array(values) => Ok(values),
-            Value::Struct(fields, _) => Ok(fields),
+            Value::Struct(fields, _) | Value::Tuple(fields) => Ok(fields),
             _ => Err(ValueError::SubValue)
         }
     }
@@ -418,6 +530,158 @@ impl Value {
         }
     }
 
+    // Copy the value and all its inner values
+    // A clone keeps sharing the inner values that are shared (Rc),
+    // the copy is independent from the original
+    pub fn deep_clone(&self) -> Value {
+        match self {
+            Value::Struct(fields, _type) => Value::Struct(fields.iter().map(ValueOwnable::deep_clone).collect(), _type.clone()),
+            Value::Array(values) => Value::Array(values.iter().map(ValueOwnable::deep_clone).collect()),
+            Value::Tuple(values) => Value::Tuple(values.iter().map(ValueOwnable::deep_clone).collect()),
+            Value::Optional(value) => Value::Optional(value.as_ref().map(ValueOwnable::deep_clone)),
+            Value::Ok(value) => Value::Ok(value.deep_clone()),
+            Value::Err(value) => Value::Err(value.deep_clone()),
+            v => v.clone()
+        }
+    }
+
+    // Equality used by the == and != operators
+    // Null and an empty optional both represent "no value" and are equal,
+    // this is applied recursively to the inner values
+    // The derived PartialEq stays structural to keep it consistent with Hash
+    #[inline]
+    pub fn is_equal(&self, other: &Value) -> Result<bool, ValueError> {
+        self.is_equal_with_max_depth(other, MAX_VALUE_DEPTH)
+    }
+
+    // Same as is_equal but fails if the values are nested deeper than max_depth
+    pub fn is_equal_with_max_depth(&self, other: &Value, max_depth: usize) -> Result<bool, ValueError> {
+        fn inner_equal(a: &Value, b: &Value, depth: usize, max_depth: usize) -> Result<bool, ValueError> {
+            let depth = depth + 1;
+            if depth > max_depth {
+                return Err(ValueError::MaxDepthReached(max_depth))
+            }
+
+            let all_equal = |a: &[ValueOwnable], b: &[ValueOwnable]| -> Result<bool, ValueError> {
+                if a.len() != b.len() {
+                    return Ok(false)
+                }
+
+                for (a, b) in a.iter().zip(b) {
+                    if !inner_equal(&a.handle(), &b.handle(), depth, max_depth)? {
+                        return Ok(false)
+                    }
+                }
+                Ok(true)
+            };
+
+            Ok(match (a, b) {
+                (Value::Null, Value::Optional(None)) | (Value::Optional(None), Value::Null) => true,
+                (Value::Optional(Some(a)), Value::Optional(Some(b)))
+                | (Value::Ok(a), Value::Ok(b))
+                | (Value::Err(a), Value::Err(b)) => inner_equal(&a.handle(), &b.handle(), depth, max_depth)?,
+                (Value::Array(a), Value::Array(b))
+                | (Value::Tuple(a), Value::Tuple(b)) => all_equal(a, b)?,
+                (Value::Struct(a, a_type), Value::Struct(b, b_type)) => a_type == b_type && all_equal(a, b)?,
+                (a, b) => a == b
+            })
+        }
+
+        inner_equal(self, other, 0, max_depth)
+    }
+
+    // Total order between two primitive values of the same type
+    // Numbers are compared by value, strings lexicographically by bytes
+    // and false is lower than true
+    pub fn compare(&self, other: &Value) -> Result<Ordering, ValueError> {
+        match (self, other) {
+            (Value::String(a), Value::String(b)) => Ok(a.cmp(b)),
+            (Value::Boolean(a), Value::Boolean(b)) => Ok(a.cmp(b)),
+            (a, b) => a.partial_cmp(b)
+                .ok_or_else(|| ValueError::Incomparable(a.clone(), b.clone()))
+        }
+    }
+
+    // Collapse the redundant wrapping of an optional value
+    // - optional(optional(x)) becomes optional(x), at any depth
+    // - optional(null) becomes an empty optional
+    // Only the optional itself is normalized, not the values it contains
+    pub fn normalize(&mut self) {
+        while let Value::Optional(inner @ Some(_)) = self {
+            let redundant = inner.as_ref()
+                .is_some_and(|v| matches!(*v.handle(), Value::Null | Value::Optional(_)));
+            if !redundant {
+                break;
+            }
+
+            *self = match inner.take().map(ValueOwnable::into_inner) {
+                Some(value @ Value::Optional(_)) => value,
+                _ => Value::Optional(None)
+            };
+        }
+    }
+
+    // Canonical form of the value, to be used before hashing, serializing
+    // or comparing values in consensus-critical paths
+    // Two values equal with is_equal have the same canonical form, which is:
+    // - every inner value is owned, a shared (Rc) value is copied
+    // - an optional is normalized: optional(optional(x)) becomes optional(x)
+    //   and optional(null) becomes an empty optional
+    // - an empty optional is represented as null
+    // - the rules are applied recursively to the fields, elements and payloads
+    // Numbers, strings, booleans, ranges and function values are kept as is
+    // Fails if the value is nested deeper than MAX_VALUE_DEPTH
+    pub fn canonicalize(self) -> Result<Value, ValueError> {
+        self.canonicalize_with_max_depth(MAX_VALUE_DEPTH)
+    }
+
+    // Same as canonicalize but fails if the value is nested deeper than max_depth
+    pub fn canonicalize_with_max_depth(self, max_depth: usize) -> Result<Value, ValueError> {
+        fn canonical(value: ValueOwnable, depth: usize, max_depth: usize) -> Result<ValueOwnable, ValueError> {
+            // A shared value is copied one level at a time, its inner values are copied by the recursion
+            let value = match value {
+                ValueOwnable::Owned(v) => *v,
+                ValueOwnable::Rc(v) => match Rc::try_unwrap(v.into_inner()) {
+                    Ok(value) => value.into_inner(),
+                    Err(rc) => rc.borrow().clone()
+                }
+            };
+
+            Ok(ValueOwnable::Owned(Box::new(inner_canonicalize(value, depth, max_depth)?)))
+        }
+
+        fn canonical_values(values: Vec<ValueOwnable>, depth: usize, max_depth: usize) -> Result<Vec<ValueOwnable>, ValueError> {
+            values.into_iter()
+                .map(|v| canonical(v, depth, max_depth))
+                .collect()
+        }
+
+        fn inner_canonicalize(value: Value, depth: usize, max_depth: usize) -> Result<Value, ValueError> {
+            let depth = depth + 1;
+            if depth > max_depth {
+                return Err(ValueError::MaxDepthReached(max_depth))
+            }
+
+            let mut value = match value {
+                Value::Struct(fields, _type) => Value::Struct(canonical_values(fields, depth, max_depth)?, _type),
+                Value::Array(values) => Value::Array(canonical_values(values, depth, max_depth)?),
+                Value::Tuple(values) => Value::Tuple(canonical_values(values, depth, max_depth)?),
+                Value::Optional(value) => Value::Optional(value.map(|v| canonical(v, depth, max_depth)).transpose()?),
+                Value::Ok(value) => Value::Ok(canonical(value, depth, max_depth)?),
+                Value::Err(value) => Value::Err(canonical(value, depth, max_depth)?),
+                v => v
+            };
+
+            value.normalize();
+            Ok(match value {
+                Value::Optional(None) => Value::Null,
+                v => v
+            })
+        }
+
+        inner_canonicalize(self, 0, max_depth)
+    }
+
     // Check if the value is a number
     #[inline]
     pub fn is_number(&self) -> bool {
@@ -428,29 +692,33 @@ impl Value {
     }
 
     // Increment the value
+    // Fails at the maximum of its type instead of wrapping, the value is left unchanged
     pub fn increment(&mut self) -> Result<(), ValueError> {
-        Ok(match self {
-            Value::U8(n) => *n += 1,
-            Value::U16(n) => *n += 1,
-            Value::U32(n) => *n += 1,
-            Value::U64(n) => *n += 1,
-            Value::U128(n) => *n += 1,
-            Value::U256(n) => *n += U256::ONE,
+        match self {
+            Value::U8(n) => *n = n.checked_add(1).ok_or(ValueError::Overflow)?,
+            Value::U16(n) => *n = n.checked_add(1).ok_or(ValueError::Overflow)?,
+            Value::U32(n) => *n = n.checked_add(1).ok_or(ValueError::Overflow)?,
+            Value::U64(n) => *n = n.checked_add(1).ok_or(ValueError::Overflow)?,
+            Value::U128(n) => *n = n.checked_add(1).ok_or(ValueError::Overflow)?,
+            Value::U256(n) => *n = n.checked_add(U256::ONE).ok_or(ValueError::Overflow)?,
             _ => return Err(ValueError::OperationNotNumberType)
-        })
+        };
+        Ok(())
     }
 
     // Decrement the value
+    // Fails at 0 instead of wrapping, the value is left unchanged
     pub fn decrement(&mut self) -> Result<(), ValueError> {
-        Ok(match self {
-            Value::U8(n) => *n -= 1,
-            Value::U16(n) => *n -= 1,
-            Value::U32(n) => *n -= 1,
-            Value::U64(n) => *n -= 1,
-            Value::U128(n) => *n -= 1,
-            Value::U256(n) => *n -= U256::ONE,
+        match self {
+            Value::U8(n) => *n = n.checked_sub(1).ok_or(ValueError::Overflow)?,
+            Value::U16(n) => *n = n.checked_sub(1).ok_or(ValueError::Overflow)?,
+            Value::U32(n) => *n = n.checked_sub(1).ok_or(ValueError::Overflow)?,
+            Value::U64(n) => *n = n.checked_sub(1).ok_or(ValueError::Overflow)?,
+            Value::U128(n) => *n = n.checked_sub(1).ok_or(ValueError::Overflow)?,
+            Value::U256(n) => *n = n.checked_sub(U256::ONE).ok_or(ValueError::Overflow)?,
             _ => return Err(ValueError::OperationNotNumberType)
-        })
+        };
+        Ok(())
     }
 
     // Cast value to string
@@ -638,6 +906,20 @@ impl Value {
     // Returns an error if it can't be determined
     #[inline]
     pub fn get_type(&self) -> Result<Type, ValueError> {
+        self.get_type_with_max_depth(MAX_VALUE_DEPTH)
+    }
+
+    // Same as get_type but fails if the value is nested deeper than max_depth
+    pub fn get_type_with_max_depth(&self, max_depth: usize) -> Result<Type, ValueError> {
+        self.get_type_internal(0, max_depth)
+    }
+
+    fn get_type_internal(&self, depth: usize, max_depth: usize) -> Result<Type, ValueError> {
+        let depth = depth + 1;
+        if depth > max_depth {
+            return Err(ValueError::MaxDepthReached(max_depth))
+        }
+
         Ok(match self {
             Value::Null => return Err(ValueError::UnknownType),
             Value::U8(_) => Type::U8,
@@ -650,20 +932,73 @@ impl Value {
             Value::Boolean(_) => Type::Bool,
             Value::Struct(_, _type) => Type::Struct(_type.clone()),
             Value::Array(inner) => match inner.first() {
-                Some(value) => Type::Array(Box::new(value.handle().get_type()?)),
+                Some(value) => Type::Array(Box::new(value.handle().get_type_internal(depth, max_depth)?)),
                 None => return Err(ValueError::UnknownType)
             },
             Value::Optional(value) => match value {
-                Some(value) => Type::Optional(Box::new(value.handle().get_type()?)),
+                Some(value) => Type::Optional(Box::new(value.handle().get_type_internal(depth, max_depth)?)),
                 None => return Err(ValueError::UnknownType)
             }
-            Value::Range(_, _, _type) => Type::Range(Box::new(_type.clone()))
+            Value::Range(_, _, _type) => Type::Range(Box::new(_type.clone())),
+            Value::Ok(value) => Type::Result(Box::new(value.handle().get_type_internal(depth, max_depth)?), Box::new(Type::Any)),
+            Value::Err(value) => Type::Result(Box::new(Type::Any), Box::new(value.handle().get_type_internal(depth, max_depth)?)),
+            Value::Tuple(values) => Type::Tuple(values.iter()
+                .map(|v| v.handle().get_type_internal(depth, max_depth))
+                .collect::<Result<_, _>>()?
+            ),
+            // The signature of a function isn't known from its id
+            Value::Function(_) => return Err(ValueError::UnknownType)
         })
     }
 }
 
-impl std::fmt::Display for Value {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+// Implement the conversions between a Rust type and its Value variant
+// TryFrom delegates to the to_* functions
+macro_rules! value_conversion {
+    ($t: ty, $variant: ident, $fn: ident) => {
+        impl From<$t> for Value {
+            #[inline]
+            fn from(value: $t) -> Self {
+                Value::$variant(value)
+            }
+        }
+
+        impl TryFrom<Value> for $t {
+            type Error = ValueError;
+
+            #[inline]
+            fn try_from(value: Value) -> Result<Self, Self::Error> {
+                value.$fn()
+            }
+        }
+    };
+}
+
+value_conversion!(u8, U8, to_u8);
+value_conversion!(u16, U16, to_u16);
+value_conversion!(u32, U32, to_u32);
+value_conversion!(u64, U64, to_u64);
+value_conversion!(u128, U128, to_u128);
+value_conversion!(U256, U256, to_u256);
+value_conversion!(bool, Boolean, to_bool);
+value_conversion!(String, String, to_string);
+
+impl From<&str> for Value {
+    #[inline]
+    fn from(value: &str) -> Self {
+        Value::String(value.to_owned())
+    }
+}
+
+// Build a Value::Array from an iterator of values
+impl FromIterator<Value> for Value {
+    fn from_iter<I: IntoIterator<Item = Value>>(iter: I) -> Self {
+        Value::Array(iter.into_iter().map(|v| ValueOwnable::Owned(Box::new(v))).collect())
+    }
+}
+
+impl core::fmt::Display for Value {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             Value::Null => write!(f, "null"),
             Value::U8(v) => write!(f, "{}", v),
@@ -689,7 +1024,351 @@ impl std::fmt::Display for Value {
                 }),
                 None => write!(f, "optional<null>")
             },
-            Value::Range(start, end, _type) => write!(f, "range<{}: {}..{}>", _type, start, end)
+            Value::Range(start, end, _type) => write!(f, "range<{}: {}..{}>", _type, start, end),
+            Value::Ok(value) => write!(f, "ok<{}>", value.handle()),
+            Value::Err(value) => write!(f, "err<{}>", value.handle()),
+            Value::Tuple(values) => {
+                let s: Vec<String> = values.iter().map(|v| format!("{}", v.handle())).collect();
+                write!(f, "({})", s.join(", "))
+            },
+            Value::Function(id) => write!(f, "fn<{}>", id)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::IdentifierType;
+    use proptest::prelude::*;
+
+    // Generate a primitive number value with its type
+    fn arb_number() -> impl Strategy<Value = Value> {
+        prop_oneof![
+            any::<u8>().prop_map(Value::U8),
+            any::<u16>().prop_map(Value::U16),
+            any::<u32>().prop_map(Value::U32),
+            any::<u64>().prop_map(Value::U64),
+            any::<u128>().prop_map(Value::U128),
+            any::<[u64; 4]>().prop_map(|[a, b, c, d]| Value::U256(U256::new(a, b, c, d))),
+        ]
+    }
+
+    // Generate a range with both bounds of the same type
+    fn arb_range() -> impl Strategy<Value = Value> {
+        arb_number().prop_flat_map(|start| {
+            let end = match &start {
+                Value::U8(_) => any::<u8>().prop_map(Value::U8).boxed(),
+                Value::U16(_) => any::<u16>().prop_map(Value::U16).boxed(),
+                Value::U32(_) => any::<u32>().prop_map(Value::U32).boxed(),
+                Value::U64(_) => any::<u64>().prop_map(Value::U64).boxed(),
+                Value::U128(_) => any::<u128>().prop_map(Value::U128).boxed(),
+                _ => any::<[u64; 4]>().prop_map(|[a, b, c, d]| Value::U256(U256::new(a, b, c, d))).boxed(),
+            };
+            let _type = start.get_type().unwrap();
+            end.prop_map(move |end| Value::Range(Box::new(start.clone()), Box::new(end), _type.clone()))
+        })
+    }
+
+    // Wrap a value either as owned or as shared
+    fn arb_ownable(inner: impl Strategy<Value = Value>) -> impl Strategy<Value = ValueOwnable> {
+        (inner, any::<bool>()).prop_map(|(value, shared)| if shared {
+            ValueOwnable::Rc(InnerValue::new(value))
+        } else {
+            ValueOwnable::Owned(Box::new(value))
+        })
+    }
+
+    // Generate any value, nested values are bounded in depth and size
+    pub(crate) fn arb_value() -> impl Strategy<Value = Value> {
+        let leaf = prop_oneof![
+            Just(Value::Null),
+            arb_number(),
+            ".{0,16}".prop_map(Value::String),
+            any::<bool>().prop_map(Value::Boolean),
+            arb_range(),
+        ];
+
+        leaf.prop_recursive(4, 64, 8, |inner| prop_oneof![
+            prop::collection::vec(arb_ownable(inner.clone()), 0..8).prop_map(Value::Array),
+            prop::option::of(arb_ownable(inner.clone())).prop_map(Value::Optional),
+            arb_ownable(inner.clone()).prop_map(Value::Ok),
+            arb_ownable(inner.clone()).prop_map(Value::Err),
+            (any::<IdentifierType>(), prop::collection::vec(arb_ownable(inner), 0..8)).prop_map(|(id, fields)| {
+                let types = fields.iter()
+                    .map(|v| v.handle().get_type().unwrap_or(Type::Any))
+                    .collect();
+                Value::Struct(fields, StructType::new(id, types))
+            }),
+        ])
+    }
+
+    // Convert every shared value into an owned one
+    fn into_owned_tree(value: Value) -> Value {
+        let convert = |values: Vec<ValueOwnable>| values.into_iter()
+            .map(|v| ValueOwnable::Owned(Box::new(into_owned_tree(v.into_inner()))))
+            .collect();
+
+        match value {
+            Value::Array(values) => Value::Array(convert(values)),
+            Value::Struct(fields, _type) => Value::Struct(convert(fields), _type),
+            Value::Optional(value) => Value::Optional(value.map(|v| ValueOwnable::Owned(Box::new(into_owned_tree(v.into_inner()))))),
+            Value::Ok(value) => Value::Ok(ValueOwnable::Owned(Box::new(into_owned_tree(value.into_inner())))),
+            Value::Err(value) => Value::Err(ValueOwnable::Owned(Box::new(into_owned_tree(value.into_inner())))),
+            v => v
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn test_shared_and_owned_are_indistinguishable(value in arb_value()) {
+            let owned = into_owned_tree(value.clone());
+            prop_assert_eq!(format!("{}", owned), format!("{}", value));
+            prop_assert_eq!(owned.get_type().ok(), value.get_type().ok());
+
+            // Shared and owned values are serialized the same way
+            let (mut bytes, mut owned_bytes) = (Vec::new(), Vec::new());
+            value.serialize(&mut bytes);
+            owned.serialize(&mut owned_bytes);
+            prop_assert_eq!(&bytes, &owned_bytes);
+        }
+
+        #[test]
+        fn test_serialize_round_trip(value in arb_value()) {
+            let mut bytes = Vec::new();
+            value.serialize(&mut bytes);
+
+            let mut reader = bytes.as_slice();
+            let decoded = Value::deserialize(&mut reader).unwrap();
+            prop_assert!(reader.is_empty());
+            prop_assert!(decoded.is_equal(&value).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_bitwise_not() {
+        assert_eq!(Value::U8(0).bitwise_not().unwrap(), Value::U8(255));
+        assert_eq!(Value::U64(5).bitwise_not().unwrap(), Value::U64(!5));
+        assert_eq!(Value::U256(U256::ZERO).bitwise_not().unwrap(), Value::U256(U256::MAX));
+        assert!(Value::Boolean(true).bitwise_not().is_err());
+    }
+
+    #[test]
+    fn test_increment_decrement_bounds() {
+        let mut value = Value::U8(u8::MAX - 1);
+        value.increment().unwrap();
+        assert_eq!(value, Value::U8(u8::MAX));
+        assert!(matches!(value.increment(), Err(ValueError::Overflow)));
+        assert_eq!(value, Value::U8(u8::MAX));
+
+        let mut value = Value::U64(1);
+        value.decrement().unwrap();
+        assert_eq!(value, Value::U64(0));
+        assert!(matches!(value.decrement(), Err(ValueError::Overflow)));
+        assert_eq!(value, Value::U64(0));
+
+        let mut value = Value::U256(U256::from(u128::MAX));
+        value.increment().unwrap();
+        assert_eq!(value, Value::U256(U256::new(0, 0, 1, 0)));
+        assert!(matches!(Value::U256(U256::default()).decrement(), Err(ValueError::Overflow)));
+        assert!(matches!(Value::U128(u128::MAX).increment(), Err(ValueError::Overflow)));
+        assert!(matches!(Value::Boolean(true).increment(), Err(ValueError::OperationNotNumberType)));
+    }
+
+    #[test]
+    fn test_as_number() {
+        assert_eq!(Value::U8(u8::MAX).as_number().unwrap(), (u8::MAX as u128, Type::U8));
+        assert_eq!(Value::U16(u16::MAX).as_number().unwrap(), (u16::MAX as u128, Type::U16));
+        assert_eq!(Value::U32(u32::MAX).as_number().unwrap(), (u32::MAX as u128, Type::U32));
+        assert_eq!(Value::U64(u64::MAX).as_number().unwrap(), (u64::MAX as u128, Type::U64));
+        assert_eq!(Value::U128(u128::MAX).as_number().unwrap(), (u128::MAX, Type::U128));
+        assert_eq!(Value::U256(U256::from(u128::MAX)).as_number().unwrap(), (u128::MAX, Type::U256));
+        assert_eq!(Value::U64(7).as_u128_lossless().unwrap(), 7);
+
+        // One more than u128::MAX
+        let too_large = U256::new(0, 0, 1, 0);
+        assert!(matches!(Value::U256(too_large).as_u128_lossless(), Err(ValueError::NumberTooLarge(n)) if n == too_large));
+        assert!(matches!(Value::Boolean(true).as_number(), Err(ValueError::OperationNotNumberType)));
+    }
+
+    #[test]
+    fn test_canonicalize() {
+        let owned = |v: Value| ValueOwnable::Owned(Box::new(v));
+        let shared = |v: Value| ValueOwnable::Rc(InnerValue::new(v));
+        let struct_type = StructType::new(0, vec![Type::U64, Type::Optional(Box::new(Type::U64))]);
+
+        // The same logical value with owned values, empty optionals and redundant optionals
+        let a = Value::Array(vec![
+            owned(Value::Struct(vec![owned(Value::U64(1)), owned(Value::Optional(None))], struct_type.clone())),
+            owned(Value::Optional(Some(owned(Value::Optional(Some(owned(Value::U8(2)))))))),
+            owned(Value::Ok(owned(Value::Optional(Some(owned(Value::Null)))))),
+        ]);
+        // Same with shared values and null
+        let b = Value::Array(vec![
+            shared(Value::Struct(vec![shared(Value::U64(1)), owned(Value::Null)], struct_type)),
+            shared(Value::Optional(Some(shared(Value::U8(2))))),
+            owned(Value::Ok(shared(Value::Null))),
+        ]);
+
+        let (mut a_bytes, mut b_bytes) = (Vec::new(), Vec::new());
+        a.clone().serialize(&mut a_bytes);
+        b.clone().serialize(&mut b_bytes);
+        assert_ne!(a_bytes, b_bytes);
+
+        let a = a.canonicalize().unwrap();
+        let b = b.canonicalize().unwrap();
+        assert_eq!(a, b);
+
+        let (mut a_bytes, mut b_bytes) = (Vec::new(), Vec::new());
+        a.serialize(&mut a_bytes);
+        b.serialize(&mut b_bytes);
+        assert_eq!(a_bytes, b_bytes);
+
+        // No shared value is left
+        let values = a.as_vec().unwrap();
+        assert!(values.iter().all(|v| matches!(v, ValueOwnable::Owned(_))));
+        assert_eq!(Value::Optional(None).canonicalize().unwrap(), Value::Null);
+        assert_eq!(Value::U64(5).canonicalize().unwrap(), Value::U64(5));
+    }
+
+    #[test]
+    fn test_truthy() {
+        assert!(!Value::Null.truthy());
+        assert!(!Value::U8(0).truthy());
+        assert!(Value::U64(1).truthy());
+        assert!(!Value::U256(U256::ZERO).truthy());
+        assert!(Value::U256(U256::MAX).truthy());
+        assert!(!Value::Boolean(false).truthy());
+        assert!(Value::Boolean(true).truthy());
+        assert!(!Value::String(String::new()).truthy());
+        assert!(Value::from("a").truthy());
+        assert!(!Value::Array(Vec::new()).truthy());
+        assert!(Value::Array(vec![ValueOwnable::Owned(Box::new(Value::Null))]).truthy());
+        assert!(!Value::Optional(None).truthy());
+        // An optional containing a falsy value is still set
+        assert!(Value::Optional(Some(ValueOwnable::Owned(Box::new(Value::U8(0))))).truthy());
+        assert!(Value::Tuple(Vec::new()).truthy());
+    }
+
+    #[test]
+    fn test_primitive_round_trip() {
+        assert_eq!(u8::try_from(Value::from(10u8)).unwrap(), 10);
+        assert_eq!(u16::try_from(Value::from(10u16)).unwrap(), 10);
+        assert_eq!(u32::try_from(Value::from(10u32)).unwrap(), 10);
+        assert_eq!(u64::try_from(Value::from(10u64)).unwrap(), 10);
+        assert_eq!(u128::try_from(Value::from(10u128)).unwrap(), 10);
+        assert_eq!(U256::try_from(Value::from(U256::from(10u64))).unwrap(), U256::from(10u64));
+        assert!(bool::try_from(Value::from(true)).unwrap());
+        assert_eq!(String::try_from(Value::from("hello")).unwrap(), "hello");
+        assert_eq!(String::try_from(Value::from("hello".to_owned())).unwrap(), "hello");
+    }
+
+    #[test]
+    fn test_try_from_invalid_type() {
+        assert!(matches!(u64::try_from(Value::U8(10)), Err(ValueError::InvalidValue(Value::U8(10), Type::U64))));
+        assert!(bool::try_from(Value::Null).is_err());
+        assert!(String::try_from(Value::U32(0)).is_err());
+    }
+
+    #[test]
+    fn test_from_iterator() {
+        let value: Value = (0..3u64).map(Value::from).collect();
+        let values = value.to_vec().unwrap();
+        assert_eq!(values.len(), 3);
+        for (i, v) in values.into_iter().enumerate() {
+            assert_eq!(v.into_inner(), Value::U64(i as u64));
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_is_equal_null_optional() {
+        let none = Value::Optional(None);
+        let some = Value::Optional(Some(ValueOwnable::Owned(Box::new(Value::U64(1)))));
+        assert!(Value::Null.is_equal(&none).unwrap());
+        assert!(none.is_equal(&Value::Null).unwrap());
+        assert!(!Value::Null.is_equal(&some).unwrap());
+        assert!(some.is_equal(&some.clone()).unwrap());
+
+        // Derived equality stays structural
+        assert_ne!(Value::Null, none);
+
+        let array = |v: Value| Value::Array(vec![ValueOwnable::Owned(Box::new(v))]);
+        assert!(array(Value::Null).is_equal(&array(none.clone())).unwrap());
+        assert!(!array(Value::Null).is_equal(&array(some)).unwrap());
+    }
+
+    #[test]
+    fn test_max_depth() {
+        let mut value = Value::U64(1);
+        for _ in 0..MAX_VALUE_DEPTH {
+            value = Value::Array(vec![ValueOwnable::Owned(Box::new(value))]);
+        }
+
+        // One level above the limit
+        assert!(matches!(value.get_type(), Err(ValueError::MaxDepthReached(MAX_VALUE_DEPTH))));
+        assert!(matches!(value.is_equal(&value), Err(ValueError::MaxDepthReached(MAX_VALUE_DEPTH))));
+        assert!(matches!(Type::from_value_with_max_depth(&value, MAX_VALUE_DEPTH), Err(ValueError::MaxDepthReached(MAX_VALUE_DEPTH))));
+        assert_eq!(Type::from_value(&value), None);
+        assert!(matches!(value.clone().canonicalize(), Err(ValueError::MaxDepthReached(MAX_VALUE_DEPTH))));
+
+        // The limit is configurable
+        assert!(value.get_type_with_max_depth(MAX_VALUE_DEPTH + 1).is_ok());
+        assert_eq!(value.is_equal_with_max_depth(&value, MAX_VALUE_DEPTH + 1).ok(), Some(true));
+        assert!(matches!(Type::from_value_with_max_depth(&value, MAX_VALUE_DEPTH + 1), Ok(Some(_))));
+        assert!(value.canonicalize_with_max_depth(MAX_VALUE_DEPTH + 1).is_ok());
+    }
+
+    #[test]
+    fn test_max_depth_shared_cycle() {
+        // An array containing itself through a shared value
+        let shared = InnerValue::new(Value::Array(Vec::new()));
+        shared.borrow_mut().as_mut_vec().unwrap().push(ValueOwnable::Rc(shared.clone()));
+
+        let value = Value::Array(vec![ValueOwnable::Rc(shared.clone())]);
+        assert!(matches!(value.get_type(), Err(ValueError::MaxDepthReached(_))));
+        assert!(matches!(value.is_equal(&value), Err(ValueError::MaxDepthReached(_))));
+        assert_eq!(Type::from_value(&value), None);
+        assert!(matches!(value.clone().canonicalize(), Err(ValueError::MaxDepthReached(_))));
+
+        // Break the cycle to release it
+        shared.borrow_mut().as_mut_vec().unwrap().clear();
+    }
+
+    #[test]
+    fn test_normalize_optional() {
+        let some = |v: Value| Value::Optional(Some(ValueOwnable::Owned(Box::new(v))));
+
+        // Double and triple wrapped
+        let mut value = some(some(Value::U64(1)));
+        value.normalize();
+        assert_eq!(value, some(Value::U64(1)));
+
+        let mut value = some(some(some(Value::U64(1))));
+        value.normalize();
+        assert_eq!(value, some(Value::U64(1)));
+
+        // Null wrapped, directly or through another optional
+        let mut value = some(Value::Null);
+        value.normalize();
+        assert_eq!(value, Value::Optional(None));
+
+        let mut value = some(some(Value::Null));
+        value.normalize();
+        assert_eq!(value, Value::Optional(None));
+
+        let mut value = some(Value::Optional(None));
+        value.normalize();
+        assert_eq!(value, Value::Optional(None));
+
+        // Shared inner values are flattened too
+        let mut value = Value::Optional(Some(ValueOwnable::Rc(InnerValue::new(some(Value::U64(1))))));
+        value.normalize();
+        assert_eq!(value, some(Value::U64(1)));
+
+        // Values contained in another value are left untouched
+        let array = Value::Array(vec![ValueOwnable::Owned(Box::new(some(some(Value::U64(1)))))]);
+        let mut value = some(array.clone());
+        value.normalize();
+        assert_eq!(value, some(array));
+    }
+}
@@ -112,7 +112,7 @@ fn bench_struct(c: &mut Criterion) {
         }
 
         entry main() {
-            let t: Test = Test {
+            var t: Test = Test {
                 f: 0
             };
 
@@ -134,7 +134,7 @@ fn bench_foreach(c: &mut Criterion) {
         r#"
         entry main() {
             let arr: u32[] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10];
-            let sum: u32 = 0;
+            var sum: u32 = 0;
 
             foreach i in arr {
                 sum += i;
@@ -150,7 +150,7 @@ fn bench_foreach(c: &mut Criterion) {
         "range",
         r#"
         entry main() {
-            let sum: u32 = 0;
+            var sum: u32 = 0;
             foreach i in 0u32..10u32 {
                 sum += i;
             }
@@ -164,7 +164,7 @@ fn bench_foreach(c: &mut Criterion) {
         "nested",
         r#"
         entry main() {
-            let sum: u32 = 0;
+            var sum: u32 = 0;
             foreach i in 0u32..10u32 {
                 foreach j in 0u32..10u32 {
                     sum += i + j;
@@ -185,7 +185,7 @@ fn bench_for(c: &mut Criterion) {
         r#"
         entry main() {
             let arr: u32[] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10];
-            let sum: u32 = 0;
+            var sum: u32 = 0;
             for i: u32 = 0; i < arr.len(); i += 1 {
                 sum += arr[i];
             }
@@ -199,7 +199,7 @@ fn bench_for(c: &mut Criterion) {
         "range",
         r#"
         entry main() {
-            let sum: u32 = 0;
+            var sum: u32 = 0;
             for i: u32 = 0; i < 10; i += 1 {
                 sum += i;
             }
@@ -213,7 +213,7 @@ fn bench_for(c: &mut Criterion) {
         "nested",
         r#"
         entry main() {
-            let sum: u32 = 0;
+            var sum: u32 = 0;
             for i: u32 = 0; i < 10; i += 1 {
                 for j: u32 = 0; j < 10; j += 1 {
                     sum += i + j;
@@ -232,8 +232,8 @@ fn bench_while(c: &mut Criterion) {
         "simple",
         r#"
         entry main() {
-            let sum: u32 = 0;
-            let i: u32 = 0;
+            var sum: u32 = 0;
+            var i: u32 = 0;
             while i < 10 {
                 sum += i;
                 i += 1;
@@ -248,10 +248,10 @@ fn bench_while(c: &mut Criterion) {
         "nested",
         r#"
         entry main() {
-            let sum: u32 = 0;
-            let i: u32 = 0;
+            var sum: u32 = 0;
+            var i: u32 = 0;
             while i < 10 {
-                let j: u32 = 0;
+                var j: u32 = 0;
                 while j < 10 {
                     sum += i + j;
                     j += 1;
@@ -311,10 +311,10 @@ fn prime_finder(c: &mut Criterion) {
         entry main() {
             let n: u32 = N;
             let primes: u32[] = [];
-            let count: u32 = 0;
+            var count: u32 = 0;
 
             for i: u32 = 2; i < n; i += 1 {
-                let is_prime: bool = true;
+                var is_prime: bool = true;
 
                 for j: u32 = 2; j < i; j += 1 {
                     if (i % j) == 0 {
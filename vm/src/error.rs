@@ -27,7 +27,8 @@ pub enum VMError {
     StackIndexOutOfBounds,
     NotEnoughArguments,
     StackOverflow,
-    CallStackOverflow
+    CallStackOverflow,
+    LimitReached
 }
 
 impl From<EnvironmentError> for VMError {
@@ -29,6 +29,14 @@ pub fn memory_set<'a>(_: &Backend<'a>, stack: &mut Stack<'a>, manager: &mut Chun
     Ok(InstructionResult::Nothing)
 }
 
+pub fn memory_set_owned<'a>(_: &Backend<'a>, stack: &mut Stack<'a>, manager: &mut ChunkManager<'a>) -> Result<InstructionResult, VMError> {
+    let index = manager.read_u16()?;
+    let value = stack.pop_stack()?.into_owned();
+    manager.set_register(index as usize, Path::Owned(value));
+
+    Ok(InstructionResult::Nothing)
+}
+
 pub fn subload<'a>(_: &Backend<'a>, stack: &mut Stack<'a>, manager: &mut ChunkManager<'a>) -> Result<InstructionResult, VMError> {
     let index = manager.read_u16()?;
     let path = stack.pop_stack()?;
@@ -100,23 +108,31 @@ pub fn syscall<'a>(backend: &Backend<'a>, stack: &mut Stack<'a>, manager: &mut C
         None
     };
 
-    let f = backend.environment.get_functions().get(id as usize)
-        .ok_or(VMError::UnknownSysCall)?;
-
     let mut instance = match on_value.as_mut() {
         Some(v) => Some(v.as_mut()),
         None => None,
     };
 
-    if let Some(v) = f.call_function(instance.as_deref_mut(), arguments.into())? {
+    // Syscalls registered by the host are looked up first
+    let result = match backend.syscalls.get(id) {
+        Some(handler) => handler(instance.as_deref_mut(), arguments.into())?,
+        None => {
+            let f = backend.environment.get_functions().get(id as usize)
+                .ok_or(VMError::UnknownSysCall)?;
+            f.call_function(instance.as_deref_mut(), arguments.into(), &mut backend.context.borrow_mut())?
+        }
+    };
+
+    if let Some(v) = result {
         stack.push_stack(Path::Owned(v))?;
     }
 
     Ok(InstructionResult::Nothing)
 }
 
-pub fn new_array<'a>(_: &Backend<'a>, stack: &mut Stack<'a>, manager: &mut ChunkManager<'a>) -> Result<InstructionResult, VMError> {
+pub fn new_array<'a>(backend: &Backend<'a>, stack: &mut Stack<'a>, manager: &mut ChunkManager<'a>) -> Result<InstructionResult, VMError> {
     let length = manager.read_u32()?;
+    backend.context.borrow_mut().allocate(length as u64)?;
     let mut array = VecDeque::with_capacity(length as usize);
     for _ in 0..length {
         let pop = stack.pop_stack()?;
@@ -127,9 +143,22 @@ pub fn new_array<'a>(_: &Backend<'a>, stack: &mut Stack<'a>, manager: &mut Chunk
     Ok(InstructionResult::Nothing)
 }
 
+pub fn new_tuple<'a>(_: &Backend<'a>, stack: &mut Stack<'a>, manager: &mut ChunkManager<'a>) -> Result<InstructionResult, VMError> {
+    let length = manager.read_u32()?;
+    let mut values = VecDeque::with_capacity(length as usize);
+    for _ in 0..length {
+        let pop = stack.pop_stack()?;
+        values.push_front(ValueOwnable::Owned(Box::new(pop.into_owned())));
+    }
+
+    stack.push_stack(Path::Owned(Value::Tuple(values.into())))?;
+    Ok(InstructionResult::Nothing)
+}
+
 pub fn new_struct<'a>(backend: &Backend<'a>, stack: &mut Stack<'a>, manager: &mut ChunkManager<'a>) -> Result<InstructionResult, VMError> {
     let id = manager.read_u16()?;
     let struct_type = backend.get_struct_with_id(id)?;
+    backend.context.borrow_mut().allocate(struct_type.fields().len() as u64)?;
 
     let mut fields = VecDeque::new();
     for _ in 0..struct_type.fields().len() {
@@ -40,6 +40,8 @@ impl<'a> InstructionTable<'a> {
         instructions[OpCode::SysCall.as_usize()] = syscall;
         instructions[OpCode::NewArray.as_usize()] = new_array;
         instructions[OpCode::NewStruct.as_usize()] = new_struct;
+        instructions[OpCode::NewTuple.as_usize()] = new_tuple;
+        instructions[OpCode::MemorySetOwned.as_usize()] = memory_set_owned;
 
         instructions[OpCode::Jump.as_usize()] = jump;
         instructions[OpCode::JumpIfFalse.as_usize()] = jump_if_false;
@@ -65,6 +67,8 @@ impl<'a> InstructionTable<'a> {
 
         instructions[OpCode::Eq.as_usize()] = eq;
         instructions[OpCode::Neg.as_usize()] = neg;
+        instructions[OpCode::Not.as_usize()] = not;
+        instructions[OpCode::Truthy.as_usize()] = truthy;
         instructions[OpCode::Gt.as_usize()] = gt;
         instructions[OpCode::Lt.as_usize()] = lt;
         instructions[OpCode::Gte.as_usize()] = gte;
@@ -77,7 +77,6 @@ opcode_fn!(xor, opcode_op, op, ^);
 opcode_fn!(shl, opcode_op, op, <<);
 opcode_fn!(shr, opcode_op, op, >>);
 
-opcode_fn!(eq, opcode_op, op_bool, ==);
 opcode_fn!(gt, opcode_op, op_bool, >);
 opcode_fn!(lt, opcode_op, op_bool, <);
 opcode_fn!(gte, opcode_op, op_bool, >=);
@@ -92,12 +91,34 @@ opcode_fn!(xor_assign, opcode_op_assign, op, ^);
 opcode_fn!(shl_assign, opcode_op_assign, op, <<);
 opcode_fn!(shr_assign, opcode_op_assign, op, >>);
 
+// Equality is defined for every value, null and an empty optional are equal
+pub fn eq<'a>(_: &Backend<'a>, stack: &mut Stack<'a>, _: &mut ChunkManager<'a>) -> Result<InstructionResult, VMError> {
+    let right = stack.pop_stack()?;
+    let left = stack.pop_stack()?;
+    let value = left.as_ref().is_equal(&right.as_ref())?;
+    // Push the result to the stack, no need to check as we poped 2 values
+    stack.push_stack_unchecked(Path::Owned(Value::Boolean(value)));
+    Ok(InstructionResult::Nothing)
+}
+
 pub fn neg<'a>(_: &Backend<'a>, stack: &mut Stack<'a>, _: &mut ChunkManager<'a>) -> Result<InstructionResult, VMError> {
     let value = stack.pop_stack()?;
     stack.push_stack_unchecked(Path::Owned(Value::Boolean(!value.as_bool()?)));
     Ok(InstructionResult::Nothing)
 }
 
+pub fn not<'a>(_: &Backend<'a>, stack: &mut Stack<'a>, _: &mut ChunkManager<'a>) -> Result<InstructionResult, VMError> {
+    let value = stack.pop_stack()?;
+    stack.push_stack_unchecked(Path::Owned(value.as_ref().bitwise_not()?));
+    Ok(InstructionResult::Nothing)
+}
+
+pub fn truthy<'a>(_: &Backend<'a>, stack: &mut Stack<'a>, _: &mut ChunkManager<'a>) -> Result<InstructionResult, VMError> {
+    let value = stack.pop_stack()?;
+    stack.push_stack_unchecked(Path::Owned(Value::Boolean(value.as_ref().truthy())));
+    Ok(InstructionResult::Nothing)
+}
+
 pub fn assign<'a>(_: &Backend<'a>, stack: &mut Stack<'a>, _: &mut ChunkManager<'a>) -> Result<InstructionResult, VMError> {
     let right = stack.pop_stack()?;
     let mut left = stack.pop_stack()?;
@@ -3,10 +3,13 @@ mod error;
 mod iterator;
 mod instructions;
 mod stack;
+mod syscall;
 
-use xelis_environment::Environment;
+use std::cell::RefCell;
+use xelis_environment::{Context, Environment};
 pub use error::VMError;
 pub use chunk::*;
+pub use syscall::*;
 use instructions::{InstructionResult, InstructionTable};
 use stack::Stack;
 
@@ -24,6 +27,10 @@ pub struct Backend<'a> {
     environment: &'a Environment,
     // The instruction table of the VM
     table: InstructionTable<'a>,
+    // Syscalls provided by the host
+    syscalls: SysCallTable,
+    // Context shared with the native functions
+    context: RefCell<Context>,
 }
 
 impl<'a> Backend<'a> {
@@ -48,22 +55,67 @@ pub struct VM<'a> {
     // The stack of the VM
     // Every values are stored here
     stack: Stack<'a>,
+    // Count of opcodes executed
+    instructions_executed: u64,
+    // Maximum count of opcodes that can be executed
+    max_instructions: Option<u64>,
 }
 
 impl<'a> VM<'a> {
     // Create a new VM
     pub fn new(module: &'a Module, environment: &'a Environment) -> Self {
+        Self::with_max_instructions(module, environment, None)
+    }
+
+    // Create a new VM with a limit on the opcodes executed
+    // None disable the limit
+    pub fn with_max_instructions(module: &'a Module, environment: &'a Environment, max_instructions: Option<u64>) -> Self {
+        let mut context = Context::with_output(environment.get_output_sink().clone());
+        context.set_random_source(environment.get_random_source().cloned());
+
         VM {
             backend: Backend {
                 module,
                 environment,
                 table: InstructionTable::new(),
+                syscalls: SysCallTable::new(),
+                context: RefCell::new(context),
             },
             call_stack: Vec::with_capacity(4),
             stack: Stack::new(),
+            instructions_executed: 0,
+            max_instructions,
         }
     }
 
+    // Set the maximum count of array and struct elements created
+    // 0 disable the limit
+    pub fn set_max_allocations(&mut self, max_allocations: u64) {
+        self.backend.context.get_mut().set_max_allocations(max_allocations);
+    }
+
+    // Get the count of opcodes executed by the last run
+    #[inline]
+    pub fn instructions_executed(&self) -> u64 {
+        self.instructions_executed
+    }
+
+    // Replace the syscall table used by the VM
+    pub fn set_syscall_table(&mut self, syscalls: SysCallTable) {
+        self.backend.syscalls = syscalls;
+    }
+
+    // Get the syscall table used by the VM
+    #[inline]
+    pub fn get_syscall_table_mut(&mut self) -> &mut SysCallTable {
+        &mut self.backend.syscalls
+    }
+
+    // Take the events emitted by the program, in order
+    pub fn take_events(&mut self) -> Vec<(String, Value)> {
+        self.backend.context.get_mut().take_events()
+    }
+
     // Get the stack
     #[inline]
     pub fn get_stack(&self) -> &Stack<'a> {
@@ -108,10 +160,18 @@ impl<'a> VM<'a> {
     // Run the VM
     // It will execute the bytecode
     // First chunk executed should always return a value
+    // The instructions counter is reset for each run
     pub fn run(&mut self) -> Result<Value, VMError> {
+        self.instructions_executed = 0;
         while let Some(mut manager) = self.call_stack.pop() {
             while let Ok(opcode) = manager.read_u8() {
-                match self.backend.table.execute(opcode, &self.backend, &mut self.stack, &mut manager)? {
+                if self.max_instructions.is_some_and(|max| self.instructions_executed >= max) {
+                    return Err(VMError::LimitReached);
+                }
+
+                let result = self.backend.table.execute(opcode, &self.backend, &mut self.stack, &mut manager)?;
+                self.instructions_executed += 1;
+                match result {
                     InstructionResult::Nothing => {},
                     InstructionResult::InvokeChunk(id) => {
                         self.call_stack.push(manager);
@@ -136,7 +196,8 @@ impl<'a> VM<'a> {
 #[cfg(test)]
 mod tests {
     use xelis_bytecode::{Chunk, Module, OpCode};
-    use xelis_types::{Type, Value, ValueOwnable};
+    use xelis_environment::EnvironmentError;
+    use xelis_types::{Type, Value, ValueError, ValueOwnable};
 
     use super::*;
 
@@ -147,6 +208,75 @@ mod tests {
         vm.run().unwrap()
     }
 
+    #[test]
+    fn test_instructions_limit() {
+        // Jump to itself forever
+        let mut module = Module::new();
+        let mut chunk = Chunk::new();
+        chunk.emit_opcode(OpCode::Jump);
+        chunk.write_u32(0);
+        module.add_chunk(chunk);
+
+        let env = Environment::new();
+        let mut vm = VM::with_max_instructions(&module, &env, Some(1000));
+        vm.invoke_chunk_id(0).unwrap();
+        assert!(matches!(vm.run(), Err(VMError::LimitReached)));
+        assert_eq!(vm.instructions_executed(), 1000);
+    }
+
+    #[test]
+    fn test_instructions_count() {
+        // Constant, Constant, Add, Return
+        let mut module = Module::new();
+        let mut chunk = Chunk::new();
+        for value in [1u64, 2] {
+            let index = module.add_constant(Value::U64(value));
+            chunk.emit_opcode(OpCode::Constant);
+            chunk.write_u16(index as u16);
+        }
+        chunk.emit_opcode(OpCode::Add);
+        chunk.emit_opcode(OpCode::Return);
+        module.add_chunk(chunk);
+
+        let env = Environment::new();
+        let mut vm = VM::with_max_instructions(&module, &env, Some(4));
+        vm.invoke_chunk_id(0).unwrap();
+        assert_eq!(vm.run().unwrap(), Value::U64(3));
+        assert_eq!(vm.instructions_executed(), 4);
+
+        // The counter is reset for each run, the limit applies to each one
+        vm.invoke_chunk_id(0).unwrap();
+        assert_eq!(vm.run().unwrap(), Value::U64(3));
+        assert_eq!(vm.instructions_executed(), 4);
+
+        let mut vm = VM::with_max_instructions(&module, &env, Some(3));
+        vm.invoke_chunk_id(0).unwrap();
+        assert!(matches!(vm.run(), Err(VMError::LimitReached)));
+        assert_eq!(vm.instructions_executed(), 3);
+    }
+
+    #[test]
+    fn test_allocations_limit() {
+        let mut module = Module::new();
+        let mut chunk = Chunk::new();
+
+        // Create a new array with 2 elements
+        for value in [10, 20] {
+            let index = module.add_constant(Value::U8(value));
+            chunk.emit_opcode(OpCode::Constant);
+            chunk.write_u16(index as u16);
+        }
+        chunk.emit_opcode(OpCode::NewArray);
+        chunk.write_u32(2);
+        module.add_chunk(chunk);
+
+        let env = Environment::new();
+        let mut vm = VM::new(&module, &env);
+        vm.set_max_allocations(1);
+        vm.invoke_chunk_id(0).unwrap();
+        assert!(matches!(vm.run(), Err(VMError::EnvironmentError(EnvironmentError::AllocationLimitReached(1)))));
+    }
+
     #[test]
     fn test_casting() {
         let mut module = Module::new();
@@ -163,6 +293,22 @@ mod tests {
         assert_eq!(run(module), Value::String("10".to_string()));
     }
 
+    #[test]
+    fn test_increment_overflow() {
+        let mut module = Module::new();
+        let mut chunk = Chunk::new();
+        let index = module.add_constant(Value::U8(u8::MAX));
+        chunk.emit_opcode(OpCode::Constant);
+        chunk.write_u16(index as u16);
+        chunk.emit_opcode(OpCode::Inc);
+        module.add_chunk(chunk);
+
+        let env = Environment::new();
+        let mut vm = VM::new(&module, &env);
+        vm.invoke_chunk_id(0).unwrap();
+        assert!(matches!(vm.run(), Err(VMError::ValueError(ValueError::Overflow))));
+    }
+
     #[test]
     fn test_array_call() {
         let mut module = Module::new();
@@ -232,6 +378,52 @@ mod tests {
         assert_eq!(run(module), Value::U8(30));
     }
 
+    #[test]
+    fn test_syscall_table() {
+        let mut module = Module::new();
+        let mut chunk = Chunk::new();
+
+        let index = module.add_constant(Value::U64(21));
+        chunk.emit_opcode(OpCode::Constant);
+        chunk.write_u16(index as u16);
+
+        // Call the syscall 0 with one argument
+        chunk.emit_opcode(OpCode::SysCall);
+        chunk.write_u16(0);
+        chunk.write_bool(false);
+        chunk.write_u8(1);
+
+        module.add_chunk(chunk);
+
+        let env = Environment::new();
+        let mut vm = VM::new(&module, &env);
+        vm.get_syscall_table_mut().register(0, |_, args| {
+            let value = args[0].as_ref().as_u64()?;
+            Ok(Some(Value::U64(value * 2)))
+        });
+
+        vm.invoke_chunk_id(0).unwrap();
+        assert_eq!(vm.run().unwrap(), Value::U64(42));
+    }
+
+    #[test]
+    fn test_unknown_syscall() {
+        let mut module = Module::new();
+        let mut chunk = Chunk::new();
+
+        chunk.emit_opcode(OpCode::SysCall);
+        chunk.write_u16(0);
+        chunk.write_bool(false);
+        chunk.write_u8(0);
+
+        module.add_chunk(chunk);
+
+        let env = Environment::new();
+        let mut vm = VM::new(&module, &env);
+        vm.invoke_chunk_id(0).unwrap();
+        assert!(matches!(vm.run(), Err(VMError::UnknownSysCall)));
+    }
+
     #[test]
     fn test_struct() {
         // Create a new struct
@@ -581,7 +773,7 @@ mod full_tests {
     use xelis_environment::Environment;
     use xelis_builder::EnvironmentBuilder;
     use xelis_lexer::Lexer;
-    use xelis_parser::Parser;
+    use xelis_parser::{Parser, ParserError};
     use xelis_types::Value;
 
     #[track_caller]
@@ -662,7 +854,7 @@ mod full_tests {
     fn test_if() {
         let code = r#"
             entry main() {
-                let x: u64 = 10;
+                var x: u64 = 10;
                 if x == 10 {
                     x = 20
                 }
@@ -774,7 +966,7 @@ mod full_tests {
     fn test_if_else() {
         let code = r#"
             entry main() {
-                let x: u64 = 10;
+                var x: u64 = 10;
                 if x == 20 {
                     x = 20
                 } else {
@@ -796,7 +988,7 @@ mod full_tests {
     fn test_if_else_positive() {
         let code = r#"
             entry main() {
-                let x: u64 = 10;
+                var x: u64 = 10;
                 if x == 10 {
                     x = 20
                 } else {
@@ -818,7 +1010,7 @@ mod full_tests {
     fn test_nested_if() {
         let code = r#"
             entry main() {
-                let x: u64 = 10;
+                var x: u64 = 10;
                 if x == 10 {
                     x = 5
                     if x == 5 {
@@ -841,7 +1033,7 @@ mod full_tests {
     fn test_if_else_if() {
         let code = r#"
             entry main() {
-                let x: u64 = 10;
+                var x: u64 = 10;
                 if x == 20 {
                     x = 20
                 } else if x == 10 {
@@ -865,7 +1057,7 @@ mod full_tests {
     fn test_while() {
         let code = r#"
             entry main() {
-                let x: u64 = 0;
+                var x: u64 = 0;
                 while x < 10 {
                     x = x + 1;
                 }
@@ -885,7 +1077,7 @@ mod full_tests {
     fn test_for() {
         let code = r#"
             entry main() {
-                let x: u64 = 0;
+                var x: u64 = 0;
                 for i: u64 = 0; i < 10; i += 1 {
                     x = x + 1;
                 }
@@ -932,7 +1124,7 @@ mod full_tests {
             }
     
             entry main() {
-                let t: Test = Test { x: 10, y: 20 };
+                var t: Test = Test { x: 10, y: 20 };
                 t.x = 30;
                 return t.x + t.y
             }
@@ -966,6 +1158,38 @@ mod full_tests {
         assert_eq!(value, Value::U64(30));
     }
     
+    #[test]
+    fn test_bitwise_not() {
+        let code = r#"
+            entry main() {
+                let a: u8 = ~0u8;
+                return a as u64
+            }
+        "#;
+
+        assert_eq!(run_code(code), Value::U64(255));
+    }
+
+    #[test]
+    fn test_function_call_default_value() {
+        let code = r#"
+            fn add(a: u64, b: u64 = 10) -> u64 {
+                return a + b
+            }
+    
+            entry main() {
+                return add(5) + add(5, 20)
+            }
+        "#;
+    
+        let (module, environment) = prepare_module(code);
+    
+        let mut vm = VM::new(&module, &environment);
+        vm.invoke_chunk_id(1).unwrap();
+        let value = vm.run().unwrap();
+        assert_eq!(value, Value::U64(40));
+    }
+
     #[test]
     fn test_array() {
         let code = r#"
@@ -1008,7 +1232,7 @@ mod full_tests {
     fn test_continue() {
         let code = r#"
             entry main() {
-                let x: u64 = 0;
+                var x: u64 = 0;
                 for i: u64 = 0; i < 10; i += 1 {
                     if ((i % 2) == 0) {
                         continue
@@ -1031,7 +1255,7 @@ mod full_tests {
     fn test_break() {
         let code = r#"
             entry main() {
-                let x: u64 = 0;
+                var x: u64 = 0;
                 for i: u64 = 0; i < 10; i += 1 {
                     if (i == 5) {
                         break
@@ -1054,7 +1278,7 @@ mod full_tests {
     fn test_nested_loops() {
         let code = r#"
             entry main() {
-                let x: u64 = 0;
+                var x: u64 = 0;
                 for i: u64 = 0; i < 10; i += 1 {
                     for j: u64 = 0; j < 10; j += 1 {
                         x = x + 1
@@ -1077,7 +1301,7 @@ mod full_tests {
         let code = r#"
             entry main() {
                 let arr: u64[] = [10, 20, 30];
-                let x: u64 = 0;
+                var x: u64 = 0;
                 for i: u32 = 0; i < arr.len(); i += 1 {
                     let y: u64 = arr[i];
                     x = x + y
@@ -1098,7 +1322,7 @@ mod full_tests {
     fn test_foreach_range() {
         let code = r#"
             entry main() {
-                let x: u64 = 0;
+                var x: u64 = 0;
                 foreach i in 0..10 {
                     x = x + i
                 }
@@ -1187,7 +1411,7 @@ mod full_tests {
     fn test_stackoverflow() {
         let code = r#"
             entry main() {
-                let x: u64 = 0;
+                var x: u64 = 0;
                 for i: u64 = 0; i < 1000000; i += 1 {
                     x = x + 1
                 }
@@ -1196,13 +1420,199 @@ mod full_tests {
 
         run_code(code);
 
-        let mut code = r#"
+        // Long chains are bounded by the parser, anything it accepts is compiled and executed
+        // The limits are sized for release builds, debug builds use larger frames
+        std::thread::Builder::new()
+            .stack_size(64 * 1024 * 1024)
+            .spawn(|| {
+                let code = |count: usize| format!("entry main() {{ let a: u64 = 1; return {} }}", vec!["a"; count].join(" + "));
+                assert_eq!(run_code(&code(200)), Value::U64(200));
+
+                let env = EnvironmentBuilder::default();
+                let source = code(10000);
+                let tokens = Lexer::new(&source).get().unwrap();
+                let err = Parser::new(tokens, &env).parse().unwrap_err();
+                assert!(matches!(err, ParserError::MaxDepthReached(_)));
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+
+    #[test]
+    fn test_struct_method() {
+        let code = r#"
+            struct Point { x: u64, y: u64 }
+            struct Line { a: Point, b: Point }
+
+            fn (self Point) sum(z: u64) -> u64 {
+                return self.x + self.y + z
+            }
+
+            fn (self Point) set_x(v: u64) {
+                self.x = v
+            }
+
+            fn (self Line) length() -> u64 {
+                return self.a.sum(0) + self.b.sum(0)
+            }
+
             entry main() {
-                let a: u64 = 1;
-                let b: u64 = a
-        "#.to_string() + "+ a + a ".repeat(10000).as_str();
-        code.push_str("return x }");
+                let l: Line = Line { a: Point { x: 1, y: 2 }, b: Point { x: 3, y: 4 } };
+                let points: Point[] = [Point { x: 100, y: 0 }];
+                l.a.set_x(11);
+                return l.length() + l.a.sum(0) + points[0].sum(1)
+            }
+        "#;
 
-        run_code(&code);
+        let (module, environment) = prepare_module(code);
+        let mut vm = VM::new(&module, &environment);
+        vm.invoke_entry_chunk(3).unwrap();
+        assert_eq!(vm.run().unwrap(), Value::U64(134));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_null_equals_empty_optional() {
+        let code = r#"
+            entry main() {
+                let a: u64[] = [5];
+                let some: optional<u64> = a.first();
+                let none: optional<u64> = a.get(5);
+                var r: u64 = 0;
+                if none == null { r += 1 }
+                if null == none { r += 10 }
+                if some != null { r += 100 }
+                if !(some == null) { r += 1000 }
+                return r
+            }
+        "#;
+        assert_eq!(run_code(code), Value::U64(1111));
+    }
+
+    #[test]
+    fn test_bit_counts() {
+        let code = r#"
+            entry main() {
+                let a: u8 = 176;
+                let b: u16 = 1;
+                let c: u32 = 0;
+                let d: u64 = 0xFF;
+                let e: u128 = 1 << 100;
+                let f: u256 = 0xF0u256;
+                let ones: u32 = a.count_ones() + d.count_ones() + f.count_ones();
+                let leading: u32 = b.leading_zeros() + c.leading_zeros() + e.leading_zeros() + f.leading_zeros();
+                let trailing: u32 = a.trailing_zeros() + e.trailing_zeros() + f.trailing_zeros();
+                return ((ones as u64) * 1000000) + ((leading as u64) * 1000) + (trailing as u64)
+            }
+        "#;
+        assert_eq!(run_code(code), Value::U64(15322108));
+    }
+
+    #[test]
+    fn test_tuple() {
+        let code = r#"
+            fn pair(a: u64, b: bool) -> (u64, bool) {
+                return (a, b)
+            }
+
+            entry main() {
+                let t: (u64, bool) = pair(10, true);
+                let (x, y) = pair(5, false);
+                var r: u64 = t.0 + x;
+                if t.1 { r += 100 }
+                if y { r += 1000 }
+                let nested: ((u8, u64), string) = ((1, 2), "a");
+                let (_, s) = nested;
+                if s == "a" { r += 10000 }
+                return r + nested.0.1
+            }
+        "#;
+        let (module, environment) = prepare_module(code);
+        let mut vm = VM::new(&module, &environment);
+        vm.invoke_entry_chunk(1).unwrap();
+        assert_eq!(vm.run().unwrap(), Value::U64(10117));
+    }
+
+    #[test]
+    fn test_emit_events() {
+        let code = r#"
+            entry main() {
+                emit("transfer", 100);
+                emit("done", "ok");
+                return 0
+            }
+        "#;
+        let (module, environment) = prepare_module(code);
+        let mut vm = VM::new(&module, &environment);
+        vm.invoke_entry_chunk(0).unwrap();
+        assert_eq!(vm.run().unwrap(), Value::U64(0));
+        assert_eq!(vm.take_events(), vec![
+            ("transfer".to_owned(), Value::U64(100)),
+            ("done".to_owned(), Value::String("ok".to_owned())),
+        ]);
+        assert!(vm.take_events().is_empty());
+    }
+
+    #[test]
+    fn test_assignment_copies_value() {
+        let code = r#"
+            struct Point { x: u64, values: u64[] }
+
+            entry main() {
+                let a: u64[][] = [[1], [2]];
+                var b: u64[][] = a;
+                b[0][0] = 10;
+                b[1][0] = 20;
+
+                let p: Point = Point { x: 1, values: [1] };
+                var q: Point = p;
+                q.x = 10;
+                q.values[0] = 5;
+                var v: u64[] = p.values;
+                v[0] = 3;
+
+                return a[0][0] + a[1][0] + p.x + p.values[0]
+            }
+        "#;
+        assert_eq!(run_code(code), Value::U64(5));
+    }
+
+    #[test]
+    fn test_truthy_conditions() {
+        let code = r#"
+            entry main() {
+                let a: u64[] = [1];
+                let s: string = "";
+                var n: u64 = 3;
+                var count: u64 = 0;
+                while n {
+                    n -= 1;
+                    count += 1;
+                }
+                if a {
+                    count += 10;
+                }
+                return s ? 0 : count
+            }
+        "#;
+        let tokens = Lexer::new(code).get().unwrap();
+        let env = EnvironmentBuilder::default();
+        let mut parser = Parser::new(tokens, &env);
+        parser.set_truthy_conditions(true);
+        let (program, _) = parser.parse().unwrap();
+        let env = env.build();
+
+        let mut compiler = Compiler::new(&program, &env);
+        compiler.set_truthy_conditions(true);
+        let module = compiler.compile().unwrap();
+        let mut vm = VM::new(&module, &env);
+        vm.invoke_entry_chunk(0).unwrap();
+        assert_eq!(vm.run().unwrap(), Value::U64(13));
+
+        // Without it, the conditions must be booleans
+        let module = Compiler::new(&program, &env).compile().unwrap();
+        let mut vm = VM::new(&module, &env);
+        vm.invoke_entry_chunk(0).unwrap();
+        assert!(vm.run().is_err());
+    }
+}
@@ -0,0 +1,49 @@
+use std::collections::HashMap;
+use xelis_types::{Path, Value};
+
+use crate::VMError;
+
+// Handler of a syscall registered by the host
+// It receives the instance (if called on a value) and the arguments popped from the stack
+// Closures can capture the host state (balances, events...) as owned or shared values
+pub type SysCallFn = Box<dyn for<'a> Fn(Option<&mut Value>, Vec<Path<'a>>) -> Result<Option<Value>, VMError>>;
+
+// Table of syscalls provided by the host
+// A syscall id registered here takes precedence over the environment function with the same id
+#[derive(Default)]
+pub struct SysCallTable {
+    handlers: HashMap<u16, SysCallFn>,
+}
+
+impl SysCallTable {
+    // Create a new empty syscall table
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Register a syscall handler for the id
+    // Returns the previous handler if the id was already registered
+    pub fn register<F>(&mut self, id: u16, handler: F) -> Option<SysCallFn>
+    where
+        F: for<'a> Fn(Option<&mut Value>, Vec<Path<'a>>) -> Result<Option<Value>, VMError> + 'static
+    {
+        self.handlers.insert(id, Box::new(handler))
+    }
+
+    // Remove a syscall handler
+    pub fn unregister(&mut self, id: u16) -> Option<SysCallFn> {
+        self.handlers.remove(&id)
+    }
+
+    // Get the handler registered for the id
+    #[inline]
+    pub fn get(&self, id: u16) -> Option<&SysCallFn> {
+        self.handlers.get(&id)
+    }
+
+    // Check if a handler is registered for the id
+    #[inline]
+    pub fn contains(&self, id: u16) -> bool {
+        self.handlers.contains_key(&id)
+    }
+}